@@ -0,0 +1,45 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Routing a request asks its target for the basename (and functions may ask for the sub-path and
+//! query), so compare parsing the target every time against the cached parse.
+//!
+//! Run with `cargo +nightly bench --bench targets`.
+
+#![feature(test)]
+
+extern crate test;
+
+use local_compute::TargetComputeFunc;
+use test::{black_box, Bencher};
+
+const TARGET: &str = "math/add/exact/i128?round=true&digits=2";
+
+/// The lookups a single dispatch does.
+fn route(target: &TargetComputeFunc) -> usize {
+    target.basename().len() + target.subpath().len() + target.query().len()
+}
+
+#[bench]
+fn parse_per_call(b: &mut Bencher) {
+    b.iter(|| route(&TargetComputeFunc::new(black_box(TARGET).to_string())));
+}
+
+#[bench]
+fn cached(b: &mut Bencher) {
+    let target = TargetComputeFunc::new(TARGET.to_string());
+    b.iter(|| route(black_box(&target)));
+}
+
+/// The cost of building a target without ever parsing it, to subtract from `parse_per_call`.
+#[bench]
+fn construct_only(b: &mut Bencher) {
+    b.iter(|| {
+        TargetComputeFunc::new(black_box(TARGET).to_string())
+            .name()
+            .len()
+    });
+}
@@ -0,0 +1,32 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! A minimal dynamically loaded [`ComputeFunction`], used as a fixture by the manager tests.
+//! Cargo builds it as a `cdylib` as part of `cargo test`, or manually with
+//! `cargo build --example sample_plugin`.
+
+use local_compute::{
+    async_trait, declare_plugin, BadRequestError, ComputeFunction, ComputeRequest, ComputeResponse,
+};
+
+#[derive(Debug, Default)]
+pub struct SamplePlugin;
+
+#[async_trait]
+impl ComputeFunction for SamplePlugin {
+    fn name(&self) -> &'static str {
+        "sample_plugin"
+    }
+
+    async fn receive_request(
+        &self,
+        request: &ComputeRequest,
+    ) -> Result<ComputeResponse, BadRequestError> {
+        Ok(ComputeResponse::json_ok(request.data().clone()))
+    }
+}
+
+declare_plugin!(SamplePlugin);
@@ -0,0 +1,23 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Feed arbitrary bytes through the same parse and validation path the servers use for untrusted
+//! input. Any panic is a bug, malformed input must always come back as a `BadInputError`.
+//!
+//! Run with `cargo +nightly fuzz run app_input` from the repository root.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use local_compute::AppInput;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(input) = AppInput::from_json_slice(data) {
+        // Anything that parsed must survive a round trip as well.
+        let bytes = serde_json::to_vec(&input).expect("parsed input must serialize");
+        AppInput::from_json_slice(&bytes).expect("serialized input must parse again");
+    }
+});
@@ -0,0 +1,20 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use clap::Parser;
+use local_compute::Cli;
+
+#[tokio::main]
+async fn main() {
+    match Cli::parse().execute().await {
+        Ok(Some(answer)) => println!("{}", answer),
+        Ok(None) => {}
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
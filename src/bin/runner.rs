@@ -8,5 +8,8 @@ use local_compute::run;
 
 #[tokio::main]
 async fn main() {
-    run().await;
+    if let Err(e) = run(None, None, None).await {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
 }
@@ -4,3 +4,276 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+//! The command line of the `local-compute` binary: `serve` starts a server, every other command
+//! sends a single [`AppInput`] to a running one on `POST /` and prints what it answered with.
+
+use std::{net::SocketAddr, path::PathBuf};
+
+use clap::{Parser, Subcommand};
+use thiserror::Error;
+
+use crate::{
+    core::types::{AddFunctionRequest, RemoveFunctionRequest},
+    AppInput, AuthToken, Backend, ComputeRequest, JsonValue, ServerError, TargetComputeFunc,
+};
+
+/// Run compute functions locally, or talk to a server that does.
+#[derive(Debug, Parser)]
+#[clap(name = "local-compute", version, about)]
+pub struct Cli {
+    /// The address to serve on, or where the server to talk to is listening.
+    #[clap(long, global = true, default_value = "127.0.0.1:3000")]
+    pub addr: SocketAddr,
+    /// The web framework to serve with. Every backend accepts the requests the other commands
+    /// send, so this only matters to `serve`.
+    #[clap(long, global = true, default_value_t = Backend::default())]
+    pub backend: Backend,
+    /// The bearer token `serve` requires from every request, or the one the other commands send.
+    #[clap(long, global = true, forbid_empty_values = true)]
+    pub auth_token: Option<String>,
+    #[clap(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Subcommand)]
+pub enum Command {
+    /// Start the server, until Ctrl-C (or `SIGTERM`).
+    Serve,
+    /// Load the plugin library at `path` into the server.
+    Load { path: PathBuf },
+    /// Unload the function `name` from the server.
+    Remove { name: String },
+    /// List the functions the server has loaded.
+    List,
+    /// Call the function `name` with `data`, which has to be JSON.
+    Call { name: String, data: String },
+}
+
+/// An error running a [`Cli`] command.
+#[derive(Debug, Error)]
+pub enum CliError {
+    #[error(transparent)]
+    Server(#[from] ServerError),
+    #[error("The data is not valid JSON: {0}")]
+    BadJson(String),
+    #[error("Unable to reach the server at {addr}: {message}")]
+    Unreachable { addr: SocketAddr, message: String },
+    #[error("The server answered with {status}: {body}")]
+    Rejected { status: u16, body: String },
+}
+
+impl Command {
+    /// The [`AppInput`] this command sends to the server, or `None` for [`Command::Serve`].
+    ///
+    /// ## Errors
+    /// [`CliError::BadJson`] if the data of a [`Command::Call`] isn't JSON.
+    pub fn input(&self) -> Result<Option<AppInput>, CliError> {
+        let input = match self {
+            Self::Serve => return Ok(None),
+            Self::Load { path } => {
+                // The server resolves relative paths against its own working directory, which
+                // is not necessarily ours.
+                let path = path.canonicalize().unwrap_or_else(|_| path.clone());
+                AppInput::AddComputeFunction(AddFunctionRequest::new(
+                    path.to_string_lossy().into_owned(),
+                ))
+            }
+            Self::Remove { name } => AppInput::RemoveComputeFunction(RemoveFunctionRequest::new(
+                TargetComputeFunc::new(name.clone()),
+            )),
+            Self::List => AppInput::ListFunctions,
+            Self::Call { name, data } => {
+                let data: JsonValue =
+                    serde_json::from_str(data).map_err(|e| CliError::BadJson(e.to_string()))?;
+                AppInput::Execute(ComputeRequest::new(
+                    TargetComputeFunc::new(name.clone()),
+                    data,
+                ))
+            }
+        };
+        Ok(Some(input))
+    }
+}
+
+impl Cli {
+    /// Run the command, returning what the server answered with, if anything.
+    ///
+    /// ## Errors
+    /// - Whatever [`crate::run`] fails with for [`Command::Serve`]
+    /// - [`CliError::BadJson`] if the data of a [`Command::Call`] isn't JSON
+    /// - [`CliError::Unreachable`] if there is no server at `addr`
+    /// - [`CliError::Rejected`] if the server answered with an error
+    pub async fn execute(&self) -> Result<Option<String>, CliError> {
+        match self.command.input()? {
+            None => {
+                let auth_token = self.auth_token.as_deref().map(AuthToken::new);
+                crate::run(Some(self.addr), Some(self.backend), auth_token.as_ref()).await?;
+                Ok(None)
+            }
+            Some(input) => send(&self.addr, self.auth_token.as_deref(), &input)
+                .await
+                .map(Some),
+        }
+    }
+}
+
+/// `POST` `input` to the server at `addr`, with `auth_token` as a bearer token if there is one, and
+/// return the body it answered with, pretty printed if it is JSON.
+///
+/// ## Errors
+/// - [`CliError::Unreachable`] if there is no server at `addr`
+/// - [`CliError::Rejected`] if the server answered with an error
+pub async fn send(
+    addr: &SocketAddr,
+    auth_token: Option<&str>,
+    input: &AppInput,
+) -> Result<String, CliError> {
+    let unreachable = |e: &reqwest::Error| CliError::Unreachable {
+        addr: *addr,
+        message: e.to_string(),
+    };
+    let mut request = reqwest::Client::new()
+        .post(format!("http://{}/", addr))
+        .json(input);
+    if let Some(token) = auth_token {
+        request = request.bearer_auth(token);
+    }
+    let response = request.send().await.map_err(|e| unreachable(&e))?;
+    let status = response.status();
+    let body = response.text().await.map_err(|e| unreachable(&e))?;
+    let body = serde_json::from_str::<JsonValue>(&body)
+        .and_then(|json| serde_json::to_string_pretty(&json))
+        .unwrap_or(body);
+
+    if status.is_success() {
+        Ok(body)
+    } else {
+        Err(CliError::Rejected {
+            status: status.as_u16(),
+            body,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::oneshot;
+
+    use super::*;
+    use crate::ComputeFunctionManager;
+
+    #[test]
+    fn parses_global_flags_and_commands() {
+        let cli = Cli::try_parse_from([
+            "local-compute",
+            "call",
+            "echo",
+            r#"{"x":1}"#,
+            "--addr",
+            "127.0.0.1:4000",
+            "--backend",
+            "warp",
+        ])
+        .unwrap();
+        assert_eq!(cli.addr, SocketAddr::from(([127, 0, 0, 1], 4000)));
+        assert_eq!(cli.backend, Backend::Warp);
+        assert_eq!(
+            cli.command,
+            Command::Call {
+                name: "echo".to_string(),
+                data: r#"{"x":1}"#.to_string()
+            }
+        );
+
+        let cli = Cli::try_parse_from(["local-compute", "list"]).unwrap();
+        assert_eq!(cli.addr, SocketAddr::from(([127, 0, 0, 1], 3000)));
+        assert_eq!(cli.backend, Backend::default());
+        assert_eq!(cli.auth_token, None);
+
+        let cli =
+            Cli::try_parse_from(["local-compute", "serve", "--auth-token", "s3cret"]).unwrap();
+        assert_eq!(cli.auth_token.as_deref(), Some("s3cret"));
+        assert!(Cli::try_parse_from(["local-compute", "serve", "--auth-token", ""]).is_err());
+
+        assert!(Cli::try_parse_from(["local-compute", "--backend", "tomcat", "list"]).is_err());
+        assert!(Cli::try_parse_from(["local-compute", "load"]).is_err());
+    }
+
+    #[test]
+    fn commands_turn_into_inputs() {
+        assert!(Command::Serve.input().unwrap().is_none());
+        assert!(matches!(
+            Command::List.input().unwrap(),
+            Some(AppInput::ListFunctions)
+        ));
+        assert!(matches!(
+            Command::Remove { name: "gone".to_string() }.input().unwrap(),
+            Some(AppInput::RemoveComputeFunction(remove)) if remove.target().name() == "gone"
+        ));
+        assert!(matches!(
+            Command::Call {
+                name: "echo".to_string(),
+                data: "not json".to_string()
+            }
+            .input(),
+            Err(CliError::BadJson(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn talks_to_a_running_server() {
+        // Bind and drop a listener to find a port nothing listens on.
+        let addr = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        assert!(matches!(
+            send(&addr, None, &AppInput::ListFunctions).await,
+            Err(CliError::Unreachable { .. })
+        ));
+
+        let (stop, shutdown_signal) = oneshot::channel();
+        let served = tokio::task::spawn(async move {
+            crate::core::server::serve(
+                Backend::default(),
+                &addr,
+                ComputeFunctionManager::with_logger(),
+                Some(&AuthToken::new("s3cret")),
+                shutdown_signal,
+            )
+            .await
+        });
+
+        let list = Command::List.input().unwrap().unwrap();
+        let listed = tokio::time::timeout(std::time::Duration::from_secs(5), async {
+            loop {
+                match send(&addr, Some("s3cret"), &list).await {
+                    Err(CliError::Unreachable { .. }) => {
+                        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+                    }
+                    other => return other,
+                }
+            }
+        })
+        .await
+        .expect("The server never started")
+        .unwrap();
+        assert!(listed.contains("logger"), "{}", listed);
+        assert!(matches!(
+            send(&addr, None, &list).await,
+            Err(CliError::Rejected { status: 401, .. })
+        ));
+
+        let missing = Command::Call {
+            name: "missing".to_string(),
+            data: "{}".to_string(),
+        };
+        assert!(matches!(
+            send(&addr, Some("s3cret"), &missing.input().unwrap().unwrap()).await,
+            Err(CliError::Rejected { .. })
+        ));
+
+        stop.send(()).unwrap();
+        assert!(served.await.unwrap().is_ok());
+    }
+}
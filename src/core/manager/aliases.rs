@@ -0,0 +1,85 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    sync::{PoisonError, RwLock},
+};
+
+/// Additional names functions can be reached under, mapped to the name each function is registered
+/// under.
+///
+/// A plain [`std::sync::RwLock`] is used since it is only ever held for a single map operation and
+/// never across an `.await`. Changes are made while holding the manager's function map lock, so the
+/// aliases always agree with the functions that are registered.
+#[derive(Debug, Default)]
+pub struct Aliases {
+    canonical: RwLock<HashMap<String, String>>,
+}
+
+impl Aliases {
+    /// The name the function reached as `name` is registered under, which is `name` itself if it
+    /// isn't an alias.
+    pub fn resolve<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        self.canonical
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(name)
+            .map_or(Cow::Borrowed(name), |canonical| {
+                Cow::Owned(canonical.clone())
+            })
+    }
+
+    /// Whether `name` is an alias.
+    pub fn contains(&self, name: &str) -> bool {
+        self.canonical
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .contains_key(name)
+    }
+
+    /// Every alias of the function registered as `canonical`, sorted.
+    pub fn of(&self, canonical: &str) -> Vec<String> {
+        let mut aliases: Vec<String> = self
+            .canonical
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .filter(|(_, name)| *name == canonical)
+            .map(|(alias, _)| alias.clone())
+            .collect();
+        aliases.sort();
+        aliases
+    }
+
+    /// Make `alias` an additional name for the function registered as `canonical`.
+    pub fn insert(&self, alias: &str, canonical: &str) {
+        self.canonical
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(alias.to_string(), canonical.to_string());
+    }
+
+    /// Point the aliases of `from` at `to`, for when a function is renamed.
+    pub fn rename(&self, from: &str, to: &str) {
+        let mut aliases = self
+            .canonical
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        for canonical in aliases.values_mut().filter(|canonical| *canonical == from) {
+            *canonical = to.to_string();
+        }
+    }
+
+    /// Forget every alias of `canonical`, for when it is unloaded.
+    pub fn remove_all_of(&self, canonical: &str) {
+        self.canonical
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .retain(|_, name| name != canonical);
+    }
+}
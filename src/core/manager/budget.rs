@@ -0,0 +1,135 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::{
+    io,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use serde_json::Value as JsonValue;
+
+/// A rough cap on the combined size of the payloads of all in-flight requests, so that many large
+/// concurrent requests can't run the process out of memory. Sizes are measured as the length of
+/// the serialized input, which undercounts what the functions actually allocate but is cheap and
+/// good enough to keep things fair.
+#[derive(Debug)]
+pub struct PayloadBudget {
+    max_bytes: usize,
+    in_use: AtomicUsize,
+}
+
+impl PayloadBudget {
+    /// Create a new [`PayloadBudget`] that allows at most `max_bytes` of payload in flight at once.
+    #[must_use]
+    pub const fn new(max_bytes: usize) -> Self {
+        Self {
+            max_bytes,
+            in_use: AtomicUsize::new(0),
+        }
+    }
+
+    /// The maximum number of payload bytes that can be in flight at once.
+    #[must_use]
+    pub const fn max_bytes(&self) -> usize {
+        self.max_bytes
+    }
+
+    /// The number of payload bytes currently reserved.
+    #[must_use]
+    pub fn in_use(&self) -> usize {
+        self.in_use.load(Ordering::Acquire)
+    }
+
+    /// Reserve room for `data`, or return [`None`] if it would push the budget over its limit.
+    /// The room is given back when the returned [`PayloadReservation`] is dropped.
+    pub fn try_reserve(&self, data: &JsonValue) -> Option<PayloadReservation<'_>> {
+        self.try_reserve_bytes(serialized_len(data))
+    }
+
+    /// Reserve `bytes` of room, or return [`None`] if it would push the budget over its limit.
+    pub fn try_reserve_bytes(&self, bytes: usize) -> Option<PayloadReservation<'_>> {
+        self.in_use
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |in_use| {
+                in_use
+                    .checked_add(bytes)
+                    .filter(|&total| total <= self.max_bytes)
+            })
+            .ok()
+            .map(|_| PayloadReservation {
+                budget: self,
+                bytes,
+            })
+    }
+}
+
+/// Bytes reserved from a [`PayloadBudget`], released when dropped.
+#[derive(Debug)]
+pub struct PayloadReservation<'a> {
+    budget: &'a PayloadBudget,
+    bytes: usize,
+}
+
+impl PayloadReservation<'_> {
+    /// The number of bytes this reservation holds.
+    #[must_use]
+    pub const fn bytes(&self) -> usize {
+        self.bytes
+    }
+}
+
+impl Drop for PayloadReservation<'_> {
+    fn drop(&mut self) {
+        self.budget.in_use.fetch_sub(self.bytes, Ordering::AcqRel);
+    }
+}
+
+/// The length of `data` serialized as JSON, without allocating the serialized form.
+pub fn serialized_len(data: &JsonValue) -> usize {
+    struct Counter(usize);
+
+    impl io::Write for Counter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0 += buf.len();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mut counter = Counter(0);
+    // Writing a `Value` into a writer that never fails can't fail either.
+    let _ = serde_json::to_writer(&mut counter, data);
+    counter.0
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn measures_the_serialized_size() {
+        let data = json!({ "a": [1, 2, 3] });
+        assert_eq!(serialized_len(&data), data.to_string().len());
+    }
+
+    #[test]
+    fn reservations_are_released_on_drop() {
+        let budget = PayloadBudget::new(16);
+        let data = json!("0123456789");
+
+        let first = budget.try_reserve(&data).unwrap();
+        assert_eq!(budget.in_use(), 12);
+        assert!(budget.try_reserve(&data).is_none());
+
+        drop(first);
+        assert_eq!(budget.in_use(), 0);
+        assert!(budget.try_reserve(&data).is_some());
+    }
+}
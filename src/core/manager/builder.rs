@@ -0,0 +1,167 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::{path::PathBuf, time::Duration};
+
+use super::ComputeFunctionManager;
+use crate::{core::types::AppResult, functions::BuiltinFunction};
+
+/// Assembles a fully configured [`ComputeFunctionManager`], builtins and dynamic libraries
+/// included, in one place before it is handed to a server. See
+/// [`ComputeFunctionManager::builder`].
+#[derive(Debug, Default, Clone)]
+pub struct ComputeFunctionManagerBuilder {
+    builtins: Vec<BuiltinFunction>,
+    libraries: Vec<String>,
+    base_dir: Option<PathBuf>,
+    default_timeout: Option<Duration>,
+    allow_legacy_plugins: bool,
+}
+
+impl ComputeFunctionManagerBuilder {
+    /// Create a new builder for an empty manager.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the builtin `function`. Adding the same builtin twice is harmless.
+    #[must_use]
+    pub fn with_builtin(mut self, function: BuiltinFunction) -> Self {
+        self.builtins.push(function);
+        self
+    }
+
+    /// Load a [`ComputeFunction`](crate::ComputeFunction) plugin from the library at `path` when the
+    /// manager is built. Libraries are loaded in the order they were added.
+    ///
+    /// ## Safety
+    /// The library is loaded with [`ComputeFunctionManager::load_plugin`], so it has to hold up
+    /// everything that function's safety section expects of it.
+    #[must_use]
+    pub unsafe fn with_library(mut self, path: impl Into<String>) -> Self {
+        self.libraries.push(path.into());
+        self
+    }
+
+    /// Resolve relative library paths against `dir`, see [`ComputeFunctionManager::set_base_dir`].
+    /// This applies to the libraries added with [`ComputeFunctionManagerBuilder::with_library`] as
+    /// well.
+    #[must_use]
+    pub fn base_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.base_dir = Some(dir.into());
+        self
+    }
+
+    /// Give up on functions that take longer than `timeout`, see
+    /// [`ComputeFunctionManager::set_default_timeout`].
+    #[must_use]
+    pub const fn default_timeout(mut self, timeout: Duration) -> Self {
+        self.default_timeout = Some(timeout);
+        self
+    }
+
+    /// Load libraries that don't export a plugin ABI version, see
+    /// [`ComputeFunctionManager::set_allow_legacy_plugins`].
+    #[must_use]
+    pub const fn allow_legacy_plugins(mut self, allow: bool) -> Self {
+        self.allow_legacy_plugins = allow;
+        self
+    }
+
+    /// Create the manager, adding the builtins and then loading the libraries.
+    ///
+    /// ## Errors
+    /// - [`AppError::Loading`](crate::AppError::Loading) with the first [`LoadingError`](crate::core::types::LoadingError)
+    ///   a builtin or library fails with, in which case nothing after it is loaded
+    pub async fn build(self) -> AppResult<ComputeFunctionManager> {
+        let mut manager = ComputeFunctionManager::new();
+        if let Some(dir) = self.base_dir {
+            manager.set_base_dir(dir);
+        }
+        if let Some(timeout) = self.default_timeout {
+            manager.set_default_timeout(timeout);
+        }
+        manager.set_allow_legacy_plugins(self.allow_legacy_plugins);
+        for builtin in self.builtins {
+            manager.load_builtin_function(builtin).await?;
+        }
+        for library in self.libraries {
+            // SAFETY: The caller vouched for the library in `with_library`.
+            unsafe { manager.load_plugin(library).await }?;
+        }
+
+        Ok(manager)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        core::types::LoadingError, AppError, ComputeFunction, ComputeRequest, ComputeResponse,
+        TargetComputeFunc,
+    };
+
+    fn assert_send<T: Send>(_: &T) {}
+
+    #[derive(Debug)]
+    struct Sleepy;
+
+    #[async_trait::async_trait]
+    impl ComputeFunction for Sleepy {
+        fn name(&self) -> &'static str {
+            "sleepy"
+        }
+
+        async fn receive_request(
+            &self,
+            _request: &ComputeRequest,
+        ) -> Result<ComputeResponse, crate::BadRequestError> {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            Ok(ComputeResponse::ok())
+        }
+    }
+
+    #[tokio::test]
+    async fn builds_a_configured_manager() {
+        let builder = ComputeFunctionManager::builder()
+            .with_builtin(BuiltinFunction::Logger)
+            .with_builtin(BuiltinFunction::Logger)
+            .default_timeout(Duration::from_millis(10));
+        assert_send(&builder);
+        let build = builder.build();
+        assert_send(&build);
+        let mut manager = build.await.unwrap();
+        manager.load_builtin_instance(Box::new(Sleepy));
+
+        let logger = ComputeRequest::new(TargetComputeFunc::new("logger".to_string()), json!("hi"));
+        assert!(manager.push_request(&logger).await.is_ok());
+        let sleepy = ComputeRequest::new(TargetComputeFunc::new("sleepy".to_string()), json!(null));
+        assert!(matches!(
+            manager.push_request(&sleepy).await,
+            Err(AppError::Timeout { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn build_fails_with_the_first_loading_error() {
+        let builder = unsafe {
+            ComputeFunctionManager::builder()
+                .with_library("relative/libmissing.so")
+                .with_library("/definitely/not/libthere.so")
+        };
+
+        let err = builder.build().await.unwrap_err();
+        assert!(
+            matches!(err, AppError::Loading(LoadingError::PathNotAbsolute(_))),
+            "{:?}",
+            err
+        );
+    }
+}
@@ -4,24 +4,159 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
 
+use chrono::{DateTime, Utc};
 use libloading::{Library, Symbol};
-use tokio::sync::Mutex;
+use tracing::{debug, warn};
 
+use super::{
+    aliases::Aliases,
+    budget::{serialized_len, PayloadBudget, PayloadReservation},
+    builder::ComputeFunctionManagerBuilder,
+    drain::DrainState,
+    manifest::{Manifest, ManifestEntry},
+    openapi,
+    queue::RequestQueue,
+    replay::{NonceCheck, ReplayGuard},
+    sampling::CallLogSampler,
+    stats::CallStats,
+    subprocess::SubprocessFunction,
+    target_ops::{TargetOp, TargetOps},
+};
 use crate::{
     core::types::{
-        AppError, AppResult, ComputeFunction, ComputeRequest, ComputeResponse, LoadingError,
-        TargetComputeFunc, UnloadingError,
+        AppError, AppResult, AuthPolicy, BadRequestError, BodyStream, Busy, ComputeFunction,
+        ComputeRequest, ComputeResponse, EventSink, FunctionDescription, FunctionInfo,
+        FunctionOrigin, FunctionStats, GenericStatusCode, HealthStatus, InputLimits, LoadOutcome,
+        LoadingError, ManagerEvent, RequestContext, ResponseSink, TargetComputeFunc,
+        UnloadingError,
+    },
+    functions::{BuiltinFunction, BuiltinFunctionList, BuiltinRegistry},
+    util::{
+        lock_order::{OrderedMutex, OrderedRwLock},
+        schema::{self, CompiledSchema},
     },
-    functions::{BuiltinFunction, BuiltinFunctionList},
 };
 
-#[derive(Debug, Default)]
+/// A dynamically loaded library along with the names of the functions that were created from it,
+/// which have to be gone before the library can safely be unloaded.
+#[derive(Debug)]
+struct LoadedLibrary {
+    path: String,
+    functions: Vec<String>,
+    library: Arc<Library>,
+}
+
+/// A function resolved for a call that outlives the lock on the function map, along with the
+/// library its code lives in, if it came from one. Fields are dropped in order, so the function is
+/// gone before the library can be freed.
+struct PinnedFunction {
+    function: Arc<dyn ComputeFunction>,
+    _library: Option<Arc<Library>>,
+}
+
+/// A function registered with the manager, along with when it was registered, where it came from
+/// and its compiled [`ComputeFunction::input_schema`].
+#[derive(Debug)]
+struct RegisteredFunction {
+    function: Arc<dyn ComputeFunction>,
+    loaded_at: DateTime<Utc>,
+    input_schema: Option<CompiledSchema>,
+    /// Where the function came from, unless it came from a [`LoadedLibrary`], which tracks its own
+    /// functions.
+    origin: FunctionOrigin,
+    /// Which builtin the function is, if it was created from a [`BuiltinFunction`], whatever name
+    /// it is registered under.
+    builtin: Option<BuiltinFunction>,
+}
+
+impl RegisteredFunction {
+    fn new(function: Box<dyn ComputeFunction>) -> Self {
+        Self {
+            input_schema: function
+                .input_schema()
+                .as_ref()
+                .map(CompiledSchema::compile),
+            function: Arc::from(function),
+            loaded_at: Utc::now(),
+            origin: FunctionOrigin::Builtin,
+            builtin: None,
+        }
+    }
+
+    /// A new instance of the builtin `kind`.
+    fn builtin(kind: BuiltinFunction) -> Self {
+        Self {
+            builtin: Some(kind),
+            ..Self::new(kind.create())
+        }
+    }
+
+    /// Record that the function came from `origin` rather than being a builtin.
+    fn with_origin(mut self, origin: FunctionOrigin) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    /// Check the data of `request` against the function's input schema, if it declared one.
+    fn check_input_schema(&self, request: &ComputeRequest) -> Result<(), BadRequestError> {
+        match &self.input_schema {
+            Some(schema) => schema.validate(request.data()).map_err(|violations| {
+                BadRequestError::new(
+                    self.function.name(),
+                    &format!(
+                        "Request data violates the input schema: {}",
+                        violations.join("; ")
+                    ),
+                    Some(request.clone()),
+                )
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug)]
 pub struct ComputeFunctionManager {
-    functions: Mutex<HashMap<String, Box<dyn ComputeFunction>>>,
-    loaded_libraries: Mutex<Vec<Library>>,
-    builtins: Mutex<BuiltinFunctionList>,
+    // Lock order: a target's operation lock (see `target_ops`) before `builtins` before `functions`
+    // before `loaded_libraries`, checked by the `deadlock-detect` feature.
+    functions: OrderedRwLock<HashMap<String, RegisteredFunction>>,
+    aliases: Aliases,
+    loaded_libraries: OrderedMutex<Vec<LoadedLibrary>>,
+    builtins: OrderedMutex<BuiltinFunctionList>,
+    target_ops: TargetOps,
+    queue: Option<RequestQueue>,
+    payload_budget: Option<PayloadBudget>,
+    call_log: CallLogSampler,
+    call_stats: CallStats,
+    total_requests: AtomicU64,
+    drain: DrainState,
+    auth_policy: Option<Box<dyn AuthPolicy>>,
+    event_sink: Option<EventSink>,
+    replay_guard: Option<ReplayGuard>,
+    input_limits: Option<InputLimits>,
+    base_dir: Option<PathBuf>,
+    slow_threshold: Option<Duration>,
+    error_rate_threshold: Option<f64>,
+    default_timeout: Option<Duration>,
+    strict_output_validation: bool,
+    no_content_as_empty_object: bool,
+    allow_legacy_plugins: bool,
+}
+
+impl Default for ComputeFunctionManager {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ComputeFunctionManager {
@@ -29,18 +164,46 @@ impl ComputeFunctionManager {
     #[must_use]
     pub fn new() -> Self {
         Self {
-            functions: Mutex::default(),
-            loaded_libraries: Mutex::default(),
-            builtins: Mutex::default(),
+            functions: OrderedRwLock::new("functions", HashMap::new()),
+            aliases: Aliases::default(),
+            loaded_libraries: OrderedMutex::new("loaded_libraries", Vec::new()),
+            target_ops: TargetOps::default(),
+            builtins: OrderedMutex::new("builtins", BuiltinFunctionList::new()),
+            queue: None,
+            payload_budget: None,
+            call_log: CallLogSampler::default(),
+            call_stats: CallStats::default(),
+            total_requests: AtomicU64::new(0),
+            drain: DrainState::default(),
+            auth_policy: None,
+            event_sink: None,
+            replay_guard: None,
+            input_limits: None,
+            base_dir: None,
+            slow_threshold: None,
+            error_rate_threshold: None,
+            default_timeout: None,
+            strict_output_validation: false,
+            no_content_as_empty_object: false,
+            allow_legacy_plugins: false,
         }
     }
 
+    /// Start building a [`ComputeFunctionManager`] that also needs dynamic libraries loaded or other
+    /// configuration, see [`ComputeFunctionManagerBuilder`].
+    #[must_use]
+    pub fn builder() -> ComputeFunctionManagerBuilder {
+        ComputeFunctionManagerBuilder::new()
+    }
+
     /// Create a new [`ComputeFunctionManager`] with the builtin logger already added.
     #[must_use]
     pub fn with_logger() -> Self {
         let mut manager = Self::new();
-        let logger = BuiltinFunction::Logger.create();
-        manager.load_builtin_instance(logger);
+        manager.functions.get_mut().insert(
+            BuiltinFunction::Logger.name().to_string(),
+            RegisteredFunction::builtin(BuiltinFunction::Logger),
+        );
         manager
     }
 
@@ -48,12 +211,254 @@ impl ComputeFunctionManager {
     #[must_use]
     pub fn with_builtins(funcs: &BuiltinFunctionList) -> Self {
         let mut manager = Self::new();
-        for func in funcs.create_all() {
-            manager.load_builtin_instance(func);
-        }
+        manager.functions.get_mut().extend(
+            funcs
+                .iter()
+                .map(|kind| (kind.name().to_string(), RegisteredFunction::builtin(kind))),
+        );
+        manager
+    }
+
+    /// Create a new [`ComputeFunctionManager`] with an instance of every function in the given
+    /// [`BuiltinRegistry`], each reachable under the name it was registered with.
+    #[must_use]
+    pub fn with_registry(registry: &BuiltinRegistry) -> Self {
+        let mut manager = Self::new();
+        manager.functions.get_mut().extend(
+            registry
+                .create_all()
+                .into_iter()
+                .map(|(name, function)| (name, RegisteredFunction::new(function))),
+        );
         manager
     }
 
+    /// Limit the number of requests that can be executing at once to `max_in_flight`. Once saturated,
+    /// further requests wait in a priority queue ordered by [`ComputeRequest::priority`], so
+    /// interactive requests can jump ahead of bulk ones.
+    pub fn set_max_in_flight(&mut self, max_in_flight: usize) {
+        self.queue = Some(RequestQueue::new(max_in_flight));
+    }
+
+    /// Cap the combined serialized size of the [`ComputeRequest::data`] of all in-flight requests at
+    /// `max_bytes`. Requests that would go over the budget are shed with [`AppError::Overloaded`] rather
+    /// than queued, so a burst of large payloads can't exhaust the process' memory. A single payload
+    /// larger than `max_bytes` can never fit and is rejected with [`AppError::PayloadTooLarge`].
+    pub fn set_max_in_flight_bytes(&mut self, max_bytes: usize) {
+        self.payload_budget = Some(PayloadBudget::new(max_bytes));
+    }
+
+    /// Log a warning for every call that takes longer than `threshold` to finish. Unlike a request
+    /// deadline this never fails the request, it only surfaces calls that are slower than expected.
+    pub fn set_slow_threshold(&mut self, threshold: Duration) {
+        self.slow_threshold = Some(threshold);
+    }
+
+    /// Report functions as [`HealthStatus::Unhealthy`] from
+    /// [`ComputeFunctionManager::health_check`] once more than a `threshold` fraction (`0.0` to
+    /// `1.0`) of their last 100 calls failed, even if they report themselves as healthy. This
+    /// catches plugins that fail silently.
+    pub fn set_error_rate_threshold(&mut self, threshold: f64) {
+        self.error_rate_threshold = Some(threshold.clamp(0.0, 1.0));
+    }
+
+    /// Give up on functions that take longer than `timeout` to answer requests sent with
+    /// [`ComputeFunctionManager::push_request`] or [`ComputeFunctionManager::try_push_request`], as
+    /// if they had been sent with [`ComputeFunctionManager::push_request_timeout`].
+    pub fn set_default_timeout(&mut self, timeout: Duration) {
+        self.default_timeout = Some(timeout);
+    }
+
+    /// Only log a `rate` fraction (`0.0` to `1.0`) of successful calls, for functions that don't
+    /// have their own rate. Failed calls are always logged. Defaults to logging every call.
+    pub fn set_call_log_sample_rate(&mut self, rate: f64) {
+        self.call_log.set_global_rate(rate);
+    }
+
+    /// Only log a `rate` fraction (`0.0` to `1.0`) of successful calls to `function`, overriding the
+    /// global rate. Failed calls are always logged.
+    pub fn set_function_call_log_sample_rate(&mut self, function: &str, rate: f64) {
+        self.call_log.set_function_rate(function, rate);
+    }
+
+    /// Consult `policy` before dispatching every request, rejecting the ones it denies with
+    /// [`AppError::Forbidden`].
+    pub fn set_auth_policy(&mut self, policy: impl AuthPolicy + 'static) {
+        self.auth_policy = Some(Box::new(policy));
+    }
+
+    /// Report every [`ManagerEvent`] to `sink`: loads (including failed ones), unloads, and the
+    /// outcome of every request, including requests that were rejected before reaching a function.
+    /// `sink` is called on the task that caused the event, after the manager's locks have been
+    /// released, so it should be quick.
+    pub fn set_event_sink(&mut self, sink: impl Fn(ManagerEvent) + Send + Sync + 'static) {
+        self.event_sink = Some(EventSink::new(sink));
+    }
+
+    /// Require every request to carry a [`RequestContext::nonce`](crate::RequestContext::nonce) and
+    /// reject any nonce that was already used within the last `window` with [`AppError::Replayed`].
+    /// At most `capacity` nonces are remembered, and while that many are, requests with new nonces
+    /// are turned away with [`AppError::Overloaded`] until the oldest expire.
+    pub fn set_replay_protection(&mut self, window: std::time::Duration, capacity: usize) {
+        self.replay_guard = Some(ReplayGuard::new(window, capacity));
+    }
+
+    /// Check the request's nonce against the replay guard, if one is configured.
+    /// Reserve room in the payload budget (if any) for the data of `request`. A payload that could
+    /// never fit is rejected with [`AppError::PayloadTooLarge`], while one that only has to wait for
+    /// other requests to finish is shed with [`AppError::Overloaded`].
+    fn reserve_payload(
+        &self,
+        request: &ComputeRequest,
+    ) -> AppResult<Option<PayloadReservation<'_>>> {
+        self.payload_budget
+            .as_ref()
+            .map(|budget| {
+                let bytes = serialized_len(request.data());
+                if bytes > budget.max_bytes() {
+                    return Err(AppError::PayloadTooLarge(format!(
+                        "Payload of {} bytes is larger than the in-flight budget of {} bytes",
+                        bytes,
+                        budget.max_bytes()
+                    )));
+                }
+                budget
+                    .try_reserve_bytes(bytes)
+                    .ok_or_else(|| AppError::overloaded("In-flight payload budget exhausted"))
+            })
+            .transpose()
+    }
+
+    fn check_replay(&self, request: &ComputeRequest) -> AppResult<()> {
+        let guard = match &self.replay_guard {
+            Some(guard) => guard,
+            None => return Ok(()),
+        };
+        match request.context().nonce() {
+            Some(nonce) => match guard.check(nonce) {
+                NonceCheck::Fresh => Ok(()),
+                NonceCheck::Replayed => Err(AppError::Replayed(nonce.to_string())),
+                NonceCheck::Full => Err(AppError::overloaded(
+                    "Too many recent nonces to remember another",
+                )),
+            },
+            None => Err(BadRequestError::new(
+                "ComputeFunctionManager",
+                "Request nonce required",
+                Some(request.clone()),
+            )
+            .into()),
+        }
+    }
+
+    /// Reject requests whose data exceeds `limits` before they are dispatched, for every function.
+    /// Functions can declare stricter limits of their own with [`ComputeFunction::input_limits`].
+    pub fn set_input_limits(&mut self, limits: InputLimits) {
+        self.input_limits = Some(limits);
+    }
+
+    /// Turn `NoContent` responses with status `Ok` into `Json` responses with an empty object (`{}`)
+    /// body, for clients that can't handle an empty `200`. Off by default.
+    pub fn set_no_content_as_empty_object(&mut self, enabled: bool) {
+        self.no_content_as_empty_object = enabled;
+    }
+
+    /// Load plugins that don't export `_plugin_abi_version` at all, which predate the check against
+    /// [`PLUGIN_ABI_VERSION`](crate::PLUGIN_ABI_VERSION). Off by default, since nothing is known
+    /// about the interface such a plugin expects. Plugins built against another version are rejected
+    /// either way.
+    pub fn set_allow_legacy_plugins(&mut self, allow: bool) {
+        self.allow_legacy_plugins = allow;
+    }
+
+    /// Resolve relative paths given to [`ComputeFunctionManager::load_plugin`] against `dir` instead
+    /// of rejecting them. Absolute paths are not affected. Relative paths can't be used to load
+    /// libraries from outside of `dir`, whether through `..` or symlinks.
+    pub fn set_base_dir(&mut self, dir: impl Into<PathBuf>) {
+        self.base_dir = Some(dir.into());
+    }
+
+    /// Turn the path given to [`ComputeFunctionManager::load_plugin`] into an absolute one, using the
+    /// base directory for relative paths if one is configured.
+    fn resolve_library_path(&self, library_path: &str) -> Result<PathBuf, LoadingError> {
+        let path = Path::new(library_path);
+        if path.is_absolute() {
+            return Ok(path.to_path_buf());
+        }
+
+        let base = self
+            .base_dir
+            .as_ref()
+            .ok_or_else(|| LoadingError::path_not_absolute(&library_path))?;
+        if Self::escapes_dir(path) {
+            return Err(LoadingError::path_outside_base_dir(&library_path));
+        }
+        let not_found = |e: std::io::Error| {
+            LoadingError::path_not_found(&format!(
+                "Path `{}` could not be resolved against `{}`: {}",
+                library_path,
+                base.display(),
+                e
+            ))
+        };
+        let resolved = base.join(path).canonicalize().map_err(not_found)?;
+        // Symlinks can still lead elsewhere, so check where the path actually ended up.
+        if !resolved.starts_with(base.canonicalize().map_err(not_found)?) {
+            return Err(LoadingError::path_outside_base_dir(&library_path));
+        }
+
+        Ok(resolved)
+    }
+
+    /// Whether the relative `path` climbs out of the directory it is relative to through `..`,
+    /// judging by its components alone.
+    fn escapes_dir(path: &Path) -> bool {
+        let mut depth = 0_usize;
+        for component in path.components() {
+            match component {
+                std::path::Component::Normal(_) => depth += 1,
+                std::path::Component::ParentDir if depth == 0 => return true,
+                std::path::Component::ParentDir => depth -= 1,
+                _ => {}
+            }
+        }
+        false
+    }
+
+    /// Check the request data against the manager's and the function's [`InputLimits`].
+    fn check_input_limits(
+        &self,
+        plugin: &dyn ComputeFunction,
+        request: &ComputeRequest,
+    ) -> Result<(), BadRequestError> {
+        for limits in self.input_limits.iter().chain(plugin.input_limits().iter()) {
+            if let Err(msg) = limits.check(request.data()) {
+                return Err(BadRequestError::new(
+                    plugin.name(),
+                    &msg,
+                    Some(request.clone()),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check the request against the configured [`AuthPolicy`], if there is one.
+    fn authorize(&self, request: &ComputeRequest) -> AppResult<()> {
+        match &self.auth_policy {
+            Some(policy) if !policy.authorize(request.context(), request.target()) => {
+                Err(AppError::Forbidden(request.target().clone()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Validate every response against its function's [`ComputeFunction::output_schema`], even in
+    /// release builds. Debug builds always validate.
+    pub const fn set_strict_output_validation(&mut self, strict: bool) {
+        self.strict_output_validation = strict;
+    }
+
     /// Internal function to create and add a [`BuiltinFunction`] to the [`ComputeFunctionManager`]. Takes a mutable
     /// reference so it's harder to use but safer (presumably?). Intended to be used when the manager is initialized.
     pub(crate) fn init_builtin_instance<F: FnOnce() -> Option<Box<dyn ComputeFunction>>>(
@@ -63,51 +468,72 @@ impl ComputeFunctionManager {
         if let Some(inst) = creator() {
             self.functions
                 .get_mut()
-                .insert(inst.name().to_string(), inst);
+                .insert(inst.name().to_string(), RegisteredFunction::new(inst));
         }
     }
 
     /// Internal function to add a [`BuiltinFunction`] instance to the [`ComputeFunctionManager`]. Takes a mutable
     /// reference so it's harder to use but safer (presumably?). Intended to be used when the manager is initialized.
     pub(crate) fn load_builtin_instance(&mut self, instance: Box<dyn ComputeFunction>) {
-        self.functions
-            .get_mut()
-            .insert(instance.name().to_string(), instance);
+        self.functions.get_mut().insert(
+            instance.name().to_string(),
+            RegisteredFunction::new(instance),
+        );
     }
 
     /// Load a built-in (hardcoded) plugin indicated by the given [`BuiltinFunction`] `kind`. This is safe
     /// as it requires no dynamic loading.
-    pub async fn load_builtin_function(&self, kind: BuiltinFunction) -> Result<bool, LoadingError> {
+    ///
+    /// ## Returns
+    /// [`LoadOutcome::Loaded`] if the builtin was added, [`LoadOutcome::AlreadyPresent`] if it was
+    /// already loaded.
+    ///
+    /// ## Errors
+    /// None currently, the [`LoadingError`] is reserved for builtins that may fail to initialize.
+    pub async fn load_builtin_function(
+        &self,
+        kind: BuiltinFunction,
+    ) -> Result<LoadOutcome, LoadingError> {
         {
             let mut lock = self.builtins.lock().await;
             if !lock.add(kind) {
-                return Ok(false);
+                return Ok(LoadOutcome::AlreadyPresent(kind.name().to_string()));
             }
         }
 
         {
-            let mut lock = self.functions.lock().await;
-            let func = kind.create();
-            lock.insert(func.name().to_string(), func);
+            let mut lock = self.functions.write().await;
+            lock.insert(kind.name().to_string(), RegisteredFunction::builtin(kind));
         }
 
-        Ok(true)
+        Ok(LoadOutcome::Loaded(kind.name().to_string()))
     }
 
     /// Loads a [`ComputeFunction`] plugin from a `cdylib` dll at the given path.
     ///
     /// ## Arguments
-    /// - `arg_name` - Argument description
+    /// - `library_path` - The path to the library to load, which must be absolute unless a base
+    ///   directory was set with [`ComputeFunctionManager::set_base_dir`]
+    ///
     /// ## Returns
-    /// This function returns something probably
+    /// [`LoadOutcome::Loaded`] with the name the new [`ComputeFunction`] was registered under, which
+    /// can be used as the target of any following [`ComputeRequest`]s. If the library at the same
+    /// (resolved) path is already loaded nothing is loaded again, and [`LoadOutcome::AlreadyPresent`]
+    /// is returned with the name its function is registered under.
     ///
     /// ## Errors
     /// Function potentially returns the following errors in the described situations:
-    /// - [`LoadingError::BadPath`] if the given path is malformed **OR NOT ABSOLUTE**
-    /// - [`LoadingError::PathNotFound`] if the given path does not exist
+    /// - [`LoadingError::PathNotAbsolute`] if the given path is relative and there is no base directory
+    /// - [`LoadingError::PathOutsideBaseDir`] if the given path is relative and resolves to outside of
+    ///   the base directory
+    /// - [`LoadingError::BadPath`] if the existence of the given path can't be checked
+    /// - [`LoadingError::PathNotFound`] if the given path does not exist (or can't be resolved against
+    ///   the base directory)
     /// - [`LoadingError::LibraryLoadFailure`] if a [`libloading::Library`] cannot be loaded from the given path
     /// - [`LoadingError::ConstructorLoadFailure`] if the [`libloading::Symbol`] `_plugin_create` cannot be found in the loaded library
     /// - [`LoadingError::ConstructorCallFailure`] if the `_plugin_create` function returns a null pointer
+    /// - [`LoadingError::FunctionNameCollision`] if a function from another library or a builtin is
+    ///   already registered under the plugin's name
     ///
     /// ## Safety
     /// The unsafe nature of this function stems from 4 calls and, due to the nature of dynamically loading
@@ -121,18 +547,75 @@ impl ComputeFunctionManager {
     /// ```ignore
     /// /// TODO Write examples
     /// ```
-    pub async unsafe fn load_plugin(&self, library_path: String) -> Result<(), LoadingError> {
-        type CfCtor = unsafe fn() -> *mut dyn ComputeFunction;
+    pub async unsafe fn load_plugin(
+        &self,
+        library_path: String,
+    ) -> Result<LoadOutcome, LoadingError> {
+        // SAFETY: Forwarded from the caller.
+        unsafe { self.load_plugin_observed(library_path) }
+            .await
+            .map(|(outcome, _)| outcome)
+    }
 
-        // Validate Path
-        let path = std::path::Path::new(&library_path);
-        if !path.is_absolute() {
-            return Err(LoadingError::bad_path(&format!(
-                "Path `{}` is not absolute.",
-                library_path
-            )));
+    /// [`ComputeFunctionManager::load_plugin`], but describing the function that was loaded (or was
+    /// already loaded from the same library) rather than just naming it.
+    ///
+    /// ## Returns
+    /// The [`FunctionInfo`] of the function, which carries the name it is registered under, the
+    /// absolute path of its library and any aliases it has, the same as
+    /// [`ComputeFunctionManager::list_functions`] would report.
+    ///
+    /// ## Errors
+    /// The same as [`ComputeFunctionManager::load_plugin`].
+    ///
+    /// ## Safety
+    /// The same as [`ComputeFunctionManager::load_plugin`].
+    pub async unsafe fn load_plugin_info(
+        &self,
+        library_path: String,
+    ) -> Result<FunctionInfo, LoadingError> {
+        // SAFETY: Forwarded from the caller.
+        unsafe { self.load_plugin_observed(library_path) }
+            .await
+            .map(|(_, info)| info)
+    }
+
+    /// Loads the plugin at `library_path` and reports the outcome to the event sink.
+    ///
+    /// ## Safety
+    /// See [`ComputeFunctionManager::load_plugin`].
+    async unsafe fn load_plugin_observed(
+        &self,
+        library_path: String,
+    ) -> Result<(LoadOutcome, FunctionInfo), LoadingError> {
+        // SAFETY: Forwarded from the caller.
+        let result = unsafe { self.load_plugin_unobserved(library_path.clone()) }.await;
+        match &result {
+            Ok((LoadOutcome::Loaded(name), _)) => self.emit(|| ManagerEvent::Loaded {
+                name: name.clone(),
+                path: library_path,
+            }),
+            Ok((LoadOutcome::AlreadyPresent(_), _)) => {}
+            Err(error) => self.emit(|| ManagerEvent::LoadFailed {
+                path: library_path,
+                error: error.clone(),
+            }),
         }
-        match std::fs::try_exists(path) {
+        result
+    }
+
+    /// The body of [`ComputeFunctionManager::load_plugin`], without the [`ManagerEvent`].
+    ///
+    /// ## Safety
+    /// See [`ComputeFunctionManager::load_plugin`].
+    async unsafe fn load_plugin_unobserved(
+        &self,
+        library_path: String,
+    ) -> Result<(LoadOutcome, FunctionInfo), LoadingError> {
+        // Validate Path
+        let path = self.resolve_library_path(&library_path)?;
+        let library_path = path.to_string_lossy().into_owned();
+        match std::fs::try_exists(&path) {
             Ok(true) => (),
             Ok(false) => {
                 return Err(LoadingError::path_not_found(&format!(
@@ -149,157 +632,3740 @@ impl ComputeFunctionManager {
             }
         };
 
-        // Unsafely load the plugin from the library
-        let plugin = unsafe {
-            // Attempt to load library from given path
-            let lib = Library::new(path).map_err(|err| LoadingError::lib_load_failure(&err))?;
-
-            // This "dance" is required to create a long-lived pointer to the library,
-            // if the library goes out of scope our plugin becomes invalid. I am not worried
-            // about the `expect` call here since something would need to be very wrong
-            // for it to fail.
-            {
-                let mut lock = self.loaded_libraries.lock().await;
-                lock.push(lib);
-            }
-
-            // self.loaded_libraries.get_mut().push(lib);
-            // let lib = self.loaded_libraries
-            // .get_mut()
-            // .last()
-            // .expect("This error should not happen, we are trying to get the last element of an array we just pushed to, so something is very wrong.");
-
-            // Get the expected constructor function from the library
-            let lib_lock = self.loaded_libraries.lock().await;
+        let functions = self.functions.read().await;
+        let libraries = self.loaded_libraries.lock().await;
+        let already_loaded = libraries
+            .iter()
+            .find(|lib| lib.path == library_path)
+            .and_then(|lib| lib.functions.first())
+            .and_then(|name| {
+                let registered = functions.get(name)?;
+                Some((name.clone(), self.info_of(name, registered, &libraries)))
+            });
+        drop(libraries);
+        drop(functions);
+        if let Some((name, info)) = already_loaded {
+            return Ok((LoadOutcome::AlreadyPresent(name), info));
+        }
 
-            let constructor: Symbol<CfCtor> = lib_lock
-                .last()
-                .unwrap()
-                .get(b"_plugin_create")
-                .map_err(|err| LoadingError::ctor_load_failure(&err))?;
+        // Attempt to load library from given path
+        let lib =
+            unsafe { Library::new(&path) }.map_err(|err| LoadingError::lib_load_failure(&err))?;
+        unsafe { self.check_abi_version(&lib) }?;
 
-            // Unsafely call the constructor function to create a new plugin
-            let boxed_raw = constructor();
-            // Ensure resulting object is not null
-            if boxed_raw.is_null() {
-                return Err(LoadingError::ctor_call_failure());
-            }
-            // Box the raw pointer for safe use
-            Box::from_raw(boxed_raw)
-        };
+        // Unsafely load the plugin from the library. The library has to outlive the plugin, so it is
+        // only moved into the list of loaded libraries once the plugin has been registered, and if
+        // anything fails before then the plugin is dropped first since it is declared after `lib`.
+        let plugin = unsafe { Self::construct_plugin(&lib) }?;
 
         let plugin_name = plugin.name();
-        {
-            let fn_lock = self.functions.lock().await;
-            if fn_lock.contains_key(plugin_name) {
-                // Name collisions are not allowed, first come first serve
-                return Err(LoadingError::name_collision(&plugin_name));
-            }
+        // Checked and registered under the same lock, so concurrent loads of the same name can't
+        // both get past the check.
+        let mut functions = self.functions.write().await;
+        if functions.contains_key(plugin_name) || self.aliases.contains(plugin_name) {
+            // Name collisions are not allowed, first come first serve
+            return Err(LoadingError::name_collision(&plugin_name));
         }
         // Allow plugin to initialize itself if necessary
         plugin.on_plugin_load();
-        let mut add_lock = self.functions.lock().await;
-        add_lock.insert(plugin_name.to_string(), plugin);
+        let registered = RegisteredFunction::new(plugin);
+        let info = FunctionInfo::new(
+            plugin_name,
+            FunctionOrigin::Dynamic {
+                path: library_path.clone(),
+            },
+            registered.loaded_at,
+        );
+        functions.insert(plugin_name.to_string(), registered);
+        self.loaded_libraries.lock().await.push(LoadedLibrary {
+            path: library_path,
+            functions: vec![plugin_name.to_string()],
+            library: Arc::new(lib),
+        });
+        drop(functions);
 
-        Ok(())
+        Ok((LoadOutcome::Loaded(plugin_name.to_string()), info))
     }
 
-    /// Unloads a [`ComputeFunction`] plugin from the manager.
+    /// Loads a [`ComputeFunction`] plugin from a WebAssembly module at the given path. Unlike
+    /// [`ComputeFunctionManager::load_plugin`] this is safe: the module runs sandboxed, without
+    /// access to anything but its own memory, so a broken or mismatched module can only fail its
+    /// own requests.
+    ///
+    /// The module gets no imports and has to export:
+    /// - `memory` - Its linear memory.
+    /// - `alloc(len: i32) -> i32` - Reserve `len` bytes for a request, returning a pointer to them.
+    ///   Only one request is handed to a module at a time, so it can reuse the same buffer.
+    /// - `name() -> i64` - The name of the function.
+    /// - `receive_request(ptr: i32, len: i32) -> i64` - Handle the JSON serialized
+    ///   [`ComputeRequest`] at `ptr`, answering with a JSON serialized [`ComputeResponse`].
+    ///
+    /// Strings returned to the host are packed into an `i64`, the pointer in the upper 32 bits and
+    /// the length in the lower 32.
     ///
     /// ## Arguments
-    /// - `target` - The target [`ComputeFunction`] to unload
+    /// - `path` - The path to the `.wasm` (or `.wat`) module, which must be absolute unless a base
+    ///   directory was set with [`ComputeFunctionManager::set_base_dir`]
     ///
     /// ## Returns
-    /// A result containing nothing upon success or an [`UnloadingError`] upon failure.
+    /// The [`FunctionInfo`] of the function, registered under the name the module reported.
     ///
     /// ## Errors
-    /// - Function errors if the target [`ComputeFunction`] is not found in the manager
+    /// An [`AppError::Loading`] with:
+    /// - [`LoadingError::PathNotAbsolute`] or [`LoadingError::PathOutsideBaseDir`] if the path can't
+    ///   be resolved, the same as for [`ComputeFunctionManager::load_plugin`]
+    /// - [`LoadingError::LibraryLoadFailure`] if the module can't be read, compiled or instantiated
+    /// - [`LoadingError::ConstructorLoadFailure`] if the module is missing one of the exports it
+    ///   needs or its `name` export fails
+    /// - [`LoadingError::FunctionNameCollision`] if a function is already registered under the name
+    ///   the module reported
+    #[cfg(feature = "wasm")]
+    pub async fn load_wasm_plugin(&self, path: impl AsRef<Path>) -> AppResult<FunctionInfo> {
+        let path = self.resolve_library_path(&path.as_ref().to_string_lossy())?;
+        let module_path = path.to_string_lossy().into_owned();
+        // Compiling a module can take a while, so keep it off of the async workers.
+        let function = tokio::task::spawn_blocking(move || super::wasm::WasmFunction::load(&path))
+            .await
+            .map_err(|e| LoadingError::lib_load_failure(&e))??;
+
+        self.register_loaded(
+            Box::new(function),
+            FunctionOrigin::Wasm {
+                path: module_path.clone(),
+            },
+            module_path,
+        )
+        .await
+    }
+
+    /// Registers a [`ComputeFunction`] that runs `command` with `args` as a separate process for
+    /// every request. The serialized [`ComputeRequest`] is written to the child's stdin as a single
+    /// line of JSON, and the first line it writes to its stdout is read back as the serialized
+    /// [`ComputeResponse`]. This keeps untrusted code out of the server's address space: a child
+    /// that can't be started, exits unsuccessfully, answers with something other than a response or
+    /// takes longer than 30 seconds (in which case it is killed) only fails the request it was
+    /// handling, with a [`BadRequestError`].
     ///
-    /// ## Example(s)
-    /// ```ignore
-    /// /// TODO Write examples
-    /// ```
-    pub async fn unload_plugin(&self, target: &TargetComputeFunc) -> Result<(), UnloadingError> {
-        let mut fn_locked = self.functions.lock().await;
-        fn_locked.remove(target.name()).map_or_else(
-            || Err(UnloadingError::TargetNotFound(target.clone())),
-            |plugin| {
-                plugin.on_plugin_unload();
-                Ok(())
+    /// ## Returns
+    /// The [`FunctionInfo`] of the function, registered under `name`.
+    ///
+    /// ## Errors
+    /// An [`AppError::Loading`] with [`LoadingError::FunctionNameCollision`] if a function is already
+    /// registered under `name`. The command isn't run until the first request, so a missing
+    /// executable is only reported then.
+    pub async fn load_subprocess(
+        &self,
+        name: &str,
+        command: &str,
+        args: Vec<String>,
+    ) -> AppResult<FunctionInfo> {
+        let origin = FunctionOrigin::Subprocess {
+            command: command.to_string(),
+            args: args.clone(),
+        };
+        self.register_loaded(
+            Box::new(SubprocessFunction::new(name, command, args)),
+            origin,
+            command.to_string(),
+        )
+        .await
+    }
+
+    /// Registers a [`ComputeFunction`] that forwards every request to a remote HTTP endpoint: the
+    /// request data is `POST`ed to `upstream_url` as JSON, and the upstream's status and body are
+    /// answered with, so remote functions can be called the same way as local ones. An upstream
+    /// that can't be reached or takes longer than 30 seconds to answer is answered with a
+    /// `502 Bad Gateway`.
+    ///
+    /// ## Returns
+    /// The [`FunctionInfo`] of the function, registered under `name`.
+    ///
+    /// ## Errors
+    /// An [`AppError::Loading`] with [`LoadingError::FunctionNameCollision`] if a function is already
+    /// registered under `name`. The upstream isn't contacted until the first request.
+    #[cfg(feature = "proxy")]
+    pub async fn load_proxy(&self, name: &str, upstream_url: &str) -> AppResult<FunctionInfo> {
+        self.register_loaded(
+            Box::new(super::proxy::ProxyFunction::new(name, upstream_url)),
+            FunctionOrigin::Proxy {
+                url: upstream_url.to_string(),
             },
+            upstream_url.to_string(),
         )
+        .await
     }
 
-    /// Unloads all functions **and libraries** that this [`ComputeFunctionManager`] is holding references for.
-    /// TODO: Should this method resize the containers to 0? There should only ever be once of these instances
-    ///       that lasts for the entire program so it seems unnecessary, but `drain` specifically states that
-    ///       the previously allocated memory is held.
-    /// TODO: This is the only method on this struct that is not async. I imagine async functions that are
-    ///       invoked during a [`Drop`] impl are not good practice. Research this more.
-    /// ## Example(s)
-    /// ```ignore
-    /// /// TODO Write examples
-    /// ```
-    pub fn unload_all(&mut self) {
-        for (_id, plugin) in self.functions.get_mut().drain() {
-            // trace!("Firing on_plugin_unload for {:?}", plugin.name());
-            plugin.on_plugin_unload();
+    /// Register `function`, loaded from `origin`, under its own name and report it as loaded from
+    /// `path` to the event sink.
+    ///
+    /// ## Errors
+    /// [`LoadingError::FunctionNameCollision`] if a function is already registered under the name.
+    async fn register_loaded(
+        &self,
+        function: Box<dyn ComputeFunction>,
+        origin: FunctionOrigin,
+        path: String,
+    ) -> AppResult<FunctionInfo> {
+        let name = function.name();
+        let mut functions = self.functions.write().await;
+        if functions.contains_key(name) || self.aliases.contains(name) {
+            return Err(LoadingError::name_collision(&name).into());
         }
+        function.on_plugin_load();
+        let registered = RegisteredFunction::new(function).with_origin(origin);
+        let info = self.info_of(name, &registered, &[]);
+        functions.insert(name.to_string(), registered);
+        drop(functions);
 
-        for lib in self.loaded_libraries.get_mut().drain(..) {
-            drop(lib);
-        }
+        self.emit(|| ManagerEvent::Loaded {
+            name: name.to_string(),
+            path,
+        });
+        Ok(info)
     }
 
-    /// TODO: It's just dawning on me that simply comparing the [`ComputeRequest::target`] to the map key
-    ///       is some real basic-bitch shit. I need to parse the target to allow for namespaces and sub-paths,
-    ///       and even path parameters & queries.
-    /// Sends a [`ComputeRequest`] to the [`ComputeFunction`] indicated by the request.
+    /// Writes every loaded function to a JSON manifest at `path`, replacing whatever was there, so
+    /// they can all be loaded again with [`ComputeFunctionManager::load_manifest`] after a restart.
+    /// Each function is recorded with its name and [`FunctionOrigin`], which holds the absolute path
+    /// of its library or module, or whatever else is needed to load it again.
     ///
-    /// ## Arguments
-    /// - `request` - The [`ComputeRequest`] to send to the currently loaded [`ComputeFunction`] plugins.
+    /// ## Errors
+    /// An [`AppError::Other`] if the manifest can't be written.
+    pub async fn save_manifest(&self, path: impl AsRef<Path>) -> AppResult<()> {
+        let renamed_builtins: HashMap<String, BuiltinFunction> = self
+            .functions
+            .read()
+            .await
+            .iter()
+            .filter_map(|(name, registered)| {
+                registered
+                    .builtin
+                    .filter(|kind| kind.name() != name)
+                    .map(|kind| (name.clone(), kind))
+            })
+            .collect();
+        let functions = self
+            .list_functions()
+            .await
+            .into_iter()
+            .map(|info| ManifestEntry {
+                name: info.name().to_string(),
+                origin: info.origin().clone(),
+                builtin: renamed_builtins.get(info.name()).map(ToString::to_string),
+            })
+            .collect();
+        Manifest::new(functions).write(path.as_ref())
+    }
+
+    /// Loads every function listed in the manifest at `path`, as written by
+    /// [`ComputeFunctionManager::save_manifest`]. Builtins are recreated by their name (or, if they
+    /// were renamed, under it), everything else is loaded again from its origin. Functions that are already loaded under the same name
+    /// (from the same library, for plugins) are left as they are.
+    ///
+    /// A function that can't be restored, because its library no longer exists or its name is taken
+    /// by another function say, is logged as a warning and skipped, it doesn't stop the rest of the
+    /// manifest from being restored.
     ///
     /// ## Returns
-    /// [`AppResult`] containing the [`ComputeResponse`] from the [`ComputeFunction`] or an [`AppError`]
-    /// indicating the type of failure that occurred.
+    /// The name of every function in the manifest, along with whether it was restored.
     ///
     /// ## Errors
-    /// - [`AppError::TargetNotFound`] if the target [`ComputeFunction`] is not found in the manager
-    /// - [`AppError::BadRequest`] if the [`ComputeRequest`] is malformed or invalid
+    /// An [`AppError::Loading`] if the manifest itself can't be read.
     ///
-    /// ## Example(s)
-    /// ```ignore
-    /// /// TODO Write examples
-    /// ```
-    pub async fn push_request(&self, request: &ComputeRequest) -> AppResult<ComputeResponse> {
-        let id = request.target().name();
+    /// ## Safety
+    /// Every plugin library in the manifest is loaded with [`ComputeFunctionManager::load_plugin`],
+    /// so they all have to hold up everything that function's safety section expects of it.
+    pub async unsafe fn load_manifest(
+        &self,
+        path: impl AsRef<Path>,
+    ) -> AppResult<Vec<(String, AppResult<()>)>> {
+        let manifest = Manifest::read(path.as_ref())?;
 
-        let plugins = self.functions.lock().await;
-        if let Some(plugin) = plugins.get(id) {
-            plugin
-                .receive_request(request)
+        let mut restored = Vec::with_capacity(manifest.functions().len());
+        for entry in manifest.into_functions() {
+            // SAFETY: The caller vouched for every library in the manifest.
+            let result = unsafe { self.restore(&entry) }.await;
+            if let Err(e) = &result {
+                warn!(
+                    "Unable to restore `{}` from the manifest: {}",
+                    entry.name, e
+                );
+            }
+            restored.push((entry.name, result));
+        }
+        Ok(restored)
+    }
+
+    /// Load the function described by a single [`ManifestEntry`] again.
+    ///
+    /// ## Safety
+    /// See [`ComputeFunctionManager::load_plugin`].
+    async unsafe fn restore(&self, entry: &ManifestEntry) -> AppResult<()> {
+        match &entry.origin {
+            FunctionOrigin::Builtin => {
+                let kind = entry
+                    .builtin
+                    .as_deref()
+                    .unwrap_or(&entry.name)
+                    .parse::<BuiltinFunction>()
+                    .map_err(|e| AppError::other(&e.to_string()))?;
+                if kind.name() == entry.name {
+                    if !self.functions.read().await.contains_key(kind.name()) {
+                        self.load_builtin_function(kind).await?;
+                    }
+                } else {
+                    let mut fn_locked = self.functions.write().await;
+                    if self.aliases.contains(&entry.name) {
+                        return Err(LoadingError::name_collision(&entry.name).into());
+                    }
+                    fn_locked
+                        .entry(entry.name.clone())
+                        .or_insert_with(|| RegisteredFunction::builtin(kind));
+                }
+                Ok(())
+            }
+            FunctionOrigin::Dynamic { path } => {
+                // SAFETY: Forwarded from the caller.
+                unsafe { self.load_plugin(path.clone()) }.await?;
+                Ok(())
+            }
+            #[cfg(feature = "wasm")]
+            FunctionOrigin::Wasm { path } => self.load_wasm_plugin(path).await.map(drop),
+            #[cfg(not(feature = "wasm"))]
+            FunctionOrigin::Wasm { .. } => Err(AppError::other(
+                "Restoring a WebAssembly plugin requires the `wasm` feature",
+            )),
+            FunctionOrigin::Subprocess { command, args } => self
+                .load_subprocess(&entry.name, command, args.clone())
                 .await
-                .map_err(std::convert::Into::into)
-        } else {
-            Err(AppError::TargetNotFound(request.target().clone()))
+                .map(drop),
+            #[cfg(feature = "proxy")]
+            FunctionOrigin::Proxy { url } => self.load_proxy(&entry.name, url).await.map(drop),
+            #[cfg(not(feature = "proxy"))]
+            FunctionOrigin::Proxy { .. } => Err(AppError::other(
+                "Restoring a proxy function requires the `proxy` feature",
+            )),
         }
     }
-}
 
-impl Drop for ComputeFunctionManager {
-    fn drop(&mut self) {
-        let has_plugins = !self.functions.get_mut().is_empty();
-        let has_libs = !self.loaded_libraries.get_mut().is_empty();
-        if has_plugins || has_libs {
-            self.unload_all();
+    /// Loads every library (files with the platform's dynamic library extension) in `dir`, in file
+    /// name order, spending at most `budget` on the whole batch. Libraries that can't be loaded
+    /// before the budget runs out are skipped with [`LoadingError::LoadTimeout`], the rest of the
+    /// batch is unaffected by them. A single library load can't be interrupted, so the last load
+    /// may overrun the budget by however long that library takes to load.
+    ///
+    /// ## Returns
+    /// The path of every library that was found, along with the outcome of
+    /// [`ComputeFunctionManager::load_plugin`] for it.
+    ///
+    /// ## Errors
+    /// - [`LoadingError::BadPath`] if `dir` can't be read
+    ///
+    /// ## Safety
+    /// Every library in `dir` is loaded with [`ComputeFunctionManager::load_plugin`], so they all have
+    /// to hold up everything that function's safety section expects of it.
+    pub async unsafe fn load_plugins_from_dir(
+        &self,
+        dir: impl AsRef<Path>,
+        budget: Duration,
+    ) -> Result<Vec<(PathBuf, Result<LoadOutcome, LoadingError>)>, LoadingError> {
+        let (libraries, unreadable) = Self::find_libraries(dir.as_ref(), false);
+        if let Some((_, err)) = unreadable.into_iter().next() {
+            return Err(err);
         }
+
+        Ok(Self::load_within_budget(libraries, budget, |path| {
+            // SAFETY: The caller vouched for every library in `dir`.
+            unsafe { self.load_plugin(path.to_string_lossy().into_owned()) }
+        })
+        .await)
     }
-}
 
-pub fn default_cfm() -> ComputeFunctionManager {
-    ComputeFunctionManager::new()
-}
+    /// Loads every library (files with the platform's dynamic library extension) in `dir`, and in
+    /// its subdirectories if `recursive` is set, one after the other in path order. Other files are
+    /// skipped, and so are symlinked directories. Nothing stops the batch: a library that can't be
+    /// loaded, e.g. because a library loaded before it already took its function's name, or a
+    /// directory that can't be read, only shows up as the error for that entry.
+    ///
+    /// ## Returns
+    /// The path of every library that was found (and of every directory that couldn't be read),
+    /// along with the outcome of [`ComputeFunctionManager::load_plugin_info`] for it (or the
+    /// [`LoadingError::BadPath`] the directory couldn't be read with), sorted by path.
+    ///
+    /// ## Safety
+    /// Every library found is loaded with [`ComputeFunctionManager::load_plugin`], so they all have
+    /// to hold up everything that function's safety section expects of it.
+    pub async unsafe fn load_plugin_dir(
+        &self,
+        dir: &Path,
+        recursive: bool,
+    ) -> Vec<(PathBuf, Result<FunctionInfo, LoadingError>)> {
+        let (libraries, unreadable) = Self::find_libraries(dir, recursive);
+        let mut outcomes = Vec::with_capacity(libraries.len() + unreadable.len());
+        for library in libraries {
+            // SAFETY: The caller vouched for every library in `dir`.
+            let outcome =
+                unsafe { self.load_plugin_info(library.to_string_lossy().into_owned()) }.await;
+            if let Err(err) = &outcome {
+                warn!("Skipping library {}: {}", library.display(), err);
+            }
+            outcomes.push((library, outcome));
+        }
+        outcomes.extend(unreadable.into_iter().map(|(path, err)| (path, Err(err))));
+        outcomes.sort_by(|(a, _), (b, _)| a.cmp(b));
 
-pub fn logger_cfm() -> ComputeFunctionManager {
-    ComputeFunctionManager::with_logger()
+        outcomes
+    }
+
+    /// The libraries (files with the platform's dynamic library extension) in `dir`, sorted, and
+    /// those in its subdirectories too if `recursive` is set, along with every directory (or
+    /// directory entry) that couldn't be read.
+    fn find_libraries(dir: &Path, recursive: bool) -> (Vec<PathBuf>, Vec<(PathBuf, LoadingError)>) {
+        let mut libraries = Vec::new();
+        let mut unreadable = Vec::new();
+        let mut pending = vec![dir.to_path_buf()];
+        while let Some(dir) = pending.pop() {
+            let entries = match std::fs::read_dir(&dir) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    unreadable.push((dir.clone(), Self::unreadable_dir(&dir, &e)));
+                    continue;
+                }
+            };
+            for entry in entries {
+                let entry = match entry {
+                    Ok(entry) => entry,
+                    Err(e) => {
+                        unreadable.push((dir.clone(), Self::unreadable_dir(&dir, &e)));
+                        continue;
+                    }
+                };
+                let path = entry.path();
+                // `file_type` doesn't follow symlinks, so symlinked directories (and any cycles
+                // through them) are never descended into.
+                if recursive && matches!(entry.file_type(), Ok(kind) if kind.is_dir()) {
+                    pending.push(path);
+                } else if path.is_file()
+                    && path.extension()
+                        == Some(std::ffi::OsStr::new(std::env::consts::DLL_EXTENSION))
+                {
+                    libraries.push(path);
+                }
+            }
+        }
+        libraries.sort();
+
+        (libraries, unreadable)
+    }
+
+    fn unreadable_dir(dir: &Path, e: &std::io::Error) -> LoadingError {
+        LoadingError::bad_path(&format!(
+            "Could not read the directory `{}`: {}",
+            dir.display(),
+            e
+        ))
+    }
+
+    /// Run `load` for each of `items` in turn until `budget` runs out, skipping the ones left over
+    /// (and giving up on one that is still loading) with [`LoadingError::LoadTimeout`].
+    async fn load_within_budget<T, F, Fut>(
+        items: Vec<PathBuf>,
+        budget: Duration,
+        mut load: F,
+    ) -> Vec<(PathBuf, Result<T, LoadingError>)>
+    where
+        F: FnMut(&Path) -> Fut,
+        Fut: std::future::Future<Output = Result<T, LoadingError>>,
+    {
+        let deadline = tokio::time::Instant::now() + budget;
+        let mut outcomes = Vec::with_capacity(items.len());
+        for item in items {
+            let timed_out = || LoadingError::load_timeout(&item.display());
+            // Polling an already expired timeout still runs the load once, so check up front.
+            let outcome = if tokio::time::Instant::now() >= deadline {
+                Err(timed_out())
+            } else {
+                tokio::time::timeout_at(deadline, load(&item))
+                    .await
+                    .unwrap_or_else(|_| Err(timed_out()))
+            };
+            if let Err(err) = &outcome {
+                warn!("Skipping library {}: {}", item.display(), err);
+            }
+            outcomes.push((item, outcome));
+        }
+
+        outcomes
+    }
+
+    /// Swaps a dynamically loaded [`ComputeFunction`] for a fresh instance loaded from the same library
+    /// path it was originally loaded from, so a rebuilt plugin can be picked up without restarting.
+    ///
+    /// The new version is fully loaded before the old one is touched, so if anything goes wrong the
+    /// old version is left registered and serving. Once it has loaded, the old plugin's
+    /// [`ComputeFunction::on_plugin_unload`] fires before the new plugin's
+    /// [`ComputeFunction::on_plugin_load`], and the old library is freed along with the old plugin.
+    /// The function's call stats carry over.
+    ///
+    /// Reloads and unloads of the same target are serialized. If an unload of `target` arrives while
+    /// it is being reloaded, the reload is abandoned (before the swap) so that the unload goes ahead.
+    ///
+    /// ## Arguments
+    /// - `target` - The target [`ComputeFunction`] to reload
+    ///
+    /// ## Errors
+    /// - [`LoadingError::NotReloadable`] if `target` is not loaded, or was not loaded from a library
+    /// - [`LoadingError::ReloadCancelled`] if `target` is being unloaded
+    /// - [`LoadingError::PathNotFound`] if the library can no longer be read from its original path
+    /// - Any of the errors that [`ComputeFunctionManager::load_plugin`] returns for a library that
+    ///   can't be loaded
+    ///
+    /// ## Safety
+    /// See [`ComputeFunctionManager::load_plugin`], the same calls are made to load the new version.
+    pub async unsafe fn reload_plugin(
+        &self,
+        target: &TargetComputeFunc,
+    ) -> Result<(), LoadingError> {
+        let op = self.target_ops.get(target.name());
+        let guard = op.lock().await;
+        let result = unsafe { self.reload_locked(target, &op) }.await;
+        drop(guard);
+        self.target_ops.release(target.name(), op);
+        result
+    }
+
+    /// The body of [`ComputeFunctionManager::reload_plugin`], run while holding the target's
+    /// operation lock.
+    async unsafe fn reload_locked(
+        &self,
+        target: &TargetComputeFunc,
+        op: &TargetOp,
+    ) -> Result<(), LoadingError> {
+        let name = target.name();
+        if op.unload_pending() {
+            return Err(LoadingError::reload_cancelled(&name));
+        }
+        let path = self
+            .loaded_libraries
+            .lock()
+            .await
+            .iter()
+            .find(|lib| lib.functions.iter().any(|function| function == name))
+            .map(|lib| lib.path.clone())
+            .ok_or_else(|| LoadingError::not_reloadable(&name))?;
+
+        // The dynamic loader hands back the already open library when asked for the same path again,
+        // so the new version is loaded from a uniquely named copy instead.
+        let file_name = Path::new(&path)
+            .file_name()
+            .map_or_else(|| name.into(), |file| file.to_string_lossy());
+        let staged = std::env::temp_dir().join(format!("{}-{}", uuid::Uuid::new_v4(), file_name));
+        std::fs::copy(&path, &staged).map_err(|e| {
+            LoadingError::path_not_found(&format!("Could not read `{}` to reload it: {}", path, e))
+        })?;
+        let lib = unsafe { Library::new(&staged) };
+        // Once the library is open the copy is no longer needed (platforms that keep it locked while
+        // it is loaded will just leave it behind in the temp directory).
+        let _ = std::fs::remove_file(&staged);
+        let lib = lib.map_err(|err| LoadingError::lib_load_failure(&err))?;
+        unsafe { self.check_abi_version(&lib) }?;
+        let plugin = unsafe { Self::construct_plugin(&lib) }?;
+        // An unload that showed up while the new version was loading wins, and the new version is
+        // thrown away (plugin first, then its library).
+        if op.unload_pending() {
+            return Err(LoadingError::reload_cancelled(&name));
+        }
+
+        let mut functions = self.functions.write().await;
+        let old = functions
+            .remove(name)
+            .ok_or_else(|| LoadingError::not_reloadable(&name))?;
+        old.function.on_plugin_unload();
+        // The old plugin has to be gone before its library is freed below.
+        drop(old);
+        plugin.on_plugin_load();
+        functions.insert(name.to_string(), RegisteredFunction::new(plugin));
+
+        let mut libraries = self.loaded_libraries.lock().await;
+        Self::release_library_of(&mut libraries, name);
+        libraries.push(LoadedLibrary {
+            path,
+            functions: vec![name.to_string()],
+            library: Arc::new(lib),
+        });
+        drop(libraries);
+        drop(functions);
+
+        Ok(())
+    }
+
+    /// Checks the `_plugin_abi_version` exported by `lib` against
+    /// [`PLUGIN_ABI_VERSION`](crate::PLUGIN_ABI_VERSION), before anything else in it is touched.
+    ///
+    /// ## Errors
+    /// - [`LoadingError::AbiMismatch`] if the versions differ, see [`compare_abi_versions`]
+    ///
+    /// ## Safety
+    /// If `lib` exports `_plugin_abi_version` it must be a function with the signature
+    /// [`declare_plugin!`](crate::declare_plugin) gives it.
+    unsafe fn check_abi_version(&self, lib: &Library) -> Result<(), LoadingError> {
+        type AbiVersion = unsafe extern "C" fn() -> u32;
+
+        let found = unsafe {
+            lib.get::<AbiVersion>(crate::core::ABI_VERSION_NAME)
+                .ok()
+                .map(|version| version())
+        };
+        compare_abi_versions(found, self.allow_legacy_plugins)
+    }
+
+    /// Creates a new [`ComputeFunction`] using the `_plugin_create` constructor exported by `lib`.
+    ///
+    /// ## Errors
+    /// - [`LoadingError::ConstructorLoadFailure`] if the constructor can't be found in the library
+    /// - [`LoadingError::ConstructorCallFailure`] if the constructor returns a null pointer
+    ///
+    /// ## Safety
+    /// The returned plugin must be dropped before `lib` is.
+    unsafe fn construct_plugin(lib: &Library) -> Result<Box<dyn ComputeFunction>, LoadingError> {
+        type CfCtor = unsafe fn() -> *mut dyn ComputeFunction;
+
+        unsafe {
+            // Get the expected constructor function from the library
+            let constructor: Symbol<CfCtor> = lib
+                .get(crate::core::CTOR_NAME)
+                .map_err(|err| LoadingError::ctor_load_failure(&err))?;
+
+            // Unsafely call the constructor function to create a new plugin
+            let boxed_raw = constructor();
+            // Ensure resulting object is not null
+            if boxed_raw.is_null() {
+                return Err(LoadingError::ctor_call_failure());
+            }
+            // Box the raw pointer for safe use
+            Ok(Box::from_raw(boxed_raw))
+        }
+    }
+
+    /// Unloads a [`ComputeFunction`] plugin from the manager. If it was the last function loaded from its
+    /// library, the library is freed as well. An unload waits for any reload of the same target that is
+    /// in progress, cancelling it if it hasn't swapped in the new version yet.
+    ///
+    /// ## Arguments
+    /// - `target` - The target [`ComputeFunction`] to unload
+    ///
+    /// ## Returns
+    /// A result containing nothing upon success or an [`UnloadingError`] upon failure.
+    ///
+    /// ## Errors
+    /// - Function errors if the target [`ComputeFunction`] is not found in the manager
+    ///
+    /// ## Example(s)
+    /// ```ignore
+    /// /// TODO Write examples
+    /// ```
+    pub async fn unload_plugin(&self, target: &TargetComputeFunc) -> Result<(), UnloadingError> {
+        let op = self.target_ops.get(target.name());
+        let pending = op.begin_unload();
+        let guard = op.lock().await;
+        let result = self.unload_locked(target).await;
+        drop(guard);
+        drop(pending);
+        self.target_ops.release(target.name(), op);
+        if result.is_ok() {
+            self.emit(|| ManagerEvent::Unloaded {
+                name: target.name().to_string(),
+            });
+        }
+        result
+    }
+
+    /// The body of [`ComputeFunctionManager::unload_plugin`], run while holding the target's
+    /// operation lock.
+    async fn unload_locked(&self, target: &TargetComputeFunc) -> Result<(), UnloadingError> {
+        let mut builtins = self.builtins.lock().await;
+        let mut fn_locked = self.functions.write().await;
+        let plugin = fn_locked
+            .remove(target.name())
+            .ok_or_else(|| UnloadingError::TargetNotFound(target.clone()))?;
+        if let Some(kind) = plugin.builtin.filter(|kind| kind.name() == target.name()) {
+            builtins.remove(kind);
+        }
+        drop(builtins);
+        plugin.function.on_plugin_unload();
+        // The plugin has to be gone before the library its code lives in is freed.
+        drop(plugin);
+        self.aliases.remove_all_of(target.name());
+        self.call_stats.remove(target.name());
+
+        let mut libraries = self.loaded_libraries.lock().await;
+        Self::release_library_of(&mut libraries, target.name());
+        drop(libraries);
+        drop(fn_locked);
+
+        Ok(())
+    }
+
+    /// Forget that `function` came from whichever library it was loaded from, freeing that library
+    /// once none of its functions are left. Builtins don't belong to a library, so this does nothing
+    /// for them. The function itself must already have been dropped.
+    fn release_library_of(libraries: &mut Vec<LoadedLibrary>, function: &str) {
+        for lib in libraries.iter_mut() {
+            lib.functions.retain(|name| name != function);
+        }
+        libraries.retain(|lib| {
+            let in_use = !lib.functions.is_empty();
+            if !in_use {
+                debug!("Freeing library {}", lib.path);
+            }
+            in_use
+        });
+    }
+
+    /// Gather everything known about the function registered as `name`: its self-reported version,
+    /// description, schemas and examples along with how often it has been called.
+    ///
+    /// ## Returns
+    /// The [`FunctionDescription`], or `None` if no function is registered under `name`.
+    pub async fn describe_function(&self, name: &str) -> Option<FunctionDescription> {
+        let functions = self.functions.read().await;
+        let description = functions.get(name).map(|function| {
+            FunctionDescription::new(name, function.function.as_ref(), self.call_stats.get(name))
+        });
+        drop(functions);
+        description
+    }
+
+    /// The call counts and durations of every function that has been called, keyed by the name it is
+    /// registered under. Durations are measured around the function handling each request, failed or
+    /// not, and exclude the time spent waiting on the manager's locks.
+    #[must_use]
+    pub fn stats(&self) -> HashMap<String, FunctionStats> {
+        self.call_stats.snapshot()
+    }
+
+    /// Assemble an `OpenAPI` document describing the HTTP API, including an operation for every loaded
+    /// function built from its [`FunctionDescription`], as served at `GET /openapi.json`.
+    pub async fn export_openapi(&self) -> serde_json::Value {
+        let functions = self.functions.read().await;
+        let mut descriptions: Vec<FunctionDescription> = functions
+            .iter()
+            .map(|(name, registered)| {
+                FunctionDescription::new(
+                    name,
+                    registered.function.as_ref(),
+                    self.call_stats.get(name),
+                )
+            })
+            .collect();
+        drop(functions);
+
+        descriptions.sort_by(|a, b| a.name().cmp(b.name()));
+        openapi::document(&descriptions)
+    }
+
+    /// Lists every loaded function along with where it came from and when it was loaded, sorted by
+    /// name.
+    pub async fn list_functions(&self) -> Vec<FunctionInfo> {
+        let functions = self.functions.read().await;
+        let libraries = self.loaded_libraries.lock().await;
+        let mut infos: Vec<FunctionInfo> = functions
+            .iter()
+            .map(|(name, registered)| self.info_of(name, registered, &libraries))
+            .collect();
+        drop(libraries);
+        drop(functions);
+
+        infos.sort_by(|a, b| a.name().cmp(b.name()));
+        infos
+    }
+
+    /// The [`FunctionInfo`] of the function registered as `name`, given the loaded `libraries`.
+    fn info_of(
+        &self,
+        name: &str,
+        registered: &RegisteredFunction,
+        libraries: &[LoadedLibrary],
+    ) -> FunctionInfo {
+        let origin = libraries
+            .iter()
+            .find(|lib| lib.functions.iter().any(|function| function == name))
+            .map_or_else(
+                || registered.origin.clone(),
+                |lib| FunctionOrigin::Dynamic {
+                    path: lib.path.clone(),
+                },
+            );
+        FunctionInfo::new(name, origin, registered.loaded_at)
+            .with_aliases(self.aliases.of(name))
+            .with_input_schema(registered.function.input_schema())
+    }
+
+    /// Asks every loaded function how it is doing, see [`ComputeFunction::health`]. The functions are
+    /// probed concurrently, so one slow probe doesn't hold up the others. Functions that fail too many
+    /// of their calls are reported unhealthy whatever they say, see
+    /// [`ComputeFunctionManager::set_error_rate_threshold`].
+    ///
+    /// ## Returns
+    /// The [`HealthStatus`] of every loaded function, by the name it is registered under.
+    pub async fn health_check(&self) -> HashMap<String, HealthStatus> {
+        let functions = self.functions.read().await;
+        let probes = functions.iter().map(|(name, registered)| async move {
+            (name.clone(), registered.function.health().await)
+        });
+        let report = futures_util::future::join_all(probes).await;
+        drop(functions);
+
+        report
+            .into_iter()
+            .map(|(name, status)| {
+                let status = match (status, self.error_rate_threshold) {
+                    (HealthStatus::Unhealthy(reason), _) => HealthStatus::Unhealthy(reason),
+                    (status, Some(threshold)) => match self.call_stats.recent_error_rate(&name) {
+                        Some(rate) if rate > threshold => HealthStatus::Unhealthy(format!(
+                            "{:.0}% of its recent calls failed",
+                            rate * 100.0
+                        )),
+                        _ => status,
+                    },
+                    (status, None) => status,
+                };
+                (name, status)
+            })
+            .collect()
+    }
+
+    /// Re-keys a loaded [`ComputeFunction`] so that it is reached under a new name, without having to
+    /// unload and reload it. Useful for swapping a new version of a function in under an existing route.
+    ///
+    /// ## Arguments
+    /// - `from` - The name the function is currently registered under
+    /// - `to` - The name to register the function under instead
+    ///
+    /// ## Errors
+    /// - [`AppError::TargetNotFound`] if no function is registered under `from`
+    /// - [`AppError::Loading`] with a [`LoadingError::FunctionNameCollision`] if `to` is already taken
+    pub async fn rename_function(&self, from: &str, to: &str) -> AppResult<()> {
+        // Both names are locked against other operations, always in the same order so that two
+        // renames in opposite directions can't deadlock.
+        let (first, second) = if from <= to { (from, to) } else { (to, from) };
+        let first_op = self.target_ops.get(first);
+        let first_guard = first_op.lock().await;
+        let second_op = (first != second).then(|| self.target_ops.get(second));
+        let second_guard = match &second_op {
+            Some(op) => Some(op.lock().await),
+            None => None,
+        };
+
+        let result = self.rename_locked(from, to).await;
+
+        drop(second_guard);
+        drop(first_guard);
+        if let Some(op) = second_op {
+            self.target_ops.release(second, op);
+        }
+        self.target_ops.release(first, first_op);
+        result
+    }
+
+    /// The body of [`ComputeFunctionManager::rename_function`], run while holding the operation
+    /// locks of both names.
+    async fn rename_locked(&self, from: &str, to: &str) -> AppResult<()> {
+        let mut builtins = self.builtins.lock().await;
+        let mut fn_locked = self.functions.write().await;
+        if !fn_locked.contains_key(from) {
+            return Err(AppError::TargetNotFound(TargetComputeFunc::new(
+                from.to_string(),
+            )));
+        }
+        if from == to {
+            return Ok(());
+        }
+        if fn_locked.contains_key(to) || self.aliases.contains(to) {
+            return Err(LoadingError::name_collision(&to).into());
+        }
+
+        if let Some(plugin) = fn_locked.remove(from) {
+            // A builtin only counts as loaded while it is reachable under its own name, so that it
+            // can be loaded again once it has been renamed away from it.
+            if let Some(kind) = plugin.builtin {
+                if kind.name() == from {
+                    builtins.remove(kind);
+                } else if kind.name() == to {
+                    builtins.add(kind);
+                }
+            }
+            fn_locked.insert(to.to_string(), plugin);
+        }
+        self.aliases.rename(from, to);
+        drop(fn_locked);
+        drop(builtins);
+        self.call_stats.rename(from, to);
+
+        // Keep the library bookkeeping in sync so the library isn't considered unused.
+        for lib in self.loaded_libraries.lock().await.iter_mut() {
+            for name in lib.functions.iter_mut().filter(|name| *name == from) {
+                *name = to.to_string();
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Makes a loaded [`ComputeFunction`] reachable under an additional name, without loading it
+    /// again. Requests for `alias` are dispatched to (and counted against) the function itself, and
+    /// the alias goes away when the function is unloaded. Aliases follow the function when it is
+    /// renamed.
+    ///
+    /// ## Arguments
+    /// - `existing` - The name (or another alias) the function is reached under
+    /// - `alias` - The additional name to reach the function under
+    ///
+    /// ## Errors
+    /// - [`AppError::TargetNotFound`] if no function is reached under `existing`
+    /// - [`AppError::Loading`] with a [`LoadingError::FunctionNameCollision`] if `alias` is already
+    ///   taken by a function or another alias
+    pub async fn add_alias(&self, existing: &str, alias: &str) -> AppResult<()> {
+        let fn_locked = self.functions.write().await;
+        let canonical = self.aliases.resolve(existing);
+        if !fn_locked.contains_key(canonical.as_ref()) {
+            return Err(AppError::TargetNotFound(TargetComputeFunc::new(
+                existing.to_string(),
+            )));
+        }
+        if fn_locked.contains_key(alias) || self.aliases.contains(alias) {
+            return Err(LoadingError::name_collision(&alias).into());
+        }
+
+        self.aliases.insert(alias, &canonical);
+        drop(fn_locked);
+
+        Ok(())
+    }
+
+    /// Stop accepting new requests and wait for the ones already being handled to finish, e.g. before
+    /// shutting down. From the moment this is called [`ComputeFunctionManager::push_request`] and
+    /// friends fail with [`AppError::Draining`], which is a 503 over HTTP. Draining can't be undone.
+    pub async fn drain(&self) {
+        self.drain.start();
+        self.drain.wait_idle().await;
+    }
+
+    /// Whether the manager has started [draining](ComputeFunctionManager::drain) and turns new
+    /// requests away.
+    #[must_use]
+    pub fn is_draining(&self) -> bool {
+        self.drain.is_draining()
+    }
+
+    /// Shuts the manager down deterministically: [drains](ComputeFunctionManager::drain) it, fires
+    /// [`ComputeFunction::on_plugin_unload`] for every function, and then frees every dynamic library,
+    /// waiting for the locks like any other operation would.
+    ///
+    /// Prefer this over just dropping the manager. [`Drop`] can't wait, so it only cleans up on a
+    /// best-effort basis and skips the unload callbacks (and leaves the libraries to be freed as
+    /// they are) if a lock happens to be held at the time.
+    pub async fn shutdown(self) {
+        self.drain().await;
+
+        let mut functions = self.functions.write().await;
+        for (_id, plugin) in functions.drain() {
+            plugin.function.on_plugin_unload();
+        }
+        // Every function is gone, so nothing points into the libraries any more.
+        self.loaded_libraries.lock().await.clear();
+        drop(functions);
+    }
+
+    /// Unloads all functions **and libraries** that this [`ComputeFunctionManager`] is holding references for.
+    /// See [`ComputeFunctionManager::shutdown`] for the async version, which also waits for in-flight
+    /// requests when the manager is shared and is the preferred way to clean up.
+    /// TODO: Should this method resize the containers to 0? There should only ever be once of these instances
+    ///       that lasts for the entire program so it seems unnecessary, but `drain` specifically states that
+    ///       the previously allocated memory is held.
+    ///
+    /// ## Panics
+    /// In debug builds, if a request is somehow still counted as in flight. Taking `&mut self` means
+    /// nothing else can be pushing a request, so there is nothing to wait for here; to let requests
+    /// finish when the manager is shared, [drain](ComputeFunctionManager::drain) it before getting
+    /// exclusive access.
+    ///
+    /// ## Example(s)
+    /// ```ignore
+    /// /// TODO Write examples
+    /// ```
+    pub fn unload_all(&mut self) {
+        debug_assert_eq!(
+            self.drain.in_flight(),
+            0,
+            "unload_all with requests in flight"
+        );
+        for (_id, plugin) in self.functions.get_mut().drain() {
+            // trace!("Firing on_plugin_unload for {:?}", plugin.name());
+            plugin.function.on_plugin_unload();
+        }
+
+        for lib in self.loaded_libraries.get_mut().drain(..) {
+            drop(lib);
+        }
+    }
+
+    /// Diagnostic that unloads every dynamic library none of whose functions are still loaded, while
+    /// leaving builtins and the functions of other libraries alone. Libraries are already freed as their
+    /// last function is unloaded, so this should normally find nothing; it is mostly useful for
+    /// verifying the library lifecycle in tests and while debugging.
+    ///
+    /// ## Returns
+    /// The number of libraries that were freed.
+    pub fn clear_dynamic_libraries(&mut self) -> usize {
+        let functions = self.functions.get_mut();
+        let libraries = self.loaded_libraries.get_mut();
+
+        let before = libraries.len();
+        libraries.retain(|lib| {
+            let in_use = lib
+                .functions
+                .iter()
+                .any(|name| functions.contains_key(name));
+            if !in_use {
+                tracing::debug!("Freeing unused library {}", lib.path);
+            }
+            in_use
+        });
+
+        before - libraries.len()
+    }
+
+    /// Sends a [`ComputeRequest`] to the [`ComputeFunction`] indicated by the request. Functions are
+    /// looked up by the target's [basename](TargetComputeFunc::basename), the sub-path and query are
+    /// left for the function to read through [`ComputeRequest::subpath`] and [`ComputeRequest::query`].
+    ///
+    /// ## Arguments
+    /// - `request` - The [`ComputeRequest`] to send to the currently loaded [`ComputeFunction`] plugins.
+    ///
+    /// ## Returns
+    /// [`AppResult`] containing the [`ComputeResponse`] from the [`ComputeFunction`] or an [`AppError`]
+    /// indicating the type of failure that occurred.
+    ///
+    /// ## Errors
+    /// - [`AppError::TargetNotFound`] if the target [`ComputeFunction`] is not found in the manager
+    /// - [`AppError::BadRequest`] if the [`ComputeRequest`] is malformed or invalid
+    /// - [`AppError::Overloaded`] if the request's payload doesn't fit in the in-flight byte budget,
+    ///   or replay protection is enabled and can't remember another nonce
+    /// - [`AppError::PayloadTooLarge`] if the request's payload is larger than the whole in-flight
+    ///   byte budget
+    /// - [`AppError::Draining`] if the manager is [draining](ComputeFunctionManager::drain)
+    /// - [`AppError::Forbidden`] if the configured [`AuthPolicy`] denies the request
+    /// - [`AppError::Replayed`] if replay protection is enabled and the request's nonce was already used
+    /// - [`AppError::DeadlineExceeded`] if the function is still running at the request's
+    ///   [`RequestContext::deadline`](crate::RequestContext::deadline)
+    /// - [`AppError::Timeout`] if the function takes longer than the
+    ///   [default timeout](ComputeFunctionManager::set_default_timeout), if one is set
+    ///
+    /// ## Example(s)
+    /// ```ignore
+    /// /// TODO Write examples
+    /// ```
+    pub async fn push_request(&self, request: &ComputeRequest) -> AppResult<ComputeResponse> {
+        self.push(request, self.default_timeout).await
+    }
+
+    /// Like [`ComputeFunctionManager::push_request`], but gives up on the target function if it takes
+    /// longer than `timeout`. The clock starts once the function has been looked up, so time spent
+    /// waiting for the function map lock or a queue slot doesn't count against it. On expiry the
+    /// function's future is dropped, cancelling it at whatever `.await` it was suspended on.
+    ///
+    /// ## Errors
+    /// - Everything [`ComputeFunctionManager::push_request`] returns
+    /// - [`AppError::Timeout`] if the function didn't finish within `timeout`
+    pub async fn push_request_timeout(
+        &self,
+        request: &ComputeRequest,
+        timeout: Duration,
+    ) -> AppResult<ComputeResponse> {
+        self.push(request, Some(timeout)).await
+    }
+
+    async fn push(
+        &self,
+        request: &ComputeRequest,
+        timeout: Option<Duration>,
+    ) -> AppResult<ComputeResponse> {
+        let started = Instant::now();
+        let result = self.push_unobserved(request, timeout).await;
+        self.emit_dispatched(request, &result, started.elapsed());
+        result
+    }
+
+    /// The body of [`ComputeFunctionManager::push`], without the [`ManagerEvent`].
+    async fn push_unobserved(
+        &self,
+        request: &ComputeRequest,
+        timeout: Option<Duration>,
+    ) -> AppResult<ComputeResponse> {
+        let _in_flight = self.drain.enter().ok_or(AppError::Draining)?;
+        request.validate()?;
+        self.authorize(request)?;
+        self.check_replay(request)?;
+        let _reservation = self.reserve_payload(request)?;
+        let _permit = match &self.queue {
+            Some(queue) => Some(queue.acquire(request.priority()).await),
+            None => None,
+        };
+        let plugins = self.functions.read().await;
+        let id = self.aliases.resolve(request.target().basename());
+        let result = if let Some(plugin) = plugins.get(id.as_ref()) {
+            self.dispatch(&id, plugin, request, timeout).await
+        } else {
+            Err(AppError::TargetNotFound(request.target().clone()))
+        };
+        drop(plugins);
+
+        self.total_requests.fetch_add(1, Ordering::Relaxed);
+        self.log_call(&id, request.request_id(), &result);
+        result
+    }
+
+    /// Non-blocking version of [`ComputeFunctionManager::push_request`] for latency-critical callers
+    /// that would rather shed a request than queue it.
+    ///
+    /// ## Returns
+    /// The result of [`ComputeFunctionManager::push_request`] if the request could be dispatched
+    /// immediately, or [`Busy`] if it would have had to wait for the function map lock or for a
+    /// slot in the request queue.
+    ///
+    /// ## Errors
+    /// - [`Busy`] if the request could not be dispatched without waiting
+    pub async fn try_push_request(
+        &self,
+        request: &ComputeRequest,
+    ) -> Result<AppResult<ComputeResponse>, Busy> {
+        let started = Instant::now();
+        let result = self.try_push_unobserved(request).await;
+        if let Ok(result) = &result {
+            self.emit_dispatched(request, result, started.elapsed());
+        }
+        result
+    }
+
+    /// The body of [`ComputeFunctionManager::try_push_request`], without the [`ManagerEvent`].
+    async fn try_push_unobserved(
+        &self,
+        request: &ComputeRequest,
+    ) -> Result<AppResult<ComputeResponse>, Busy> {
+        let _in_flight = match self.drain.enter() {
+            Some(in_flight) => in_flight,
+            None => return Ok(Err(AppError::Draining)),
+        };
+        if let Err(err) = request.validate() {
+            return Ok(Err(err.into()));
+        }
+        if let Err(err) = self
+            .authorize(request)
+            .and_then(|()| self.check_replay(request))
+        {
+            return Ok(Err(err));
+        }
+        let _reservation = match self.reserve_payload(request) {
+            Ok(reservation) => reservation,
+            Err(AppError::Overloaded(_)) => return Err(Busy),
+            Err(err) => return Ok(Err(err)),
+        };
+        let _permit = match &self.queue {
+            Some(queue) => Some(queue.try_acquire().ok_or(Busy)?),
+            None => None,
+        };
+        let plugins = self.functions.try_read().map_err(|_| Busy)?;
+
+        let id = self.aliases.resolve(request.target().basename());
+        let result = if let Some(plugin) = plugins.get(id.as_ref()) {
+            self.dispatch(&id, plugin, request, self.default_timeout)
+                .await
+        } else {
+            Err(AppError::TargetNotFound(request.target().clone()))
+        };
+        drop(plugins);
+
+        self.total_requests.fetch_add(1, Ordering::Relaxed);
+        self.log_call(&id, request.request_id(), &result);
+        Ok(result)
+    }
+
+    /// The number of requests processed by [`ComputeFunctionManager::push_request`] and
+    /// [`ComputeFunctionManager::try_push_request`] (successful or not) since the manager was created
+    /// or the count was last reset. Requests rejected up front (invalid, unauthorized or shed) are not
+    /// counted.
+    #[must_use]
+    pub fn total_requests(&self) -> u64 {
+        self.total_requests.load(Ordering::Relaxed)
+    }
+
+    /// Reset [`ComputeFunctionManager::total_requests`] back to zero, e.g. between benchmark runs.
+    pub fn reset_total_requests(&self) {
+        self.total_requests.store(0, Ordering::Relaxed);
+    }
+
+    /// Report whatever `event` builds to the event sink, if one is set.
+    fn emit(&self, event: impl FnOnce() -> ManagerEvent) {
+        if let Some(sink) = &self.event_sink {
+            sink.emit(event());
+        }
+    }
+
+    /// Report the outcome of a request to the event sink.
+    fn emit_dispatched(
+        &self,
+        request: &ComputeRequest,
+        result: &AppResult<ComputeResponse>,
+        elapsed: Duration,
+    ) {
+        self.emit(|| {
+            let (status, error_code) = match result {
+                Ok(response) => (response.status().to_u16(), None),
+                Err(err) => (err.as_generic_status_code().to_u16(), Some(err.code())),
+            };
+            ManagerEvent::RequestDispatched {
+                target: request.target().name().to_string(),
+                status,
+                error_code,
+                elapsed,
+            }
+        });
+    }
+
+    /// Log the outcome of a call to `function`, subject to the configured sample rate. Returns whether
+    /// the call was logged.
+    fn log_call(
+        &self,
+        function: &str,
+        request_id: Option<&str>,
+        result: &AppResult<ComputeResponse>,
+    ) -> bool {
+        if !self.call_log.should_log(function, result.is_err()) {
+            return false;
+        }
+
+        let request_id = request_id.unwrap_or("none");
+        match result {
+            Ok(response) => debug!(
+                "Call to '{}' succeeded with status {}, request id: {}",
+                function,
+                response.status().to_u16(),
+                request_id
+            ),
+            Err(err) => warn!(
+                "Call to '{}' failed: {}, request id: {}",
+                function, err, request_id
+            ),
+        }
+        true
+    }
+
+    /// Warn about a call that took `elapsed`, if that is over the slow threshold. Returns whether the
+    /// call was slow.
+    fn warn_if_slow(&self, request: &ComputeRequest, elapsed: Duration) -> bool {
+        match self.slow_threshold {
+            Some(threshold) if elapsed > threshold => {
+                warn!(
+                    "Slow call to '{}' took {}ms (threshold {}ms), request id: {}",
+                    request.target().name(),
+                    elapsed.as_millis(),
+                    threshold.as_millis(),
+                    request.context().request_id().unwrap_or("none")
+                );
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Resolve the function registered as `name` for a call that shouldn't hold the lock on the
+    /// function map while it runs, along with the library it came from, if any.
+    async fn pin_function(&self, name: &str) -> Option<PinnedFunction> {
+        let functions = self.functions.read().await;
+        let function = functions.get(name)?.function.clone();
+        let library = self
+            .loaded_libraries
+            .lock()
+            .await
+            .iter()
+            .find(|lib| lib.functions.iter().any(|function| function == name))
+            .map(|lib| lib.library.clone());
+        drop(functions);
+        Some(PinnedFunction {
+            function,
+            _library: library,
+        })
+    }
+
+    /// Streams a raw request body to the [`ComputeFunction`] indicated by `target`, see
+    /// [`ComputeFunction::receive_body_stream`]. The request goes through the same checks as
+    /// [`ComputeFunctionManager::push_request`], with `context` standing in for the context of a
+    /// [`ComputeRequest`]. Streamed bodies are not counted against the in-flight payload budget, or
+    /// as bytes in by [`ComputeFunctionManager::stats`], since they are never buffered, but
+    /// they do take a slot in the request queue, and are bound by the request's deadline and the
+    /// [default timeout](ComputeFunctionManager::set_default_timeout) like any other call.
+    ///
+    /// The function map isn't locked while the body is read, so loading and unloading functions
+    /// doesn't wait for the client. A function unloaded in the meantime still gets to finish the
+    /// body, and its library is only freed once it has.
+    ///
+    /// ## Errors
+    /// - [`AppError::TargetNotFound`] if the target [`ComputeFunction`] is not found in the manager
+    /// - [`AppError::BadRequest`] if the function rejects the body, or doesn't accept streamed bodies
+    /// - [`AppError::Draining`] if the manager is [draining](ComputeFunctionManager::drain)
+    /// - [`AppError::Forbidden`] if the configured [`AuthPolicy`] denies the request
+    /// - [`AppError::Replayed`] if replay protection is enabled and the request's nonce was already used
+    /// - [`AppError::Overloaded`] if replay protection is enabled and can't remember another nonce
+    /// - [`AppError::DeadlineExceeded`] if the body is still being read at the request's
+    ///   [`RequestContext::deadline`]
+    /// - [`AppError::Timeout`] if reading the body takes longer than the default timeout, if one is
+    ///   set
+    pub async fn push_body_stream(
+        &self,
+        target: &TargetComputeFunc,
+        context: RequestContext,
+        body: BodyStream,
+    ) -> AppResult<ComputeResponse> {
+        // The body is never buffered, so the request only carries what the checks look at.
+        let request =
+            ComputeRequest::new(target.clone(), serde_json::Value::Null).with_context(context);
+        let started = Instant::now();
+        let result = self.push_body_stream_unobserved(&request, body).await;
+        self.emit_dispatched(&request, &result, started.elapsed());
+        result
+    }
+
+    /// The body of [`ComputeFunctionManager::push_body_stream`], without the [`ManagerEvent`].
+    async fn push_body_stream_unobserved(
+        &self,
+        request: &ComputeRequest,
+        body: BodyStream,
+    ) -> AppResult<ComputeResponse> {
+        let _in_flight = self.drain.enter().ok_or(AppError::Draining)?;
+        request.validate()?;
+        self.authorize(request)?;
+        self.check_replay(request)?;
+        let _permit = match &self.queue {
+            Some(queue) => Some(queue.acquire(request.priority()).await),
+            None => None,
+        };
+
+        let id = self.aliases.resolve(request.target().basename());
+        let result = if let Some(pinned) = self.pin_function(&id).await {
+            let started = Instant::now();
+            let receive = async {
+                pinned
+                    .function
+                    .receive_body_stream(request.target(), body)
+                    .await
+                    .map_err(|err| err.or_request_id_of(request).into())
+            };
+            let result = bounded(request, self.default_timeout, receive).await;
+            self.call_stats.record(
+                &id,
+                result.is_err(),
+                started.elapsed(),
+                0,
+                result.as_ref().map_or(0, response_len),
+            );
+            result
+        } else {
+            Err(AppError::TargetNotFound(request.target().clone()))
+        };
+
+        self.total_requests.fetch_add(1, Ordering::Relaxed);
+        self.log_call(&id, request.request_id(), &result);
+        result
+    }
+
+    /// Like [`ComputeFunctionManager::push_request`], but the target's output is sent to `sink` a frame
+    /// at a time as it is produced, see [`ComputeFunction::receive_streaming`]. The request goes
+    /// through the same checks and bookkeeping as any other, and is bound by its deadline and the
+    /// [default timeout](ComputeFunctionManager::set_default_timeout) like any other, so set a
+    /// deadline to give longer streams more time. Output schemas aren't checked. Since the frames
+    /// have already gone to the client, a stream that ends well is counted (and reported to the event
+    /// sink) as an empty `200`.
+    ///
+    /// ## Errors
+    /// - [`AppError::TargetNotFound`] if the target [`ComputeFunction`] is not found in the manager
+    /// - [`AppError::BadRequest`] if the [`ComputeRequest`] is malformed or the function rejects it
+    /// - [`AppError::Overloaded`] if the request's payload doesn't fit in the in-flight byte budget,
+    ///   or replay protection is enabled and can't remember another nonce
+    /// - [`AppError::PayloadTooLarge`] if the request's payload is larger than the whole in-flight
+    ///   byte budget
+    /// - [`AppError::Draining`] if the manager is [draining](ComputeFunctionManager::drain)
+    /// - [`AppError::Forbidden`] if the configured [`AuthPolicy`] denies the request
+    /// - [`AppError::Replayed`] if replay protection is enabled and the request's nonce was already used
+    /// - [`AppError::DeadlineExceeded`] if the function is still streaming at the request's
+    ///   [`RequestContext::deadline`]
+    /// - [`AppError::Timeout`] if the function streams for longer than the default timeout, if one
+    ///   is set
+    pub async fn push_streaming_request(
+        &self,
+        request: &ComputeRequest,
+        sink: ResponseSink,
+    ) -> AppResult<()> {
+        let started = Instant::now();
+        let result = self.push_streaming_unobserved(request, sink).await;
+        self.emit_dispatched(request, &streamed_outcome(&result), started.elapsed());
+        result
+    }
+
+    /// The body of [`ComputeFunctionManager::push_streaming_request`], without the [`ManagerEvent`].
+    async fn push_streaming_unobserved(
+        &self,
+        request: &ComputeRequest,
+        sink: ResponseSink,
+    ) -> AppResult<()> {
+        let _in_flight = self.drain.enter().ok_or(AppError::Draining)?;
+        request.validate()?;
+        self.authorize(request)?;
+        self.check_replay(request)?;
+        let _reservation = self.reserve_payload(request)?;
+        let _permit = match &self.queue {
+            Some(queue) => Some(queue.acquire(request.priority()).await),
+            None => None,
+        };
+
+        let plugins = self.functions.read().await;
+        let id = self.aliases.resolve(request.target().basename());
+        let result = if let Some(plugin) = plugins.get(id.as_ref()) {
+            let started = Instant::now();
+            let receive = async {
+                match self
+                    .check_input_limits(plugin.function.as_ref(), request)
+                    .and_then(|()| plugin.check_input_schema(request))
+                {
+                    Ok(()) => plugin.function.receive_streaming(request, sink).await,
+                    Err(err) => Err(err),
+                }
+                .map_err(|err| err.or_request_id_of(request).into())
+            };
+            let result = bounded(request, self.default_timeout, receive).await;
+            self.call_stats.record(
+                &id,
+                result.is_err(),
+                started.elapsed(),
+                serialized_len(request.data()),
+                0,
+            );
+            result
+        } else {
+            Err(AppError::TargetNotFound(request.target().clone()))
+        };
+        drop(plugins);
+
+        self.total_requests.fetch_add(1, Ordering::Relaxed);
+        self.log_call(&id, request.request_id(), &streamed_outcome(&result));
+        result
+    }
+
+    /// Send the request to the resolved plugin, registered as `name`, giving up after `timeout` if
+    /// there is one. The request data is checked against the plugin's declared
+    /// [`ComputeFunction::input_schema`] first, and its response against the declared
+    /// [`ComputeFunction::output_schema`] if validation is enabled.
+    async fn dispatch(
+        &self,
+        name: &str,
+        plugin: &RegisteredFunction,
+        request: &ComputeRequest,
+        timeout: Option<Duration>,
+    ) -> AppResult<ComputeResponse> {
+        let started = Instant::now();
+        let result = self.dispatch_checked(plugin, request, timeout).await;
+        let elapsed = started.elapsed();
+        if !matches!(
+            result,
+            Err(AppError::DeadlineExceeded(_) | AppError::Timeout { .. })
+        ) {
+            self.warn_if_slow(request, elapsed);
+        }
+        self.call_stats.record(
+            name,
+            result.is_err(),
+            elapsed,
+            serialized_len(request.data()),
+            result.as_ref().map_or(0, response_len),
+        );
+
+        match result {
+            Ok(ComputeResponse::NoContent(GenericStatusCode::Ok))
+                if self.no_content_as_empty_object =>
+            {
+                Ok(ComputeResponse::json_ok(serde_json::json!({})))
+            }
+            other => other,
+        }
+    }
+
+    async fn dispatch_checked(
+        &self,
+        registered: &RegisteredFunction,
+        request: &ComputeRequest,
+        timeout: Option<Duration>,
+    ) -> AppResult<ComputeResponse> {
+        let plugin = registered.function.as_ref();
+        self.check_input_limits(plugin, request)?;
+        registered.check_input_schema(request)?;
+
+        let receive = async {
+            plugin
+                .receive_request(request)
+                .await
+                .map_err(|err| err.or_request_id_of(request).into())
+        };
+        let response = bounded(request, timeout, receive).await?;
+
+        if !response.is_error() && (cfg!(debug_assertions) || self.strict_output_validation) {
+            if let (Some(schema), Some(data)) = (plugin.output_schema(), response.data()) {
+                if let Err(violations) = schema::validate(&schema, &data) {
+                    return Err(AppError::Other(format!(
+                        "Compute function '{}' returned data violating its output schema: {}",
+                        plugin.name(),
+                        violations.join("; ")
+                    )));
+                }
+            }
+        }
+
+        Ok(response)
+    }
+}
+
+impl Drop for ComputeFunctionManager {
+    /// Best-effort fallback for managers that weren't [shut down](ComputeFunctionManager::shutdown),
+    /// which finds nothing left to do if they were.
+    fn drop(&mut self) {
+        // Functions have to be dropped before the libraries their code lives in, so if the functions
+        // can't be cleaned up the libraries are left alone as well. Field drop order (functions
+        // before libraries) keeps whatever is left over sound.
+        if let Ok(mut functions) = self.functions.try_write() {
+            for (_id, plugin) in functions.drain() {
+                plugin.function.on_plugin_unload();
+            }
+        } else {
+            warn!("Function map locked during drop, skipping on_plugin_unload callbacks");
+            return;
+        }
+
+        if let Ok(mut libraries) = self.loaded_libraries.try_lock() {
+            libraries.clear();
+        } else {
+            warn!("Library list locked during drop, leaving it to be released as-is");
+        }
+    }
+}
+
+/// Await `call`, made on behalf of `request`, giving up with [`AppError::Timeout`] once it has taken
+/// longer than `timeout` (if there is one), or with [`AppError::DeadlineExceeded`] at the request's
+/// [`RequestContext::deadline`]. Giving up drops `call`, cancelling it at whatever `.await` it was
+/// suspended on.
+async fn bounded<T>(
+    request: &ComputeRequest,
+    timeout: Option<Duration>,
+    call: impl std::future::Future<Output = AppResult<T>>,
+) -> AppResult<T> {
+    let started = Instant::now();
+    let call = async {
+        match timeout {
+            Some(timeout) => {
+                tokio::time::timeout(timeout, call)
+                    .await
+                    .map_err(|_| AppError::Timeout {
+                        target: request.target().clone(),
+                        elapsed: started.elapsed(),
+                    })?
+            }
+            None => call.await,
+        }
+    };
+    match request.context().deadline() {
+        Some(deadline) => tokio::time::timeout_at(deadline, call)
+            .await
+            .map_err(|_| AppError::DeadlineExceeded(request.target().clone()))?,
+        None => call.await,
+    }
+}
+
+/// The outcome of a call to [`ComputeFunctionManager::push_streaming_request`] as it is logged and
+/// reported. Its frames already went to the client, so a stream that ended well counts as an empty
+/// `200`.
+fn streamed_outcome(result: &AppResult<()>) -> AppResult<ComputeResponse> {
+    result.clone().map(|()| ComputeResponse::ok())
+}
+
+/// The size of `response`'s body, as counted for [`ComputeFunctionManager::stats`].
+fn response_len(response: &ComputeResponse) -> usize {
+    response.bytes().map_or_else(
+        || response.data_ref().map_or(0, serialized_len),
+        <[u8]>::len,
+    )
+}
+
+/// Compare the ABI version a library `found` to export (`None` if it exports none) against
+/// [`PLUGIN_ABI_VERSION`](crate::PLUGIN_ABI_VERSION). Libraries without a version are taken to be
+/// version `0`, and only let through if `allow_legacy` is set.
+fn compare_abi_versions(found: Option<u32>, allow_legacy: bool) -> Result<(), LoadingError> {
+    match found {
+        Some(crate::core::PLUGIN_ABI_VERSION) => Ok(()),
+        None if allow_legacy => Ok(()),
+        found => Err(LoadingError::abi_mismatch(found.unwrap_or(0))),
+    }
+}
+
+pub fn default_cfm() -> ComputeFunctionManager {
+    ComputeFunctionManager::new()
+}
+
+pub fn logger_cfm() -> ComputeFunctionManager {
+    ComputeFunctionManager::with_logger()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::util::fixtures;
+
+    fn request(target: &str, data: serde_json::Value) -> ComputeRequest {
+        ComputeRequest::new(TargetComputeFunc::new(target.to_string()), data)
+    }
+
+    /// Only relies on the core types and builtins, so this also runs under
+    /// `cargo test --no-default-features --features core`.
+    #[tokio::test]
+    async fn push_request_without_any_server_backend() {
+        let manager = ComputeFunctionManager::with_logger();
+
+        let response = manager
+            .push_request(&request("logger", json!("hello")))
+            .await
+            .unwrap();
+        assert!(response.data().is_none());
+
+        let missing = manager.push_request(&request("missing", json!(null))).await;
+        assert!(matches!(missing, Err(AppError::TargetNotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn load_plugin_returns_the_registered_name() {
+        let manager = ComputeFunctionManager::new();
+
+        let name = unsafe { manager.load_plugin(fixtures::sample_plugin_path()).await }
+            .unwrap()
+            .into_name();
+        assert_eq!(name, fixtures::SAMPLE_PLUGIN_NAME);
+
+        let response = manager
+            .push_request(&request(&name, json!({ "x": 1 })))
+            .await
+            .unwrap();
+        assert_eq!(response.data(), Some(json!({ "x": 1 })));
+    }
+
+    #[tokio::test]
+    async fn load_outcomes_tell_fresh_loads_from_duplicates() {
+        let manager = ComputeFunctionManager::new();
+
+        let fresh = manager.load_builtin_function(BuiltinFunction::Logger).await;
+        assert_eq!(fresh.unwrap(), LoadOutcome::Loaded("logger".to_string()));
+        let duplicate = manager.load_builtin_function(BuiltinFunction::Logger).await;
+        assert_eq!(
+            duplicate.unwrap(),
+            LoadOutcome::AlreadyPresent("logger".to_string())
+        );
+
+        let fresh = unsafe { manager.load_plugin(fixtures::sample_plugin_path()).await }.unwrap();
+        assert!(fresh.is_loaded());
+        assert_eq!(fresh.name(), fixtures::SAMPLE_PLUGIN_NAME);
+        let duplicate =
+            unsafe { manager.load_plugin(fixtures::sample_plugin_path()).await }.unwrap();
+        assert_eq!(
+            duplicate,
+            LoadOutcome::AlreadyPresent(fixtures::SAMPLE_PLUGIN_NAME.to_string())
+        );
+        assert_eq!(manager.list_functions().await.len(), 2);
+        assert_eq!(manager.loaded_libraries.lock().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn load_plugin_info_describes_the_loaded_function() {
+        let manager = ComputeFunctionManager::new();
+
+        let path = fixtures::sample_plugin_path();
+        let info = unsafe { manager.load_plugin_info(path.clone()).await }.unwrap();
+        assert_eq!(info.name(), fixtures::SAMPLE_PLUGIN_NAME);
+        assert_eq!(
+            info.origin(),
+            &FunctionOrigin::Dynamic { path: path.clone() }
+        );
+        assert!(info.aliases().is_empty());
+
+        manager
+            .add_alias(fixtures::SAMPLE_PLUGIN_NAME, "sample")
+            .await
+            .unwrap();
+        let again = unsafe { manager.load_plugin_info(path).await }.unwrap();
+        assert_eq!(again.aliases(), ["sample".to_string()]);
+        assert_eq!(again.loaded_at(), info.loaded_at());
+        assert_eq!(manager.list_functions().await, [again]);
+    }
+
+    #[test]
+    fn plugins_must_match_the_abi_version() {
+        assert!(compare_abi_versions(Some(crate::PLUGIN_ABI_VERSION), false).is_ok());
+        assert!(matches!(
+            compare_abi_versions(Some(crate::PLUGIN_ABI_VERSION + 1), true),
+            Err(LoadingError::AbiMismatch { expected, found })
+                if expected == crate::PLUGIN_ABI_VERSION && found == crate::PLUGIN_ABI_VERSION + 1
+        ));
+        assert!(matches!(
+            compare_abi_versions(None, false),
+            Err(LoadingError::AbiMismatch { found: 0, .. })
+        ));
+        assert!(compare_abi_versions(None, true).is_ok());
+    }
+
+    #[tokio::test]
+    async fn declared_plugins_export_the_abi_version() {
+        let manager = ComputeFunctionManager::new();
+        let lib = unsafe { Library::new(fixtures::sample_plugin_path()) }.unwrap();
+
+        assert!(unsafe { manager.check_abi_version(&lib) }.is_ok());
+    }
+
+    #[tokio::test]
+    async fn loads_within_budget_skip_what_does_not_fit() {
+        let load = |path: &Path| {
+            let path = path.to_path_buf();
+            async move {
+                if path.starts_with("slow") {
+                    tokio::time::sleep(Duration::from_secs(60)).await;
+                }
+                Ok(path.to_string_lossy().into_owned())
+            }
+        };
+        let items = ["fast/a", "fast/b", "slow/c", "fast/d"]
+            .map(PathBuf::from)
+            .to_vec();
+
+        let started = Instant::now();
+        let outcomes =
+            ComputeFunctionManager::load_within_budget(items, Duration::from_millis(50), load)
+                .await;
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        let loaded: Vec<_> = outcomes
+            .iter()
+            .filter_map(|(_, outcome)| outcome.as_ref().ok())
+            .collect();
+        assert_eq!(loaded, ["fast/a", "fast/b"]);
+        assert!(matches!(outcomes[2].1, Err(LoadingError::LoadTimeout(_))));
+        assert!(matches!(outcomes[3].1, Err(LoadingError::LoadTimeout(_))));
+    }
+
+    #[tokio::test]
+    async fn load_plugins_from_dir_loads_every_library() {
+        let dir = std::env::temp_dir().join(format!("cfm-dir-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir(&dir).unwrap();
+        let library = dir.join(format!("sample.{}", std::env::consts::DLL_EXTENSION));
+        std::fs::copy(fixtures::sample_plugin_path(), &library).unwrap();
+        std::fs::write(dir.join("README.md"), b"not a library").unwrap();
+
+        let manager = ComputeFunctionManager::new();
+        let outcomes = unsafe {
+            manager
+                .load_plugins_from_dir(&dir, Duration::from_secs(30))
+                .await
+        }
+        .unwrap();
+        assert_eq!(outcomes.len(), 1);
+        assert_eq!(outcomes[0].0, library);
+        assert_eq!(
+            outcomes[0].1.as_ref().unwrap(),
+            &LoadOutcome::Loaded(fixtures::SAMPLE_PLUGIN_NAME.to_string())
+        );
+
+        // With no budget at all nothing is loaded.
+        let manager = ComputeFunctionManager::new();
+        let outcomes =
+            unsafe { manager.load_plugins_from_dir(&dir, Duration::ZERO).await }.unwrap();
+        assert!(matches!(outcomes[0].1, Err(LoadingError::LoadTimeout(_))));
+        assert!(manager.list_functions().await.is_empty());
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let missing = unsafe { manager.load_plugins_from_dir(&dir, Duration::ZERO).await };
+        assert!(matches!(missing, Err(LoadingError::BadPath(_))));
+    }
+
+    #[tokio::test]
+    async fn load_plugin_dir_reports_every_entry() {
+        let dir = std::env::temp_dir().join(format!("cfm-plugin-dir-{}", uuid::Uuid::new_v4()));
+        let nested = dir.join("nested");
+        std::fs::create_dir_all(&nested).unwrap();
+        let extension = std::env::consts::DLL_EXTENSION;
+        let top = dir.join(format!("a.{}", extension));
+        let duplicate = nested.join(format!("b.{}", extension));
+        std::fs::copy(fixtures::sample_plugin_path(), &top).unwrap();
+        std::fs::copy(fixtures::sample_plugin_path(), &duplicate).unwrap();
+        std::fs::write(dir.join("notes.txt"), b"not a library").unwrap();
+
+        let manager = ComputeFunctionManager::new();
+        let outcomes = unsafe { manager.load_plugin_dir(&dir, false).await };
+        assert_eq!(outcomes.len(), 1);
+        assert_eq!(outcomes[0].0, top);
+        assert_eq!(
+            outcomes[0].1.as_ref().unwrap().name(),
+            fixtures::SAMPLE_PLUGIN_NAME
+        );
+
+        let manager = ComputeFunctionManager::new();
+        let outcomes = unsafe { manager.load_plugin_dir(&dir, true).await };
+        let paths: Vec<_> = outcomes.iter().map(|(path, _)| path.clone()).collect();
+        assert_eq!(paths, [top, duplicate]);
+        assert!(outcomes[0].1.is_ok());
+        assert!(matches!(
+            outcomes[1].1,
+            Err(LoadingError::FunctionNameCollision(_))
+        ));
+        assert_eq!(manager.list_functions().await.len(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let missing = unsafe { manager.load_plugin_dir(&dir, true).await };
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].0, dir);
+        assert!(matches!(missing[0].1, Err(LoadingError::BadPath(_))));
+    }
+
+    #[tokio::test]
+    async fn lifecycle_and_request_events_reach_the_sink() {
+        let events = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut manager = ComputeFunctionManager::with_logger();
+        manager.set_event_sink({
+            let events = std::sync::Arc::clone(&events);
+            move |event| events.lock().unwrap().push(event)
+        });
+
+        let failed = unsafe { manager.load_plugin("/not/a/libplugin.so".to_string()).await };
+        assert!(failed.is_err());
+        let name = unsafe { manager.load_plugin(fixtures::sample_plugin_path()).await }
+            .unwrap()
+            .into_name();
+        assert!(manager
+            .push_request(&request(&name, json!(1)))
+            .await
+            .is_ok());
+        assert!(manager
+            .push_request(&request("missing", json!(1)))
+            .await
+            .is_err());
+        assert!(manager.push_request(&request(" ", json!(1))).await.is_err());
+        manager
+            .unload_plugin(&TargetComputeFunc::new(name.clone()))
+            .await
+            .unwrap();
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 6, "{:?}", events);
+        assert!(matches!(
+            &events[0],
+            ManagerEvent::LoadFailed { path, error: LoadingError::PathNotFound(_) }
+                if path == "/not/a/libplugin.so"
+        ));
+        assert!(matches!(&events[1], ManagerEvent::Loaded { name: n, .. } if *n == name));
+        assert!(matches!(
+            &events[2],
+            ManagerEvent::RequestDispatched {
+                status: 200,
+                error_code: None,
+                ..
+            }
+        ));
+        assert!(matches!(
+            &events[3],
+            ManagerEvent::RequestDispatched {
+                status: 404,
+                error_code: Some("target_not_found"),
+                ..
+            }
+        ));
+        assert!(matches!(
+            &events[4],
+            ManagerEvent::RequestDispatched {
+                status: 400,
+                error_code: Some("bad_request"),
+                ..
+            }
+        ));
+        assert!(matches!(&events[5], ManagerEvent::Unloaded { name: n } if *n == name));
+        drop(events);
+    }
+
+    #[tokio::test]
+    async fn reload_plugin_swaps_in_a_fresh_copy() {
+        let mut manager = ComputeFunctionManager::with_logger();
+        let name = unsafe { manager.load_plugin(fixtures::sample_plugin_path()).await }
+            .unwrap()
+            .into_name();
+        let target = TargetComputeFunc::new(name.clone());
+        manager
+            .push_request(&request(&name, json!(1)))
+            .await
+            .unwrap();
+
+        unsafe { manager.reload_plugin(&target).await }.unwrap();
+
+        let response = manager
+            .push_request(&request(&name, json!({ "x": 1 })))
+            .await
+            .unwrap();
+        assert_eq!(response.data(), Some(json!({ "x": 1 })));
+        assert_eq!(manager.call_stats.get(&name).calls, 2);
+        // The old library went away with the old plugin.
+        assert_eq!(manager.loaded_libraries.get_mut().len(), 1);
+        assert_eq!(manager.clear_dynamic_libraries(), 0);
+    }
+
+    #[tokio::test]
+    async fn failed_reload_keeps_the_old_version() {
+        let manager = ComputeFunctionManager::with_logger();
+        let copy = std::env::temp_dir().join(format!(
+            "{}-{}",
+            uuid::Uuid::new_v4(),
+            fixtures::SAMPLE_PLUGIN_NAME
+        ));
+        std::fs::copy(fixtures::sample_plugin_path(), &copy).unwrap();
+        let name = unsafe {
+            manager
+                .load_plugin(copy.to_string_lossy().into_owned())
+                .await
+        }
+        .unwrap()
+        .into_name();
+        // Replace (rather than overwrite) the file, the loaded library is still mapped from it.
+        std::fs::remove_file(&copy).unwrap();
+        std::fs::write(&copy, b"not a library").unwrap();
+
+        let target = TargetComputeFunc::new(name.clone());
+        let reloaded = unsafe { manager.reload_plugin(&target).await };
+        std::fs::remove_file(&copy).unwrap();
+        assert!(matches!(reloaded, Err(LoadingError::LibraryLoadFailure(_))));
+        let response = manager
+            .push_request(&request(&name, json!(1)))
+            .await
+            .unwrap();
+        assert_eq!(response.data(), Some(json!(1)));
+
+        let builtin = TargetComputeFunc::new("logger".to_string());
+        assert!(matches!(
+            unsafe { manager.reload_plugin(&builtin).await },
+            Err(LoadingError::NotReloadable(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn unload_cancels_a_reload_in_progress() {
+        let manager = ComputeFunctionManager::new();
+        let name = unsafe { manager.load_plugin(fixtures::sample_plugin_path()).await }
+            .unwrap()
+            .into_name();
+        let target = TargetComputeFunc::new(name.clone());
+
+        // Hold the target's operation lock so both operations queue up behind it, reload first.
+        let op = manager.target_ops.get(&name);
+        let guard = op.lock().await;
+        let (reloaded, unloaded, ()) = tokio::join!(
+            unsafe { manager.reload_plugin(&target) },
+            manager.unload_plugin(&target),
+            async move {
+                tokio::task::yield_now().await;
+                drop(guard);
+            },
+        );
+
+        assert!(matches!(reloaded, Err(LoadingError::ReloadCancelled(_))));
+        assert!(unloaded.is_ok());
+        assert!(manager.describe_function(&name).await.is_none());
+        assert!(manager.loaded_libraries.lock().await.is_empty());
+    }
+
+    // A task that moves threads while holding a lock confuses the deadlock detector.
+    #[cfg(not(feature = "deadlock-detect"))]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn interleaved_reloads_and_unloads_stay_consistent() {
+        let manager = std::sync::Arc::new(ComputeFunctionManager::new());
+        let target = TargetComputeFunc::new(fixtures::SAMPLE_PLUGIN_NAME.to_string());
+
+        for _ in 0..20 {
+            unsafe { manager.load_plugin(fixtures::sample_plugin_path()).await }.unwrap();
+
+            let reload = tokio::spawn({
+                let (manager, target) = (std::sync::Arc::clone(&manager), target.clone());
+                async move { unsafe { manager.reload_plugin(&target).await } }
+            });
+            let unload = tokio::spawn({
+                let (manager, target) = (std::sync::Arc::clone(&manager), target.clone());
+                async move { manager.unload_plugin(&target).await }
+            });
+
+            assert!(unload.await.unwrap().is_ok());
+            assert!(matches!(
+                reload.await.unwrap(),
+                Ok(()) | Err(LoadingError::ReloadCancelled(_) | LoadingError::NotReloadable(_))
+            ));
+            assert!(manager.describe_function(target.name()).await.is_none());
+            assert!(manager.loaded_libraries.lock().await.is_empty());
+        }
+    }
+
+    // A task that moves threads while holding a lock confuses the deadlock detector.
+    #[cfg(not(feature = "deadlock-detect"))]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn concurrent_loads_of_the_same_name_register_it_once() {
+        for _ in 0..20 {
+            let manager = std::sync::Arc::new(ComputeFunctionManager::new());
+            let loads: Vec<_> = (0..4)
+                .map(|_| {
+                    let manager = std::sync::Arc::clone(&manager);
+                    tokio::spawn(async move {
+                        unsafe { manager.load_plugin(fixtures::sample_plugin_path()).await }
+                    })
+                })
+                .collect();
+
+            let mut loaded = 0;
+            for load in loads {
+                match load.await.unwrap() {
+                    Ok(LoadOutcome::Loaded(_)) => loaded += 1,
+                    Ok(LoadOutcome::AlreadyPresent(_))
+                    | Err(LoadingError::FunctionNameCollision(_)) => (),
+                    Err(err) => panic!("{}", err),
+                }
+            }
+            assert_eq!(loaded, 1);
+            assert_eq!(manager.loaded_libraries.lock().await.len(), 1);
+        }
+    }
+
+    #[test]
+    fn libraries_are_freed_with_their_last_function() {
+        let library = unsafe { Library::new(fixtures::sample_plugin_path()) }.unwrap();
+        let mut libraries = vec![LoadedLibrary {
+            path: fixtures::sample_plugin_path(),
+            functions: vec!["first".to_string(), "second".to_string()],
+            library: Arc::new(library),
+        }];
+
+        ComputeFunctionManager::release_library_of(&mut libraries, "logger");
+        ComputeFunctionManager::release_library_of(&mut libraries, "first");
+        assert_eq!(libraries.len(), 1);
+        assert_eq!(libraries[0].functions, ["second"]);
+
+        ComputeFunctionManager::release_library_of(&mut libraries, "second");
+        assert!(libraries.is_empty());
+    }
+
+    #[cfg(feature = "wasm")]
+    #[tokio::test]
+    async fn wasm_plugins_are_loaded_and_callable() {
+        let manager = ComputeFunctionManager::with_logger();
+        let path = fixtures::wasm_module(fixtures::FIXED_WASM_MODULE);
+
+        let info = manager.load_wasm_plugin(&path).await.unwrap();
+        assert_eq!(info.name(), fixtures::FIXED_WASM_NAME);
+        assert_eq!(
+            info.origin(),
+            &FunctionOrigin::Wasm {
+                path: path.to_string_lossy().into_owned()
+            }
+        );
+        assert!(manager
+            .list_functions()
+            .await
+            .iter()
+            .any(|listed| listed == &info));
+
+        let response = manager
+            .push_request(&request(
+                fixtures::FIXED_WASM_NAME,
+                json!({ "any": "thing" }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.data(), Some(json!({ "fixed": true })));
+
+        let again = manager.load_wasm_plugin(&path).await;
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(
+            again,
+            Err(AppError::Loading(LoadingError::FunctionNameCollision(_)))
+        ));
+    }
+
+    #[cfg(all(feature = "proxy", feature = "axum"))]
+    #[tokio::test]
+    async fn proxies_forward_to_their_upstream() {
+        use axum::{http::StatusCode, routing::post, Json, Router};
+
+        let upstream = Router::new().route(
+            "/compute",
+            post(|Json(body): Json<serde_json::Value>| async move {
+                (StatusCode::CREATED, Json(json!({ "echoed": body })))
+            }),
+        );
+        let server =
+            axum::Server::bind(&([127, 0, 0, 1], 0).into()).serve(upstream.into_make_service());
+        let url = format!("http://{}/compute", server.local_addr());
+        tokio::spawn(server);
+
+        let manager = ComputeFunctionManager::with_logger();
+        let info = manager.load_proxy("remote", &url).await.unwrap();
+        assert_eq!(info.origin(), &FunctionOrigin::Proxy { url });
+
+        let response = manager
+            .push_request(&request("remote", json!({ "x": 1 })))
+            .await
+            .unwrap();
+        assert_eq!(response.status().to_u16(), 201);
+        assert_eq!(response.data(), Some(json!({ "echoed": { "x": 1 } })));
+
+        assert!(matches!(
+            manager.load_proxy("remote", "http://127.0.0.1:1/").await,
+            Err(AppError::Loading(LoadingError::FunctionNameCollision(_)))
+        ));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn subprocess_functions_are_loaded_and_callable() {
+        let manager = ComputeFunctionManager::with_logger();
+        let args = vec![
+            "-c".to_string(),
+            r#"read -r line; echo '{"Json":{"status":200,"data":"from a child"}}'"#.to_string(),
+        ];
+
+        let info = manager
+            .load_subprocess("child", "sh", args.clone())
+            .await
+            .unwrap();
+        assert_eq!(
+            info.origin(),
+            &FunctionOrigin::Subprocess {
+                command: "sh".to_string(),
+                args
+            }
+        );
+
+        let response = manager
+            .push_request(&request("child", json!(null)))
+            .await
+            .unwrap();
+        assert_eq!(response.data(), Some(json!("from a child")));
+
+        let crashing = manager
+            .load_subprocess(
+                "crashing",
+                "sh",
+                vec!["-c".to_string(), "exit 1".to_string()],
+            )
+            .await;
+        assert!(crashing.is_ok());
+        assert!(matches!(
+            manager
+                .push_request(&request("crashing", json!(null)))
+                .await,
+            Err(AppError::BadRequest(_))
+        ));
+
+        assert!(matches!(
+            manager.load_subprocess("logger", "sh", Vec::new()).await,
+            Err(AppError::Loading(LoadingError::FunctionNameCollision(_)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn manifests_restore_the_loaded_functions() {
+        let manager = ComputeFunctionManager::with_logger();
+        manager
+            .load_builtin_function(BuiltinFunction::Math)
+            .await
+            .unwrap();
+        unsafe { manager.load_plugin(fixtures::sample_plugin_path()) }
+            .await
+            .unwrap();
+        manager
+            .load_subprocess("child", "sh", vec!["-c".to_string(), "cat".to_string()])
+            .await
+            .unwrap();
+
+        let path = std::env::temp_dir().join(format!("manifest-{}.json", uuid::Uuid::new_v4()));
+        manager.save_manifest(&path).await.unwrap();
+
+        let restored = ComputeFunctionManager::new();
+        let outcomes = unsafe { restored.load_manifest(&path) }.await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(outcomes.iter().all(|(_, result)| result.is_ok()));
+
+        let origins = |functions: Vec<FunctionInfo>| {
+            functions
+                .into_iter()
+                .map(|info| (info.name().to_string(), info.origin().clone()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            origins(restored.list_functions().await),
+            origins(manager.list_functions().await)
+        );
+    }
+
+    #[tokio::test]
+    async fn manifest_entries_that_cant_be_restored_are_skipped() {
+        let path = std::env::temp_dir().join(format!("manifest-{}.json", uuid::Uuid::new_v4()));
+        let manifest = Manifest::new(vec![
+            ManifestEntry {
+                name: "not-a-builtin".to_string(),
+                origin: FunctionOrigin::Builtin,
+                builtin: None,
+            },
+            ManifestEntry {
+                name: "vanished".to_string(),
+                origin: FunctionOrigin::Dynamic {
+                    path: std::env::temp_dir()
+                        .join(format!("{}.so", uuid::Uuid::new_v4()))
+                        .to_string_lossy()
+                        .into_owned(),
+                },
+                builtin: None,
+            },
+            ManifestEntry {
+                name: "logger".to_string(),
+                origin: FunctionOrigin::Subprocess {
+                    command: "sh".to_string(),
+                    args: Vec::new(),
+                },
+                builtin: None,
+            },
+            ManifestEntry {
+                name: "echo".to_string(),
+                origin: FunctionOrigin::Builtin,
+                builtin: None,
+            },
+        ]);
+        manifest.write(&path).unwrap();
+
+        let manager = ComputeFunctionManager::with_logger();
+        let outcomes = unsafe { manager.load_manifest(&path) }.await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let failed: Vec<&str> = outcomes
+            .iter()
+            .filter(|(_, result)| result.is_err())
+            .map(|(name, _)| name.as_str())
+            .collect();
+        assert_eq!(failed, ["not-a-builtin", "vanished", "logger"]);
+        assert!(manager
+            .push_request(&request("echo", json!("back")))
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn list_functions_reports_origins_sorted_by_name() {
+        let mut manager = ComputeFunctionManager::with_logger();
+        manager.load_builtin_instance(Box::new(Echo));
+        let before = Utc::now();
+        let name = unsafe { manager.load_plugin(fixtures::sample_plugin_path()).await }
+            .unwrap()
+            .into_name();
+
+        let functions = manager.list_functions().await;
+        let names: Vec<&str> = functions.iter().map(FunctionInfo::name).collect();
+        assert_eq!(names, ["echo", "logger", name.as_str()]);
+        assert_eq!(functions[0].origin(), &FunctionOrigin::Builtin);
+        assert_eq!(functions[1].origin(), &FunctionOrigin::Builtin);
+        assert!(matches!(
+            functions[2].origin(),
+            FunctionOrigin::Dynamic { path } if path.contains(fixtures::SAMPLE_PLUGIN_NAME)
+        ));
+        assert!(functions[2].loaded_at() >= before);
+    }
+
+    #[tokio::test]
+    async fn clear_dynamic_libraries_frees_only_unused_libraries() {
+        let mut manager = ComputeFunctionManager::with_logger();
+
+        let name = unsafe { manager.load_plugin(fixtures::sample_plugin_path()).await }
+            .unwrap()
+            .into_name();
+        assert_eq!(manager.clear_dynamic_libraries(), 0);
+
+        manager.rename_function(&name, "renamed").await.unwrap();
+        assert_eq!(manager.clear_dynamic_libraries(), 0);
+
+        manager
+            .unload_plugin(&TargetComputeFunc::new("renamed".to_string()))
+            .await
+            .unwrap();
+        // Unloading already freed the library.
+        assert!(manager.loaded_libraries.get_mut().is_empty());
+        assert_eq!(manager.clear_dynamic_libraries(), 0);
+        assert!(manager
+            .push_request(&request("logger", json!("still here")))
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn try_push_request_is_busy_while_the_lock_is_held() {
+        let manager = ComputeFunctionManager::with_logger();
+        let req = request("logger", json!("hello"));
+
+        let guard = manager.functions.write().await;
+        assert_eq!(manager.try_push_request(&req).await.unwrap_err(), Busy);
+        drop(guard);
+
+        assert!(manager.try_push_request(&req).await.unwrap().is_ok());
+    }
+
+    #[derive(Debug)]
+    struct BrokenContract;
+
+    #[async_trait::async_trait]
+    impl ComputeFunction for BrokenContract {
+        fn name(&self) -> &'static str {
+            "broken_contract"
+        }
+
+        fn output_schema(&self) -> Option<serde_json::Value> {
+            Some(json!({
+                "type": "object",
+                "required": ["result"],
+                "properties": { "result": { "type": "number" } }
+            }))
+        }
+
+        async fn receive_request(
+            &self,
+            request: &ComputeRequest,
+        ) -> Result<ComputeResponse, crate::BadRequestError> {
+            Ok(ComputeResponse::json_ok(request.data().clone()))
+        }
+    }
+
+    #[tokio::test]
+    async fn responses_violating_the_output_schema_are_rejected() {
+        let mut manager = ComputeFunctionManager::new();
+        manager.set_strict_output_validation(true);
+        manager.load_builtin_instance(Box::new(BrokenContract));
+
+        let valid = manager
+            .push_request(&request("broken_contract", json!({ "result": 1 })))
+            .await;
+        assert!(valid.is_ok());
+
+        let invalid = manager
+            .push_request(&request("broken_contract", json!({ "result": "one" })))
+            .await;
+        assert!(matches!(invalid, Err(AppError::Other(msg)) if msg.contains("output schema")));
+    }
+
+    #[tokio::test]
+    async fn rename_function_rekeys_the_function() {
+        let manager = ComputeFunctionManager::with_logger();
+
+        manager.rename_function("logger", "audit").await.unwrap();
+
+        let old = manager.push_request(&request("logger", json!("hi"))).await;
+        assert!(matches!(old, Err(AppError::TargetNotFound(_))));
+        let new = manager.push_request(&request("audit", json!("hi"))).await;
+        assert!(new.is_ok());
+    }
+
+    #[tokio::test]
+    async fn rename_function_requires_an_existing_target() {
+        let manager = ComputeFunctionManager::new();
+
+        let result = manager.rename_function("missing", "anything").await;
+        assert!(matches!(result, Err(AppError::TargetNotFound(t)) if t.name() == "missing"));
+    }
+
+    #[tokio::test]
+    async fn rename_function_refuses_to_overwrite() {
+        let mut manager = ComputeFunctionManager::with_logger();
+        manager.load_builtin_instance(Box::new(BrokenContract));
+
+        let result = manager.rename_function("logger", "broken_contract").await;
+        assert!(matches!(
+            result,
+            Err(AppError::Loading(LoadingError::FunctionNameCollision(_)))
+        ));
+        assert!(manager
+            .push_request(&request("logger", json!("still here")))
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn renamed_builtins_can_be_loaded_again() {
+        let manager = ComputeFunctionManager::new();
+        manager
+            .load_builtin_function(BuiltinFunction::Logger)
+            .await
+            .unwrap();
+
+        manager.rename_function("logger", "audit").await.unwrap();
+        let outcome = manager
+            .load_builtin_function(BuiltinFunction::Logger)
+            .await
+            .unwrap();
+        assert_eq!(outcome, LoadOutcome::Loaded("logger".to_string()));
+        assert!(manager
+            .push_request(&request("logger", json!("hi")))
+            .await
+            .is_ok());
+        assert!(manager
+            .push_request(&request("audit", json!("hi")))
+            .await
+            .is_ok());
+
+        manager
+            .unload_plugin(&TargetComputeFunc::new("logger".to_string()))
+            .await
+            .unwrap();
+        manager.rename_function("audit", "logger").await.unwrap();
+        let outcome = manager
+            .load_builtin_function(BuiltinFunction::Logger)
+            .await
+            .unwrap();
+        assert_eq!(outcome, LoadOutcome::AlreadyPresent("logger".to_string()));
+    }
+
+    #[tokio::test]
+    async fn renamed_builtins_are_restored_from_manifests() {
+        let manager = ComputeFunctionManager::with_logger();
+        manager.rename_function("logger", "audit").await.unwrap();
+
+        let path = std::env::temp_dir().join(format!("manifest-{}.json", uuid::Uuid::new_v4()));
+        manager.save_manifest(&path).await.unwrap();
+        let restored = ComputeFunctionManager::new();
+        let outcomes = unsafe { restored.load_manifest(&path) }.await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(
+            outcomes.iter().all(|(_, result)| result.is_ok()),
+            "{:?}",
+            outcomes
+        );
+        let functions = restored.list_functions().await;
+        assert_eq!(functions.len(), 1);
+        assert_eq!(functions[0].name(), "audit");
+        assert_eq!(functions[0].origin(), &FunctionOrigin::Builtin);
+        assert!(restored
+            .push_request(&request("audit", json!("hi")))
+            .await
+            .is_ok());
+        let outcome = restored
+            .load_builtin_function(BuiltinFunction::Logger)
+            .await
+            .unwrap();
+        assert_eq!(outcome, LoadOutcome::Loaded("logger".to_string()));
+    }
+
+    #[tokio::test]
+    async fn aliases_dispatch_to_the_function_until_it_is_unloaded() {
+        let mut manager = ComputeFunctionManager::with_logger();
+        manager.load_builtin_instance(Box::new(Echo));
+
+        manager.add_alias("echo", "repeat").await.unwrap();
+        manager.add_alias("repeat", "parrot").await.unwrap();
+        for name in ["repeat", "parrot", "parrot?x=1"] {
+            let response = manager.push_request(&request(name, json!(1))).await;
+            assert_eq!(response.unwrap().data(), Some(json!(1)));
+        }
+        assert_eq!(manager.call_stats.get("echo").calls, 3);
+        assert!(manager.describe_function("repeat").await.is_none());
+
+        let collisions = [("echo", "logger"), ("logger", "repeat")];
+        for (existing, alias) in collisions {
+            assert!(matches!(
+                manager.add_alias(existing, alias).await,
+                Err(AppError::Loading(LoadingError::FunctionNameCollision(_)))
+            ));
+        }
+        assert!(matches!(
+            manager.rename_function("logger", "parrot").await,
+            Err(AppError::Loading(LoadingError::FunctionNameCollision(_)))
+        ));
+        assert!(matches!(
+            manager.add_alias("missing", "lost").await,
+            Err(AppError::TargetNotFound(_))
+        ));
+
+        manager.rename_function("echo", "reverb").await.unwrap();
+        assert!(manager
+            .push_request(&request("repeat", json!(1)))
+            .await
+            .is_ok());
+
+        manager
+            .unload_plugin(&TargetComputeFunc::new("reverb".to_string()))
+            .await
+            .unwrap();
+        assert!(matches!(
+            manager.push_request(&request("repeat", json!(1))).await,
+            Err(AppError::TargetNotFound(_))
+        ));
+        manager.add_alias("logger", "repeat").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn aliased_plugins_are_loaded_once() {
+        let manager = ComputeFunctionManager::new();
+        let name = unsafe { manager.load_plugin(fixtures::sample_plugin_path()).await }
+            .unwrap()
+            .into_name();
+
+        manager.add_alias(&name, "sample_alias").await.unwrap();
+        assert!(manager
+            .push_request(&request("sample_alias", json!(1)))
+            .await
+            .is_ok());
+        assert_eq!(manager.loaded_libraries.lock().await.len(), 1);
+        assert_eq!(manager.list_functions().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn push_request_rejects_a_blank_target() {
+        let manager = ComputeFunctionManager::with_logger();
+
+        let result = manager.push_request(&request(" ", json!(null))).await;
+        assert!(matches!(result, Err(AppError::BadRequest(_))));
+    }
+
+    #[tokio::test]
+    async fn dropping_a_shared_manager_does_not_panic() {
+        let manager = std::sync::Arc::new(tokio::sync::Mutex::new(
+            ComputeFunctionManager::with_logger(),
+        ));
+        let clone = manager.clone();
+
+        let handle = tokio::spawn(async move {
+            let locked = clone.lock().await;
+            locked
+                .push_request(&request("logger", json!("bye")))
+                .await
+                .map(|_| ())
+        });
+        handle.await.unwrap().unwrap();
+
+        drop(manager);
+    }
+
+    #[derive(Debug)]
+    struct Gate {
+        entered: std::sync::Arc<tokio::sync::Notify>,
+        release: std::sync::Arc<tokio::sync::Notify>,
+    }
+
+    #[async_trait::async_trait]
+    impl ComputeFunction for Gate {
+        fn name(&self) -> &'static str {
+            "gate"
+        }
+
+        async fn receive_request(
+            &self,
+            _request: &ComputeRequest,
+        ) -> Result<ComputeResponse, crate::BadRequestError> {
+            self.entered.notify_one();
+            self.release.notified().await;
+            Ok(ComputeResponse::ok())
+        }
+    }
+
+    #[tokio::test]
+    async fn requests_over_the_payload_budget_are_shed() {
+        let entered = std::sync::Arc::new(tokio::sync::Notify::new());
+        let release = std::sync::Arc::new(tokio::sync::Notify::new());
+        let mut manager = ComputeFunctionManager::with_logger();
+        manager.load_builtin_instance(Box::new(Gate {
+            entered: entered.clone(),
+            release: release.clone(),
+        }));
+        manager.set_max_in_flight_bytes(24);
+        let manager = std::sync::Arc::new(manager);
+
+        let payload = json!("0123456789abcdef");
+        let blocked = {
+            let manager = manager.clone();
+            let payload = payload.clone();
+            tokio::spawn(async move { manager.push_request(&request("gate", payload)).await })
+        };
+        entered.notified().await;
+
+        let shed = manager
+            .push_request(&request("logger", payload.clone()))
+            .await;
+        assert!(matches!(shed, Err(AppError::Overloaded(_))));
+        assert_eq!(shed.unwrap_err().as_generic_status_code().to_u16(), 503);
+
+        release.notify_one();
+        assert!(blocked.await.unwrap().is_ok());
+        assert!(manager
+            .push_request(&request("logger", payload))
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn payloads_larger_than_the_whole_budget_are_too_large() {
+        let mut manager = ComputeFunctionManager::with_logger();
+        manager.set_max_in_flight_bytes(8);
+        let payload = json!("0123456789abcdef");
+
+        let rejected = manager
+            .push_request(&request("logger", payload.clone()))
+            .await;
+        assert!(matches!(rejected, Err(AppError::PayloadTooLarge(_))));
+        assert_eq!(rejected.unwrap_err().as_generic_status_code().to_u16(), 413);
+        assert!(matches!(
+            manager.try_push_request(&request("logger", payload)).await,
+            Ok(Err(AppError::PayloadTooLarge(_)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn drain_waits_for_in_flight_requests_and_turns_new_ones_away() {
+        let entered = std::sync::Arc::new(tokio::sync::Notify::new());
+        let release = std::sync::Arc::new(tokio::sync::Notify::new());
+        let mut manager = ComputeFunctionManager::with_logger();
+        manager.load_builtin_instance(Box::new(Gate {
+            entered: entered.clone(),
+            release: release.clone(),
+        }));
+        let manager = std::sync::Arc::new(manager);
+
+        let slow = {
+            let manager = manager.clone();
+            tokio::spawn(async move { manager.push_request(&request("gate", json!(null))).await })
+        };
+        entered.notified().await;
+
+        let mut drain = {
+            let manager = manager.clone();
+            tokio::spawn(async move { manager.drain().await })
+        };
+        while !manager.is_draining() {
+            tokio::task::yield_now().await;
+        }
+        let turned_away = manager.push_request(&request("logger", json!("hi"))).await;
+        let err = turned_away.unwrap_err();
+        assert_eq!(err.code(), "draining");
+        assert_eq!(err.as_generic_status_code().to_u16(), 503);
+        assert!(matches!(
+            manager
+                .try_push_request(&request("logger", json!("hi")))
+                .await,
+            Ok(Err(AppError::Draining))
+        ));
+
+        let early = tokio::time::timeout(Duration::from_millis(20), &mut drain).await;
+        assert!(early.is_err(), "drain finished with a request in flight");
+        assert_eq!(manager.drain.in_flight(), 1);
+
+        release.notify_one();
+        assert!(slow.await.unwrap().is_ok());
+        tokio::time::timeout(Duration::from_secs(5), drain)
+            .await
+            .expect("drain never finished")
+            .unwrap();
+        assert_eq!(manager.drain.in_flight(), 0);
+
+        let mut manager = std::sync::Arc::try_unwrap(manager).unwrap();
+        manager.unload_all();
+        assert!(manager.list_functions().await.is_empty());
+    }
+
+    #[derive(Debug)]
+    struct UnloadCounter(std::sync::Arc<AtomicU64>);
+
+    #[async_trait::async_trait]
+    impl ComputeFunction for UnloadCounter {
+        fn name(&self) -> &'static str {
+            "unload_counter"
+        }
+
+        fn on_plugin_unload(&self) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+
+        async fn receive_request(
+            &self,
+            _request: &ComputeRequest,
+        ) -> Result<ComputeResponse, crate::BadRequestError> {
+            Ok(ComputeResponse::ok())
+        }
+    }
+
+    #[tokio::test]
+    async fn shutdown_unloads_everything_exactly_once() {
+        let unloads = std::sync::Arc::new(AtomicU64::new(0));
+        let mut manager = ComputeFunctionManager::new();
+        manager.load_builtin_instance(Box::new(UnloadCounter(std::sync::Arc::clone(&unloads))));
+        unsafe { manager.load_plugin(fixtures::sample_plugin_path()).await }.unwrap();
+        assert_eq!(manager.loaded_libraries.lock().await.len(), 1);
+
+        manager.shutdown().await;
+        assert_eq!(unloads.load(Ordering::SeqCst), 1);
+    }
+
+    #[derive(Debug, Default)]
+    struct ByteCounter;
+
+    #[async_trait::async_trait]
+    impl ComputeFunction for ByteCounter {
+        fn name(&self) -> &'static str {
+            "byte_counter"
+        }
+
+        async fn receive_request(
+            &self,
+            _request: &ComputeRequest,
+        ) -> Result<ComputeResponse, crate::BadRequestError> {
+            Ok(ComputeResponse::ok())
+        }
+
+        async fn receive_body_stream(
+            &self,
+            _target: &TargetComputeFunc,
+            mut body: BodyStream,
+        ) -> Result<ComputeResponse, crate::BadRequestError> {
+            let mut total = 0;
+            while let Some(chunk) = body.next_chunk().await {
+                let chunk = chunk
+                    .map_err(|err| crate::BadRequestError::without_request(self.name(), &err))?;
+                total += chunk.len();
+            }
+            Ok(ComputeResponse::json_ok(json!({ "bytes": total })))
+        }
+    }
+
+    #[tokio::test]
+    async fn streamed_bodies_reach_the_function_in_chunks() {
+        let mut manager = ComputeFunctionManager::with_logger();
+        manager.load_builtin_instance(Box::new(ByteCounter));
+
+        let chunks = (0..64).map(|_| Ok::<_, std::io::Error>(vec![7_u8; 64 * 1024]));
+        let body = BodyStream::from_stream(futures_util::stream::iter(chunks));
+        let response = manager
+            .push_body_stream(
+                &TargetComputeFunc::new("byte_counter".to_string()),
+                RequestContext::new(),
+                body,
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.data(), Some(json!({ "bytes": 64 * 64 * 1024 })));
+
+        let empty =
+            BodyStream::from_stream(futures_util::stream::empty::<Result<Vec<u8>, String>>());
+        let rejected = manager
+            .push_body_stream(
+                &TargetComputeFunc::new("logger".to_string()),
+                RequestContext::new(),
+                empty,
+            )
+            .await;
+        assert!(matches!(rejected, Err(AppError::BadRequest(_))));
+    }
+
+    #[tokio::test]
+    async fn streamed_bodies_are_bounded_without_holding_up_unloads() {
+        let mut manager = ComputeFunctionManager::new();
+        manager.load_builtin_instance(Box::new(ByteCounter));
+        manager.set_default_timeout(Duration::from_millis(50));
+        let target = TargetComputeFunc::new("byte_counter".to_string());
+        // A client that never finishes sending its body.
+        let stalled =
+            || BodyStream::from_stream(futures_util::stream::pending::<Result<Vec<u8>, String>>());
+
+        let timed_out = manager
+            .push_body_stream(&target, RequestContext::new(), stalled())
+            .await;
+        assert!(matches!(timed_out, Err(AppError::Timeout { .. })));
+
+        let context = RequestContext::new().with_timeout(Duration::from_millis(10));
+        let past_deadline = manager.push_body_stream(&target, context, stalled()).await;
+        assert!(matches!(past_deadline, Err(AppError::DeadlineExceeded(_))));
+
+        let (streamed, unloaded) = tokio::join!(
+            manager.push_body_stream(&target, RequestContext::new(), stalled()),
+            tokio::time::timeout(Duration::from_millis(25), manager.unload_plugin(&target)),
+        );
+        assert!(matches!(streamed, Err(AppError::Timeout { .. })));
+        assert!(matches!(unloaded, Ok(Ok(()))));
+    }
+
+    #[derive(Debug)]
+    struct Countdown;
+
+    #[async_trait::async_trait]
+    impl ComputeFunction for Countdown {
+        fn name(&self) -> &'static str {
+            "countdown"
+        }
+
+        async fn receive_request(
+            &self,
+            _request: &ComputeRequest,
+        ) -> Result<ComputeResponse, crate::BadRequestError> {
+            Ok(ComputeResponse::json_ok(json!({ "remaining": 0 })))
+        }
+
+        async fn receive_streaming(
+            &self,
+            _request: &ComputeRequest,
+            sink: ResponseSink,
+        ) -> Result<(), crate::BadRequestError> {
+            for remaining in (0..3).rev() {
+                if !sink
+                    .send(ComputeResponse::json_ok(json!({ "remaining": remaining })))
+                    .await
+                {
+                    break;
+                }
+            }
+            Ok(())
+        }
+    }
+
+    /// The data of every frame `target` streams for a request of `data`.
+    async fn streamed_frames(
+        manager: &ComputeFunctionManager,
+        target: &str,
+        data: serde_json::Value,
+    ) -> AppResult<Vec<Option<serde_json::Value>>> {
+        let (sink, mut receiver) = ResponseSink::channel(1);
+        let request = ComputeRequest::new(TargetComputeFunc::new(target.to_string()), data);
+        let collect = async move {
+            let mut frames = Vec::new();
+            while let Some(frame) = receiver.recv().await {
+                frames.push(frame.data());
+            }
+            frames
+        };
+        let (result, frames) =
+            tokio::join!(manager.push_streaming_request(&request, sink), collect);
+        result.map(|_| frames)
+    }
+
+    #[tokio::test]
+    async fn streaming_requests_emit_every_frame() {
+        let mut manager = ComputeFunctionManager::with_logger();
+        manager.load_builtin_instance(Box::new(Countdown));
+
+        assert_eq!(
+            streamed_frames(&manager, "countdown", json!(null))
+                .await
+                .unwrap(),
+            vec![
+                Some(json!({ "remaining": 2 })),
+                Some(json!({ "remaining": 1 })),
+                Some(json!({ "remaining": 0 })),
+            ]
+        );
+        // Functions that don't stream answer with a single frame.
+        assert_eq!(
+            streamed_frames(&manager, "logger", json!("streamed"))
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(matches!(
+            streamed_frames(&manager, "missing", json!(null)).await,
+            Err(AppError::TargetNotFound(_))
+        ));
+    }
+
+    #[derive(Debug)]
+    struct Stalled;
+
+    #[async_trait::async_trait]
+    impl ComputeFunction for Stalled {
+        fn name(&self) -> &'static str {
+            "stalled"
+        }
+
+        async fn receive_request(
+            &self,
+            _request: &ComputeRequest,
+        ) -> Result<ComputeResponse, crate::BadRequestError> {
+            Ok(ComputeResponse::ok())
+        }
+
+        async fn receive_streaming(
+            &self,
+            _request: &ComputeRequest,
+            _sink: ResponseSink,
+        ) -> Result<(), crate::BadRequestError> {
+            std::future::pending().await
+        }
+    }
+
+    #[tokio::test]
+    async fn streaming_requests_are_bounded_counted_and_reported() {
+        let events = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut manager = ComputeFunctionManager::new();
+        manager.load_builtin_instance(Box::new(Countdown));
+        manager.load_builtin_instance(Box::new(Stalled));
+        manager.set_default_timeout(Duration::from_millis(50));
+        manager.set_max_in_flight_bytes(16);
+        manager.set_event_sink({
+            let events = std::sync::Arc::clone(&events);
+            move |event| events.lock().unwrap().push(event)
+        });
+
+        assert!(streamed_frames(&manager, "countdown", json!(null))
+            .await
+            .is_ok());
+        assert!(matches!(
+            streamed_frames(&manager, "stalled", json!(null)).await,
+            Err(AppError::Timeout { .. })
+        ));
+        assert!(matches!(
+            streamed_frames(&manager, "countdown", json!("x".repeat(32))).await,
+            Err(AppError::PayloadTooLarge(_))
+        ));
+
+        let stats = manager.stats();
+        assert_eq!(
+            (
+                stats["countdown"].call_count,
+                stats["countdown"].error_count
+            ),
+            (1, 0)
+        );
+        assert_eq!(
+            (stats["stalled"].call_count, stats["stalled"].error_count),
+            (1, 1)
+        );
+        let dispatched: Vec<_> = events
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|event| match event {
+                ManagerEvent::RequestDispatched {
+                    status, error_code, ..
+                } => Some((*status, *error_code)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            dispatched,
+            vec![
+                (200, None),
+                (504, Some("timeout")),
+                (413, Some("payload_too_large"))
+            ]
+        );
+    }
+
+    #[test]
+    fn call_logging_samples_successes_but_not_errors() {
+        let mut manager = ComputeFunctionManager::new();
+        manager.set_call_log_sample_rate(0.1);
+        let ok = Ok(ComputeResponse::ok());
+        let err = Err(AppError::other("broken"));
+
+        let logged_ok = (0..10_000)
+            .filter(|_| manager.log_call("logger", None, &ok))
+            .count();
+        assert!(
+            (500..1_500).contains(&logged_ok),
+            "logged {} of 10000",
+            logged_ok
+        );
+        assert!((0..1_000).all(|_| manager.log_call("logger", None, &err)));
+
+        manager.set_function_call_log_sample_rate("logger", 0.0);
+        assert!((0..1_000).all(|_| !manager.log_call("logger", None, &ok)));
+        assert!((0..1_000).all(|_| manager.log_call("logger", None, &err)));
+    }
+
+    #[derive(Debug)]
+    struct EchoOnlyForGuests;
+
+    impl AuthPolicy for EchoOnlyForGuests {
+        fn authorize(&self, ctx: &crate::RequestContext, target: &TargetComputeFunc) -> bool {
+            ctx.tenant() != Some("guest") || target.name() == "echo"
+        }
+    }
+
+    #[derive(Debug)]
+    struct Echo;
+
+    #[async_trait::async_trait]
+    impl ComputeFunction for Echo {
+        fn name(&self) -> &'static str {
+            "echo"
+        }
+
+        async fn receive_request(
+            &self,
+            request: &ComputeRequest,
+        ) -> Result<ComputeResponse, crate::BadRequestError> {
+            Ok(ComputeResponse::json_ok(request.data().clone()))
+        }
+    }
+
+    #[tokio::test]
+    async fn auth_policy_is_consulted_before_dispatch() {
+        let mut manager = ComputeFunctionManager::with_logger();
+        manager.load_builtin_instance(Box::new(Echo));
+        manager.set_auth_policy(EchoOnlyForGuests);
+        let guest = |target| {
+            request(target, json!("hi"))
+                .with_context(crate::RequestContext::new().with_tenant("guest"))
+        };
+
+        assert!(manager.push_request(&guest("echo")).await.is_ok());
+        let denied = manager.push_request(&guest("logger")).await;
+        assert!(matches!(&denied, Err(AppError::Forbidden(t)) if t.name() == "logger"));
+        assert_eq!(denied.unwrap_err().as_generic_status_code().to_u16(), 403);
+
+        let member = request("logger", json!("hi"))
+            .with_context(crate::RequestContext::new().with_tenant("member"));
+        assert!(manager.push_request(&member).await.is_ok());
+    }
+
+    #[derive(Debug)]
+    struct Adder;
+
+    #[async_trait::async_trait]
+    impl ComputeFunction for Adder {
+        fn name(&self) -> &'static str {
+            "math/add"
+        }
+
+        async fn receive_request(
+            &self,
+            request: &ComputeRequest,
+        ) -> Result<ComputeResponse, crate::BadRequestError> {
+            Ok(ComputeResponse::json_ok(json!({
+                "subpath": request.subpath(),
+                "round": request.query().get("round"),
+            })))
+        }
+    }
+
+    #[tokio::test]
+    async fn requests_are_dispatched_on_the_target_basename() {
+        let mut manager = ComputeFunctionManager::with_logger();
+        manager.load_builtin_instance(Box::new(Adder));
+
+        let response = manager
+            .push_request(&request("math/add/exact?round=true", json!(null)))
+            .await
+            .unwrap();
+        assert_eq!(
+            response.data(),
+            Some(json!({ "subpath": "exact", "round": "true" }))
+        );
+        assert!(manager
+            .push_request(&request("logger?level=debug", json!("hi")))
+            .await
+            .is_ok());
+        assert!(matches!(
+            manager
+                .push_request(&request("math/sub", json!(null)))
+                .await,
+            Err(AppError::TargetNotFound(_))
+        ));
+        let description = manager.describe_function("math/add").await.unwrap();
+        assert_eq!(description.metrics().calls, 1);
+    }
+
+    #[derive(Debug)]
+    struct Sleepy;
+
+    #[async_trait::async_trait]
+    impl ComputeFunction for Sleepy {
+        fn name(&self) -> &'static str {
+            "sleepy"
+        }
+
+        async fn receive_request(
+            &self,
+            _request: &ComputeRequest,
+        ) -> Result<ComputeResponse, crate::BadRequestError> {
+            tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+            Ok(ComputeResponse::ok())
+        }
+    }
+
+    #[derive(Debug)]
+    struct Lagging;
+
+    #[async_trait::async_trait]
+    impl ComputeFunction for Lagging {
+        fn name(&self) -> &'static str {
+            "lagging"
+        }
+
+        async fn receive_request(
+            &self,
+            _request: &ComputeRequest,
+        ) -> Result<ComputeResponse, crate::BadRequestError> {
+            tokio::time::sleep(Duration::from_millis(30)).await;
+            Ok(ComputeResponse::ok())
+        }
+    }
+
+    #[tokio::test]
+    async fn slow_calls_are_warned_about_but_still_succeed() {
+        let logs = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let writer = {
+            let logs = std::sync::Arc::clone(&logs);
+            move || LogBuffer(std::sync::Arc::clone(&logs))
+        };
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(writer)
+            .with_ansi(false)
+            .finish();
+        let _default = tracing::subscriber::set_default(subscriber);
+
+        let mut manager = ComputeFunctionManager::with_logger();
+        manager.load_builtin_instance(Box::new(Lagging));
+        manager.set_slow_threshold(Duration::from_millis(10));
+        let lagging = request("lagging", json!(null))
+            .with_context(crate::RequestContext::new().with_request_id("req-42"));
+
+        assert!(manager.push_request(&lagging).await.is_ok());
+        assert!(manager
+            .push_request(&request("logger", json!("quick")))
+            .await
+            .is_ok());
+
+        let logs = String::from_utf8(logs.lock().unwrap().clone()).unwrap();
+        let warnings: Vec<&str> = logs
+            .lines()
+            .filter(|line| line.contains("Slow call"))
+            .collect();
+        assert_eq!(warnings.len(), 1, "{}", logs);
+        assert!(warnings[0].contains("'lagging'"));
+        assert!(warnings[0].contains("req-42"));
+    }
+
+    #[derive(Debug)]
+    struct Picky;
+
+    #[async_trait::async_trait]
+    impl ComputeFunction for Picky {
+        fn name(&self) -> &'static str {
+            "picky"
+        }
+
+        async fn receive_request(
+            &self,
+            _request: &ComputeRequest,
+        ) -> Result<ComputeResponse, crate::BadRequestError> {
+            Err(crate::BadRequestError::without_request(
+                self.name(),
+                "never satisfied",
+            ))
+        }
+    }
+
+    #[tokio::test]
+    async fn rejections_carry_the_request_id() {
+        let mut manager = ComputeFunctionManager::with_logger();
+        manager.load_builtin_instance(Box::new(Picky));
+        let tagged = |target: &str| {
+            request(target, json!(1))
+                .with_context(crate::RequestContext::new().with_request_id("req-7"))
+        };
+
+        for target in ["picky", "logger"] {
+            match manager.push_request(&tagged(target)).await {
+                Err(AppError::BadRequest(err)) => assert_eq!(err.request_id(), Some("req-7")),
+                other => panic!("expected a BadRequest from '{}', got {:?}", target, other),
+            }
+        }
+        match manager.push_request(&request("picky", json!(1))).await {
+            Err(AppError::BadRequest(err)) => assert_eq!(err.request_id(), None),
+            other => panic!("expected a BadRequest, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn requests_are_dispatched_concurrently() {
+        const REQUESTS: u32 = 10;
+        let mut manager = ComputeFunctionManager::with_logger();
+        manager.load_builtin_instance(Box::new(Lagging));
+        let lagging = request("lagging", json!(null));
+
+        let started = Instant::now();
+        let results =
+            futures_util::future::join_all((0..REQUESTS).map(|_| manager.push_request(&lagging)))
+                .await;
+        let elapsed = started.elapsed();
+
+        assert!(results.iter().all(Result::is_ok));
+        assert!(
+            elapsed < Duration::from_millis(30) * REQUESTS / 2,
+            "{} requests took {:?}",
+            REQUESTS,
+            elapsed
+        );
+    }
+
+    /// Collects everything written to it, for checking what was logged.
+    struct LogBuffer(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for LogBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn push_request_timeout_gives_up_on_slow_functions() {
+        let mut manager = ComputeFunctionManager::with_logger();
+        manager.load_builtin_instance(Box::new(Sleepy));
+
+        let result = manager
+            .push_request_timeout(&request("sleepy", json!(null)), Duration::from_millis(10))
+            .await;
+        let err = result.unwrap_err();
+        assert_eq!(err.code(), "timeout");
+        assert_eq!(err.as_generic_status_code().to_u16(), 504);
+        match err {
+            AppError::Timeout { target, elapsed } => {
+                assert_eq!(target.name(), "sleepy");
+                assert!(elapsed >= Duration::from_millis(10));
+            }
+            other => panic!("expected a timeout, got {:?}", other),
+        }
+        assert_eq!(manager.call_stats.get("sleepy").errors, 1);
+    }
+
+    #[tokio::test]
+    async fn push_request_timeout_does_not_count_lock_waits() {
+        let mut manager = ComputeFunctionManager::with_logger();
+        manager.load_builtin_instance(Box::new(Echo));
+
+        let echo = request("echo", json!(1));
+        let guard = manager.functions.write().await;
+        let (result, ()) = tokio::join!(
+            manager.push_request_timeout(&echo, Duration::from_millis(20)),
+            async move {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                drop(guard);
+            },
+        );
+        assert_eq!(result.unwrap().data(), Some(json!(1)));
+    }
+
+    #[tokio::test]
+    async fn stats_track_durations_on_success_and_failure_but_not_lock_waits() {
+        let mut manager = ComputeFunctionManager::with_logger();
+        manager.load_builtin_instance(Box::new(Lagging));
+        manager.load_builtin_instance(Box::new(Sleepy));
+
+        let lagging = request("lagging", json!(null));
+        let guard = manager.functions.write().await;
+        let (result, ()) = tokio::join!(manager.push_request(&lagging), async move {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            drop(guard);
+        });
+        assert!(result.is_ok());
+        manager.push_request(&lagging).await.unwrap();
+        let timed_out = manager
+            .push_request_timeout(&request("sleepy", json!(null)), Duration::from_millis(10))
+            .await;
+        assert!(timed_out.is_err());
+
+        let stats = manager.stats();
+        assert_eq!(stats.len(), 2);
+        let lagging = stats["lagging"];
+        assert_eq!((lagging.call_count, lagging.error_count), (2, 0));
+        assert!(lagging.max_duration >= Duration::from_millis(30));
+        assert!(lagging.max_duration < Duration::from_millis(200));
+        assert!(lagging.total_duration >= Duration::from_millis(60));
+        assert!(lagging.mean_duration() >= Duration::from_millis(30));
+        let sleepy = stats["sleepy"];
+        assert_eq!((sleepy.call_count, sleepy.error_count), (1, 1));
+        assert!(sleepy.max_duration >= Duration::from_millis(10));
+
+        let serialized = serde_json::to_value(lagging).unwrap();
+        assert_eq!(serialized["call_count"], json!(2));
+        assert!(serialized["max_duration"].is_object());
+    }
+
+    #[tokio::test]
+    async fn functions_are_abandoned_at_the_request_deadline() {
+        let mut manager = ComputeFunctionManager::with_logger();
+        manager.load_builtin_instance(Box::new(Sleepy));
+        let context =
+            || crate::RequestContext::new().with_timeout(std::time::Duration::from_millis(10));
+
+        let late = manager
+            .push_request(&request("sleepy", json!(null)).with_context(context()))
+            .await;
+        assert!(matches!(&late, Err(AppError::DeadlineExceeded(t)) if t.name() == "sleepy"));
+        assert_eq!(late.unwrap_err().as_generic_status_code().to_u16(), 504);
+
+        let on_time = manager
+            .push_request(&request("logger", json!("quick")).with_context(context()))
+            .await;
+        assert!(on_time.is_ok());
+    }
+
+    #[tokio::test]
+    async fn registered_factories_are_loaded_on_startup() {
+        let mut registry = BuiltinRegistry::with_builtins();
+        registry.register("custom_echo", || Box::new(Echo));
+        let manager = ComputeFunctionManager::with_registry(&registry);
+
+        let echoed = manager
+            .push_request(&request("custom_echo", json!({ "hello": "world" })))
+            .await
+            .unwrap();
+        assert_eq!(echoed.data(), Some(json!({ "hello": "world" })));
+        assert!(manager
+            .push_request(&request("logger", json!("hi")))
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn requests_exceeding_input_limits_are_rejected_before_dispatch() {
+        let mut manager = ComputeFunctionManager::with_logger();
+        manager.load_builtin_instance(Box::new(Echo));
+        manager.set_input_limits(InputLimits::new(4, 1024));
+
+        let keys: serde_json::Map<_, _> = (0..5).map(|i| (i.to_string(), json!(i))).collect();
+        let too_many = manager
+            .push_request(&request("echo", serde_json::Value::Object(keys)))
+            .await;
+        assert!(matches!(too_many, Err(AppError::BadRequest(e)) if e.message().contains("keys")));
+
+        let too_long = manager
+            .push_request(&request("echo", json!("x".repeat(1025))))
+            .await;
+        assert!(matches!(too_long, Err(AppError::BadRequest(e)) if e.message().contains("bytes")));
+
+        assert!(manager
+            .push_request(&request("echo", json!({ "fine": "x" })))
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn load_plugin_rejects_relative_paths() {
+        let manager = ComputeFunctionManager::new();
+
+        let result = unsafe { manager.load_plugin("plugins/sample.so".to_string()).await };
+
+        assert!(matches!(
+            &result,
+            Err(LoadingError::PathNotAbsolute(path)) if path == "plugins/sample.so"
+        ));
+        assert_eq!(
+            AppError::from(result.unwrap_err())
+                .as_generic_status_code()
+                .to_u16(),
+            400
+        );
+    }
+
+    #[tokio::test]
+    async fn load_plugin_resolves_relative_paths_against_the_base_dir() {
+        let library = fixtures::example_library(fixtures::SAMPLE_PLUGIN_NAME);
+        let examples_dir = library.parent().unwrap();
+        let profile_dir = examples_dir.parent().unwrap();
+        let relative = Path::new("examples").join(library.file_name().unwrap());
+
+        let mut manager = ComputeFunctionManager::new();
+        manager.set_base_dir(profile_dir);
+        let outcome = unsafe {
+            manager
+                .load_plugin(relative.to_string_lossy().into_owned())
+                .await
+        }
+        .unwrap();
+        assert_eq!(outcome.name(), fixtures::SAMPLE_PLUGIN_NAME);
+
+        // Absolute paths skip the base dir entirely.
+        let mut manager = ComputeFunctionManager::new();
+        manager.set_base_dir("/does/not/exist");
+        assert!(unsafe { manager.load_plugin(fixtures::sample_plugin_path()).await }.is_ok());
+    }
+
+    #[tokio::test]
+    async fn load_plugin_reports_relative_paths_missing_from_the_base_dir() {
+        let mut manager = ComputeFunctionManager::new();
+        manager.set_base_dir(std::env::temp_dir());
+
+        let result = unsafe { manager.load_plugin("no_such_plugin.so".to_string()).await };
+
+        assert!(matches!(result, Err(LoadingError::PathNotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn load_plugin_rejects_relative_paths_escaping_the_base_dir() {
+        let library = fixtures::example_library(fixtures::SAMPLE_PLUGIN_NAME);
+        let examples_dir = library.parent().unwrap();
+        let file_name = library.file_name().unwrap().to_string_lossy().into_owned();
+        let mut manager = ComputeFunctionManager::new();
+        manager.set_base_dir(examples_dir.join("nested"));
+
+        for escaping in [
+            format!("../{}", file_name),
+            format!("a/../../{}", file_name),
+        ] {
+            let result = unsafe { manager.load_plugin(escaping).await };
+            assert!(
+                matches!(result, Err(LoadingError::PathOutsideBaseDir(_))),
+                "{:?}",
+                result
+            );
+        }
+
+        // A symlink inside the base dir pointing out of it is caught once the path is resolved.
+        #[cfg(unix)]
+        {
+            let base = std::env::temp_dir().join(format!("cfm-base-{}", uuid::Uuid::new_v4()));
+            std::fs::create_dir(&base).unwrap();
+            std::os::unix::fs::symlink(&library, base.join(&file_name)).unwrap();
+            manager.set_base_dir(&base);
+            let result = unsafe { manager.load_plugin(file_name.clone()).await };
+            assert!(
+                matches!(result, Err(LoadingError::PathOutsideBaseDir(_))),
+                "{:?}",
+                result
+            );
+            std::fs::remove_dir_all(&base).unwrap();
+        }
+
+        manager.set_base_dir(examples_dir);
+        let outcome = unsafe { manager.load_plugin(format!("./{}", file_name)).await }.unwrap();
+        assert_eq!(outcome.name(), fixtures::SAMPLE_PLUGIN_NAME);
+    }
+
+    #[derive(Debug)]
+    struct Documented;
+
+    #[async_trait::async_trait]
+    impl ComputeFunction for Documented {
+        fn name(&self) -> &'static str {
+            "documented"
+        }
+
+        fn version(&self) -> Option<&'static str> {
+            Some("1.2.0")
+        }
+
+        fn input_schema(&self) -> Option<serde_json::Value> {
+            Some(json!({ "type": "string" }))
+        }
+
+        fn output_schema(&self) -> Option<serde_json::Value> {
+            Some(json!({ "type": "string" }))
+        }
+
+        fn examples(&self) -> Vec<serde_json::Value> {
+            vec![json!("hello")]
+        }
+
+        async fn receive_request(
+            &self,
+            request: &ComputeRequest,
+        ) -> Result<ComputeResponse, BadRequestError> {
+            match request.data() {
+                serde_json::Value::String(_) => {
+                    Ok(ComputeResponse::json_ok(request.data().clone()))
+                }
+                _ => Err(BadRequestError::new(
+                    self.name(),
+                    "Data must be a string",
+                    Some(request.clone()),
+                )),
+            }
+        }
+    }
+
+    #[derive(Debug)]
+    struct Resize(std::sync::Arc<AtomicU64>);
+
+    #[async_trait::async_trait]
+    impl ComputeFunction for Resize {
+        fn name(&self) -> &'static str {
+            "resize"
+        }
+
+        fn input_schema(&self) -> Option<serde_json::Value> {
+            Some(json!({
+                "type": "object",
+                "properties": { "width": { "type": "integer", "minimum": 1 } },
+                "required": ["width"],
+            }))
+        }
+
+        async fn receive_request(
+            &self,
+            _request: &ComputeRequest,
+        ) -> Result<ComputeResponse, BadRequestError> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Ok(ComputeResponse::ok())
+        }
+    }
+
+    #[tokio::test]
+    async fn requests_violating_the_input_schema_never_reach_the_function() {
+        let calls = std::sync::Arc::new(AtomicU64::new(0));
+        let mut manager = ComputeFunctionManager::new();
+        manager.load_builtin_instance(Box::new(Resize(std::sync::Arc::clone(&calls))));
+
+        for data in [json!("wide"), json!({}), json!({ "width": 0 })] {
+            match manager.push_request(&request("resize", data)).await {
+                Err(AppError::BadRequest(err)) => {
+                    assert!(err.message().contains("input schema"), "{}", err);
+                }
+                other => panic!("expected a BadRequest, got {:?}", other),
+            }
+        }
+        assert!(manager
+            .push_request(&request("resize", json!({ "width": 640 })))
+            .await
+            .is_ok());
+
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        let listed = manager.list_functions().await;
+        assert_eq!(
+            listed[0].input_schema().unwrap()["required"],
+            json!(["width"])
+        );
+    }
+
+    #[tokio::test]
+    async fn describe_function_includes_schema_and_call_counts() {
+        let mut manager = ComputeFunctionManager::new();
+        manager.load_builtin_instance(Box::new(Documented));
+        assert!(manager.describe_function("missing").await.is_none());
+
+        for data in [json!("a"), json!("b"), json!(3)] {
+            let _ = manager.push_request(&request("documented", data)).await;
+        }
+
+        let description = manager.describe_function("documented").await.unwrap();
+        assert_eq!(description.name(), "documented");
+        assert_eq!(description.version(), Some("1.2.0"));
+        assert_eq!(
+            description.input_schema(),
+            Some(&json!({ "type": "string" }))
+        );
+        assert_eq!(
+            description.output_schema(),
+            Some(&json!({ "type": "string" }))
+        );
+        assert_eq!(description.examples(), &[json!("hello")]);
+        assert_eq!(
+            description.metrics(),
+            crate::CallCounts {
+                calls: 3,
+                errors: 1,
+                ..description.metrics()
+            }
+        );
+
+        manager
+            .rename_function("documented", "renamed")
+            .await
+            .unwrap();
+        let renamed = manager.describe_function("renamed").await.unwrap();
+        assert_eq!(renamed.metrics().calls, 3);
+    }
+
+    /// The parts of the `OpenAPI` 3.0 document structure that tooling relies on.
+    fn openapi_schema() -> serde_json::Value {
+        let json_content = json!({
+            "type": "object",
+            "additionalProperties": {
+                "type": "object",
+                "required": ["schema"],
+                "properties": { "schema": { "type": "object" } },
+            },
+        });
+        let operation = json!({
+            "type": "object",
+            "required": ["responses"],
+            "properties": {
+                "operationId": { "type": "string" },
+                "summary": { "type": "string" },
+                "requestBody": {
+                    "type": "object",
+                    "required": ["content"],
+                    "properties": { "content": json_content },
+                },
+                "responses": {
+                    "type": "object",
+                    "minProperties": 1,
+                    "additionalProperties": {
+                        "type": "object",
+                        "required": ["description"],
+                    },
+                },
+            },
+        });
+
+        json!({
+            "type": "object",
+            "required": ["openapi", "info", "paths"],
+            "properties": {
+                "openapi": { "type": "string", "pattern": "^3\\.0\\.\\d+$" },
+                "info": {
+                    "type": "object",
+                    "required": ["title", "version"],
+                    "properties": {
+                        "title": { "type": "string" },
+                        "version": { "type": "string" },
+                    },
+                },
+                "paths": {
+                    "type": "object",
+                    "propertyNames": { "pattern": "^/" },
+                    "additionalProperties": {
+                        "type": "object",
+                        "properties": { "get": operation, "post": operation },
+                    },
+                },
+            },
+        })
+    }
+
+    #[tokio::test]
+    async fn export_openapi_describes_loaded_functions() {
+        let mut manager = ComputeFunctionManager::with_logger();
+        manager.load_builtin_instance(Box::new(Documented));
+
+        let document = manager.export_openapi().await;
+        if let Err(violations) = schema::validate(&openapi_schema(), &document) {
+            panic!("not a valid OpenAPI document: {:?}", violations);
+        }
+
+        let documented = &document["paths"]["/functions/documented"]["post"];
+        assert_eq!(documented["x-function-version"], "1.2.0");
+        assert_eq!(
+            documented["requestBody"]["content"]["application/json"]["schema"],
+            json!({ "type": "string" })
+        );
+        assert_eq!(
+            documented["requestBody"]["content"]["application/json"]["example"],
+            "hello"
+        );
+        assert!(document["paths"]["/functions/logger"].is_object());
+        assert!(document["paths"]["/functions"]["get"].is_object());
+    }
+
+    #[tokio::test]
+    async fn call_stats_count_payload_bytes() {
+        let mut manager = ComputeFunctionManager::with_logger();
+        manager.load_builtin_instance(Box::new(Echo));
+        let payload = json!({ "data": "x".repeat(1000) });
+        let size = payload.to_string().len() as u64;
+
+        manager
+            .push_request(&request("echo", payload.clone()))
+            .await
+            .unwrap();
+        manager
+            .push_request(&request("logger", json!("hello")))
+            .await
+            .unwrap();
+
+        let echo = manager.call_stats.get("echo");
+        assert_eq!(echo.bytes_in, size);
+        assert_eq!(echo.bytes_out, size);
+        let logger = manager.call_stats.get("logger");
+        assert_eq!(logger.bytes_in, 7);
+        assert_eq!(logger.bytes_out, 0);
+    }
+
+    #[tokio::test]
+    async fn total_requests_counts_across_requests_and_resets() {
+        let manager = ComputeFunctionManager::with_logger();
+        assert_eq!(manager.total_requests(), 0);
+
+        for _ in 0..3 {
+            manager
+                .push_request(&request("logger", json!("hello")))
+                .await
+                .unwrap();
+        }
+        let _ = manager.push_request(&request("missing", json!(null))).await;
+        let _ = manager
+            .try_push_request(&request("logger", json!("hello")))
+            .await;
+        assert_eq!(manager.total_requests(), 5);
+
+        manager.reset_total_requests();
+        assert_eq!(manager.total_requests(), 0);
+        manager
+            .push_request(&request("logger", json!("hello")))
+            .await
+            .unwrap();
+        assert_eq!(manager.total_requests(), 1);
+    }
+
+    #[tokio::test]
+    async fn replayed_nonces_are_rejected() {
+        let mut manager = ComputeFunctionManager::with_logger();
+        manager.set_replay_protection(std::time::Duration::from_secs(60), 1024);
+        let with_nonce = |nonce: &str| {
+            request("logger", json!("hello"))
+                .with_context(crate::RequestContext::new().with_nonce(Some(nonce.to_string())))
+        };
+
+        assert!(manager.push_request(&with_nonce("first")).await.is_ok());
+        assert!(matches!(
+            manager.push_request(&with_nonce("first")).await,
+            Err(AppError::Replayed(nonce)) if nonce == "first"
+        ));
+        assert!(manager.push_request(&with_nonce("second")).await.is_ok());
+        assert!(matches!(
+            manager
+                .push_request(&request("logger", json!("hello")))
+                .await,
+            Err(AppError::BadRequest(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn no_content_can_be_normalized_to_an_empty_object() {
+        let mut manager = ComputeFunctionManager::with_logger();
+
+        let lean = manager
+            .push_request(&request("logger", json!("hello")))
+            .await
+            .unwrap();
+        assert!(lean.data().is_none());
+
+        manager.set_no_content_as_empty_object(true);
+        let normalized = manager
+            .push_request(&request("logger", json!("hello")))
+            .await
+            .unwrap();
+        assert_eq!(normalized.status().to_u16(), 200);
+        assert_eq!(normalized.data(), Some(json!({})));
+    }
 }
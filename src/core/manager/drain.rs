@@ -0,0 +1,75 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use tokio::sync::Notify;
+
+/// Tracks the requests that are currently being handled, so that the manager can stop taking new
+/// ones and wait for the rest to finish before any functions (and the libraries their code lives in)
+/// go away.
+#[derive(Debug, Default)]
+pub struct DrainState {
+    draining: AtomicBool,
+    in_flight: AtomicUsize,
+    idle: Notify,
+}
+
+impl DrainState {
+    /// Count a request as in flight until the returned [`InFlight`] is dropped, or return [`None`]
+    /// if the manager is draining and new requests are turned away.
+    pub fn enter(&self) -> Option<InFlight<'_>> {
+        // Counting first means `wait_idle` can't miss a request that slips in as draining starts,
+        // it either sees the count or the request sees the flag (or both).
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        let guard = InFlight { state: self };
+        if self.draining.load(Ordering::SeqCst) {
+            return None;
+        }
+        Some(guard)
+    }
+
+    /// Turn away any new requests from now on.
+    pub fn start(&self) {
+        self.draining.store(true, Ordering::SeqCst);
+    }
+
+    /// Whether new requests are being turned away.
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::SeqCst)
+    }
+
+    /// The number of requests currently being handled.
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::SeqCst)
+    }
+
+    /// Wait until no requests are being handled.
+    pub async fn wait_idle(&self) {
+        loop {
+            // Created before checking the count so a request finishing in between still wakes it.
+            let idle = self.idle.notified();
+            if self.in_flight() == 0 {
+                return;
+            }
+            idle.await;
+        }
+    }
+}
+
+/// A request counted by [`DrainState::enter`], which stops counting when dropped.
+#[derive(Debug)]
+pub struct InFlight<'a> {
+    state: &'a DrainState,
+}
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        if self.state.in_flight.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.state.idle.notify_waiters();
+        }
+    }
+}
@@ -0,0 +1,156 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::types::{AppError, FunctionOrigin, LoadingError};
+
+/// The version of the manifest format written by [`Manifest::write`].
+pub const MANIFEST_VERSION: u32 = 1;
+
+/// The set of functions a manager had loaded, written by
+/// [`ComputeFunctionManager::save_manifest`](crate::ComputeFunctionManager::save_manifest) so that
+/// [`ComputeFunctionManager::load_manifest`](crate::ComputeFunctionManager::load_manifest) can load
+/// them again after a restart.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Manifest {
+    version: u32,
+    functions: Vec<ManifestEntry>,
+}
+
+/// A single function in a [`Manifest`], with everything needed to load it again.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ManifestEntry {
+    pub name: String,
+    pub origin: FunctionOrigin,
+    /// The [`BuiltinFunction`](crate::BuiltinFunction) a builtin was created as, for one that was
+    /// renamed and so can't be told apart by its name anymore.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub builtin: Option<String>,
+}
+
+impl Manifest {
+    /// Create a new [`Manifest`] of the current [`MANIFEST_VERSION`] listing `functions`.
+    #[must_use]
+    pub const fn new(functions: Vec<ManifestEntry>) -> Self {
+        Self {
+            version: MANIFEST_VERSION,
+            functions,
+        }
+    }
+
+    /// Read the manifest at `path`.
+    ///
+    /// ## Errors
+    /// - [`LoadingError::PathNotFound`] if there is nothing at `path`
+    /// - [`LoadingError::BadPath`] if `path` can't be read or isn't a manifest of a version this
+    ///   build understands
+    pub fn read(path: &Path) -> Result<Self, LoadingError> {
+        let contents = std::fs::read(path).map_err(|e| {
+            let message = format!("Unable to read manifest `{}`: {}", path.display(), e);
+            if e.kind() == std::io::ErrorKind::NotFound {
+                LoadingError::path_not_found(&message)
+            } else {
+                LoadingError::bad_path(&message)
+            }
+        })?;
+        let manifest: Self = serde_json::from_slice(&contents).map_err(|e| {
+            LoadingError::bad_path(&format!("`{}` is not a manifest: {}", path.display(), e))
+        })?;
+        if manifest.version > MANIFEST_VERSION {
+            return Err(LoadingError::bad_path(&format!(
+                "`{}` is a version {} manifest, only up to version {} is understood",
+                path.display(),
+                manifest.version,
+                MANIFEST_VERSION
+            )));
+        }
+        Ok(manifest)
+    }
+
+    /// Write the manifest to `path`, replacing whatever was there. It is written next to `path`
+    /// first and then moved into place, so a crash halfway through never leaves a truncated
+    /// manifest behind.
+    ///
+    /// ## Errors
+    /// An [`AppError::Other`] if the manifest can't be written.
+    pub fn write(&self, path: &Path) -> Result<(), AppError> {
+        let failed = |e: &dyn std::fmt::Display| {
+            AppError::other(&format!(
+                "Unable to write manifest `{}`: {}",
+                path.display(),
+                e
+            ))
+        };
+        let contents = serde_json::to_vec_pretty(self).map_err(|e| failed(&e))?;
+        let staged = path.with_extension("tmp");
+        std::fs::write(&staged, contents).map_err(|e| failed(&e))?;
+        std::fs::rename(&staged, path).map_err(|e| failed(&e))
+    }
+
+    /// The functions listed in the manifest.
+    #[must_use]
+    pub fn functions(&self) -> &[ManifestEntry] {
+        &self.functions
+    }
+
+    /// Consume the manifest and return the functions it lists.
+    #[must_use]
+    pub fn into_functions(self) -> Vec<ManifestEntry> {
+        self.functions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_through_a_file() {
+        let path = std::env::temp_dir().join(format!("{}.json", uuid::Uuid::new_v4()));
+        let manifest = Manifest::new(vec![
+            ManifestEntry {
+                name: "logger".to_string(),
+                origin: FunctionOrigin::Builtin,
+                builtin: None,
+            },
+            ManifestEntry {
+                name: "sample".to_string(),
+                origin: FunctionOrigin::Dynamic {
+                    path: "/plugins/libsample.so".to_string(),
+                },
+                builtin: None,
+            },
+            ManifestEntry {
+                name: "audit".to_string(),
+                origin: FunctionOrigin::Builtin,
+                builtin: Some("logger".to_string()),
+            },
+        ]);
+
+        manifest.write(&path).unwrap();
+        let read = Manifest::read(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(read.unwrap(), manifest);
+    }
+
+    #[test]
+    fn unreadable_manifests_are_rejected() {
+        let missing = std::env::temp_dir().join(format!("{}.json", uuid::Uuid::new_v4()));
+        assert!(matches!(
+            Manifest::read(&missing),
+            Err(LoadingError::PathNotFound(_))
+        ));
+
+        let newer = std::env::temp_dir().join(format!("{}.json", uuid::Uuid::new_v4()));
+        std::fs::write(&newer, r#"{ "version": 99, "functions": [] }"#).unwrap();
+        let read = Manifest::read(&newer);
+        std::fs::remove_file(&newer).unwrap();
+        assert!(matches!(read, Err(LoadingError::BadPath(m)) if m.contains("version 99")));
+    }
+}
@@ -4,6 +4,24 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+mod aliases;
+mod budget;
+mod builder;
 mod cfm;
+mod drain;
+mod manifest;
+mod names;
+mod openapi;
+#[cfg(feature = "proxy")]
+mod proxy;
+mod queue;
+mod replay;
+mod sampling;
+mod stats;
+mod subprocess;
+mod target_ops;
+#[cfg(feature = "wasm")]
+mod wasm;
 
-pub use cfm::{default_cfm, logger_cfm, ComputeFunctionManager};
+pub use builder::ComputeFunctionManagerBuilder;
+pub use cfm::ComputeFunctionManager;
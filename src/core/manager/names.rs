@@ -0,0 +1,45 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::{
+    collections::HashSet,
+    sync::{Mutex, PoisonError},
+};
+
+lazy_static::lazy_static! {
+    /// Every name handed out by [`intern`] so far.
+    static ref NAMES: Mutex<HashSet<&'static str>> = Mutex::default();
+}
+
+/// `name` as the `&'static str` [`ComputeFunction::name`](crate::ComputeFunction::name) requires,
+/// for functions that are only named at runtime. Each distinct name is leaked once and handed out
+/// again after that, so loading (and unloading, or failing to load) functions under the same names
+/// over and over doesn't leak any more memory.
+pub fn intern(name: &str) -> &'static str {
+    let mut names = NAMES.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(interned) = names.get(name) {
+        return interned;
+    }
+    let interned: &'static str = Box::leak(name.to_string().into_boxed_str());
+    names.insert(interned);
+    interned
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_are_only_leaked_once() {
+        let name = format!("interned-{}", uuid::Uuid::new_v4());
+        let first = intern(&name);
+        let second = intern(&name);
+
+        assert_eq!(first, name);
+        assert!(std::ptr::eq(first, second));
+        assert!(!std::ptr::eq(first, intern("something else")));
+    }
+}
@@ -0,0 +1,153 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use serde_json::{json, Map, Value as JsonValue};
+
+use crate::core::types::FunctionDescription;
+
+/// The `OpenAPI` version the generated documents conform to.
+pub const OPENAPI_VERSION: &str = "3.0.3";
+
+/// Assemble an `OpenAPI` document describing the HTTP API, with a `POST /functions/{name}` operation
+/// for each of `functions` built from its self-reported metadata and schemas. Only routes the
+/// server actually serves are described.
+pub fn document(functions: &[FunctionDescription]) -> JsonValue {
+    let mut paths = Map::new();
+    paths.insert(
+        "/".to_string(),
+        json!({
+            "post": {
+                "operationId": "process_input",
+                "summary": "Execute a request, or add, remove or list functions",
+                "requestBody": json_body(&json!({ "type": "object" }), None),
+                "responses": responses(&json!({})),
+            }
+        }),
+    );
+    paths.insert(
+        "/functions".to_string(),
+        json!({
+            "get": {
+                "operationId": "list_functions",
+                "summary": "List every loaded function",
+                "responses": responses(&json!({ "type": "array", "items": { "type": "object" } })),
+            }
+        }),
+    );
+    for function in functions {
+        paths.insert(
+            format!("/functions/{}", function.name()),
+            function_path(function),
+        );
+    }
+    paths.insert(
+        "/stream/{target}".to_string(),
+        json!({
+            "post": {
+                "operationId": "stream_body",
+                "summary": "Stream a raw request body to a function",
+                "parameters": [{
+                    "name": "target",
+                    "in": "path",
+                    "required": true,
+                    "schema": { "type": "string" },
+                }],
+                "requestBody": {
+                    "required": true,
+                    "content": {
+                        "application/octet-stream": {
+                            "schema": { "type": "string", "format": "binary" }
+                        }
+                    },
+                },
+                "responses": responses(&json!({})),
+            }
+        }),
+    );
+    paths.insert(
+        "/openapi.json".to_string(),
+        json!({
+            "get": {
+                "operationId": "openapi",
+                "summary": "This document",
+                "responses": responses(&json!({ "type": "object" })),
+            }
+        }),
+    );
+
+    json!({
+        "openapi": OPENAPI_VERSION,
+        "info": {
+            "title": env!("CARGO_PKG_NAME"),
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "paths": paths,
+        "components": {
+            "schemas": {
+                "ErrorEnvelope": {
+                    "type": "object",
+                    "required": ["status", "code", "message", "details"],
+                    "properties": {
+                        "status": { "type": "integer" },
+                        "code": { "type": "string" },
+                        "message": { "type": "string" },
+                        "details": {},
+                    },
+                }
+            }
+        },
+    })
+}
+
+/// The path item executing `function` with the request body as its data.
+fn function_path(function: &FunctionDescription) -> JsonValue {
+    let any = json!({});
+    let mut operation = json!({
+        "operationId": format!("execute_{}", function.name()),
+        "summary": function.description().unwrap_or_else(|| function.name()),
+        "requestBody": json_body(
+            function.input_schema().unwrap_or(&any),
+            function.examples().first(),
+        ),
+        "responses": responses(function.output_schema().unwrap_or(&any)),
+    });
+    if let Some(version) = function.version() {
+        operation["x-function-version"] = json!(version);
+    }
+
+    json!({ "post": operation })
+}
+
+/// A required JSON request body matching `schema`.
+fn json_body(schema: &JsonValue, example: Option<&JsonValue>) -> JsonValue {
+    let mut media = json!({ "schema": schema });
+    if let Some(example) = example {
+        media["example"] = example.clone();
+    }
+
+    json!({
+        "required": true,
+        "content": { "application/json": media },
+    })
+}
+
+/// A successful JSON response matching `schema`, and the error envelope for everything else.
+fn responses(schema: &JsonValue) -> JsonValue {
+    json!({
+        "200": {
+            "description": "Success",
+            "content": { "application/json": { "schema": schema } },
+        },
+        "default": {
+            "description": "Error",
+            "content": {
+                "application/json": {
+                    "schema": { "$ref": "#/components/schemas/ErrorEnvelope" }
+                }
+            },
+        },
+    })
+}
@@ -0,0 +1,213 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! [`ComputeFunction`]s that forward their requests to a remote HTTP endpoint, see
+//! [`ComputeFunctionManager::load_proxy`](crate::ComputeFunctionManager::load_proxy).
+
+use std::time::Duration;
+
+use reqwest::{header::CONTENT_TYPE, Client};
+
+use super::names::intern;
+use crate::core::types::{
+    BadRequestError, ComputeFunction, ComputeJsonResponse, ComputeRequest, ComputeResponse,
+    GenericStatusCode,
+};
+
+/// A [`ComputeFunction`] that `POST`s the data of every request it receives to `upstream_url` and
+/// answers with whatever the upstream answered, status included. A JSON body is passed on as JSON,
+/// anything else as a [`ComputeResponse::Binary`] of the upstream's content type.
+///
+/// An upstream that can't be reached, or doesn't answer within the timeout (or what is left of the
+/// request's deadline, whichever runs out first), is answered with a `502 Bad Gateway`.
+#[derive(Debug)]
+pub struct ProxyFunction {
+    /// The name given to the function, interned to hand out as the `&'static str`
+    /// [`ComputeFunction::name`] requires, see [`intern`].
+    name: &'static str,
+    upstream_url: String,
+    client: Client,
+}
+
+impl ProxyFunction {
+    /// How long the upstream may take to answer, if the request's deadline doesn't run out sooner.
+    pub const TIMEOUT: Duration = Duration::from_secs(30);
+
+    /// Create a new [`ProxyFunction`] named `name` that forwards to `upstream_url`.
+    #[must_use]
+    pub fn new(name: &str, upstream_url: impl Into<String>) -> Self {
+        Self {
+            name: intern(name),
+            upstream_url: upstream_url.into(),
+            client: Client::new(),
+        }
+    }
+
+    /// The URL requests are forwarded to.
+    #[must_use]
+    pub fn upstream_url(&self) -> &str {
+        &self.upstream_url
+    }
+
+    /// The `502 Bad Gateway` answered when the upstream couldn't be reached.
+    fn bad_gateway(&self, error: &reqwest::Error) -> ComputeResponse {
+        let reason = if error.is_timeout() {
+            "timed out".to_string()
+        } else {
+            error.to_string()
+        };
+        ComputeResponse::error(
+            GenericStatusCode::BadGateway,
+            "bad_gateway",
+            &format!("Upstream `{}` {}", self.upstream_url, reason),
+        )
+    }
+}
+
+/// Turn what the upstream answered with into a [`ComputeResponse`].
+fn into_response(
+    status: GenericStatusCode,
+    content_type: Option<String>,
+    body: &[u8],
+) -> ComputeResponse {
+    if body.is_empty() {
+        return ComputeResponse::status_only(status);
+    }
+
+    let is_json = content_type
+        .as_deref()
+        .map_or(true, |content_type| content_type.contains("json"));
+    match serde_json::from_slice(body) {
+        Ok(data) if is_json && status.to_u16() >= 400 => {
+            ComputeResponse::Error(ComputeJsonResponse::new(status, data))
+        }
+        Ok(data) if is_json => ComputeResponse::json(status, data),
+        _ => ComputeResponse::binary(
+            status,
+            content_type.unwrap_or_else(|| "application/octet-stream".to_string()),
+            body.to_vec(),
+        ),
+    }
+}
+
+#[async_trait::async_trait]
+impl ComputeFunction for ProxyFunction {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    async fn receive_request(
+        &self,
+        request: &ComputeRequest,
+    ) -> Result<ComputeResponse, BadRequestError> {
+        // Give up on the upstream once the client has given up on us.
+        let timeout = request
+            .context()
+            .remaining()
+            .map_or(Self::TIMEOUT, |remaining| remaining.min(Self::TIMEOUT));
+        let response = match self
+            .client
+            .post(&self.upstream_url)
+            .timeout(timeout)
+            .json(request.data())
+            .send()
+            .await
+        {
+            Ok(response) => response,
+            Err(e) => return Ok(self.bad_gateway(&e)),
+        };
+
+        let status = GenericStatusCode::from_u16(response.status().as_u16());
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(ToString::to_string);
+        match response.bytes().await {
+            Ok(body) => Ok(into_response(status, content_type, &body)),
+            Err(e) => Ok(self.bad_gateway(&e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn upstream_bodies_keep_their_shape() {
+        let json = into_response(GenericStatusCode::Created, None, br#"{"a":1}"#);
+        assert!(matches!(json, ComputeResponse::Json(_)));
+        assert_eq!(json.status().to_u16(), 201);
+        assert_eq!(json.data(), Some(json!({ "a": 1 })));
+
+        let failed = into_response(
+            GenericStatusCode::NotFound,
+            Some("application/json".to_string()),
+            br#"{"error":"nope"}"#,
+        );
+        assert!(failed.is_error());
+
+        let text = into_response(GenericStatusCode::Ok, Some("text/plain".to_string()), b"hi");
+        assert_eq!(text.bytes(), Some(&b"hi"[..]));
+        assert_eq!(text.content_type(), Some("text/plain"));
+
+        let empty = into_response(GenericStatusCode::Ok, None, b"");
+        assert!(matches!(empty, ComputeResponse::NoContent(_)));
+    }
+
+    #[tokio::test]
+    async fn unreachable_upstreams_are_a_bad_gateway() {
+        // Bind and drop a listener to find a port nothing listens on.
+        let addr = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let proxy = ProxyFunction::new("gone", format!("http://{}/", addr));
+        let request = ComputeRequest::new(
+            crate::TargetComputeFunc::new("gone".to_string()),
+            json!(null),
+        );
+
+        let response = proxy.receive_request(&request).await.unwrap();
+        assert!(response.is_error());
+        assert_eq!(response.status().to_u16(), 502);
+        let body = response.data().unwrap();
+        assert_eq!(body["status"], 502);
+        assert_eq!(body["code"], "bad_gateway");
+    }
+
+    #[tokio::test]
+    async fn upstream_calls_are_abandoned_with_the_deadline() {
+        // An upstream that accepts the connection and then never answers.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let hung = tokio::task::spawn(async move {
+            let (_socket, _) = listener.accept().await.unwrap();
+            std::future::pending::<()>().await;
+        });
+
+        let proxy = ProxyFunction::new("hung", format!("http://{}/", addr));
+        let request = ComputeRequest::new(
+            crate::TargetComputeFunc::new("hung".to_string()),
+            json!(null),
+        )
+        .with_context(crate::RequestContext::new().with_timeout(Duration::from_millis(100)));
+
+        let started = std::time::Instant::now();
+        let response = proxy.receive_request(&request).await.unwrap();
+        assert_eq!(response.status().to_u16(), 502);
+        assert!(
+            format!("{:?}", response.data()).contains("timed out"),
+            "{:?}",
+            response
+        );
+        assert!(started.elapsed() < ProxyFunction::TIMEOUT / 2);
+        hung.abort();
+    }
+}
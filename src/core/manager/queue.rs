@@ -0,0 +1,239 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::{cmp::Ordering, collections::BinaryHeap, sync::Mutex};
+
+use tokio::sync::oneshot;
+
+/// A bounded-concurrency gate for [`ComputeRequest`](crate::ComputeRequest)s. At most `max_in_flight`
+/// permits can be held at once, and once the queue is saturated any further callers wait in a
+/// priority queue so that high-priority requests are let through before low-priority ones. Requests
+/// with the same priority are served first come first serve.
+#[derive(Debug)]
+pub struct RequestQueue {
+    max_in_flight: usize,
+    state: Mutex<QueueState>,
+}
+
+#[derive(Debug, Default)]
+struct QueueState {
+    in_flight: usize,
+    next_seq: u64,
+    waiting: BinaryHeap<Waiter>,
+}
+
+#[derive(Debug)]
+struct Waiter {
+    priority: u8,
+    seq: u64,
+    wake: oneshot::Sender<()>,
+}
+
+impl PartialEq for Waiter {
+    fn eq(&self, other: &Self) -> bool {
+        self.priority == other.priority && self.seq == other.seq
+    }
+}
+
+impl Eq for Waiter {}
+
+impl PartialOrd for Waiter {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Waiter {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Higher priority first, then lower sequence number (older waiter) first.
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+impl RequestQueue {
+    /// Create a new [`RequestQueue`] that allows at most `max_in_flight` permits at once. A value
+    /// of zero is treated as one, otherwise nothing would ever get through.
+    #[must_use]
+    pub fn new(max_in_flight: usize) -> Self {
+        Self {
+            max_in_flight: max_in_flight.max(1),
+            state: Mutex::default(),
+        }
+    }
+
+    /// The maximum number of permits that can be held at once.
+    #[must_use]
+    pub const fn max_in_flight(&self) -> usize {
+        self.max_in_flight
+    }
+
+    /// The number of permits currently held.
+    #[must_use]
+    pub fn in_flight(&self) -> usize {
+        self.lock_state().in_flight
+    }
+
+    /// The number of callers currently waiting for a permit.
+    #[must_use]
+    pub fn waiting(&self) -> usize {
+        self.lock_state().waiting.len()
+    }
+
+    /// Wait for a permit, queueing behind any higher-priority callers if the queue is saturated.
+    /// The permit is released when the returned [`QueuePermit`] is dropped.
+    pub async fn acquire(&self, priority: u8) -> QueuePermit<'_> {
+        let rx = {
+            let mut state = self.lock_state();
+            if state.in_flight < self.max_in_flight && state.waiting.is_empty() {
+                state.in_flight += 1;
+                return QueuePermit { queue: self };
+            }
+
+            let (wake, rx) = oneshot::channel();
+            let seq = state.next_seq;
+            state.next_seq += 1;
+            state.waiting.push(Waiter {
+                priority,
+                seq,
+                wake,
+            });
+            rx
+        };
+
+        let mut pending = PendingPermit {
+            queue: self,
+            rx: Some(rx),
+        };
+        if let Some(rx) = pending.rx.as_mut() {
+            // The sender can only be dropped along with the queue itself, which cannot happen
+            // while we are borrowing it, so the result can be ignored.
+            let _ = rx.await;
+        }
+        // The releasing permit handed its slot directly to us, so there is nothing left to undo.
+        pending.rx = None;
+
+        QueuePermit { queue: self }
+    }
+
+    /// Take a permit only if one is immediately available, without queueing. Returns [`None`] if
+    /// the queue is saturated or other callers are already waiting.
+    pub fn try_acquire(&self) -> Option<QueuePermit<'_>> {
+        let mut state = self.lock_state();
+        if state.in_flight < self.max_in_flight && state.waiting.is_empty() {
+            state.in_flight += 1;
+            drop(state);
+            Some(QueuePermit { queue: self })
+        } else {
+            None
+        }
+    }
+
+    /// Hand the released slot to the highest-priority waiter that is still listening, or free it
+    /// if nobody is waiting.
+    fn release(&self) {
+        let mut state = self.lock_state();
+        while let Some(waiter) = state.waiting.pop() {
+            if waiter.wake.send(()).is_ok() {
+                return;
+            }
+        }
+        state.in_flight = state.in_flight.saturating_sub(1);
+    }
+
+    fn lock_state(&self) -> std::sync::MutexGuard<'_, QueueState> {
+        // The state is only ever touched in short non-panicking sections, so a poisoned lock
+        // still holds consistent data.
+        self.state
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+/// A slot in a [`RequestQueue`], released when dropped.
+#[derive(Debug)]
+pub struct QueuePermit<'a> {
+    queue: &'a RequestQueue,
+}
+
+impl Drop for QueuePermit<'_> {
+    fn drop(&mut self) {
+        self.queue.release();
+    }
+}
+
+/// Guards a queued [`RequestQueue::acquire`] call so that a caller who gives up after being handed
+/// a slot (but before noticing) does not leak it.
+struct PendingPermit<'a> {
+    queue: &'a RequestQueue,
+    rx: Option<oneshot::Receiver<()>>,
+}
+
+impl Drop for PendingPermit<'_> {
+    fn drop(&mut self) {
+        if let Some(mut rx) = self.rx.take() {
+            rx.close();
+            if rx.try_recv().is_ok() {
+                self.queue.release();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn high_priority_is_served_before_low_priority_under_saturation() {
+        let queue = Arc::new(RequestQueue::new(1));
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        let blocker = queue.acquire(0).await;
+        assert_eq!(queue.in_flight(), 1);
+
+        let mut handles = Vec::new();
+        for (label, priority) in [("low", 0_u8), ("high", 255_u8)] {
+            let task_queue = queue.clone();
+            let task_order = order.clone();
+            handles.push(tokio::spawn(async move {
+                let _permit = task_queue.acquire(priority).await;
+                task_order.lock().unwrap().push(label);
+            }));
+            // Make sure each request is queued before the next one so arrival order is fixed.
+            while queue.waiting() < handles.len() {
+                tokio::task::yield_now().await;
+            }
+        }
+
+        drop(blocker);
+        for handle in handles {
+            handle.await.unwrap();
+        }
+
+        assert_eq!(*order.lock().unwrap(), vec!["high", "low"]);
+        assert_eq!(queue.in_flight(), 0);
+        assert_eq!(queue.waiting(), 0);
+    }
+
+    #[tokio::test]
+    async fn abandoned_waiter_does_not_leak_its_slot() {
+        let queue = RequestQueue::new(1);
+        let blocker = queue.acquire(0).await;
+
+        let abandoned =
+            tokio::time::timeout(std::time::Duration::from_millis(10), queue.acquire(0)).await;
+        assert!(abandoned.is_err());
+
+        drop(blocker);
+        assert_eq!(queue.in_flight(), 0);
+        drop(queue.acquire(0).await);
+        assert_eq!(queue.in_flight(), 0);
+    }
+}
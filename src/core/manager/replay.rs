@@ -0,0 +1,116 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::{
+    collections::{HashSet, VecDeque},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::util::hashing::sea_hash_bytes;
+
+/// Remembers the nonces of recent requests so that a captured request can't be submitted again.
+///
+/// Nonces are kept for `window` and only their hashes are stored. At most `capacity` nonces are
+/// remembered at once. Forgetting a nonce before its window is up would let its request be replayed,
+/// so once that many are remembered new nonces are turned away until the oldest expire.
+#[derive(Debug)]
+pub struct ReplayGuard {
+    window: Duration,
+    capacity: usize,
+    seen: Mutex<Seen>,
+}
+
+/// The outcome of [`ReplayGuard::check`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NonceCheck {
+    /// The nonce hasn't been seen within the window, and is remembered from now on.
+    Fresh,
+    /// The nonce was already used within the window.
+    Replayed,
+    /// The nonce is new, but `capacity` nonces are already remembered, none of them expired.
+    Full,
+}
+
+#[derive(Debug, Default)]
+struct Seen {
+    hashes: HashSet<u64>,
+    by_age: VecDeque<(Instant, u64)>,
+}
+
+impl ReplayGuard {
+    /// Create a new [`ReplayGuard`] remembering up to `capacity` nonces for `window` each.
+    #[must_use]
+    pub fn new(window: Duration, capacity: usize) -> Self {
+        Self {
+            window,
+            capacity: capacity.max(1),
+            seen: Mutex::default(),
+        }
+    }
+
+    /// Check `nonce` and remember it, if there is room to.
+    ///
+    /// ## Returns
+    /// Whether the nonce is [fresh](NonceCheck::Fresh), a [replay](NonceCheck::Replayed), or can't
+    /// be remembered because the guard is [full](NonceCheck::Full).
+    pub fn check(&self, nonce: &str) -> NonceCheck {
+        let hash = sea_hash_bytes(nonce.as_bytes());
+        let now = Instant::now();
+        let mut seen = self
+            .seen
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+
+        while let Some(&(at, old)) = seen.by_age.front() {
+            if now.duration_since(at) < self.window {
+                break;
+            }
+            seen.by_age.pop_front();
+            seen.hashes.remove(&old);
+        }
+
+        if seen.hashes.contains(&hash) {
+            return NonceCheck::Replayed;
+        }
+        if seen.by_age.len() >= self.capacity {
+            return NonceCheck::Full;
+        }
+        seen.hashes.insert(hash);
+        seen.by_age.push_back((now, hash));
+        NonceCheck::Fresh
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nonces_are_forgotten_after_the_window() {
+        let guard = ReplayGuard::new(Duration::from_millis(20), 16);
+
+        assert_eq!(guard.check("a"), NonceCheck::Fresh);
+        assert_eq!(guard.check("a"), NonceCheck::Replayed);
+        std::thread::sleep(Duration::from_millis(30));
+        assert_eq!(guard.check("a"), NonceCheck::Fresh);
+    }
+
+    #[test]
+    fn unexpired_nonces_are_never_evicted_at_capacity() {
+        let guard = ReplayGuard::new(Duration::from_millis(50), 2);
+
+        assert_eq!(guard.check("a"), NonceCheck::Fresh);
+        assert_eq!(guard.check("b"), NonceCheck::Fresh);
+        assert_eq!(guard.check("c"), NonceCheck::Full);
+        // Filling the guard up doesn't make room for replays.
+        assert_eq!(guard.check("a"), NonceCheck::Replayed);
+
+        std::thread::sleep(Duration::from_millis(60));
+        assert_eq!(guard.check("c"), NonceCheck::Fresh);
+        assert_eq!(guard.check("a"), NonceCheck::Fresh);
+    }
+}
@@ -0,0 +1,149 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+/// Decides which calls get logged, so that logging every single request doesn't become the bottleneck
+/// under high load. Successful calls are logged with a probability given by the sample rate of the
+/// function (or the global rate if it has none), failed calls are always logged.
+#[derive(Debug)]
+pub struct CallLogSampler {
+    global_rate: f64,
+    function_rates: HashMap<String, f64>,
+    rng: AtomicU64,
+}
+
+impl Default for CallLogSampler {
+    fn default() -> Self {
+        Self::new(1.0)
+    }
+}
+
+impl CallLogSampler {
+    /// Create a new [`CallLogSampler`] that logs a `global_rate` fraction of successful calls. Rates are
+    /// clamped to `0.0..=1.0`.
+    #[must_use]
+    pub fn new(global_rate: f64) -> Self {
+        let seed = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0x9E37_79B9_7F4A_7C15, |elapsed| {
+                elapsed.as_secs() ^ (u64::from(elapsed.subsec_nanos()) << 32)
+            });
+
+        Self {
+            global_rate: clamp_rate(global_rate),
+            function_rates: HashMap::new(),
+            // Xorshift gets stuck on zero.
+            rng: AtomicU64::new(seed | 1),
+        }
+    }
+
+    /// Set the fraction of successful calls that are logged for functions without their own rate.
+    pub fn set_global_rate(&mut self, rate: f64) {
+        self.global_rate = clamp_rate(rate);
+    }
+
+    /// Set the fraction of successful calls to `function` that are logged.
+    pub fn set_function_rate(&mut self, function: &str, rate: f64) {
+        self.function_rates
+            .insert(function.to_string(), clamp_rate(rate));
+    }
+
+    /// The sample rate that applies to `function`.
+    #[must_use]
+    pub fn rate_for(&self, function: &str) -> f64 {
+        self.function_rates
+            .get(function)
+            .copied()
+            .unwrap_or(self.global_rate)
+    }
+
+    /// Whether a call to `function` should be logged.
+    #[must_use]
+    pub fn should_log(&self, function: &str, is_error: bool) -> bool {
+        if is_error {
+            return true;
+        }
+
+        let rate = self.rate_for(function);
+        if rate >= 1.0 {
+            true
+        } else if rate <= 0.0 {
+            false
+        } else {
+            self.next_unit() < rate
+        }
+    }
+
+    /// A cheap, non-cryptographic, uniformly distributed value in `0.0..1.0` (xorshift64*).
+    #[allow(clippy::cast_precision_loss)]
+    fn next_unit(&self) -> f64 {
+        let step = |mut x: u64| {
+            x ^= x >> 12;
+            x ^= x << 25;
+            x ^= x >> 27;
+            x
+        };
+        // The closure never fails, so neither does the update.
+        let previous = self
+            .rng
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |x| Some(step(x)))
+            .unwrap_or_default();
+        let value = step(previous).wrapping_mul(0x2545_F491_4F6C_DD1D);
+
+        (value >> 11) as f64 / (1_u64 << 53) as f64
+    }
+}
+
+fn clamp_rate(rate: f64) -> f64 {
+    if rate.is_nan() {
+        1.0
+    } else {
+        rate.clamp(0.0, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn logs_roughly_the_configured_fraction() {
+        let sampler = CallLogSampler::new(0.25);
+
+        let logged = (0..10_000)
+            .filter(|_| sampler.should_log("anything", false))
+            .count();
+        assert!(
+            (2_000..3_000).contains(&logged),
+            "logged {} of 10000",
+            logged
+        );
+    }
+
+    #[test]
+    fn errors_are_always_logged() {
+        let mut sampler = CallLogSampler::new(0.0);
+        sampler.set_function_rate("quiet", 0.0);
+
+        assert!((0..1_000).all(|_| sampler.should_log("quiet", true)));
+        assert!((0..1_000).all(|_| !sampler.should_log("quiet", false)));
+    }
+
+    #[test]
+    fn function_rates_override_the_global_rate() {
+        let mut sampler = CallLogSampler::new(0.0);
+        sampler.set_function_rate("loud", 1.0);
+        sampler.set_function_rate("clamped", 7.0);
+
+        assert!((0..1_000).all(|_| sampler.should_log("loud", false)));
+        assert!((0..1_000).all(|_| !sampler.should_log("other", false)));
+        assert!((sampler.rate_for("clamped") - 1.0).abs() < f64::EPSILON);
+    }
+}
@@ -0,0 +1,130 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+    time::Duration,
+};
+
+use crate::core::types::{CallCounts, FunctionStats};
+
+/// How many of the latest calls to each function [`CallStats::recent_error_rate`] looks at.
+pub const RECENT_CALLS: usize = 100;
+
+#[derive(Debug, Clone, Default)]
+struct Entry {
+    counts: CallCounts,
+    total_duration: Duration,
+    max_duration: Duration,
+    /// Whether each of the last [`RECENT_CALLS`] calls failed, oldest first.
+    recent: VecDeque<bool>,
+}
+
+impl Entry {
+    const fn stats(&self) -> FunctionStats {
+        FunctionStats {
+            call_count: self.counts.calls,
+            error_count: self.counts.errors,
+            total_duration: self.total_duration,
+            max_duration: self.max_duration,
+        }
+    }
+}
+
+/// Per-function call and error counts and durations, keyed by the name each function is registered
+/// under.
+///
+/// A plain [`std::sync::Mutex`] is used since it is only ever held for a single map operation and
+/// never across an `.await`.
+#[derive(Debug, Default)]
+pub struct CallStats {
+    counts: Mutex<HashMap<String, Entry>>,
+}
+
+impl CallStats {
+    /// Count a call to `function`, whether it failed, how long it took, and the size of its request
+    /// and response payloads.
+    pub fn record(
+        &self,
+        function: &str,
+        failed: bool,
+        elapsed: Duration,
+        bytes_in: usize,
+        bytes_out: usize,
+    ) {
+        if let Ok(mut counts) = self.counts.lock() {
+            let entry = counts.entry(function.to_string()).or_default();
+            entry.counts.calls += 1;
+            if failed {
+                entry.counts.errors += 1;
+            }
+            entry.counts.bytes_in += bytes_in as u64;
+            entry.counts.bytes_out += bytes_out as u64;
+            entry.total_duration += elapsed;
+            entry.max_duration = entry.max_duration.max(elapsed);
+            if entry.recent.len() == RECENT_CALLS {
+                entry.recent.pop_front();
+            }
+            entry.recent.push_back(failed);
+        }
+    }
+
+    /// The fraction (`0.0` to `1.0`) of the last [`RECENT_CALLS`] calls to `function` that failed,
+    /// or `None` if it has never been called.
+    #[must_use]
+    pub fn recent_error_rate(&self, function: &str) -> Option<f64> {
+        let (errors, calls) = self.counts.lock().ok()?.get(function).map(|entry| {
+            let errors = entry.recent.iter().filter(|failed| **failed).count();
+            (errors, entry.recent.len())
+        })?;
+        if calls == 0 {
+            return None;
+        }
+        #[allow(clippy::cast_precision_loss)]
+        Some(errors as f64 / calls as f64)
+    }
+
+    /// The counts for `function`, which are all zero if it has never been called.
+    #[must_use]
+    pub fn get(&self, function: &str) -> CallCounts {
+        self.counts
+            .lock()
+            .ok()
+            .and_then(|counts| counts.get(function).map(|entry| entry.counts))
+            .unwrap_or_default()
+    }
+
+    /// The [`FunctionStats`] of every function that has been called, by name.
+    #[must_use]
+    pub fn snapshot(&self) -> HashMap<String, FunctionStats> {
+        self.counts
+            .lock()
+            .map(|counts| {
+                counts
+                    .iter()
+                    .map(|(name, entry)| (name.clone(), entry.stats()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Move the counts of `from` over to `to`, for when a function is renamed.
+    pub fn rename(&self, from: &str, to: &str) {
+        if let Ok(mut counts) = self.counts.lock() {
+            if let Some(entry) = counts.remove(from) {
+                counts.insert(to.to_string(), entry);
+            }
+        }
+    }
+
+    /// Forget the counts for `function`, for when it is unloaded.
+    pub fn remove(&self, function: &str) {
+        if let Ok(mut counts) = self.counts.lock() {
+            counts.remove(function);
+        }
+    }
+}
@@ -0,0 +1,232 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! [`ComputeFunction`]s run as a separate process for every request, see
+//! [`ComputeFunctionManager::load_subprocess`](crate::ComputeFunctionManager::load_subprocess).
+
+use std::{process::Stdio, time::Duration};
+
+use tokio::{io::AsyncWriteExt, process::Command};
+
+use super::names::intern;
+use crate::core::types::{BadRequestError, ComputeFunction, ComputeRequest, ComputeResponse};
+
+/// A [`ComputeFunction`] that runs an executable for every request it receives. The serialized
+/// [`ComputeRequest`] is written to the child's stdin as a single line of JSON, and the first line
+/// the child writes to its stdout is read back as the serialized [`ComputeResponse`].
+///
+/// Nothing the child does can take the server down with it: a child that can't be started, exits
+/// unsuccessfully, answers with something other than a response or runs past its timeout or the
+/// request's deadline, whichever comes first (in which case it is killed), fails the request with a
+/// [`BadRequestError`].
+#[derive(Debug)]
+pub struct SubprocessFunction {
+    /// The name given to the function, interned to hand out as the `&'static str`
+    /// [`ComputeFunction::name`] requires, see [`intern`].
+    name: &'static str,
+    command: String,
+    args: Vec<String>,
+    timeout: Duration,
+}
+
+impl SubprocessFunction {
+    /// How long a child may take to answer unless configured otherwise.
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+    /// Create a new [`SubprocessFunction`] named `name` that runs `command` with `args`.
+    #[must_use]
+    pub fn new(name: &str, command: impl Into<String>, args: Vec<String>) -> Self {
+        Self {
+            name: intern(name),
+            command: command.into(),
+            args,
+            timeout: Self::DEFAULT_TIMEOUT,
+        }
+    }
+
+    /// Consume this function and return it with the given timeout for each run of the child.
+    #[must_use]
+    pub const fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// The executable run for every request.
+    #[must_use]
+    pub fn command(&self) -> &str {
+        &self.command
+    }
+
+    /// The arguments the executable is run with.
+    #[must_use]
+    pub fn args(&self) -> &[String] {
+        &self.args
+    }
+
+    /// Run the child once, handing it `input` and returning the first line of its output, killing it
+    /// if it hasn't answered within `timeout`.
+    async fn run(&self, input: &[u8], timeout: Duration) -> Result<Vec<u8>, String> {
+        let mut child = Command::new(&self.command)
+            .args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| format!("Unable to start `{}`: {}", self.command, e))?;
+        let mut stdin = child.stdin.take().ok_or("The child's stdin is not piped")?;
+
+        // Dropping the child when the timeout runs out kills it.
+        let output = tokio::time::timeout(timeout, async move {
+            // A child that exits without reading its input is reported by its exit status instead.
+            let _ = stdin.write_all(input).await;
+            let _ = stdin.write_all(b"\n").await;
+            drop(stdin);
+            child.wait_with_output().await
+        })
+        .await
+        .map_err(|_| format!("Timed out after {}ms", timeout.as_millis()))?
+        .map_err(|e| format!("Unable to read the child's output: {}", e))?;
+
+        if !output.status.success() {
+            return Err(format!(
+                "Exited with {}: {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        output
+            .stdout
+            .split(|&b| b == b'\n')
+            .find(|line| !line.is_empty())
+            .map(<[u8]>::to_vec)
+            .ok_or_else(|| "Exited without answering".to_string())
+    }
+}
+
+#[async_trait::async_trait]
+impl ComputeFunction for SubprocessFunction {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    async fn receive_request(
+        &self,
+        request: &ComputeRequest,
+    ) -> Result<ComputeResponse, BadRequestError> {
+        let bad_request = |msg: &str| BadRequestError::new(self.name, msg, Some(request.clone()));
+
+        let input = serde_json::to_vec(request)
+            .map_err(|e| bad_request(&format!("Unable to serialize the request: {}", e)))?;
+        let timeout = request
+            .context()
+            .remaining()
+            .map_or(self.timeout, |remaining| remaining.min(self.timeout));
+        let output = self
+            .run(&input, timeout)
+            .await
+            .map_err(|e| bad_request(&format!("Subprocess `{}` failed: {}", self.command, e)))?;
+
+        serde_json::from_slice(&output).map_err(|e| {
+            bad_request(&format!(
+                "Subprocess `{}` returned an invalid response: {}",
+                self.command, e
+            ))
+        })
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::TargetComputeFunc;
+
+    fn shell(name: &str, script: &str) -> SubprocessFunction {
+        SubprocessFunction::new(name, "sh", vec!["-c".to_string(), script.to_string()])
+    }
+
+    async fn call(function: &SubprocessFunction) -> Result<ComputeResponse, BadRequestError> {
+        let request = ComputeRequest::new(
+            TargetComputeFunc::new(function.name().to_string()),
+            json!({ "x": 1 }),
+        );
+        function.receive_request(&request).await
+    }
+
+    #[tokio::test]
+    async fn answers_with_the_first_line_of_output() {
+        let function = shell(
+            "fixed",
+            r#"read -r line; echo '{"Json":{"status":200,"data":{"fixed":true}}}'; echo ignored"#,
+        );
+        let response = call(&function).await.unwrap();
+        assert_eq!(response.data(), Some(json!({ "fixed": true })));
+    }
+
+    #[tokio::test]
+    async fn the_request_is_written_to_stdin() {
+        // Hand the request back as the data of the response.
+        let function = shell(
+            "mirror",
+            r#"read -r line; printf '{"Json":{"status":200,"data":%s}}\n' "$line""#,
+        );
+        let response = call(&function).await.unwrap();
+        assert_eq!(response.data().unwrap()["data"], json!({ "x": 1 }));
+    }
+
+    #[tokio::test]
+    async fn crashes_are_bad_requests() {
+        let function = shell("crashy", "echo 'something broke' >&2; exit 3");
+        let err = call(&function).await.unwrap_err();
+        assert!(
+            err.message().contains("something broke"),
+            "{}",
+            err.message()
+        );
+
+        let garbage = shell("garbage", "read -r line; echo 'not json'");
+        let err = call(&garbage).await.unwrap_err();
+        assert!(
+            err.message().contains("invalid response"),
+            "{}",
+            err.message()
+        );
+
+        let missing = SubprocessFunction::new("missing", "/definitely/not/a/program", Vec::new());
+        let err = call(&missing).await.unwrap_err();
+        assert!(
+            err.message().contains("Unable to start"),
+            "{}",
+            err.message()
+        );
+    }
+
+    #[tokio::test]
+    async fn slow_children_are_killed() {
+        let function = shell("slow", "sleep 10").with_timeout(Duration::from_millis(100));
+        let started = std::time::Instant::now();
+        let err = call(&function).await.unwrap_err();
+        assert!(err.message().contains("Timed out"), "{}", err.message());
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn children_are_killed_when_the_deadline_passes() {
+        let function = shell("slow", "sleep 10");
+        let request = ComputeRequest::new(
+            TargetComputeFunc::new(function.name().to_string()),
+            json!({ "x": 1 }),
+        )
+        .with_context(crate::RequestContext::new().with_timeout(Duration::from_millis(100)));
+        let started = std::time::Instant::now();
+        let err = function.receive_request(&request).await.unwrap_err();
+        assert!(err.message().contains("Timed out"), "{}", err.message());
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
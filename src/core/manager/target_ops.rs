@@ -0,0 +1,128 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use crate::util::lock_order::{OrderedMutex, OrderedMutexGuard};
+
+/// Per-target locks that serialize operations which replace or remove a function (reloading and
+/// unloading), so that they can't interleave on the same name and leave it half swapped.
+///
+/// A plain [`std::sync::Mutex`] guards the map since it is only held to look an entry up, never
+/// across an `.await`.
+#[derive(Debug, Default)]
+pub struct TargetOps {
+    ops: Mutex<HashMap<String, Arc<TargetOp>>>,
+}
+
+impl TargetOps {
+    /// The operation lock for `target`, created on first use.
+    pub fn get(&self, target: &str) -> Arc<TargetOp> {
+        let mut ops = self
+            .ops
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        Arc::clone(ops.entry(target.to_string()).or_default())
+    }
+
+    /// Give back the operation lock for `target` once an operation is done with it, forgetting it
+    /// unless another operation on the same name is still using it.
+    pub fn release(&self, target: &str, op: Arc<TargetOp>) {
+        let mut ops = self
+            .ops
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        // One reference is held by the map, the other is `op`.
+        if Arc::strong_count(&op) == 2 {
+            ops.remove(target);
+        }
+        drop(ops);
+        drop(op);
+    }
+}
+
+/// The operation lock for a single target, along with the number of unloads waiting for it so that
+/// a reload holding the lock can tell it is about to be made pointless.
+#[derive(Debug)]
+pub struct TargetOp {
+    lock: OrderedMutex<()>,
+    pending_unloads: AtomicUsize,
+}
+
+impl Default for TargetOp {
+    fn default() -> Self {
+        Self {
+            lock: OrderedMutex::new("target_op", ()),
+            pending_unloads: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl TargetOp {
+    /// Wait for exclusive access to the target.
+    pub async fn lock(&self) -> OrderedMutexGuard<'_, ()> {
+        self.lock.lock().await
+    }
+
+    /// Announce an unload of the target, which stays pending until the returned
+    /// [`PendingUnload`] is dropped.
+    pub fn begin_unload(&self) -> PendingUnload<'_> {
+        self.pending_unloads.fetch_add(1, Ordering::AcqRel);
+        PendingUnload(self)
+    }
+
+    /// Whether an unload of the target has been announced and not yet finished.
+    #[must_use]
+    pub fn unload_pending(&self) -> bool {
+        self.pending_unloads.load(Ordering::Acquire) > 0
+    }
+}
+
+/// An announced unload, see [`TargetOp::begin_unload`].
+#[derive(Debug)]
+pub struct PendingUnload<'a>(&'a TargetOp);
+
+impl Drop for PendingUnload<'_> {
+    fn drop(&mut self) {
+        self.0.pending_unloads.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unloads_are_pending_until_dropped() {
+        let ops = TargetOps::default();
+        let op = ops.get("echo");
+        assert!(!op.unload_pending());
+
+        let unload = op.begin_unload();
+        assert!(ops.get("echo").unload_pending());
+        drop(unload);
+        assert!(!op.unload_pending());
+    }
+
+    #[test]
+    fn released_ops_are_forgotten_unless_shared() {
+        let ops = TargetOps::default();
+        let first = ops.get("echo");
+        let second = ops.get("echo");
+        assert!(Arc::ptr_eq(&first, &second));
+
+        ops.release("echo", first);
+        assert!(Arc::ptr_eq(&ops.get("echo"), &second));
+        ops.release("echo", second);
+        assert_eq!(ops.ops.lock().unwrap().len(), 0);
+    }
+}
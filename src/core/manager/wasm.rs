@@ -0,0 +1,224 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! [`ComputeFunction`]s compiled to WebAssembly, see
+//! [`ComputeFunctionManager::load_wasm_plugin`](crate::ComputeFunctionManager::load_wasm_plugin).
+//!
+//! The module has to export what that function's documentation describes, it gets no imports.
+
+use std::{
+    path::Path,
+    sync::{Mutex, PoisonError},
+};
+
+use wasmtime::{Engine, Instance, Memory, Module, Store, TypedFunc};
+
+use super::names::intern;
+use crate::core::types::{
+    BadRequestError, ComputeFunction, ComputeRequest, ComputeResponse, LoadingError,
+};
+
+/// A [`ComputeFunction`] backed by an instance of a WebAssembly module.
+pub struct WasmFunction {
+    /// The name the module reported, interned to hand out as the `&'static str`
+    /// [`ComputeFunction::name`] requires, see [`intern`].
+    name: &'static str,
+    guest: Mutex<Guest>,
+}
+
+/// The instantiated module, only ever called into by one request at a time.
+struct Guest {
+    store: Store<()>,
+    memory: Memory,
+    alloc: TypedFunc<i32, i32>,
+    receive_request: TypedFunc<(i32, i32), i64>,
+}
+
+impl WasmFunction {
+    /// Compile and instantiate the module at `path` (binary `.wasm`, or `.wat` text) and ask it for
+    /// its name.
+    ///
+    /// ## Errors
+    /// - [`LoadingError::LibraryLoadFailure`] if the module can't be read, compiled or instantiated
+    /// - [`LoadingError::ConstructorLoadFailure`] if the module is missing one of the exports it
+    ///   needs, or its `name` export fails or returns something that isn't a UTF-8 string
+    pub fn load(path: &Path) -> Result<Self, LoadingError> {
+        let engine = Engine::default();
+        let module =
+            Module::from_file(&engine, path).map_err(|e| LoadingError::lib_load_failure(&e))?;
+        let mut store = Store::new(&engine, ());
+        let instance = Instance::new(&mut store, &module, &[])
+            .map_err(|e| LoadingError::lib_load_failure(&e))?;
+
+        let missing = |export: &str, e: &dyn std::fmt::Display| {
+            LoadingError::ctor_load_failure(&format!(
+                "Missing or invalid export `{}`: {}",
+                export, e
+            ))
+        };
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or_else(|| missing("memory", &"not a memory"))?;
+        let name = instance
+            .get_typed_func::<(), i64, _>(&mut store, "name")
+            .map_err(|e| missing("name", &e))?;
+        let alloc = instance
+            .get_typed_func::<i32, i32, _>(&mut store, "alloc")
+            .map_err(|e| missing("alloc", &e))?;
+        let receive_request = instance
+            .get_typed_func::<(i32, i32), i64, _>(&mut store, "receive_request")
+            .map_err(|e| missing("receive_request", &e))?;
+
+        let mut guest = Guest {
+            store,
+            memory,
+            alloc,
+            receive_request,
+        };
+        let packed = name
+            .call(&mut guest.store, ())
+            .map_err(|e| missing("name", &e))?;
+        let name = guest
+            .read(packed)
+            .and_then(|bytes| String::from_utf8(bytes).map_err(|e| e.to_string()))
+            .map_err(|e| missing("name", &e))?;
+
+        Ok(Self {
+            name: intern(&name),
+            guest: Mutex::new(guest),
+        })
+    }
+}
+
+impl Guest {
+    /// Hand `input` to the module's `receive_request` and read back what it answered with.
+    fn call(&mut self, input: &[u8]) -> Result<Vec<u8>, String> {
+        let len = i32::try_from(input.len()).map_err(|_| "Request is too large".to_string())?;
+        let ptr = self
+            .alloc
+            .call(&mut self.store, len)
+            .map_err(|e| e.to_string())?;
+        let offset = usize::try_from(ptr).map_err(|_| format!("Invalid pointer {}", ptr))?;
+        self.memory
+            .write(&mut self.store, offset, input)
+            .map_err(|e| e.to_string())?;
+        let packed = self
+            .receive_request
+            .call(&mut self.store, (ptr, len))
+            .map_err(|e| e.to_string())?;
+        self.read(packed)
+    }
+
+    /// Copy the string `packed` points to out of the module's memory.
+    #[allow(
+        clippy::cast_sign_loss,
+        clippy::cast_possible_truncation,
+        reason = "The i64 is just a carrier for two u32s."
+    )]
+    fn read(&self, packed: i64) -> Result<Vec<u8>, String> {
+        let packed = packed as u64;
+        let ptr = (packed >> 32) as usize;
+        let len = (packed & u64::from(u32::MAX)) as usize;
+        let mut bytes = vec![0; len];
+        self.memory
+            .read(&self.store, ptr, &mut bytes)
+            .map_err(|e| e.to_string())?;
+        Ok(bytes)
+    }
+}
+
+impl std::fmt::Debug for WasmFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WasmFunction")
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+#[async_trait::async_trait]
+impl ComputeFunction for WasmFunction {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    #[allow(clippy::unused_async)]
+    async fn receive_request(
+        &self,
+        request: &ComputeRequest,
+    ) -> Result<ComputeResponse, BadRequestError> {
+        let bad_request = |msg: &str| BadRequestError::new(self.name, msg, Some(request.clone()));
+
+        let input = serde_json::to_vec(request)
+            .map_err(|e| bad_request(&format!("Unable to serialize the request: {}", e)))?;
+        let output = self
+            .guest
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .call(&input)
+            .map_err(|e| bad_request(&format!("WASM function failed: {}", e)))?;
+
+        serde_json::from_slice(&output).map_err(|e| {
+            bad_request(&format!(
+                "WASM function returned an invalid response: {}",
+                e
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        util::fixtures::{wasm_module, FIXED_WASM_MODULE, FIXED_WASM_NAME},
+        TargetComputeFunc,
+    };
+
+    #[tokio::test]
+    async fn calls_into_the_module() {
+        let path = wasm_module(FIXED_WASM_MODULE);
+        let function = WasmFunction::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(function.name(), FIXED_WASM_NAME);
+        let request = ComputeRequest::new(
+            TargetComputeFunc::new(FIXED_WASM_NAME.to_string()),
+            json!(1),
+        );
+        let response = function.receive_request(&request).await.unwrap();
+        assert_eq!(response.data(), Some(json!({ "fixed": true })));
+    }
+
+    #[test]
+    fn modules_missing_an_export_are_rejected() {
+        let path = wasm_module(r#"(module (memory (export "memory") 1))"#);
+        let err = WasmFunction::load(&path).unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(err, LoadingError::ConstructorLoadFailure(m) if m.contains("`name`")));
+    }
+
+    #[tokio::test]
+    async fn traps_are_bad_requests() {
+        let path = wasm_module(
+            r#"
+            (module
+              (memory (export "memory") 1)
+              (data (i32.const 0) "trapper")
+              (func (export "alloc") (param i32) (result i32) (i32.const 1024))
+              (func (export "name") (result i64) (i64.const 7))
+              (func (export "receive_request") (param i32 i32) (result i64) unreachable))
+            "#,
+        );
+        let function = WasmFunction::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let request = ComputeRequest::new(TargetComputeFunc::new("trapper".to_string()), json!(1));
+        let err = function.receive_request(&request).await.unwrap_err();
+        assert!(err.message().starts_with("WASM function failed"));
+    }
+}
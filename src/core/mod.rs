@@ -5,31 +5,89 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 mod manager;
+#[cfg(any(feature = "axum", feature = "warp"))]
 pub mod server;
 pub mod types;
 
-pub use manager::ComputeFunctionManager;
+pub use manager::{ComputeFunctionManager, ComputeFunctionManagerBuilder};
 
 pub const CTOR_NAME: &[u8; 14] = b"_plugin_create";
+pub const ABI_VERSION_NAME: &[u8; 19] = b"_plugin_abi_version";
 
-/// Declare a plugin type and its constructor.
+/// The version of the interface between the manager and its plugins.
+///
+/// A plugin exports the version it was built against as `_plugin_abi_version` (which [`declare_plugin!`] takes care of), and
+/// [`ComputeFunctionManager::load_plugin`] refuses to load plugins built against another version.
+///
+/// This is bumped whenever a change to [`ComputeFunction`](crate::ComputeFunction) or the types it
+/// uses would break plugins built before it.
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+
+/// Declare a plugin type and its constructor, exporting the `_plugin_create` and
+/// `_plugin_abi_version` symbols that [`ComputeFunctionManager::load_plugin`] looks for.
+///
+/// The constructor can be omitted, in which case the plugin is created with [`Default::default`].
+///
+/// # Example
+///
+/// ```
+/// use local_compute::{
+///     async_trait, declare_plugin, BadRequestError, ComputeFunction, ComputeRequest,
+///     ComputeResponse,
+/// };
+///
+/// #[derive(Debug, Default)]
+/// struct Echo;
+///
+/// #[async_trait]
+/// impl ComputeFunction for Echo {
+///     fn name(&self) -> &'static str {
+///         "echo"
+///     }
+///
+///     async fn receive_request(
+///         &self,
+///         request: &ComputeRequest,
+///     ) -> Result<ComputeResponse, BadRequestError> {
+///         Ok(ComputeResponse::json_ok(request.data().clone()))
+///     }
+/// }
+///
+/// declare_plugin!(Echo);
+/// ```
 ///
 /// # Notes
 ///
-/// This works by automatically generating an `extern "C"` function with a
-/// pre-defined signature and symbol name. Therefore you will only be able to
+/// This works by automatically generating `extern "C"` functions with
+/// pre-defined signatures and symbol names. Therefore you will only be able to
 /// declare one plugin per library.
 #[macro_export]
 macro_rules! declare_plugin {
-    ($plugin_type:ty, $constructor:path) => {
+    (@export $plugin_type:ty, $constructor:expr) => {
         #[no_mangle]
-        pub extern "C" fn _plugin_create() -> *mut $crate::Plugin {
+        #[allow(improper_ctypes_definitions)]
+        pub extern "C" fn _plugin_create() -> *mut dyn $crate::ComputeFunction {
             // make sure the constructor is the correct type.
             let constructor: fn() -> $plugin_type = $constructor;
 
             let object = constructor();
-            let boxed: Box<$crate::Plugin> = Box::new(object);
-            Box::into_raw(boxed)
+            let boxed: ::std::boxed::Box<dyn $crate::ComputeFunction> =
+                ::std::boxed::Box::new(object);
+            ::std::boxed::Box::into_raw(boxed)
+        }
+
+        #[no_mangle]
+        pub extern "C" fn _plugin_abi_version() -> u32 {
+            $crate::PLUGIN_ABI_VERSION
         }
     };
+    ($plugin_type:ty) => {
+        $crate::declare_plugin!(
+            @export $plugin_type,
+            <$plugin_type as ::std::default::Default>::default
+        );
+    };
+    ($plugin_type:ty, $constructor:path) => {
+        $crate::declare_plugin!(@export $plugin_type, $constructor);
+    };
 }
@@ -0,0 +1,144 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::sync::Arc;
+
+/// The token clients have to send as `Authorization: Bearer <token>` before a server looks at their
+/// request at all.
+#[derive(Clone, PartialEq, Eq)]
+pub struct AuthToken(Arc<str>);
+
+impl AuthToken {
+    /// Require `token` from every client.
+    ///
+    /// ## Panics
+    /// If `token` is empty, which would let through anyone sending `Bearer ` with nothing after it.
+    #[must_use]
+    pub fn new(token: impl Into<String>) -> Self {
+        let token = token.into();
+        assert!(!token.is_empty(), "The auth token must not be empty");
+        Self(token.into())
+    }
+
+    /// Whether `authorization`, the value of a request's `Authorization` header, carries this token.
+    /// The scheme is matched case-insensitively, the token itself in constant time.
+    #[must_use]
+    pub fn verify(&self, authorization: Option<&[u8]>) -> bool {
+        const SCHEME: &[u8] = b"bearer ";
+
+        match authorization {
+            Some(value) if value.len() >= SCHEME.len() => {
+                let (scheme, token) = value.split_at(SCHEME.len());
+                scheme.eq_ignore_ascii_case(SCHEME) && constant_time_eq(self.0.as_bytes(), token)
+            }
+            _ => false,
+        }
+    }
+
+    /// A layer that answers requests without this token with an
+    /// [`AppError::Unauthorized`](crate::AppError::Unauthorized), before they are routed.
+    #[cfg(feature = "axum")]
+    #[must_use]
+    pub fn layer(&self) -> tower_http::auth::RequireAuthorizationLayer<Self> {
+        tower_http::auth::RequireAuthorizationLayer::custom(self.clone())
+    }
+
+    /// A filter that rejects requests without this token with an [`Unauthorized`], which
+    /// `handle_rejection` turns into an [`AppError::Unauthorized`](crate::AppError::Unauthorized).
+    #[cfg(feature = "warp")]
+    #[must_use]
+    pub fn warp(&self) -> impl warp::Filter<Extract = (), Error = warp::Rejection> + Clone {
+        use warp::Filter;
+
+        let token = self.clone();
+        warp::header::headers_cloned()
+            .and_then(move |headers: hyper::HeaderMap| {
+                let authorization = headers.get(hyper::header::AUTHORIZATION);
+                let authorized =
+                    token.verify(authorization.map(hyper::header::HeaderValue::as_bytes));
+                async move {
+                    if authorized {
+                        Ok(())
+                    } else {
+                        Err(warp::reject::custom(Unauthorized))
+                    }
+                }
+            })
+            .untuple_one()
+    }
+}
+
+impl std::fmt::Debug for AuthToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("AuthToken(<redacted>)")
+    }
+}
+
+#[cfg(feature = "axum")]
+impl<B> tower_http::auth::AuthorizeRequest<B> for AuthToken {
+    type ResponseBody = axum::body::BoxBody;
+
+    fn authorize(
+        &mut self,
+        request: &mut hyper::Request<B>,
+    ) -> Result<(), hyper::Response<Self::ResponseBody>> {
+        let authorization = request.headers().get(hyper::header::AUTHORIZATION);
+        if self.verify(authorization.map(hyper::header::HeaderValue::as_bytes)) {
+            Ok(())
+        } else {
+            Err(crate::AppError::Unauthorized.into_axum())
+        }
+    }
+}
+
+/// The rejection of the [`AuthToken::warp`] filter.
+#[cfg(feature = "warp")]
+#[derive(Debug)]
+pub struct Unauthorized;
+
+#[cfg(feature = "warp")]
+impl warp::reject::Reject for Unauthorized {}
+
+/// Compare `expected` to `given` in time that only depends on the length of `expected`, so that how
+/// long a comparison takes doesn't tell how much of a guess was right.
+fn constant_time_eq(expected: &[u8], given: &[u8]) -> bool {
+    let mut diff = expected.len() ^ given.len();
+    for (i, byte) in expected.iter().enumerate() {
+        diff |= usize::from(byte ^ given.get(i).copied().unwrap_or_default());
+    }
+    diff == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_exact_bearer_token_is_accepted() {
+        let token = AuthToken::new("s3cret");
+
+        assert!(token.verify(Some(&b"Bearer s3cret"[..])));
+        assert!(token.verify(Some(&b"bearer s3cret"[..])));
+        for wrong in [
+            &b""[..],
+            b"Bearer",
+            b"Bearer ",
+            b"Bearer s3cre",
+            b"Bearer s3crets",
+            b"Bearer S3CRET",
+            b"Basic s3cret",
+            b"s3cret",
+        ] {
+            assert!(!token.verify(Some(wrong)), "{:?}", wrong);
+        }
+        assert!(!token.verify(None));
+    }
+
+    #[test]
+    fn tokens_are_not_printed() {
+        assert!(!format!("{:?}", AuthToken::new("s3cret")).contains("s3cret"));
+    }
+}
@@ -11,6 +11,7 @@ use lazy_static::lazy_static;
 
 pub async fn run_hello_server(
     addr: SocketAddr,
+    shutdown_signal: tokio::sync::oneshot::Receiver<()>,
 ) -> tokio::task::JoinHandle<Result<(), hyper::Error>> {
     // build our application with a route
     let app = Router::new().route("/", get(handler));
@@ -21,6 +22,9 @@ pub async fn run_hello_server(
     tokio::task::spawn(async move {
         axum::Server::bind(&addr)
             .serve(app.into_make_service())
+            .with_graceful_shutdown(async move {
+                let _ = shutdown_signal.await;
+            })
             .await
     })
 }
@@ -4,22 +4,253 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-use std::{net::SocketAddr, sync::Arc};
+use std::{
+    convert::Infallible, future::Future, net::SocketAddr, ops::Deref, pin::Pin, sync::Arc,
+    time::Instant,
+};
 
 use axum::{
-    extract::Extension,
-    routing::{post, IntoMakeService},
-    AddExtensionLayer, Json, Router, Server,
+    async_trait,
+    extract::{
+        rejection::QueryRejection,
+        ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade},
+        ConnectInfo, Extension, FromRequest, Path, Query, RequestParts,
+    },
+    http::HeaderValue,
+    response::{
+        sse::{Event, Sse},
+        IntoResponse, Response,
+    },
+    routing::{get, post},
+    Json, Router, Server,
 };
-use hyper::server::conn::AddrIncoming;
+use futures_util::Stream;
 use serde::{Deserialize, Serialize};
-use tokio::sync::{Mutex, RwLock};
+use tokio::sync::{
+    mpsc, watch, Mutex, OwnedMutexGuard, OwnedRwLockReadGuard, OwnedRwLockWriteGuard, RwLock,
+};
+use tracing::{error, warn};
 
 use crate::core::{
-    types::{AppInput, AppOutput, AppResult},
+    server::{
+        auth::AuthToken, instance::ServerSlot, shutdown_on_signal, supervise, CorsConfig,
+        RestartPolicy, ServerError, ServerInstance, SupervisorExit, DEFAULT_MAX_BODY_BYTES,
+    },
+    types::{
+        AddFunctionRequest, AppError, AppInput, AppOutput, AppResult, BodyStream, CacheStatus,
+        ComputeRequest, ComputeResponse, ExecutionMeta, MetaMode, RemoveFunctionRequest,
+        RequestContext, ResponseSink, TargetComputeFunc, META_REQUEST_HEADER, NONCE_HEADER,
+        REQUEST_ID_HEADER,
+    },
     ComputeFunctionManager,
 };
 
+/// Build the [`RequestContext`] for a request from whatever connection info the server provided.
+fn request_context(
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    RequestNonce(nonce): RequestNonce,
+    RequestId(request_id): RequestId,
+) -> RequestContext {
+    RequestContext::new()
+        .with_peer_addr(connect_info.map(|ConnectInfo(addr)| addr))
+        .with_nonce(nonce)
+        .with_request_id(request_id)
+}
+
+/// The nonce a client sent in the [`NONCE_HEADER`], if any.
+struct RequestNonce(Option<String>);
+
+#[async_trait]
+impl<B: Send> FromRequest<B> for RequestNonce {
+    type Rejection = Infallible;
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
+        let value = req
+            .headers()
+            .and_then(|headers| headers.get(NONCE_HEADER))
+            .and_then(|value| value.to_str().ok())
+            .map(ToString::to_string);
+        Ok(Self(value))
+    }
+}
+
+/// The id a client sent in the [`REQUEST_ID_HEADER`], or a fresh UUID if it didn't send one.
+struct RequestId(String);
+
+#[async_trait]
+impl<B: Send> FromRequest<B> for RequestId {
+    type Rejection = Infallible;
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
+        let value = req
+            .headers()
+            .and_then(|headers| headers.get(REQUEST_ID_HEADER))
+            .and_then(|value| value.to_str().ok())
+            .filter(|value| !value.is_empty())
+            .map_or_else(|| uuid::Uuid::new_v4().to_string(), ToString::to_string);
+        Ok(Self(value))
+    }
+}
+
+/// A [`Json`] body whose rejections are reported as an [`AppError::MalformedBody`], so that a body
+/// that can't be parsed gets the same error envelope as every other error.
+struct AppJson<T>(T);
+
+#[async_trait]
+impl<B, T> FromRequest<B> for AppJson<T>
+where
+    B: Send,
+    T: Send,
+    Json<T>: FromRequest<B>,
+    <Json<T> as FromRequest<B>>::Rejection: std::error::Error + 'static,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
+        Json::<T>::from_request(req)
+            .await
+            .map(|Json(value)| Self(value))
+            .map_err(|rejection| {
+                if exceeds_body_limit(&rejection) {
+                    AppError::PayloadTooLarge(rejection.to_string())
+                } else {
+                    AppError::MalformedBody(rejection.to_string())
+                }
+            })
+    }
+}
+
+/// An [`AppInput`] body, parsed with [`AppInput::from_json_slice`] like on every other server. Like
+/// [`AppJson`] it insists on a JSON content type and reports rejections as [`AppError`]s.
+struct AppInputBody(AppInput);
+
+#[async_trait]
+impl<B> FromRequest<B> for AppInputBody
+where
+    B: Send,
+    axum::body::Bytes: FromRequest<B>,
+    <axum::body::Bytes as FromRequest<B>>::Rejection: std::error::Error + 'static,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
+        let is_json = req
+            .headers()
+            .and_then(|headers| headers.get(axum::http::header::CONTENT_TYPE))
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(';').next())
+            .map(|mime| mime.trim().to_ascii_lowercase())
+            .map_or(false, |mime| {
+                mime == "application/json" || mime.ends_with("+json")
+            });
+        if !is_json {
+            return Err(AppError::MalformedBody(
+                "Expected request with `Content-Type: application/json`".to_string(),
+            ));
+        }
+
+        let bytes = axum::body::Bytes::from_request(req)
+            .await
+            .map_err(|rejection| {
+                if exceeds_body_limit(&rejection) {
+                    AppError::PayloadTooLarge(rejection.to_string())
+                } else {
+                    AppError::MalformedBody(rejection.to_string())
+                }
+            })?;
+        super::parse_app_input(&bytes).map(Self)
+    }
+}
+
+/// The request body type behind [`limit_body`].
+type LimitedBody = http_body::Limited<axum::body::Body>;
+
+/// Turn away request bodies over `max_body_bytes` with a `413`. Bodies that announce their length
+/// are turned away before any handler runs, others once a handler reads past the limit (see
+/// [`exceeds_body_limit`]).
+fn limit_body(router: Router<LimitedBody>, max_body_bytes: usize) -> Router {
+    router.layer(tower_http::limit::RequestBodyLimitLayer::new(
+        max_body_bytes,
+    ))
+}
+
+/// Whether `error` came from reading past the limit set with [`limit_body`].
+fn exceeds_body_limit(error: &(dyn std::error::Error + 'static)) -> bool {
+    let mut source = Some(error);
+    while let Some(error) = source {
+        if error.is::<http_body::LengthLimitError>() {
+            return true;
+        }
+        source = error.source();
+    }
+    false
+}
+
+/// The [`MetaMode`] a client asked for with the [`META_REQUEST_HEADER`].
+struct RequestedMeta(MetaMode);
+
+#[async_trait]
+impl<B: Send> FromRequest<B> for RequestedMeta {
+    type Rejection = Infallible;
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
+        let value = req
+            .headers()
+            .and_then(|headers| headers.get(META_REQUEST_HEADER))
+            .and_then(|value| value.to_str().ok());
+        Ok(Self(MetaMode::from_header(value)))
+    }
+}
+
+/// Await `process` and, if the client asked for it, attach the [`ExecutionMeta`] of `input` to the
+/// response. Only [`AppInput::Execute`] requests carry metadata.
+async fn respond_with_meta<F>(mode: MetaMode, input: &AppInput, process: F) -> Response
+where
+    F: Future<Output = AppResult<AppOutput>>,
+{
+    let started = Instant::now();
+    let result = process.await;
+    let request = match input {
+        AppInput::Execute(request) if mode != MetaMode::Off => request,
+        _ => return result.into_response(),
+    };
+
+    // There is no response cache yet, so nothing can be a hit or a miss.
+    let meta = ExecutionMeta::new(
+        request.target().name(),
+        started.elapsed(),
+        CacheStatus::Bypass,
+    );
+    let result = match result {
+        Ok(AppOutput::ComputeResponse(response)) if mode == MetaMode::Body => {
+            Ok(AppOutput::ComputeResponse(with_meta_block(response, &meta)))
+        }
+        other => other,
+    };
+
+    let mut response = result.into_response();
+    for (name, value) in meta.headers() {
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            response.headers_mut().insert(name, value);
+        }
+    }
+    response
+}
+
+/// Add a `_meta` block to `response` if its data is a JSON object, otherwise return it untouched.
+fn with_meta_block(response: ComputeResponse, meta: &ExecutionMeta) -> ComputeResponse {
+    match response.data() {
+        Some(serde_json::Value::Object(mut data)) if !response.is_error() => {
+            data.insert("_meta".to_string(), meta.to_json());
+            response.headers().into_iter().flatten().fold(
+                ComputeResponse::json(response.status(), serde_json::Value::Object(data)),
+                |with_meta, (name, value)| with_meta.with_header(name.clone(), value.clone()),
+            )
+        }
+        _ => response,
+    }
+}
+
 /// Used to simplify [`axum::extract::Extension`] extraction of the [`ComputeFunctionManager`]
 type MutexManager = Arc<Mutex<ComputeFunctionManager>>;
 /// Used to simplify [`axum::extract::Extension`] extraction of the [`ComputeFunctionManager`].
@@ -27,72 +258,609 @@ type MutexManager = Arc<Mutex<ComputeFunctionManager>>;
 /// be more efficient in this particular use case.
 type RwLockManager = Arc<RwLock<ComputeFunctionManager>>;
 
-async fn process_input_mutex(pm: &MutexManager, input: &AppInput) -> AppResult<AppOutput> {
+/// A lock the [`ComputeFunctionManager`] can be shared behind, so that the router and its handlers
+/// are written once for every [`ServerSyncType`].
+#[async_trait]
+trait ManagerLock: Default + Send + Sync + 'static {
+    /// The guard for executing requests and reading the manager.
+    type Shared: Deref<Target = ComputeFunctionManager> + Send;
+    /// The guard for loading and unloading functions.
+    type Exclusive: Deref<Target = ComputeFunctionManager> + Send;
+
+    /// Wait for access that a [`RwLock`] shares with other readers.
+    async fn shared(manager: Arc<Self>) -> Self::Shared;
+    /// Wait for access that nobody else has at the same time.
+    async fn exclusive(manager: Arc<Self>) -> Self::Exclusive;
+}
+
+#[async_trait]
+impl ManagerLock for Mutex<ComputeFunctionManager> {
+    type Shared = OwnedMutexGuard<ComputeFunctionManager>;
+    type Exclusive = OwnedMutexGuard<ComputeFunctionManager>;
+
+    async fn shared(manager: Arc<Self>) -> Self::Shared {
+        manager.lock_owned().await
+    }
+
+    async fn exclusive(manager: Arc<Self>) -> Self::Exclusive {
+        manager.lock_owned().await
+    }
+}
+
+#[async_trait]
+impl ManagerLock for RwLock<ComputeFunctionManager> {
+    type Shared = OwnedRwLockReadGuard<ComputeFunctionManager>;
+    type Exclusive = OwnedRwLockWriteGuard<ComputeFunctionManager>;
+
+    async fn shared(manager: Arc<Self>) -> Self::Shared {
+        manager.read_owned().await
+    }
+
+    async fn exclusive(manager: Arc<Self>) -> Self::Exclusive {
+        manager.write_owned().await
+    }
+}
+
+/// The manager a router serves, behind whichever lock its [`ServerSyncType`] resolved to. Kept by
+/// the runners so they can shut the manager down once the server has stopped.
+#[derive(Debug, Clone)]
+enum SharedManager {
+    Mutex(MutexManager),
+    RwLock(RwLockManager),
+}
+
+impl SharedManager {
+    fn new(sync_type: ServerSyncType, manager: ComputeFunctionManager) -> Self {
+        match sync_type.resolve() {
+            ServerSyncType::Mutex => Self::Mutex(Arc::new(Mutex::new(manager))),
+            ServerSyncType::RwLock | ServerSyncType::Auto => {
+                Self::RwLock(Arc::new(RwLock::new(manager)))
+            }
+        }
+    }
+
+    /// [Shut down](ComputeFunctionManager::shutdown) the manager, so every function gets its
+    /// [`on_plugin_unload`](crate::ComputeFunction::on_plugin_unload). The server has to be gone by
+    /// now, if anything else still shares the manager it is left to be cleaned up when dropped.
+    async fn shutdown(self) {
+        let manager = match self {
+            Self::Mutex(manager) => Arc::try_unwrap(manager).map(Mutex::into_inner).ok(),
+            Self::RwLock(manager) => Arc::try_unwrap(manager).map(RwLock::into_inner).ok(),
+        };
+        match manager {
+            Some(manager) => manager.shutdown().await,
+            None => {
+                warn!("The manager is still in use after the server stopped, not shutting it down");
+            }
+        }
+    }
+}
+
+/// Lets the long-lived `/ws` and `/sse` sessions of a router know that the server is shutting
+/// down, so they can end cleanly rather than hold the graceful shutdown up or be reset by it.
+#[derive(Debug, Clone)]
+struct Sessions {
+    /// Bumped every time the sessions are closed.
+    closings: Arc<watch::Sender<u64>>,
+    latest: watch::Receiver<u64>,
+}
+
+impl Default for Sessions {
+    fn default() -> Self {
+        let (closings, latest) = watch::channel(0);
+        Self {
+            closings: Arc::new(closings),
+            latest,
+        }
+    }
+}
+
+impl Sessions {
+    /// Tell every session that is open right now to wrap up. Sessions opened later are not affected,
+    /// so a server can be started again with the same router.
+    fn close(&self) {
+        // The value can't be borrowed while it is replaced.
+        let next = *self.latest.borrow() + 1;
+        let _ = self.closings.send(next);
+    }
+
+    /// Wait for `shutdown_signal`, then [close](Sessions::close) the sessions. Meant to be handed to
+    /// [`Server::with_graceful_shutdown`].
+    async fn close_on(self, shutdown_signal: impl Future) {
+        shutdown_signal.await;
+        self.close();
+    }
+
+    /// Resolves once the sessions are [closed](Sessions::close), for a session opened now.
+    fn closed(&self) -> impl Future<Output = ()> + Send + 'static {
+        let mut latest = self.latest.clone();
+        let opened = *latest.borrow();
+        async move {
+            while *latest.borrow() == opened {
+                if latest.changed().await.is_err() {
+                    // The router is gone, and with it every way of closing the sessions.
+                    return std::future::pending().await;
+                }
+            }
+        }
+    }
+}
+
+async fn process_input<L: ManagerLock>(pm: &Arc<L>, input: &AppInput) -> AppResult<AppOutput> {
     match input {
         AppInput::AddComputeFunction(add) => unsafe {
-            pm.lock()
+            L::exclusive(pm.clone())
                 .await
-                .load_plugin(add.lib_path().to_string())
+                .load_plugin_info(add.lib_path().to_string())
                 .await
-                .map(|_| AppOutput::AddFunctionSuccess)
+                .map(AppOutput::add_function_success)
                 .map_err(std::convert::Into::into)
         },
-        AppInput::RemoveComputeFunction(remove) => pm
-            .lock()
+        AppInput::RemoveComputeFunction(remove) => L::exclusive(pm.clone())
             .await
             .unload_plugin(remove.target())
             .await
             .map(|_| AppOutput::RemoveFunctionSuccess)
             .map_err(std::convert::Into::into),
-        AppInput::Execute(req) => pm
-            .lock()
+        AppInput::Execute(req) => L::shared(pm.clone())
             .await
             .push_request(req)
             .await
             .map(AppOutput::compute_response),
+        AppInput::ListFunctions => Ok(AppOutput::FunctionList(
+            L::shared(pm.clone()).await.list_functions().await,
+        )),
+        AppInput::HealthCheck => Ok(AppOutput::HealthReport(
+            L::shared(pm.clone()).await.health_check().await,
+        )),
     }
 }
 
-async fn process_input_rw(pm: RwLockManager, input: &AppInput) -> AppResult<AppOutput> {
-    match input {
-        AppInput::AddComputeFunction(add) => unsafe {
-            let pm_writer = pm.write_owned().await;
-            pm_writer
-                .load_plugin(add.lib_path().to_string())
-                .await
-                .map(|_| AppOutput::AddFunctionSuccess)
-                .map_err(std::convert::Into::into)
+async fn process_input_handler<L: ManagerLock>(
+    AppInputBody(mut payload): AppInputBody,
+    Extension(state): Extension<Arc<L>>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    nonce: RequestNonce,
+    request_id: RequestId,
+    RequestedMeta(meta): RequestedMeta,
+) -> Response {
+    payload.set_context(request_context(connect_info, nonce, request_id));
+    respond_with_meta(meta, &payload, process_input(&state, &payload)).await
+}
+
+/// Stream the raw request body to the target function without buffering it, see
+/// [`ComputeFunctionManager::push_body_stream`].
+async fn stream_body_handler<L: ManagerLock>(
+    Path(target): Path<String>,
+    Extension(state): Extension<Arc<L>>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    nonce: RequestNonce,
+    request_id: RequestId,
+    body: axum::extract::BodyStream,
+) -> AppResult<AppOutput> {
+    L::shared(state)
+        .await
+        .push_body_stream(
+            &TargetComputeFunc::new(target),
+            request_context(connect_info, nonce, request_id),
+            BodyStream::from_stream(body),
+        )
+        .await
+        .map(AppOutput::compute_response)
+}
+
+/// List every loaded function, see [`ComputeFunctionManager::list_functions`].
+async fn list_functions_handler<L: ManagerLock>(Extension(state): Extension<Arc<L>>) -> AppOutput {
+    AppOutput::FunctionList(L::shared(state).await.list_functions().await)
+}
+
+/// The [`AppInput::Execute`] sending `data` to the function `target`.
+fn execute_input(target: String, data: serde_json::Value, context: RequestContext) -> AppInput {
+    AppInput::Execute(
+        ComputeRequest::new(TargetComputeFunc::new(target), data).with_context(context),
+    )
+}
+
+/// Execute the function named in the path, with the request body as its data.
+async fn execute_function_handler<L: ManagerLock>(
+    Path(target): Path<String>,
+    AppJson(data): AppJson<serde_json::Value>,
+    Extension(state): Extension<Arc<L>>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    nonce: RequestNonce,
+    request_id: RequestId,
+    RequestedMeta(meta): RequestedMeta,
+) -> Response {
+    let payload = execute_input(
+        target,
+        data,
+        request_context(connect_info, nonce, request_id),
+    );
+    respond_with_meta(meta, &payload, process_input(&state, &payload)).await
+}
+
+/// Execute a [`ComputeRequest`] sent on its own, without the [`AppInput`] envelope.
+async fn execute_request_handler<L: ManagerLock>(
+    AppJson(mut request): AppJson<ComputeRequest>,
+    Extension(state): Extension<Arc<L>>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    nonce: RequestNonce,
+    request_id: RequestId,
+    RequestedMeta(meta): RequestedMeta,
+) -> Response {
+    request.set_context(request_context(connect_info, nonce, request_id));
+    let payload = AppInput::Execute(request);
+    respond_with_meta(meta, &payload, process_input(&state, &payload)).await
+}
+
+/// Load the plugin library named in the body, see [`AppInput::AddComputeFunction`].
+async fn add_function_handler<L: ManagerLock>(
+    AppJson(add): AppJson<AddFunctionRequest>,
+    Extension(state): Extension<Arc<L>>,
+) -> AppResult<AppOutput> {
+    process_input(&state, &AppInput::AddComputeFunction(add)).await
+}
+
+/// The [`AppInput::RemoveComputeFunction`] unloading the function `name`.
+fn remove_input(name: String) -> AppInput {
+    AppInput::RemoveComputeFunction(RemoveFunctionRequest::new(TargetComputeFunc::new(name)))
+}
+
+/// Unload the function named in the path, see [`AppInput::RemoveComputeFunction`].
+async fn remove_function_handler<L: ManagerLock>(
+    Path(name): Path<String>,
+    Extension(state): Extension<Arc<L>>,
+) -> AppResult<AppOutput> {
+    process_input(&state, &remove_input(name)).await
+}
+
+/// Serve the `OpenAPI` document, see [`ComputeFunctionManager::export_openapi`].
+async fn openapi_handler<L: ManagerLock>(
+    Extension(state): Extension<Arc<L>>,
+) -> Json<serde_json::Value> {
+    Json(L::shared(state).await.export_openapi().await)
+}
+
+/// How many frames of a [`ResponseSink`] are buffered before the function waits to hand more over.
+const STREAM_FRAME_BUFFER: usize = 16;
+
+/// How many frames a client can fall behind a streamed function before its session is closed, see
+/// [`spawn_stream`].
+const STREAM_CLIENT_BUFFER: usize = 1024;
+
+/// A streamed function's frames on their way to the client, and the task running the function. The
+/// frames end in an [`AppError::Overloaded`] if the client fell too far behind.
+type SpawnedStream = (
+    mpsc::Receiver<AppResult<ComputeResponse>>,
+    tokio::task::JoinHandle<AppResult<()>>,
+);
+
+/// Run `push` for `request` in a task of its own, with the frames it sends passed on to the returned
+/// receiver. Up to [`STREAM_CLIENT_BUFFER`] frames are buffered on the way, so a slow client doesn't
+/// hold up the function, and with it the lock on the manager. A client that falls further behind
+/// than that is cut off rather than waited for: the function's [`ResponseSink`] reports it as gone,
+/// and the frames end in an [`AppError::Overloaded`] for the session to close with. Once the
+/// receiver is dropped the function's [`ResponseSink`] reports the client as gone as well.
+fn spawn_stream<F, Fut>(request: ComputeRequest, push: F) -> SpawnedStream
+where
+    F: FnOnce(ComputeRequest, ResponseSink) -> Fut,
+    Fut: Future<Output = AppResult<()>> + Send + 'static,
+{
+    let (sink, mut frames) = ResponseSink::channel(STREAM_FRAME_BUFFER);
+    // One more slot than the client may fall behind by, so there is always room for the error.
+    let (sender, receiver) = mpsc::channel(STREAM_CLIENT_BUFFER + 1);
+    tokio::task::spawn(async move {
+        while let Some(frame) = frames.recv().await {
+            if sender.capacity() == 1 {
+                drop(frames);
+                let _ = sender
+                    .send(Err(AppError::overloaded(
+                        "The client fell too far behind the stream",
+                    )))
+                    .await;
+                return;
+            }
+            if sender.send(Ok(frame)).await.is_err() {
+                return;
+            }
+        }
+    });
+    (receiver, tokio::task::spawn(push(request, sink)))
+}
+
+/// The JSON text of a frame sent over the `/ws` route, with the same `status` and `data` fields as
+/// each entry of [`ComputeResponse::merge`]. The data of a [`ComputeResponse::Binary`] frame holds
+/// its `content_type` and base64 encoded `bytes`.
+fn frame_text(frame: &ComputeResponse) -> String {
+    let data = match frame {
+        ComputeResponse::Binary {
+            content_type,
+            bytes,
+            ..
+        } => serde_json::json!({ "content_type": content_type, "bytes": base64::encode(bytes) }),
+        _ => frame.data().unwrap_or_default(),
+    };
+    serde_json::json!({ "status": frame.status().to_u16(), "data": data }).to_string()
+}
+
+/// The close code sent to WebSocket clients when the server shuts down, `1001 Going Away`.
+const GOING_AWAY: u16 = 1001;
+
+/// Read a single [`ComputeRequest`] from `socket`, hand it to `push` with a [`ResponseSink`], and
+/// forward every frame the function sends as a text message. A request that can't be parsed, or an
+/// error from `push`, is sent as a final frame holding the usual error envelope. The socket is
+/// closed once the function is done, and the function is cancelled if the client goes away first.
+///
+/// Once `closed` resolves the function is cancelled as well: the frame being sent is finished, an
+/// [`AppError::Draining`] envelope follows it, and the socket is closed with [`GOING_AWAY`]. A client
+/// that falls too far behind is sent an [`AppError::Overloaded`] envelope and closed on in the same
+/// way, see [`spawn_stream`].
+async fn stream_over_socket<F, Fut>(
+    mut socket: WebSocket,
+    context: RequestContext,
+    closed: impl Future<Output = ()>,
+    push: F,
+) where
+    F: FnOnce(ComputeRequest, ResponseSink) -> Fut,
+    Fut: Future<Output = AppResult<()>> + Send + 'static,
+{
+    tokio::pin!(closed);
+    let request = tokio::select! {
+        message = socket.recv() => match message {
+            Some(Ok(Message::Text(text))) => serde_json::from_str::<ComputeRequest>(&text),
+            Some(Ok(Message::Binary(bytes))) => serde_json::from_slice::<ComputeRequest>(&bytes),
+            // The client hung up without asking for anything.
+            _ => return,
         },
-        AppInput::RemoveComputeFunction(rm) => {
-            let pm_writer = pm.write_owned().await;
-            pm_writer
-                .unload_plugin(rm.target())
-                .await
-                .map(|_| AppOutput::RemoveFunctionSuccess)
-                .map_err(std::convert::Into::into)
+        () = &mut closed => {
+            go_away(socket).await;
+            return;
         }
-        AppInput::Execute(req) => {
-            let pm_reader = pm.read_owned().await;
-            pm_reader
-                .push_request(req)
-                .await
-                .map(AppOutput::compute_response)
+    };
+    let mut request = match request {
+        Ok(request) => request,
+        Err(e) => {
+            let error = AppError::MalformedBody(e.to_string());
+            let _ = socket
+                .send(Message::Text(error.envelope().to_string()))
+                .await;
+            let _ = socket.close().await;
+            return;
         }
+    };
+    request.set_context(context);
+
+    let (mut frames, function) = spawn_stream(request, push);
+    loop {
+        tokio::select! {
+            frame = frames.recv() => match frame {
+                Some(Ok(frame)) => {
+                    if socket.send(Message::Text(frame_text(&frame))).await.is_err() {
+                        function.abort();
+                        return;
+                    }
+                }
+                Some(Err(error)) => {
+                    function.abort();
+                    let _ = socket.send(Message::Text(error.envelope().to_string())).await;
+                    let _ = socket.close().await;
+                    return;
+                }
+                None => break,
+            },
+            () = &mut closed => {
+                function.abort();
+                let _ = socket
+                    .send(Message::Text(AppError::Draining.envelope().to_string()))
+                    .await;
+                go_away(socket).await;
+                return;
+            }
+        }
+    }
+    if let Ok(Err(error)) = function.await {
+        let _ = socket
+            .send(Message::Text(error.envelope().to_string()))
+            .await;
     }
+    let _ = socket.close().await;
 }
 
-async fn process_input_mutex_handler(
-    Json(payload): Json<AppInput>,
-    Extension(state): Extension<MutexManager>,
-) -> AppResult<AppOutput> {
-    process_input_mutex(&state, &payload).await
+/// Close `socket` with [`GOING_AWAY`], because the server is shutting down.
+async fn go_away(mut socket: WebSocket) {
+    let _ = socket
+        .send(Message::Close(Some(CloseFrame {
+            code: GOING_AWAY,
+            reason: "The server is shutting down".into(),
+        })))
+        .await;
 }
 
-async fn process_input_rw_handler(
-    Json(payload): Json<AppInput>,
-    Extension(state): Extension<RwLockManager>,
-) -> AppResult<AppOutput> {
-    process_input_rw(state.clone(), &payload).await
+/// Upgrade to a WebSocket streaming a function's output, see [`stream_over_socket`].
+async fn stream_ws_handler<L: ManagerLock>(
+    upgrade: WebSocketUpgrade,
+    Extension(state): Extension<Arc<L>>,
+    Extension(sessions): Extension<Sessions>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    nonce: RequestNonce,
+    request_id: RequestId,
+) -> Response {
+    let context = request_context(connect_info, nonce, request_id);
+    let closed = sessions.closed();
+    upgrade.on_upgrade(move |socket| {
+        stream_over_socket(socket, context, closed, move |request, sink| async move {
+            L::shared(state)
+                .await
+                .push_streaming_request(&request, sink)
+                .await
+        })
+    })
+}
+
+/// Where [`sse_events`] is in the stream.
+enum SseStage {
+    /// Passing frames on until the function is done, or the sessions are closed.
+    Frames(
+        tokio::task::JoinHandle<AppResult<()>>,
+        Pin<Box<dyn Future<Output = ()> + Send>>,
+    ),
+    /// The function failed (or was cancelled) and its error was sent, only the `done` event is left.
+    Done,
+    /// The `done` event was sent.
+    Closed,
+}
+
+/// The server-sent events for a function's `frames`: an unnamed event per frame holding the
+/// serialized [`ComputeResponse`], then, if `function` failed, an `error` event holding the usual
+/// error envelope, and finally an empty `done` event. If `closed` resolves first the function is
+/// cancelled and the `error` event holds an [`AppError::Draining`] envelope instead, as it holds
+/// an [`AppError::Overloaded`] one if the client fell too far behind (see [`spawn_stream`]).
+fn sse_events(
+    frames: mpsc::Receiver<AppResult<ComputeResponse>>,
+    function: tokio::task::JoinHandle<AppResult<()>>,
+    closed: impl Future<Output = ()> + Send + 'static,
+) -> impl Stream<Item = Result<Event, Infallible>> {
+    let error_event = |error: &AppError| {
+        Event::default()
+            .event("error")
+            .data(error.envelope().to_string())
+    };
+    futures_util::stream::unfold(
+        (frames, SseStage::Frames(function, Box::pin(closed))),
+        move |(mut frames, stage)| async move {
+            let (event, stage) = match stage {
+                SseStage::Frames(function, mut closed) => tokio::select! {
+                    frame = frames.recv() => match frame {
+                        Some(Ok(frame)) => (
+                            Event::default()
+                                .json_data(&frame)
+                                .expect("ComputeResponse is always serializable"),
+                            SseStage::Frames(function, closed),
+                        ),
+                        Some(Err(error)) => {
+                            function.abort();
+                            (error_event(&error), SseStage::Done)
+                        }
+                        None => match function.await {
+                            Ok(Err(error)) => (error_event(&error), SseStage::Done),
+                            _ => (Event::default().event("done").data(""), SseStage::Closed),
+                        },
+                    },
+                    () = &mut closed => {
+                        function.abort();
+                        (error_event(&AppError::Draining), SseStage::Done)
+                    }
+                },
+                SseStage::Done => (Event::default().event("done").data(""), SseStage::Closed),
+                SseStage::Closed => return None,
+            };
+            Some((Ok(event), (frames, stage)))
+        },
+    )
+}
+
+/// The query of a `GET /sse`, holding the JSON of the [`ComputeRequest`] to run.
+#[derive(Debug, Deserialize)]
+struct SseQuery {
+    request: String,
+}
+
+/// The [`ComputeRequest`] in the query of a `GET /sse`.
+fn sse_query_request(query: Result<Query<SseQuery>, QueryRejection>) -> AppResult<ComputeRequest> {
+    let Query(query) = query.map_err(|e| AppError::MalformedBody(e.to_string()))?;
+    serde_json::from_str(&query.request).map_err(|e| AppError::MalformedBody(e.to_string()))
+}
+
+/// Stream the output of `request` as server-sent events, see [`sse_events`].
+fn sse<L: ManagerLock>(
+    state: Arc<L>,
+    sessions: &Sessions,
+    request: ComputeRequest,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let (frames, function) = spawn_stream(request, move |request, sink| async move {
+        L::shared(state)
+            .await
+            .push_streaming_request(&request, sink)
+            .await
+    });
+    Sse::new(sse_events(frames, function, sessions.closed()))
+}
+
+/// Stream the output of the [`ComputeRequest`] in the `request` query parameter as server-sent
+/// events, for clients that can't use the `/ws` route.
+async fn sse_query_handler<L: ManagerLock>(
+    query: Result<Query<SseQuery>, QueryRejection>,
+    Extension(state): Extension<Arc<L>>,
+    Extension(sessions): Extension<Sessions>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    nonce: RequestNonce,
+    request_id: RequestId,
+) -> AppResult<Sse<impl Stream<Item = Result<Event, Infallible>>>> {
+    let request =
+        sse_query_request(query)?.with_context(request_context(connect_info, nonce, request_id));
+    Ok(sse(state, &sessions, request))
+}
+
+/// Stream the output of the [`ComputeRequest`] in the body as server-sent events.
+async fn sse_body_handler<L: ManagerLock>(
+    AppJson(request): AppJson<ComputeRequest>,
+    Extension(state): Extension<Arc<L>>,
+    Extension(sessions): Extension<Sessions>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    nonce: RequestNonce,
+    request_id: RequestId,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    sse(
+        state,
+        &sessions,
+        request.with_context(request_context(connect_info, nonce, request_id)),
+    )
+}
+
+/// Build the router every axum runner serves, sharing `manager` between the handlers behind the
+/// lock `L`, ending its streaming sessions when `sessions` are closed and turning away buffered
+/// bodies over `max_body_bytes`. Bodies sent to `/stream/:target` are never buffered, so they are
+/// left for the target function to bound.
+fn app_router<L: ManagerLock>(
+    manager: Arc<L>,
+    sessions: &Sessions,
+    max_body_bytes: usize,
+) -> Router {
+    let streamed = Router::new()
+        .route("/stream/:target", post(stream_body_handler::<L>))
+        .layer(Extension(manager.clone()));
+    let buffered = Router::new()
+        .route("/", post(process_input_handler::<L>))
+        .route("/ws", get(stream_ws_handler::<L>))
+        .route(
+            "/sse",
+            get(sse_query_handler::<L>).post(sse_body_handler::<L>),
+        )
+        .route("/execute", post(execute_request_handler::<L>))
+        .route(
+            "/functions",
+            get(list_functions_handler::<L>).post(add_function_handler::<L>),
+        )
+        .route(
+            "/functions/:name",
+            post(execute_function_handler::<L>).delete(remove_function_handler::<L>),
+        )
+        .route("/openapi.json", get(openapi_handler::<L>))
+        .layer(Extension(manager))
+        .layer(Extension(sessions.clone()));
+    limit_body(buffered, max_body_bytes).merge(streamed)
+}
+
+/// Layer the check for `auth_token` (if there is one) and then `cors` over `router`, in that order
+/// so that preflights are answered before the token is checked.
+fn protect(mut router: Router, auth_token: Option<&AuthToken>, cors: &CorsConfig) -> Router {
+    if let Some(token) = auth_token {
+        router = router.layer(token.layer());
+    }
+    if let Some(cors) = cors.layer() {
+        router = router.layer(cors);
+    }
+    router
 }
 
 async fn fake_main() {
@@ -100,7 +868,7 @@ async fn fake_main() {
     let (sender, receiver): (oneshot::Sender<()>, oneshot::Receiver<()>) = oneshot::channel::<()>();
 
     let addr = SocketAddr::from(([127, 0, 0, 1], 8080));
-    let res = run_rw_axum_with_shutdown(&addr, receiver);
+    let res = run_rw_axum_with_shutdown(&addr, None, receiver);
 
     tokio::task::spawn(async move {
         tokio::time::sleep(std::time::Duration::from_millis(10 * 1000)).await;
@@ -116,17 +884,21 @@ async fn fake_main() {
 
 pub async fn run_rw_axum_with_shutdown(
     addr: &std::net::SocketAddr,
+    auth_token: Option<&AuthToken>,
     rx: tokio::sync::oneshot::Receiver<()>,
 ) -> tokio::task::JoinHandle<String> {
-    let app: Router = Router::new()
-        .route("/", post(process_input_rw_handler))
-        .layer(AddExtensionLayer::new(RwLockManager::default()));
+    let sessions = Sessions::default();
+    let app = protect(
+        app_router(RwLockManager::default(), &sessions, DEFAULT_MAX_BODY_BYTES),
+        auth_token,
+        &CorsConfig::default(),
+    );
 
     let server = axum::Server::bind(addr)
-        .serve(app.into_make_service())
-        .with_graceful_shutdown(async move {
+        .serve(app.into_make_service_with_connect_info::<SocketAddr, _>())
+        .with_graceful_shutdown(sessions.close_on(async move {
             rx.await.ok();
-        });
+        }));
 
     tokio::task::spawn(async move {
         if let Err(e) = server.await {
@@ -137,30 +909,171 @@ pub async fn run_rw_axum_with_shutdown(
     })
 }
 
-pub async fn run_axum_with_mutex(addr: &std::net::SocketAddr) -> Result<(), hyper::Error> {
-    let app: Router = Router::new()
-        .route("/", post(process_input_mutex_handler))
-        .layer(AddExtensionLayer::new(MutexManager::default()));
+/// Serve a new manager behind a [`Mutex`] on `addr`, turning away bodies over `max_body_bytes` and
+/// requests without `auth_token` (if there is one), and answering cross-origin requests as `cors`
+/// allows.
+pub async fn run_axum_with_mutex(
+    addr: &std::net::SocketAddr,
+    max_body_bytes: usize,
+    cors: &CorsConfig,
+    auth_token: Option<&AuthToken>,
+) -> Result<(), hyper::Error> {
+    let app = protect(
+        app_router(
+            MutexManager::default(),
+            &Sessions::default(),
+            max_body_bytes,
+        ),
+        auth_token,
+        cors,
+    );
 
     axum::Server::bind(addr)
-        .serve(app.into_make_service())
+        .serve(app.into_make_service_with_connect_info::<SocketAddr, _>())
         .await
 }
 
-pub async fn run_axum_with_rw(addr: &std::net::SocketAddr) -> Result<(), hyper::Error> {
-    let app: Router = Router::new()
-        .route("/", post(process_input_rw_handler))
-        .layer(AddExtensionLayer::new(RwLockManager::default()));
+/// Serve a new manager behind a [`RwLock`] on `addr`, turning away bodies over `max_body_bytes` and
+/// requests without `auth_token` (if there is one), and answering cross-origin requests as `cors`
+/// allows.
+pub async fn run_axum_with_rw(
+    addr: &std::net::SocketAddr,
+    max_body_bytes: usize,
+    cors: &CorsConfig,
+    auth_token: Option<&AuthToken>,
+) -> Result<(), hyper::Error> {
+    let app = protect(
+        app_router(
+            RwLockManager::default(),
+            &Sessions::default(),
+            max_body_bytes,
+        ),
+        auth_token,
+        cors,
+    );
 
     axum::Server::bind(addr)
-        .serve(app.into_make_service())
+        .serve(app.into_make_service_with_connect_info::<SocketAddr, _>())
         .await
 }
 
+/// Serve a new manager on `addr` until `shutdown_signal` fires, with a route per operation so that
+/// plain `curl` calls work without the [`AppInput`] envelope:
+///
+/// - `POST /execute` runs a [`ComputeRequest`]
+/// - `GET /functions` lists the loaded functions
+/// - `POST /functions` loads the library named by an [`AddFunctionRequest`]
+/// - `DELETE /functions/:name` unloads the function `name`
+///
+/// The envelope is still accepted on `POST /` for existing clients. Requests without `auth_token`
+/// (if there is one) are turned away on every route.
+#[must_use]
+pub fn run_axum_rest(
+    addr: &SocketAddr,
+    sync_type: ServerSyncType,
+    auth_token: Option<&AuthToken>,
+    shutdown_signal: tokio::sync::oneshot::Receiver<()>,
+) -> tokio::task::JoinHandle<Result<(), hyper::Error>> {
+    AxumServer::run(
+        addr,
+        sync_type,
+        DEFAULT_MAX_BODY_BYTES,
+        auth_token,
+        shutdown_signal,
+    )
+}
+
+/// Serve the same router as [`AxumServer::run`] over HTTPS, until `shutdown_signal` fires.
+///
+/// The PEM encoded certificate (chain) is read from `cert_path` and the private key from
+/// `key_path`. Requests without `auth_token` (if there is one) are turned away.
+///
+/// ## Errors
+/// The returned task fails with an [`std::io::Error`] naming both files if the certificate or key
+/// can't be read or parsed, in which case nothing is bound, or if the server fails.
+#[cfg(feature = "tls")]
+pub fn run_axum_tls(
+    addr: &SocketAddr,
+    sync_type: ServerSyncType,
+    cert_path: impl AsRef<std::path::Path>,
+    key_path: impl AsRef<std::path::Path>,
+    auth_token: Option<&AuthToken>,
+    shutdown_signal: tokio::sync::oneshot::Receiver<()>,
+) -> tokio::task::JoinHandle<std::io::Result<()>> {
+    use axum_server::{tls_rustls::RustlsConfig, Handle};
+
+    let addr = *addr;
+    let auth_token = auth_token.cloned();
+    let cert_path = cert_path.as_ref().to_path_buf();
+    let key_path = key_path.as_ref().to_path_buf();
+    tokio::task::spawn(async move {
+        let config = RustlsConfig::from_pem_file(&cert_path, &key_path)
+            .await
+            .map_err(|e| {
+                std::io::Error::new(
+                    e.kind(),
+                    format!(
+                        "Could not load TLS certificate `{}` and key `{}`: {}",
+                        cert_path.display(),
+                        key_path.display(),
+                        e
+                    ),
+                )
+            })?;
+
+        let handle = Handle::new();
+        let shutdown = handle.clone();
+        let sessions = Sessions::default();
+        let closing = sessions.clone();
+        tokio::task::spawn(async move {
+            if shutdown_signal.await.is_ok() {
+                closing.close();
+                shutdown.graceful_shutdown(None);
+            }
+        });
+
+        let manager = SharedManager::new(sync_type, ComputeFunctionManager::default());
+        let router = protect(
+            AxumServer::shared_router(&manager, &sessions, DEFAULT_MAX_BODY_BYTES),
+            auth_token.as_ref(),
+            &CorsConfig::default(),
+        );
+        let result = axum_server::bind_rustls(addr, config)
+            .handle(handle)
+            .serve(router.into_make_service_with_connect_info::<SocketAddr, _>())
+            .await;
+        manager.shutdown().await;
+        result
+    })
+}
+
 #[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq, Hash)]
+#[cfg_attr(not(feature = "pascal-case-wire"), serde(rename_all = "snake_case"))]
 pub enum ServerSyncType {
     Mutex,
     RwLock,
+    /// Pick the lock based on the workload, which is the recommended choice. `Execute` requests only
+    /// need shared access to the manager while adding and removing functions need exclusive access,
+    /// and compute traffic is far more common, so this currently always resolves to
+    /// [`ServerSyncType::RwLock`].
+    Auto,
+}
+
+impl Default for ServerSyncType {
+    fn default() -> Self {
+        Self::Auto
+    }
+}
+
+impl ServerSyncType {
+    /// The lock that is actually used for this sync type, i.e. [`ServerSyncType::Auto`] resolved.
+    #[must_use]
+    pub const fn resolve(self) -> Self {
+        match self {
+            Self::Auto => Self::RwLock,
+            other => other,
+        }
+    }
 }
 
 impl std::fmt::Display for ServerSyncType {
@@ -168,173 +1081,1355 @@ impl std::fmt::Display for ServerSyncType {
         match self {
             ServerSyncType::Mutex => write!(f, "Mutex"),
             ServerSyncType::RwLock => write!(f, "RwLock"),
+            ServerSyncType::Auto => write!(f, "Auto"),
         }
     }
 }
 
+/// The axum backend as a [`ServerInstance`], serving a manager of its own.
 #[derive(Debug)]
 pub struct AxumServer {
-    addr: std::net::SocketAddr,
     router: Router,
-    is_running: bool,
-    server: Option<hyper::server::Server<AddrIncoming, IntoMakeService<Router>>>,
-    shutdown_signal: tokio::sync::oneshot::Receiver<()>,
+    sessions: Sessions,
     sync_type: ServerSyncType,
+    cors: CorsConfig,
+    auth_token: Option<AuthToken>,
+    running: ServerSlot,
 }
 
 impl AxumServer {
-    async fn process_input_mutex(pm: &MutexManager, input: &AppInput) -> AppResult<AppOutput> {
-        match input {
-            AppInput::AddComputeFunction(add) => unsafe {
-                pm.lock()
-                    .await
-                    .load_plugin(add.lib_path().to_string())
-                    .await
-                    .map(|_| AppOutput::AddFunctionSuccess)
-                    .map_err(std::convert::Into::into)
-            },
-            AppInput::RemoveComputeFunction(rm) => pm
-                .lock()
-                .await
-                .unload_plugin(rm.target())
-                .await
-                .map(|_| AppOutput::RemoveFunctionSuccess)
-                .map_err(std::convert::Into::into),
-            AppInput::Execute(req) => pm
-                .lock()
-                .await
-                .push_request(req)
-                .await
-                .map(AppOutput::compute_response),
+    /// Build the router for `sync_type`, sharing `manager` between handlers behind the matching lock
+    /// and turning away bodies over `max_body_bytes`.
+    fn router(
+        sync_type: ServerSyncType,
+        manager: ComputeFunctionManager,
+        sessions: &Sessions,
+        max_body_bytes: usize,
+    ) -> Router {
+        Self::shared_router(
+            &SharedManager::new(sync_type, manager),
+            sessions,
+            max_body_bytes,
+        )
+    }
+
+    /// Build the router around an already shared `manager`, see [`app_router`].
+    fn shared_router(
+        manager: &SharedManager,
+        sessions: &Sessions,
+        max_body_bytes: usize,
+    ) -> Router {
+        match manager {
+            SharedManager::Mutex(manager) => app_router(manager.clone(), sessions, max_body_bytes),
+            SharedManager::RwLock(manager) => app_router(manager.clone(), sessions, max_body_bytes),
         }
     }
 
-    async fn process_input_rw(pm: RwLockManager, input: &AppInput) -> AppResult<AppOutput> {
-        match input {
-            AppInput::AddComputeFunction(add) => unsafe {
-                let pm_writer = pm.write_owned().await;
-                pm_writer
-                    .load_plugin(add.lib_path().to_string())
-                    .await
-                    .map(|_| AppOutput::AddFunctionSuccess)
-                    .map_err(std::convert::Into::into)
-            },
-            AppInput::RemoveComputeFunction(rm) => {
-                let pm_writer = pm.write_owned().await;
-                pm_writer
-                    .unload_plugin(rm.target())
-                    .await
-                    .map(|_| AppOutput::RemoveFunctionSuccess)
-                    .map_err(std::convert::Into::into)
+    /// Build the [`ServerSyncType::RwLock`] router around an existing `manager`. Nothing closes its
+    /// streaming sessions, they last until the client or the function is done.
+    pub(crate) fn rw_router(manager: RwLockManager, max_body_bytes: usize) -> Router {
+        app_router(manager, &Sessions::default(), max_body_bytes)
+    }
+
+    /// Create a new [`AxumServer`] around an empty manager, to be started with
+    /// [`ServerInstance::start`]. Pass [`ServerSyncType::default`] (which is
+    /// [`ServerSyncType::Auto`]) unless there is a reason to force a particular lock, and
+    /// [`DEFAULT_MAX_BODY_BYTES`] unless functions need larger bodies.
+    #[must_use]
+    pub fn new(sync_type: ServerSyncType, max_body_bytes: usize) -> Self {
+        let sessions = Sessions::default();
+        Self {
+            router: Self::router(
+                sync_type,
+                ComputeFunctionManager::default(),
+                &sessions,
+                max_body_bytes,
+            ),
+            sessions,
+            sync_type,
+            cors: CorsConfig::default(),
+            auth_token: None,
+            running: ServerSlot::default(),
+        }
+    }
+
+    /// Answer cross-origin requests as `cors` allows.
+    #[must_use]
+    pub fn with_cors(mut self, cors: &CorsConfig) -> Self {
+        self.cors = cors.clone();
+        self
+    }
+
+    /// Require every request, on every route, to carry `Authorization: Bearer <token>`. Requests
+    /// that don't are answered with a `401` ([`AppError::Unauthorized`]) before their body is read,
+    /// so in particular no library can be loaded without the token. CORS preflights are still
+    /// answered, since browsers never send credentials with them.
+    ///
+    /// ## Panics
+    /// If `token` is empty.
+    #[must_use]
+    pub fn with_auth_token(mut self, token: impl Into<String>) -> Self {
+        self.auth_token = Some(AuthToken::new(token));
+        self
+    }
+
+    /// The router with the auth check and CORS layered over it, see [`protect`].
+    fn app(&self) -> Router {
+        protect(self.router.clone(), self.auth_token.as_ref(), &self.cors)
+    }
+
+    /// The lock the manager is shared behind.
+    #[must_use]
+    pub const fn sync_type(&self) -> ServerSyncType {
+        self.sync_type
+    }
+
+    /// Serve a new manager on `addr` until `shutdown_signal` fires, then
+    /// [shut the manager down](ComputeFunctionManager::shutdown) so every function gets its
+    /// [`on_plugin_unload`](crate::ComputeFunction::on_plugin_unload). Requests without
+    /// `auth_token` (if there is one) are turned away, see [`AxumServer::with_auth_token`].
+    pub fn run(
+        addr: &SocketAddr,
+        sync_type: ServerSyncType,
+        max_body_bytes: usize,
+        auth_token: Option<&AuthToken>,
+        shutdown_signal: tokio::sync::oneshot::Receiver<()>,
+    ) -> tokio::task::JoinHandle<Result<(), hyper::Error>> {
+        let addr = *addr;
+        let auth_token = auth_token.cloned();
+        tokio::task::spawn(async move {
+            let manager = SharedManager::new(sync_type, ComputeFunctionManager::default());
+            let sessions = Sessions::default();
+            let router = protect(
+                Self::shared_router(&manager, &sessions, max_body_bytes),
+                auth_token.as_ref(),
+                &CorsConfig::default(),
+            );
+            let server = Server::bind(&addr)
+                .serve(router.into_make_service_with_connect_info::<SocketAddr, _>())
+                .with_graceful_shutdown(sessions.close_on(async move {
+                    let _ = shutdown_signal.await.ok();
+                }));
+
+            let result = server.await;
+            manager.shutdown().await;
+            result
+        })
+    }
+
+    /// Serve `manager` on `addr` until `shutdown_signal` fires, like [`AxumServer::run`] does with a
+    /// manager of its own. Unlike it, `addr` is bound right away so that failing to bind is an
+    /// error here rather than a panic in the spawned task.
+    ///
+    /// ## Errors
+    /// [`ServerError::Bind`] if `addr` can't be bound, in which case nothing is spawned.
+    pub fn serve(
+        addr: &SocketAddr,
+        sync_type: ServerSyncType,
+        manager: ComputeFunctionManager,
+        max_body_bytes: usize,
+        auth_token: Option<&AuthToken>,
+        shutdown_signal: tokio::sync::oneshot::Receiver<()>,
+    ) -> Result<tokio::task::JoinHandle<Result<(), hyper::Error>>, ServerError> {
+        let manager = SharedManager::new(sync_type, manager);
+        let sessions = Sessions::default();
+        let router = protect(
+            Self::shared_router(&manager, &sessions, max_body_bytes),
+            auth_token,
+            &CorsConfig::default(),
+        );
+        let server = Server::try_bind(addr)
+            .map_err(|e| ServerError::Bind {
+                addr: *addr,
+                message: e.to_string(),
+            })?
+            .serve(router.into_make_service_with_connect_info::<SocketAddr, _>())
+            .with_graceful_shutdown(sessions.close_on(async move {
+                let _ = shutdown_signal.await;
+            }));
+
+        Ok(tokio::task::spawn(async move {
+            let result = server.await;
+            manager.shutdown().await;
+            result
+        }))
+    }
+
+    /// Like [`AxumServer::run`], but the server runs until the process receives Ctrl-C, or `SIGTERM`
+    /// on Unix. See [`shutdown_on_signal`].
+    pub fn run_until_signal(
+        addr: &SocketAddr,
+        sync_type: ServerSyncType,
+        max_body_bytes: usize,
+        auth_token: Option<&AuthToken>,
+    ) -> tokio::task::JoinHandle<Result<(), hyper::Error>> {
+        Self::run(
+            addr,
+            sync_type,
+            max_body_bytes,
+            auth_token,
+            shutdown_on_signal(),
+        )
+    }
+
+    /// Like [`AxumServer::run`], but the server is [`supervise`]d: if it panics or fails it is bound
+    /// and started again according to `policy`. The manager is shared between restarts, so loaded
+    /// functions and their state survive them, and is shut down once the supervisor gives up.
+    pub fn run_supervised(
+        addr: &SocketAddr,
+        sync_type: ServerSyncType,
+        max_body_bytes: usize,
+        auth_token: Option<&AuthToken>,
+        shutdown_signal: tokio::sync::oneshot::Receiver<()>,
+        policy: RestartPolicy,
+    ) -> tokio::task::JoinHandle<SupervisorExit> {
+        let addr = *addr;
+        // The oneshot can only be awaited once, so fan it out to every incarnation of the server.
+        let (shutdown_sender, shutdown) = tokio::sync::watch::channel(false);
+        let sessions = Sessions::default();
+        let closing = sessions.clone();
+        tokio::task::spawn(async move {
+            if shutdown_signal.await.is_ok() {
+                closing.close();
+                let _ = shutdown_sender.send(true);
+            }
+        });
+
+        let manager = SharedManager::new(sync_type, ComputeFunctionManager::default());
+        let router = protect(
+            Self::shared_router(&manager, &sessions, max_body_bytes),
+            auth_token,
+            &CorsConfig::default(),
+        );
+        let supervised = supervise(policy, move || {
+            let mut shutdown = shutdown.clone();
+            Server::bind(&addr)
+                .serve(
+                    router
+                        .clone()
+                        .into_make_service_with_connect_info::<SocketAddr, _>(),
+                )
+                .with_graceful_shutdown(async move {
+                    while !*shutdown.borrow() {
+                        if shutdown.changed().await.is_err() {
+                            return;
+                        }
+                    }
+                })
+        });
+        tokio::task::spawn(async move {
+            // The supervisor owns every copy of the router, so the manager is no longer shared once
+            // it returns.
+            let exit = supervised.await;
+            manager.shutdown().await;
+            exit
+        })
+    }
+}
+
+impl ServerInstance for AxumServer {
+    type Error = ServerError;
+
+    fn start(&self, addr: &SocketAddr) -> Result<(), ServerError> {
+        let router = self.app();
+        let sessions = self.sessions.clone();
+        self.running.start(|shutdown| {
+            let server = Server::try_bind(addr)
+                .map_err(|e| ServerError::Bind {
+                    addr: *addr,
+                    message: e.to_string(),
+                })?
+                .serve(router.into_make_service_with_connect_info::<SocketAddr, _>());
+            let addr = server.local_addr();
+            let server = server.with_graceful_shutdown(sessions.close_on(async move {
+                let _ = shutdown.await;
+            }));
+            Ok((addr, async move {
+                if let Err(e) = server.await {
+                    error!("axum server on {} failed: {}", addr, e);
+                }
+            }))
+        })
+    }
+
+    fn stop(&self) -> Result<(), ServerError> {
+        self.running.stop()
+    }
+
+    fn local_addr(&self) -> Option<SocketAddr> {
+        self.running.local_addr()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{body::Body, http::Request, routing::post};
+    use hyper::service::Service;
+
+    use super::*;
+    use crate::{
+        async_trait, core::types::BadRequestError, json, ComputeFunction, ComputeRequest,
+        ComputeResponse,
+    };
+
+    #[derive(Debug)]
+    struct ByteCounter;
+
+    #[derive(Debug)]
+    struct Slow;
+
+    #[async_trait]
+    impl ComputeFunction for Slow {
+        fn name(&self) -> &'static str {
+            "slow"
+        }
+
+        async fn receive_request(
+            &self,
+            _request: &ComputeRequest,
+        ) -> Result<ComputeResponse, BadRequestError> {
+            tokio::time::sleep(std::time::Duration::from_millis(25)).await;
+            Ok(ComputeResponse::json_ok(json!({ "done": true })))
+        }
+    }
+
+    fn slow_router() -> Router {
+        let mut manager = ComputeFunctionManager::new();
+        manager.load_builtin_instance(Box::new(Slow));
+        Router::new()
+            .route(
+                "/",
+                post(process_input_handler::<RwLock<ComputeFunctionManager>>),
+            )
+            .layer(Extension(Arc::new(RwLock::new(manager))))
+    }
+
+    fn slow_server() -> crate::test_support::TestServer {
+        let mut manager = ComputeFunctionManager::new();
+        manager.load_builtin_instance(Box::new(Slow));
+        crate::test_support::TestServer::new(manager)
+    }
+
+    fn execute_slow(meta: Option<&str>) -> Request<Body> {
+        let mut request = Request::post("/").header("content-type", "application/json");
+        if let Some(meta) = meta {
+            request = request.header(META_REQUEST_HEADER, meta);
+        }
+        let input = AppInput::Execute(ComputeRequest::new(
+            TargetComputeFunc::new("slow".to_string()),
+            serde_json::Value::Null,
+        ));
+        request
+            .body(Body::from(serde_json::to_vec(&input).unwrap()))
+            .unwrap()
+    }
+
+    #[async_trait]
+    impl ComputeFunction for ByteCounter {
+        fn name(&self) -> &'static str {
+            "byte_counter"
+        }
+
+        async fn receive_request(
+            &self,
+            _request: &ComputeRequest,
+        ) -> Result<ComputeResponse, BadRequestError> {
+            Ok(ComputeResponse::ok())
+        }
+
+        async fn receive_body_stream(
+            &self,
+            _target: &TargetComputeFunc,
+            mut body: BodyStream,
+        ) -> Result<ComputeResponse, BadRequestError> {
+            let mut total = 0;
+            while let Some(chunk) = body.next_chunk().await {
+                total += chunk
+                    .map_err(|err| BadRequestError::without_request(self.name(), &err))?
+                    .len();
             }
-            AppInput::Execute(req) => {
-                let pm_reader = pm.read_owned().await;
-                pm_reader
-                    .push_request(req)
+            Ok(ComputeResponse::json_ok(json!({ "bytes": total })))
+        }
+    }
+
+    #[tokio::test]
+    async fn streams_large_bodies_to_the_target_function() {
+        let mut manager = ComputeFunctionManager::new();
+        manager.load_builtin_instance(Box::new(ByteCounter));
+        let mut router =
+            AxumServer::rw_router(Arc::new(RwLock::new(manager)), DEFAULT_MAX_BODY_BYTES);
+
+        // Far more than the buffered-body limit, which streamed bodies aren't held to.
+        let (mut sender, body) = Body::channel();
+        let upload = tokio::spawn(async move {
+            for _ in 0..64 {
+                sender
+                    .send_data(vec![1_u8; 64 * 1024].into())
                     .await
-                    .map(AppOutput::compute_response)
+                    .unwrap();
+            }
+        });
+        let request = Request::post("/stream/byte_counter").body(body).unwrap();
+
+        let response = router.call(request).await.unwrap();
+        upload.await.unwrap();
+
+        assert_eq!(response.status(), 200);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["bytes"], 64 * 64 * 1024);
+    }
+
+    #[derive(Debug)]
+    struct NoCounting;
+
+    impl crate::AuthPolicy for NoCounting {
+        fn authorize(&self, _ctx: &RequestContext, target: &TargetComputeFunc) -> bool {
+            target.name() != "byte_counter"
+        }
+    }
+
+    #[tokio::test]
+    async fn streamed_bodies_are_authorized_like_any_request() {
+        let mut manager = ComputeFunctionManager::new();
+        manager.load_builtin_instance(Box::new(ByteCounter));
+        manager.set_auth_policy(NoCounting);
+        manager.set_replay_protection(std::time::Duration::from_secs(60), 16);
+        let mut router =
+            AxumServer::rw_router(Arc::new(RwLock::new(manager)), DEFAULT_MAX_BODY_BYTES);
+
+        let stream = |target: &str, nonce: &str| {
+            Request::post(format!("/stream/{}", target))
+                .header(NONCE_HEADER, nonce)
+                .body(Body::from(vec![1_u8; 16]))
+                .unwrap()
+        };
+
+        let denied = router.call(stream("byte_counter", "a")).await.unwrap();
+        assert_eq!(denied.status(), 403);
+
+        // The nonce reaches the manager, so replays are caught on this route as well.
+        let missing = router.call(stream("missing", "b")).await.unwrap();
+        assert_eq!(missing.status(), 404);
+        let replayed = router.call(stream("missing", "b")).await.unwrap();
+        assert_eq!(replayed.status(), 409);
+    }
+
+    #[tokio::test]
+    async fn attaches_execution_meta_headers_when_asked() {
+        let response = slow_router().call(execute_slow(Some("1"))).await.unwrap();
+
+        assert_eq!(response.status(), 200);
+        let header = |name: &str| response.headers()[name].to_str().unwrap().to_string();
+        assert_eq!(header("X-Compute-Function"), "slow");
+        assert!(header("X-Compute-Duration-Ms").parse::<u128>().unwrap() >= 25);
+        assert_eq!(header("X-Compute-Cache"), "bypass");
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json, json!({ "done": true }));
+    }
+
+    #[tokio::test]
+    async fn attaches_a_meta_block_when_asked() {
+        let response = slow_router()
+            .call(execute_slow(Some("body")))
+            .await
+            .unwrap();
+
+        assert!(response.headers().contains_key("X-Compute-Function"));
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["done"], true);
+        assert_eq!(json["_meta"]["function"], "slow");
+        assert_eq!(json["_meta"]["cache"], "bypass");
+        assert!(json["_meta"]["duration_ms"].as_u64().unwrap() >= 25);
+    }
+
+    #[tokio::test]
+    async fn omits_execution_meta_by_default() {
+        let response = slow_server().execute("slow", serde_json::Value::Null).await;
+
+        assert_eq!(response.status(), 200);
+        assert!(!response.headers().contains_key("X-Compute-Function"));
+        assert!(!response.headers().contains_key("X-Compute-Duration-Ms"));
+        assert!(!response.headers().contains_key("X-Compute-Cache"));
+    }
+
+    #[derive(Debug)]
+    struct EchoPeer;
+
+    #[async_trait]
+    impl ComputeFunction for EchoPeer {
+        fn name(&self) -> &'static str {
+            "echo_peer"
+        }
+
+        async fn receive_request(
+            &self,
+            request: &ComputeRequest,
+        ) -> Result<ComputeResponse, BadRequestError> {
+            let peer = request.context().peer_addr().map(|addr| addr.to_string());
+            Ok(ComputeResponse::json_ok(json!({ "peer": peer })))
+        }
+    }
+
+    #[tokio::test]
+    async fn plugins_can_read_the_connecting_address() {
+        let mut manager = ComputeFunctionManager::new();
+        manager.load_builtin_instance(Box::new(EchoPeer));
+        let mut router = Router::new()
+            .route(
+                "/",
+                post(process_input_handler::<RwLock<ComputeFunctionManager>>),
+            )
+            .layer(Extension(Arc::new(RwLock::new(manager))));
+
+        let peer: SocketAddr = ([10, 1, 2, 3], 4567).into();
+        let input = AppInput::Execute(ComputeRequest::new(
+            TargetComputeFunc::new("echo_peer".to_string()),
+            serde_json::Value::Null,
+        ));
+        let request = Request::post("/")
+            .header("content-type", "application/json")
+            .extension(ConnectInfo(peer))
+            .body(Body::from(serde_json::to_vec(&input).unwrap()))
+            .unwrap();
+        let response = router.call(request).await.unwrap();
+
+        assert_eq!(response.status(), 200);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json, json!({ "peer": "10.1.2.3:4567" }));
+    }
+
+    #[cfg(not(feature = "pascal-case-wire"))]
+    #[tokio::test]
+    async fn lists_loaded_functions() {
+        let functions = slow_server().list_functions().await;
+
+        assert_eq!(functions.len(), 1);
+        assert_eq!(functions[0].name(), "slow");
+        assert_eq!(functions[0].origin(), &crate::FunctionOrigin::Builtin);
+    }
+
+    #[derive(Debug)]
+    struct Unreachable;
+
+    #[async_trait]
+    impl ComputeFunction for Unreachable {
+        fn name(&self) -> &'static str {
+            "unreachable"
+        }
+
+        async fn health(&self) -> crate::HealthStatus {
+            crate::HealthStatus::Unhealthy("upstream is down".to_string())
+        }
+
+        async fn receive_request(
+            &self,
+            _request: &ComputeRequest,
+        ) -> Result<ComputeResponse, BadRequestError> {
+            Ok(ComputeResponse::ok())
+        }
+    }
+
+    #[cfg(not(feature = "pascal-case-wire"))]
+    #[tokio::test]
+    async fn health_checks_report_every_function() {
+        let server = slow_server();
+        let healthy = server.send(&AppInput::HealthCheck).await;
+        assert_eq!(healthy.status(), 200);
+        assert_eq!(healthy.body(), Some(&json!({ "slow": "ok" })));
+
+        let mut manager = ComputeFunctionManager::new();
+        manager.load_builtin_instance(Box::new(Slow));
+        manager.load_builtin_instance(Box::new(Unreachable));
+        let server = crate::test_support::TestServer::new(manager);
+        let unhealthy = server.send(&AppInput::HealthCheck).await;
+        assert_eq!(unhealthy.status(), 503);
+        assert_eq!(
+            unhealthy.body(),
+            Some(&json!({ "slow": "ok", "unreachable": { "unhealthy": "upstream is down" } }))
+        );
+    }
+
+    #[derive(Debug)]
+    struct Flaky;
+
+    #[async_trait]
+    impl ComputeFunction for Flaky {
+        fn name(&self) -> &'static str {
+            "flaky"
+        }
+
+        async fn receive_request(
+            &self,
+            request: &ComputeRequest,
+        ) -> Result<ComputeResponse, BadRequestError> {
+            if request.data() == &json!("fail") {
+                Err(BadRequestError::without_request(
+                    self.name(),
+                    "failed silently",
+                ))
+            } else {
+                Ok(ComputeResponse::ok())
             }
         }
     }
 
-    async fn input_handler_mutex(
-        Json(payload): Json<AppInput>,
-        Extension(state): Extension<MutexManager>,
-    ) -> AppResult<AppOutput> {
-        Self::process_input_mutex(&state, &payload).await
+    #[cfg(not(feature = "pascal-case-wire"))]
+    #[tokio::test]
+    async fn health_checks_fail_functions_that_fail_too_often() {
+        let mut manager = ComputeFunctionManager::new();
+        manager.load_builtin_instance(Box::new(Flaky));
+        manager.set_error_rate_threshold(0.5);
+        let server = crate::test_support::TestServer::new(manager);
+
+        server.execute("flaky", json!("fail")).await;
+        server.execute("flaky", json!("pass")).await;
+        let healthy = server.send(&AppInput::HealthCheck).await;
+        assert_eq!(healthy.status(), 200);
+        assert_eq!(healthy.body(), Some(&json!({ "flaky": "ok" })));
+
+        server.execute("flaky", json!("fail")).await;
+        let unhealthy = server.send(&AppInput::HealthCheck).await;
+        assert_eq!(unhealthy.status(), 503);
+        assert_eq!(
+            unhealthy.body(),
+            Some(&json!({ "flaky": { "unhealthy": "67% of its recent calls failed" } }))
+        );
     }
 
-    async fn input_handler_rw(
-        Json(payload): Json<AppInput>,
-        Extension(state): Extension<RwLockManager>,
-    ) -> AppResult<AppOutput> {
-        Self::process_input_rw(state.clone(), &payload).await
+    #[tokio::test]
+    async fn serves_rest_style_function_routes() {
+        let mut manager = ComputeFunctionManager::new();
+        manager.load_builtin_instance(Box::new(Slow));
+        let mut router =
+            AxumServer::rw_router(Arc::new(RwLock::new(manager)), DEFAULT_MAX_BODY_BYTES);
+
+        let request = Request::get("/openapi.json").body(Body::empty()).unwrap();
+        let response = router.call(request).await.unwrap();
+        assert_eq!(response.status(), 200);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let document: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert!(document["paths"]["/functions/slow"]["post"].is_object());
+
+        let request = Request::get("/functions").body(Body::empty()).unwrap();
+        let response = router.call(request).await.unwrap();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let functions: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(functions[0]["name"], "slow");
+
+        let request = Request::post("/functions/slow")
+            .header("content-type", "application/json")
+            .body(Body::from("null"))
+            .unwrap();
+        let response = router.call(request).await.unwrap();
+        assert_eq!(response.status(), 200);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json, json!({ "done": true }));
     }
 
-    fn init_as_rw(
-        addr: SocketAddr,
-        start: bool,
-        shutdown_receiver: tokio::sync::oneshot::Receiver<()>,
-    ) -> Self {
-        let router = Router::new()
-            .route("/", post(Self::input_handler_rw))
-            .layer(AddExtensionLayer::new(RwLockManager::default()));
+    #[tokio::test]
+    async fn executes_and_removes_functions_without_the_envelope() {
+        let mut manager = ComputeFunctionManager::new();
+        manager.load_builtin_instance(Box::new(Slow));
+        let mut router =
+            AxumServer::rw_router(Arc::new(RwLock::new(manager)), DEFAULT_MAX_BODY_BYTES);
 
-        let server: Option<Server<AddrIncoming, IntoMakeService<Router>>> = if start {
-            Some(Server::bind(&addr).serve(router.clone().into_make_service()))
-        } else {
-            None
+        let request = Request::post("/execute")
+            .header("content-type", "application/json")
+            .body(Body::from(
+                serde_json::to_vec(&ComputeRequest::new(
+                    TargetComputeFunc::new("slow".to_string()),
+                    serde_json::Value::Null,
+                ))
+                .unwrap(),
+            ))
+            .unwrap();
+        let response = router.call(request).await.unwrap();
+        assert_eq!(response.status(), 200);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json, json!({ "done": true }));
+
+        let request = Request::delete("/functions/slow")
+            .body(Body::empty())
+            .unwrap();
+        let response = router.call(request).await.unwrap();
+        assert_eq!(response.status(), 200);
+
+        let request = Request::get("/functions").body(Body::empty()).unwrap();
+        let response = router.call(request).await.unwrap();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let functions: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(functions, json!([]));
+
+        let request = Request::delete("/functions/slow")
+            .body(Body::empty())
+            .unwrap();
+        let response = router.call(request).await.unwrap();
+        assert_eq!(response.status(), 404);
+    }
+
+    /// The `(event, data)` of every server-sent event in `body`.
+    fn sse_events_in(body: &[u8]) -> Vec<(Option<String>, String)> {
+        std::str::from_utf8(body)
+            .unwrap()
+            .split("\n\n")
+            .filter(|event| !event.is_empty())
+            .map(|event| {
+                let mut name = None;
+                let mut data = String::new();
+                for line in event.lines() {
+                    if let Some(value) = line.strip_prefix("event:") {
+                        name = Some(value.trim().to_string());
+                    } else if let Some(value) = line.strip_prefix("data:") {
+                        data.push_str(value.trim());
+                    }
+                }
+                (name, data)
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn streams_frames_as_server_sent_events() {
+        let mut manager = ComputeFunctionManager::new();
+        manager.load_builtin_instance(Box::new(Slow));
+        let router = AxumServer::rw_router(Arc::new(RwLock::new(manager)), DEFAULT_MAX_BODY_BYTES);
+        let request = |target: &str| {
+            serde_json::to_string(&ComputeRequest::new(
+                TargetComputeFunc::new(target.to_string()),
+                serde_json::Value::Null,
+            ))
+            .unwrap()
         };
 
-        Self {
-            addr,
-            router,
-            is_running: start,
-            server,
-            shutdown_signal: shutdown_receiver,
-            sync_type: ServerSyncType::RwLock,
+        let posted = Request::post("/sse")
+            .header("content-type", "application/json")
+            .body(Body::from(request("slow")))
+            .unwrap();
+        let response = router.clone().call(posted).await.unwrap();
+        assert_eq!(response.headers()["content-type"], "text/event-stream");
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let events = sse_events_in(&body);
+        assert_eq!(events.len(), 2, "{:?}", events);
+        assert_eq!(events[0].0, None);
+        let frame: ComputeResponse = serde_json::from_str(&events[0].1).unwrap();
+        assert_eq!(frame.data(), Some(json!({ "done": true })));
+        assert_eq!(events[1], (Some("done".to_string()), String::new()));
+
+        let query = url_encode(&request("missing"));
+        let queried = Request::get(format!("/sse?request={}", query))
+            .body(Body::empty())
+            .unwrap();
+        let response = router.clone().call(queried).await.unwrap();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let events = sse_events_in(&body);
+        assert_eq!(events.len(), 2, "{:?}", events);
+        assert_eq!(events[0].0.as_deref(), Some("error"));
+        let envelope: serde_json::Value = serde_json::from_str(&events[0].1).unwrap();
+        assert_eq!(envelope["status"], 404);
+        assert_eq!(events[1].0.as_deref(), Some("done"));
+    }
+
+    /// Streams a tick every few milliseconds until the client goes away.
+    #[derive(Debug)]
+    struct Ticker;
+
+    #[async_trait]
+    impl ComputeFunction for Ticker {
+        fn name(&self) -> &'static str {
+            "ticker"
+        }
+
+        async fn receive_request(
+            &self,
+            _request: &ComputeRequest,
+        ) -> Result<ComputeResponse, BadRequestError> {
+            Ok(ComputeResponse::json_ok(json!({ "tick": 0 })))
+        }
+
+        async fn receive_streaming(
+            &self,
+            _request: &ComputeRequest,
+            sink: ResponseSink,
+        ) -> Result<(), BadRequestError> {
+            for tick in 0.. {
+                if !sink
+                    .send(ComputeResponse::json_ok(json!({ "tick": tick })))
+                    .await
+                {
+                    break;
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            }
+            Ok(())
         }
     }
 
-    fn init_as_mutex(
-        addr: SocketAddr,
-        start: bool,
-        shutdown_receiver: tokio::sync::oneshot::Receiver<()>,
-    ) -> Self {
-        let router = Router::new()
-            .route("/", post(Self::input_handler_mutex))
-            .layer(AddExtensionLayer::new(MutexManager::default()));
+    fn tick_request() -> String {
+        serde_json::to_string(&ComputeRequest::new(
+            TargetComputeFunc::new("ticker".to_string()),
+            serde_json::Value::Null,
+        ))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn server_sent_events_end_when_the_sessions_close() {
+        use http_body::Body as _;
 
-        let server: Option<Server<AddrIncoming, IntoMakeService<Router>>> = if start {
-            Some(Server::bind(&addr).serve(router.clone().into_make_service()))
-        } else {
-            None
+        let mut manager = ComputeFunctionManager::new();
+        manager.load_builtin_instance(Box::new(Ticker));
+        let sessions = Sessions::default();
+        let mut router = app_router(
+            Arc::new(RwLock::new(manager)),
+            &sessions,
+            DEFAULT_MAX_BODY_BYTES,
+        );
+
+        let request = Request::post("/sse")
+            .header("content-type", "application/json")
+            .body(Body::from(tick_request()))
+            .unwrap();
+        let mut body = router.call(request).await.unwrap().into_body();
+        let first = body.data().await.unwrap().unwrap();
+        assert!(std::str::from_utf8(&first).unwrap().contains("tick"));
+
+        sessions.close();
+        let rest = tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            hyper::body::to_bytes(body),
+        )
+        .await
+        .expect("The stream never ended")
+        .unwrap();
+        let events = sse_events_in(&rest);
+        let (error, done) = (&events[events.len() - 2], &events[events.len() - 1]);
+        assert_eq!(error.0.as_deref(), Some("error"), "{:?}", events);
+        let envelope: serde_json::Value = serde_json::from_str(&error.1).unwrap();
+        assert_eq!(envelope["code"], "draining");
+        assert_eq!(done.0.as_deref(), Some("done"));
+    }
+
+    #[tokio::test]
+    async fn clients_that_fall_too_far_behind_are_cut_off() {
+        let request = ComputeRequest::new(TargetComputeFunc::new("flood".to_string()), json!(null));
+        let (mut frames, function) = spawn_stream(request, |_request, sink| async move {
+            let mut sent = 0;
+            while sink.send(ComputeResponse::json_ok(json!(sent))).await {
+                sent += 1;
+            }
+            Ok(())
+        });
+
+        // Nothing reads the frames, yet the function is not held up.
+        tokio::time::timeout(std::time::Duration::from_secs(5), function)
+            .await
+            .expect("The function waited for the client")
+            .unwrap()
+            .unwrap();
+
+        let mut received = 0;
+        let error = loop {
+            match frames
+                .recv()
+                .await
+                .expect("The frames ended without an error")
+            {
+                Ok(frame) => {
+                    assert_eq!(frame.data(), Some(json!(received)));
+                    received += 1;
+                }
+                Err(error) => break error,
+            }
         };
+        assert_eq!(received, STREAM_CLIENT_BUFFER);
+        assert!(matches!(error, AppError::Overloaded(_)), "{:?}", error);
+        assert!(frames.recv().await.is_none());
+    }
 
-        Self {
-            addr,
-            router,
-            is_running: start,
-            server,
-            shutdown_signal: shutdown_receiver,
-            sync_type: ServerSyncType::Mutex,
+    #[tokio::test]
+    async fn websockets_are_closed_on_shutdown() {
+        use futures_util::{SinkExt, StreamExt};
+        use tokio_tungstenite::tungstenite::{
+            protocol::frame::coding::CloseCode, Message as WsMessage,
+        };
+
+        // Bind and drop a listener to find a free port.
+        let addr = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let mut manager = ComputeFunctionManager::new();
+        manager.load_builtin_instance(Box::new(Ticker));
+        let (stop, shutdown_signal) = tokio::sync::oneshot::channel();
+        let server = AxumServer::serve(
+            &addr,
+            ServerSyncType::default(),
+            manager,
+            DEFAULT_MAX_BODY_BYTES,
+            None,
+            shutdown_signal,
+        )
+        .unwrap();
+
+        let (mut socket, _) = tokio_tungstenite::connect_async(format!("ws://{}/ws", addr))
+            .await
+            .unwrap();
+        socket.send(WsMessage::Text(tick_request())).await.unwrap();
+        let first = socket.next().await.unwrap().unwrap();
+        assert!(first.to_text().unwrap().contains("tick"), "{:?}", first);
+
+        stop.send(()).unwrap();
+        let mut last_text = None;
+        let close = loop {
+            let message = tokio::time::timeout(std::time::Duration::from_secs(5), socket.next())
+                .await
+                .expect("No close frame arrived")
+                .unwrap()
+                .unwrap();
+            match message {
+                WsMessage::Text(text) => last_text = Some(text),
+                WsMessage::Close(frame) => break frame,
+                _ => {}
+            }
+        };
+        assert_eq!(close.unwrap().code, CloseCode::Away);
+        let envelope: serde_json::Value = serde_json::from_str(&last_text.unwrap()).unwrap();
+        assert_eq!(envelope["code"], "draining");
+
+        tokio::time::timeout(std::time::Duration::from_secs(5), server)
+            .await
+            .expect("The server never stopped")
+            .unwrap()
+            .unwrap();
+    }
+
+    /// Percent-encode every byte of `value` that isn't alphanumeric, for use in a query string.
+    fn url_encode(value: &str) -> String {
+        value
+            .bytes()
+            .map(|byte| {
+                if byte.is_ascii_alphanumeric() {
+                    (byte as char).to_string()
+                } else {
+                    format!("%{:02X}", byte)
+                }
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn rejections_report_the_request_id() {
+        let router = AxumServer::rw_router(
+            Arc::new(RwLock::new(ComputeFunctionManager::with_logger())),
+            DEFAULT_MAX_BODY_BYTES,
+        );
+        let reject = |request_id: Option<&str>| {
+            let mut request =
+                Request::post("/functions/logger").header("content-type", "application/json");
+            if let Some(request_id) = request_id {
+                request = request.header(REQUEST_ID_HEADER, request_id);
+            }
+            request.body(Body::from("1")).unwrap()
+        };
+
+        let response = router.clone().call(reject(Some("abc-123"))).await.unwrap();
+        assert_eq!(response.status(), 400);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["details"]["request_id"], "abc-123");
+
+        let response = router.clone().call(reject(None)).await.unwrap();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let generated = json["details"]["request_id"]
+            .as_str()
+            .unwrap();
+        assert!(uuid::Uuid::parse_str(generated).is_ok(), "{}", generated);
+    }
+
+    #[tokio::test]
+    async fn inputs_are_parsed_like_on_every_server() {
+        let post = |body: String| {
+            Request::post("/")
+                .header("content-type", "application/json")
+                .body(Body::from(body))
+                .unwrap()
+        };
+
+        // Well-formed, but an empty library path fails validation while parsing.
+        let invalid = serde_json::to_string(&AppInput::AddComputeFunction(
+            AddFunctionRequest::new("  ".to_string()),
+        ))
+        .unwrap();
+        let response = slow_router().call(post(invalid)).await.unwrap();
+        assert_eq!(response.status(), 400);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["code"], "bad_input");
+
+        let request = Request::post("/")
+            .body(Body::from(
+                serde_json::to_vec(&AppInput::ListFunctions).unwrap(),
+            ))
+            .unwrap();
+        let response = slow_router().call(request).await.unwrap();
+        assert_eq!(response.status(), 400);
+    }
+
+    #[tokio::test]
+    async fn malformed_bodies_get_the_error_envelope() {
+        let request = Request::post("/")
+            .header("content-type", "application/json")
+            .body(Body::from("{ not json"))
+            .unwrap();
+        let response = slow_router().call(request).await.unwrap();
+
+        assert_eq!(response.status(), 400);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["code"], "malformed_body");
+        assert_eq!(json["status"], 400);
+    }
+
+    #[tokio::test]
+    async fn oversized_bodies_are_turned_away_with_a_413() {
+        let router = AxumServer::rw_router(Arc::default(), 64);
+        let input = serde_json::to_vec(&AppInput::Execute(ComputeRequest::new(
+            TargetComputeFunc::new("logger".to_string()),
+            serde_json::Value::String("x".repeat(64)),
+        )))
+        .unwrap();
+
+        let announced = Request::post("/")
+            .header("content-type", "application/json")
+            .header("content-length", input.len())
+            .body(Body::from(input.clone()))
+            .unwrap();
+        let response = router.clone().call(announced).await.unwrap();
+        assert_eq!(response.status(), 413);
+
+        // Without a length up front the body is only found to be too large while it's being read.
+        let chunks: Vec<Result<_, std::io::Error>> =
+            input.chunks(16).map(|chunk| Ok(chunk.to_vec())).collect();
+        let streamed = Request::post("/")
+            .header("content-type", "application/json")
+            .body(Body::wrap_stream(futures_util::stream::iter(chunks)))
+            .unwrap();
+        let response = router.clone().call(streamed).await.unwrap();
+        assert_eq!(
+            response.status(),
+            crate::core::types::GenericStatusCode::PayloadTooLarge.to_status_code()
+        );
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["code"], "payload_too_large");
+    }
+
+    #[cfg(not(feature = "pascal-case-wire"))]
+    #[test]
+    fn sync_type_uses_snake_case_variant_names() {
+        for (sync, wire) in [
+            (ServerSyncType::Mutex, "\"mutex\""),
+            (ServerSyncType::RwLock, "\"rw_lock\""),
+            (ServerSyncType::Auto, "\"auto\""),
+        ] {
+            assert_eq!(serde_json::to_string(&sync).unwrap(), wire);
+            assert_eq!(serde_json::from_str::<ServerSyncType>(wire).unwrap(), sync);
         }
     }
 
-    pub fn init(
-        addr: &SocketAddr,
-        start: bool,
-        sync_type: ServerSyncType,
-        shutdown_receiver: tokio::sync::oneshot::Receiver<()>,
-    ) -> Self {
-        match sync_type {
-            ServerSyncType::Mutex => Self::init_as_mutex(*addr, start, shutdown_receiver),
-            ServerSyncType::RwLock => Self::init_as_rw(*addr, start, shutdown_receiver),
+    #[derive(Debug)]
+    struct UnloadCounter(Arc<std::sync::atomic::AtomicUsize>);
+
+    #[async_trait]
+    impl ComputeFunction for UnloadCounter {
+        fn name(&self) -> &'static str {
+            "unload_counter"
+        }
+
+        fn on_plugin_unload(&self) {
+            self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        }
+
+        async fn receive_request(
+            &self,
+            _request: &ComputeRequest,
+        ) -> Result<ComputeResponse, BadRequestError> {
+            Ok(ComputeResponse::ok())
         }
     }
 
-    pub fn run(
-        addr: &SocketAddr,
-        sync_type: ServerSyncType,
-        shutdown_signal: tokio::sync::oneshot::Receiver<()>,
-    ) -> tokio::task::JoinHandle<Result<(), hyper::Error>> {
-        let addr = *addr;
-        tokio::task::spawn(async move {
-            let router = match sync_type {
-                ServerSyncType::Mutex => Router::new()
-                    .route("/", post(Self::input_handler_mutex))
-                    .layer(AddExtensionLayer::new(MutexManager::default())),
-                ServerSyncType::RwLock => Router::new()
-                    .route("/", post(Self::input_handler_rw))
-                    .layer(AddExtensionLayer::new(RwLockManager::default())),
-            };
-            let server = Server::bind(&addr)
-                .serve(router.into_make_service())
-                .with_graceful_shutdown(async move {
-                    let _ = shutdown_signal.await.ok();
-                });
+    #[tokio::test]
+    async fn stopped_servers_unload_their_functions() {
+        for sync_type in [ServerSyncType::Mutex, ServerSyncType::RwLock] {
+            let unloads = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+            let mut manager = ComputeFunctionManager::new();
+            manager.load_builtin_instance(Box::new(UnloadCounter(unloads.clone())));
+            let manager = SharedManager::new(sync_type, manager);
+
+            let router =
+                AxumServer::shared_router(&manager, &Sessions::default(), DEFAULT_MAX_BODY_BYTES);
+            drop(router);
+            manager.shutdown().await;
+            assert_eq!(unloads.load(std::sync::atomic::Ordering::SeqCst), 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn servers_stop_when_signalled() {
+        let (stop, shutdown_signal) = tokio::sync::oneshot::channel();
+        let handle = AxumServer::run(
+            &SocketAddr::from(([127, 0, 0, 1], 0)),
+            ServerSyncType::Auto,
+            DEFAULT_MAX_BODY_BYTES,
+            None,
+            shutdown_signal,
+        );
+        stop.send(()).unwrap();
+        let result = tokio::time::timeout(std::time::Duration::from_secs(5), handle)
+            .await
+            .expect("The server did not stop when signalled");
+        assert!(result.unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn auto_serves_execute_requests_concurrently() {
+        assert_eq!(ServerSyncType::default(), ServerSyncType::Auto);
+        assert_eq!(ServerSyncType::Auto.resolve(), ServerSyncType::RwLock);
+
+        let mut manager = ComputeFunctionManager::new();
+        manager.load_builtin_instance(Box::new(Slow));
+        let manager = Arc::new(RwLock::new(manager));
+        let router = AxumServer::rw_router(manager.clone(), DEFAULT_MAX_BODY_BYTES);
+
+        // Another reader holding the manager doesn't hold up `Execute` requests, where a `Mutex`
+        // would have to wait for it.
+        let other_reader = manager.read().await;
+        let response = tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            router.clone().call(execute_slow(None)),
+        )
+        .await
+        .expect("Execute request was serialized behind another reader")
+        .unwrap();
+        assert_eq!(response.status(), 200);
+        drop(other_reader);
+    }
+
+    #[tokio::test]
+    async fn cors_preflights_are_only_answered_when_allowed() {
+        let preflight = || {
+            Request::builder()
+                .method("OPTIONS")
+                .uri("/functions")
+                .header("origin", "http://localhost:5173")
+                .header("access-control-request-method", "GET")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let closed = AxumServer::new(ServerSyncType::Auto, DEFAULT_MAX_BODY_BYTES);
+        let response = closed.app().call(preflight()).await.unwrap();
+        assert!(!response
+            .headers()
+            .contains_key("access-control-allow-origin"));
 
-            server.await
+        let open = AxumServer::new(ServerSyncType::Auto, DEFAULT_MAX_BODY_BYTES)
+            .with_cors(&CorsConfig::new().allow_origin("http://localhost:5173"));
+        let response = open.app().call(preflight()).await.unwrap();
+        assert!(response.status().is_success(), "{}", response.status());
+        assert_eq!(
+            response.headers()["access-control-allow-origin"],
+            "http://localhost:5173"
+        );
+        assert_eq!(
+            response.headers()["access-control-allow-methods"],
+            "GET,POST"
+        );
+    }
+
+    /// Send `AppInput::ListFunctions` to `addr` over a bare HTTP/1.1 connection, with
+    /// `authorization` if there is one, as soon as the server there is up, and return the status
+    /// line of the response.
+    async fn list_when_up(addr: SocketAddr, authorization: Option<&str>) -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let body = serde_json::to_vec(&AppInput::ListFunctions).unwrap();
+        let authorization = authorization
+            .map(|value| format!("authorization: {}\r\n", value))
+            .unwrap_or_default();
+        tokio::time::timeout(std::time::Duration::from_secs(5), async {
+            loop {
+                if let Ok(mut stream) = tokio::net::TcpStream::connect(addr).await {
+                    let request = format!(
+                        "POST / HTTP/1.1\r\nhost: {}\r\ncontent-type: application/json\r\ncontent-length: {}\r\n{}connection: close\r\n\r\n",
+                        addr,
+                        body.len(),
+                        authorization
+                    );
+                    stream.write_all(request.as_bytes()).await.unwrap();
+                    stream.write_all(&body).await.unwrap();
+                    let mut response = String::new();
+                    stream.read_to_string(&mut response).await.unwrap();
+                    return response.lines().next().unwrap_or_default().to_string();
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            }
         })
+        .await
+        .expect("The server never started")
+    }
+
+    #[tokio::test]
+    async fn free_runners_require_the_auth_token() {
+        // Bind and drop listeners to find ports nothing listens on.
+        let free_port = || {
+            std::net::TcpListener::bind("127.0.0.1:0")
+                .unwrap()
+                .local_addr()
+                .unwrap()
+        };
+        let token = AuthToken::new("s3cret");
+
+        let (mutex, rw, with_shutdown, rest) = (free_port(), free_port(), free_port(), free_port());
+        let forever = [
+            tokio::task::spawn({
+                let token = token.clone();
+                async move {
+                    run_axum_with_mutex(
+                        &mutex,
+                        DEFAULT_MAX_BODY_BYTES,
+                        &CorsConfig::default(),
+                        Some(&token),
+                    )
+                    .await
+                }
+            }),
+            tokio::task::spawn({
+                let token = token.clone();
+                async move {
+                    run_axum_with_rw(
+                        &rw,
+                        DEFAULT_MAX_BODY_BYTES,
+                        &CorsConfig::default(),
+                        Some(&token),
+                    )
+                    .await
+                }
+            }),
+        ];
+        let (stop_with_shutdown, shutdown_signal) = tokio::sync::oneshot::channel();
+        let shut_down =
+            run_rw_axum_with_shutdown(&with_shutdown, Some(&token), shutdown_signal).await;
+        let (stop_rest, shutdown_signal) = tokio::sync::oneshot::channel();
+        let rested = run_axum_rest(&rest, ServerSyncType::Auto, Some(&token), shutdown_signal);
+
+        for addr in [mutex, rw, with_shutdown, rest] {
+            for authorization in [None, Some("Bearer wrong")] {
+                let status = list_when_up(addr, authorization).await;
+                assert!(status.starts_with("HTTP/1.1 401"), "{}: {}", addr, status);
+            }
+            let status = list_when_up(addr, Some("Bearer s3cret")).await;
+            assert!(status.starts_with("HTTP/1.1 200"), "{}: {}", addr, status);
+        }
+
+        forever.iter().for_each(tokio::task::JoinHandle::abort);
+        stop_with_shutdown.send(()).unwrap();
+        assert_eq!(shut_down.await.unwrap(), "server shutdown without error");
+        stop_rest.send(()).unwrap();
+        assert!(rested.await.unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn requests_without_the_auth_token_are_turned_away() {
+        let add = |authorization: Option<&str>| {
+            let mut request = Request::post("/").header("content-type", "application/json");
+            if let Some(authorization) = authorization {
+                request = request.header("authorization", authorization);
+            }
+            request
+                .body(Body::from(
+                    serde_json::to_vec(&AppInput::AddComputeFunction(
+                        crate::core::types::AddFunctionRequest::new(
+                            "/definitely/not/libthere.so".to_string(),
+                        ),
+                    ))
+                    .unwrap(),
+                ))
+                .unwrap()
+        };
+        let server = AxumServer::new(ServerSyncType::Auto, DEFAULT_MAX_BODY_BYTES)
+            .with_cors(&CorsConfig::new().allow_origin("http://localhost:5173"))
+            .with_auth_token("s3cret");
+
+        for authorization in [None, Some("Bearer wrong"), Some("s3cret")] {
+            let response = server.app().call(add(authorization)).await.unwrap();
+            assert_eq!(response.status(), 401, "{:?}", authorization);
+            assert_eq!(response.headers()["www-authenticate"], "Bearer");
+            let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+            let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            assert_eq!(body["code"], "unauthorized");
+        }
+
+        // The token is checked before the body is even looked at.
+        let oversized = Request::post("/")
+            .body(Body::from(vec![b' '; DEFAULT_MAX_BODY_BYTES + 1]))
+            .unwrap();
+        let response = server.app().call(oversized).await.unwrap();
+        assert_eq!(response.status(), 401);
+
+        let response = server.app().call(add(Some("Bearer s3cret"))).await.unwrap();
+        assert_ne!(response.status(), 401);
+
+        let preflight = Request::builder()
+            .method("OPTIONS")
+            .uri("/")
+            .header("origin", "http://localhost:5173")
+            .header("access-control-request-method", "POST")
+            .body(Body::empty())
+            .unwrap();
+        let response = server.app().call(preflight).await.unwrap();
+        assert!(response.status().is_success(), "{}", response.status());
+    }
+
+    #[cfg(feature = "tls")]
+    #[tokio::test]
+    async fn tls_reports_unusable_certificates_instead_of_panicking() {
+        let addr = SocketAddr::from(([127, 0, 0, 1], 0));
+        let dir = std::env::temp_dir();
+        let garbage = dir.join(format!("{}-garbage.pem", uuid::Uuid::new_v4()));
+        std::fs::write(&garbage, "not a certificate").unwrap();
+
+        let missing = dir.join(format!("{}-missing.pem", uuid::Uuid::new_v4()));
+        let (_tx, rx) = tokio::sync::oneshot::channel();
+        let err = run_axum_tls(&addr, ServerSyncType::Auto, &missing, &missing, None, rx)
+            .await
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+        assert!(
+            err.to_string().contains(&*missing.to_string_lossy()),
+            "{}",
+            err
+        );
+
+        let (_tx, rx) = tokio::sync::oneshot::channel();
+        let err = run_axum_tls(&addr, ServerSyncType::Auto, &garbage, &garbage, None, rx)
+            .await
+            .unwrap()
+            .unwrap_err();
+        assert!(
+            err.to_string().contains(&*garbage.to_string_lossy()),
+            "{}",
+            err
+        );
+        let _ = std::fs::remove_file(&garbage);
     }
 }
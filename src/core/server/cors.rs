@@ -0,0 +1,232 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::time::Duration;
+
+use hyper::{
+    header::{HeaderName, CONTENT_TYPE},
+    Method, Uri,
+};
+
+/// Either everything, or only the listed values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Allowed<T> {
+    Any,
+    List(Vec<T>),
+}
+
+impl<T> Allowed<T> {
+    fn push(&mut self, value: T) {
+        match self {
+            Self::Any => {}
+            Self::List(values) => values.push(value),
+        }
+    }
+}
+
+/// Which cross-origin requests the server answers, and with which CORS headers.
+///
+/// The default allows no origins at all, in which case no CORS headers are sent and browsers only
+/// let pages from the server's own origin call it. Allowing an origin allows `GET` and `POST`
+/// requests with a `content-type` header (i.e. JSON bodies) from it, unless other methods or
+/// headers are set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorsConfig {
+    origins: Allowed<String>,
+    methods: Allowed<Method>,
+    headers: Allowed<HeaderName>,
+    max_age: Option<Duration>,
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            origins: Allowed::List(Vec::new()),
+            methods: Allowed::List(vec![Method::GET, Method::POST]),
+            headers: Allowed::List(vec![CONTENT_TYPE]),
+            max_age: None,
+        }
+    }
+}
+
+impl CorsConfig {
+    /// A config that allows no cross-origin requests, see [`CorsConfig::default`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allow any origin to make any request with any headers, and let browsers cache that for an
+    /// hour. Meant for local development only.
+    #[must_use]
+    pub fn permissive() -> Self {
+        Self {
+            origins: Allowed::Any,
+            methods: Allowed::Any,
+            headers: Allowed::Any,
+            max_age: Some(Duration::from_secs(60 * 60)),
+        }
+    }
+
+    /// Allow requests from `origin`, e.g. `http://localhost:5173`.
+    ///
+    /// ## Panics
+    /// If `origin` isn't a scheme and host (and maybe a port) without anything after them.
+    #[must_use]
+    pub fn allow_origin(mut self, origin: &str) -> Self {
+        match origin.parse::<Uri>() {
+            Ok(uri)
+                if uri.scheme().is_some()
+                    && uri.authority().is_some()
+                    && matches!(
+                        uri.path_and_query()
+                            .map(hyper::http::uri::PathAndQuery::as_str),
+                        None | Some("" | "/")
+                    )
+                    && !origin.ends_with('/') => {}
+            _ => panic!("`{}` is not a valid CORS origin", origin),
+        }
+        self.origins.push(origin.to_string());
+        self
+    }
+
+    /// Allow requests from any origin.
+    #[must_use]
+    pub fn allow_any_origin(mut self) -> Self {
+        self.origins = Allowed::Any;
+        self
+    }
+
+    /// Allow cross-origin requests with `methods`, replacing the methods allowed so far.
+    #[must_use]
+    pub fn allow_methods(mut self, methods: impl IntoIterator<Item = Method>) -> Self {
+        self.methods = Allowed::List(methods.into_iter().collect());
+        self
+    }
+
+    /// Allow cross-origin requests to send `headers`, replacing the headers allowed so far.
+    #[must_use]
+    pub fn allow_headers(mut self, headers: impl IntoIterator<Item = HeaderName>) -> Self {
+        self.headers = Allowed::List(headers.into_iter().collect());
+        self
+    }
+
+    /// Allow cross-origin requests to send any headers.
+    #[must_use]
+    pub fn allow_any_header(mut self) -> Self {
+        self.headers = Allowed::Any;
+        self
+    }
+
+    /// Let browsers cache the answer to a preflight request for `max_age`.
+    #[must_use]
+    pub const fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Whether any cross-origin requests are allowed at all. If not, servers leave CORS out
+    /// entirely.
+    #[must_use]
+    pub fn is_enabled(&self) -> bool {
+        match &self.origins {
+            Allowed::Any => true,
+            Allowed::List(origins) => !origins.is_empty(),
+        }
+    }
+
+    /// The [`tower_http::cors::CorsLayer`] for the axum server, or `None` if CORS is disabled.
+    #[cfg(feature = "axum")]
+    #[must_use]
+    pub fn layer(&self) -> Option<tower_http::cors::CorsLayer> {
+        use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+
+        let mut layer = match &self.origins {
+            _ if !self.is_enabled() => return None,
+            Allowed::Any => CorsLayer::new().allow_origin(Any),
+            Allowed::List(origins) => CorsLayer::new().allow_origin(AllowOrigin::list(
+                // Validated in `allow_origin`.
+                origins.iter().filter_map(|origin| origin.parse().ok()),
+            )),
+        };
+        layer = match &self.methods {
+            Allowed::Any => layer.allow_methods(Any),
+            Allowed::List(methods) => layer.allow_methods(methods.clone()),
+        };
+        layer = match &self.headers {
+            Allowed::Any => layer.allow_headers(Any),
+            Allowed::List(headers) => layer.allow_headers(headers.clone()),
+        };
+        if let Some(max_age) = self.max_age {
+            layer = layer.max_age(max_age);
+        }
+
+        Some(layer)
+    }
+
+    /// The [`warp::cors`] filter for the warp server, or `None` if CORS is disabled.
+    #[cfg(feature = "warp")]
+    #[must_use]
+    pub fn warp(&self) -> Option<warp::cors::Builder> {
+        let mut cors = match &self.origins {
+            _ if !self.is_enabled() => return None,
+            Allowed::Any => warp::cors().allow_any_origin(),
+            Allowed::List(origins) => {
+                warp::cors().allow_origins(origins.iter().map(String::as_str))
+            }
+        };
+        cors = match &self.methods {
+            // warp has no wildcard for methods, so list every method a server could route.
+            Allowed::Any => cors.allow_methods([
+                Method::GET,
+                Method::POST,
+                Method::PUT,
+                Method::PATCH,
+                Method::DELETE,
+                Method::HEAD,
+                Method::OPTIONS,
+            ]),
+            Allowed::List(methods) => cors.allow_methods(methods.clone()),
+        };
+        cors = match &self.headers {
+            // Nor for headers, so allow the ones a client of the JSON API would send.
+            Allowed::Any => cors.allow_headers([
+                CONTENT_TYPE,
+                hyper::header::ACCEPT,
+                hyper::header::AUTHORIZATION,
+                HeaderName::from_static(crate::core::types::NONCE_HEADER),
+                HeaderName::from_static(crate::core::types::META_REQUEST_HEADER),
+            ]),
+            Allowed::List(headers) => cors.allow_headers(headers.clone()),
+        };
+        if let Some(max_age) = self.max_age {
+            cors = cors.max_age(max_age);
+        }
+
+        Some(cors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_configs_with_origins_are_enabled() {
+        assert!(!CorsConfig::default().is_enabled());
+        assert!(!CorsConfig::new().allow_methods([Method::PUT]).is_enabled());
+        assert!(CorsConfig::permissive().is_enabled());
+        assert!(CorsConfig::new()
+            .allow_origin("http://localhost:5173")
+            .is_enabled());
+    }
+
+    #[test]
+    #[should_panic(expected = "not a valid CORS origin")]
+    fn origins_are_validated() {
+        let _ = CorsConfig::new().allow_origin("http://localhost:5173/app");
+    }
+}
@@ -0,0 +1,395 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::{
+    future::Future,
+    net::SocketAddr,
+    sync::{Mutex, PoisonError},
+};
+
+use thiserror::Error;
+use tokio::sync::oneshot;
+
+use super::{AuthToken, ServerInstance};
+
+/// The web framework a server is built on, for picking one at runtime with [`spawn_server`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Backend {
+    Axum,
+    Warp,
+    /// A server built directly on hyper. There is no such server yet, so this is always
+    /// [`ServerError::Unsupported`].
+    Hyper,
+}
+
+impl Default for Backend {
+    /// [`Backend::Axum`], or [`Backend::Warp`] in builds without it.
+    fn default() -> Self {
+        if cfg!(feature = "axum") {
+            Self::Axum
+        } else {
+            Self::Warp
+        }
+    }
+}
+
+impl std::str::FromStr for Backend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "axum" => Ok(Self::Axum),
+            "warp" => Ok(Self::Warp),
+            "hyper" => Ok(Self::Hyper),
+            other => Err(format!(
+                "Unknown backend '{}', expected one of axum, warp or hyper",
+                other
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for Backend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Axum => write!(f, "axum"),
+            Self::Warp => write!(f, "warp"),
+            Self::Hyper => write!(f, "hyper"),
+        }
+    }
+}
+
+/// An error starting or stopping a [`ServerInstance`].
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum ServerError {
+    #[error("Server is already running on {0}")]
+    AlreadyRunning(SocketAddr),
+    #[error("Server is not running")]
+    NotRunning,
+    #[error("Could not bind server to {addr}: {message}")]
+    Bind { addr: SocketAddr, message: String },
+    #[error("The {0} backend is not available in this build")]
+    Unsupported(Backend),
+    #[error("Server failed: {0}")]
+    Failed(String),
+}
+
+/// Create a server for `backend` and start it on `addr`, see [`ServerInstance::start`].
+///
+/// ## Errors
+/// - [`ServerError::Unsupported`] if `backend` isn't compiled in (or, for [`Backend::Hyper`], doesn't
+///   exist yet)
+/// - [`ServerError::Bind`] if `addr` can't be bound
+pub fn spawn_server(
+    backend: Backend,
+    addr: &SocketAddr,
+) -> Result<Box<dyn ServerInstance<Error = ServerError> + Send>, ServerError> {
+    let server: Box<dyn ServerInstance<Error = ServerError> + Send> = match backend {
+        #[cfg(feature = "axum")]
+        Backend::Axum => Box::new(super::AxumServer::new(
+            super::ServerSyncType::default(),
+            super::DEFAULT_MAX_BODY_BYTES,
+        )),
+        #[cfg(feature = "warp")]
+        Backend::Warp => Box::new(super::WarpServer::new()),
+        unsupported => return Err(ServerError::Unsupported(unsupported)),
+    };
+    server.start(addr)?;
+    Ok(server)
+}
+
+/// Serve `manager` with `backend` on `addr` until `shutdown_signal` fires, then
+/// [shut the manager down](crate::ComputeFunctionManager::shutdown). Requests are accepted as an
+/// [`AppInput`](crate::AppInput) on `POST /` by every backend, next to the backend's own routes.
+/// Requests without `auth_token` (if there is one) are turned away on every route.
+///
+/// ## Errors
+/// - [`ServerError::Unsupported`] if `backend` isn't compiled in (or, for [`Backend::Hyper`], doesn't
+///   exist yet)
+/// - [`ServerError::Bind`] if `addr` can't be bound
+/// - [`ServerError::Failed`] if the server fails while serving
+pub async fn serve(
+    backend: Backend,
+    addr: &SocketAddr,
+    manager: crate::ComputeFunctionManager,
+    auth_token: Option<&AuthToken>,
+    shutdown_signal: oneshot::Receiver<()>,
+) -> Result<(), ServerError> {
+    let failed = |e: &dyn std::fmt::Display| ServerError::Failed(e.to_string());
+    match backend {
+        #[cfg(feature = "axum")]
+        Backend::Axum => super::AxumServer::serve(
+            addr,
+            super::ServerSyncType::default(),
+            manager,
+            super::DEFAULT_MAX_BODY_BYTES,
+            auth_token,
+            shutdown_signal,
+        )?
+        .await
+        .map_err(|e| failed(&e))?
+        .map_err(|e| failed(&e)),
+        #[cfg(feature = "warp")]
+        Backend::Warp => super::WarpServer::serve(addr, manager, auth_token, shutdown_signal)?
+            .await
+            .map_err(|e| failed(&e)),
+        unsupported => Err(ServerError::Unsupported(unsupported)),
+    }
+}
+
+/// The running half of a [`ServerInstance`]: where it is bound and how to shut it down.
+#[derive(Debug)]
+struct Running {
+    addr: SocketAddr,
+    shutdown: oneshot::Sender<()>,
+}
+
+/// Keeps track of whether a [`ServerInstance`] is running, so that its `start` and `stop` only need
+/// `&self`.
+#[derive(Debug, Default)]
+pub struct ServerSlot(Mutex<Option<Running>>);
+
+impl ServerSlot {
+    /// Start a server with `bind`, which is given the shutdown signal and returns the address it
+    /// bound and the server future, which has to finish once the signal fires (or its sender is
+    /// dropped). The future is spawned onto the current tokio runtime.
+    ///
+    /// ## Errors
+    /// - [`ServerError::AlreadyRunning`] if a server was started and not stopped yet
+    /// - Whatever `bind` fails with
+    pub fn start<F, S>(&self, bind: F) -> Result<(), ServerError>
+    where
+        F: FnOnce(oneshot::Receiver<()>) -> Result<(SocketAddr, S), ServerError>,
+        S: Future<Output = ()> + Send + 'static,
+    {
+        let mut running = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(running) = &*running {
+            return Err(ServerError::AlreadyRunning(running.addr));
+        }
+
+        let (shutdown, signal) = oneshot::channel();
+        let (addr, server) = bind(signal)?;
+        tokio::task::spawn(server);
+        *running = Some(Running { addr, shutdown });
+        drop(running);
+        Ok(())
+    }
+
+    /// Signal the running server to shut down gracefully. This doesn't wait for it to finish.
+    ///
+    /// ## Errors
+    /// - [`ServerError::NotRunning`] if no server was started, or it was already stopped
+    pub fn stop(&self) -> Result<(), ServerError> {
+        let running = self
+            .0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+            .ok_or(ServerError::NotRunning)?;
+        // The server is gone already if it failed on its own, which leaves nothing to signal.
+        let _ = running.shutdown.send(());
+        Ok(())
+    }
+
+    /// The address the running server is bound to.
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .as_ref()
+            .map(|running| running.addr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpStream,
+    };
+
+    use super::*;
+
+    /// Send a bare HTTP/1.1 request to `addr` and return the status line of the response.
+    async fn status_line(addr: SocketAddr, method: &str, path: &str) -> String {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        let request = format!(
+            "{} {} HTTP/1.1\r\nhost: {}\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+            method, path, addr
+        );
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response.lines().next().unwrap_or_default().to_string()
+    }
+
+    async fn wait_until_refused(addr: SocketAddr) {
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while TcpStream::connect(addr).await.is_ok() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("The server kept accepting connections after it was stopped");
+    }
+
+    #[cfg(all(feature = "axum", feature = "warp"))]
+    #[tokio::test]
+    async fn every_backend_starts_and_stops_the_same_way() {
+        let any_port = SocketAddr::from(([127, 0, 0, 1], 0));
+        for (backend, method, path) in [
+            (Backend::Axum, "GET", "/functions"),
+            (Backend::Warp, "POST", "/list"),
+        ] {
+            let server = spawn_server(backend, &any_port).unwrap();
+            let addr = server.local_addr().unwrap();
+            assert_ne!(addr.port(), 0);
+            assert_eq!(
+                server.start(&any_port),
+                Err(ServerError::AlreadyRunning(addr))
+            );
+            assert!(
+                status_line(addr, method, path).await.contains(" 200 "),
+                "{}",
+                backend
+            );
+
+            server.stop().unwrap();
+            assert_eq!(server.local_addr(), None);
+            assert_eq!(server.stop(), Err(ServerError::NotRunning));
+            wait_until_refused(addr).await;
+        }
+
+        assert_eq!(
+            spawn_server(Backend::Hyper, &any_port).err(),
+            Some(ServerError::Unsupported(Backend::Hyper))
+        );
+    }
+
+    /// `POST` `body` to `/` on `addr` as JSON, with `authorization` if there is one, as soon as the
+    /// server there is up, and return the whole response.
+    async fn post_when_up(addr: SocketAddr, authorization: Option<&str>, body: &[u8]) -> String {
+        let authorization = authorization
+            .map(|value| format!("authorization: {}\r\n", value))
+            .unwrap_or_default();
+        tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                if let Ok(mut stream) = TcpStream::connect(addr).await {
+                    let request = format!(
+                        "POST / HTTP/1.1\r\nhost: {}\r\ncontent-type: application/json\r\ncontent-length: {}\r\n{}connection: close\r\n\r\n",
+                        addr,
+                        body.len(),
+                        authorization
+                    );
+                    stream.write_all(request.as_bytes()).await.unwrap();
+                    stream.write_all(body).await.unwrap();
+                    let mut response = String::new();
+                    stream.read_to_string(&mut response).await.unwrap();
+                    return response;
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("The server never started")
+    }
+
+    #[cfg(all(feature = "axum", feature = "warp"))]
+    #[tokio::test]
+    async fn every_backend_serves_app_input_until_signalled() {
+        for backend in [Backend::Axum, Backend::Warp] {
+            // Bind and drop a listener to find a port nothing listens on.
+            let addr = std::net::TcpListener::bind("127.0.0.1:0")
+                .unwrap()
+                .local_addr()
+                .unwrap();
+            let (stop, shutdown_signal) = oneshot::channel();
+            let served = tokio::task::spawn(async move {
+                serve(
+                    backend,
+                    &addr,
+                    crate::ComputeFunctionManager::with_logger(),
+                    None,
+                    shutdown_signal,
+                )
+                .await
+            });
+
+            let body = serde_json::to_vec(&crate::AppInput::ListFunctions).unwrap();
+            let response = post_when_up(addr, None, &body).await;
+            assert!(
+                response.starts_with("HTTP/1.1 200"),
+                "{}: {}",
+                backend,
+                response
+            );
+            assert!(response.contains("logger"), "{}: {}", backend, response);
+
+            stop.send(()).unwrap();
+            assert_eq!(served.await.unwrap(), Ok(()));
+        }
+    }
+
+    #[cfg(all(feature = "axum", feature = "warp"))]
+    #[tokio::test]
+    async fn every_backend_requires_the_auth_token() {
+        for backend in [Backend::Axum, Backend::Warp] {
+            // Bind and drop a listener to find a port nothing listens on.
+            let addr = std::net::TcpListener::bind("127.0.0.1:0")
+                .unwrap()
+                .local_addr()
+                .unwrap();
+            let (stop, shutdown_signal) = oneshot::channel();
+            let served = tokio::task::spawn(async move {
+                serve(
+                    backend,
+                    &addr,
+                    crate::ComputeFunctionManager::with_logger(),
+                    Some(&AuthToken::new("s3cret")),
+                    shutdown_signal,
+                )
+                .await
+            });
+
+            let body = serde_json::to_vec(&crate::AppInput::ListFunctions).unwrap();
+            for authorization in [None, Some("Bearer wrong")] {
+                let response = post_when_up(addr, authorization, &body).await;
+                assert!(
+                    response.starts_with("HTTP/1.1 401"),
+                    "{}: {}",
+                    backend,
+                    response
+                );
+            }
+            let response = post_when_up(addr, Some("Bearer s3cret"), &body).await;
+            assert!(
+                response.starts_with("HTTP/1.1 200"),
+                "{}: {}",
+                backend,
+                response
+            );
+
+            stop.send(()).unwrap();
+            assert_eq!(served.await.unwrap(), Ok(()));
+        }
+    }
+
+    #[cfg(all(feature = "axum", feature = "warp"))]
+    #[tokio::test]
+    async fn binding_a_taken_address_is_an_error() {
+        let any_port = SocketAddr::from(([127, 0, 0, 1], 0));
+        let server = spawn_server(Backend::Axum, &any_port).unwrap();
+        let taken = server.local_addr().unwrap();
+
+        assert!(matches!(
+            spawn_server(Backend::Warp, &taken).err(),
+            Some(ServerError::Bind { addr, .. }) if addr == taken
+        ));
+        server.stop().unwrap();
+    }
+}
@@ -4,15 +4,62 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+mod auth;
+#[cfg(feature = "axum")]
 mod axum_hello;
+#[cfg(feature = "axum")]
 mod axum_server;
+mod cors;
 mod hyper_server;
+mod instance;
+mod signal;
+mod supervisor;
+#[cfg(feature = "warp")]
 mod warp_server;
 
+use crate::core::types::{AppError, AppInput};
+
+/// The largest request body the servers accept by default, in bytes. Larger bodies are turned away
+/// with a `413 Payload Too Large` before they reach a function.
+pub const DEFAULT_MAX_BODY_BYTES: usize = 16 * 1024;
+
+/// Parse the body of a `POST /` with [`AppInput::from_json_slice`], the one parse path every server
+/// (and the `app_input` fuzz target) goes through. A body that isn't an [`AppInput`] at all is an
+/// [`AppError::MalformedBody`], one that fails validation an [`AppError::BadInput`].
+fn parse_app_input(bytes: &[u8]) -> Result<AppInput, AppError> {
+    AppInput::from_json_slice(bytes).map_err(|e| match e.input() {
+        Some(_) => AppError::BadInput(e),
+        None => AppError::MalformedBody(e.message().to_string()),
+    })
+}
+
+/// A server that can be started and stopped in place, whichever backend it's built on. See
+/// [`spawn_server`] for picking the backend at runtime.
 pub trait ServerInstance {
     type Error;
+    /// Bind `addr` and start serving in the background, on the current tokio runtime.
+    ///
+    /// ## Errors
+    /// If the server is already running or `addr` can't be bound.
     fn start(&self, addr: &std::net::SocketAddr) -> Result<(), Self::Error>;
+    /// Shut the server down gracefully, letting requests that are already being handled finish.
+    ///
+    /// ## Errors
+    /// If the server isn't running.
     fn stop(&self) -> Result<(), Self::Error>;
+    /// The address the server is bound to, or `None` if it isn't running. This is where to find a
+    /// server that was started on port `0`.
+    fn local_addr(&self) -> Option<std::net::SocketAddr>;
 }
 
-pub use axum_hello::run_hello_server;
+pub use auth::AuthToken;
+#[cfg(feature = "tls")]
+pub use axum_server::run_axum_tls;
+#[cfg(feature = "axum")]
+pub use axum_server::{run_axum_rest, AxumServer, ServerSyncType};
+pub use cors::CorsConfig;
+pub use instance::{serve, spawn_server, Backend, ServerError};
+pub use signal::shutdown_on_signal;
+pub use supervisor::{supervise, RestartPolicy, SupervisorExit};
+#[cfg(feature = "warp")]
+pub use warp_server::WarpServer;
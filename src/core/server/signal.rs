@@ -0,0 +1,58 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use tokio::sync::oneshot;
+use tracing::{info, warn};
+
+/// Wait until the process is asked to stop: Ctrl-C (`SIGINT`) anywhere, and `SIGTERM` on Unix as
+/// well, which is what service managers and container runtimes send.
+///
+/// A handler that can't be installed is logged and never fires, so this only ever resolves because
+/// of an actual signal.
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            warn!("Unable to listen for Ctrl-C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(e) => {
+                warn!("Unable to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => info!("Received Ctrl-C, shutting down"),
+        _ = terminate => info!("Received SIGTERM, shutting down"),
+    }
+}
+
+/// Install the [`shutdown_signal`] handlers on the current tokio runtime and return the receiving
+/// half of a graceful-shutdown oneshot that fires once the process is asked to stop, ready to be
+/// handed to any of the server runners.
+#[must_use]
+pub fn shutdown_on_signal() -> oneshot::Receiver<()> {
+    let (sender, receiver) = oneshot::channel();
+    tokio::task::spawn(async move {
+        shutdown_signal().await;
+        // The server is gone already if it failed on its own, which leaves nothing to signal.
+        let _ = sender.send(());
+    });
+    receiver
+}
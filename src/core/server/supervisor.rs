@@ -0,0 +1,187 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::{fmt::Display, future::Future, time::Duration};
+
+use tracing::{error, info, warn};
+
+/// How a [`supervise`]d server task is restarted after it panics or exits with an error.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RestartPolicy {
+    max_restarts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            max_restarts: 5,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(10),
+        }
+    }
+}
+
+impl RestartPolicy {
+    /// A policy that restarts the task up to `max_restarts` times, waiting `initial_backoff` before
+    /// the first restart and doubling the wait after every further one, up to `max_backoff`.
+    #[must_use]
+    pub const fn new(max_restarts: u32, initial_backoff: Duration, max_backoff: Duration) -> Self {
+        Self {
+            max_restarts,
+            initial_backoff,
+            max_backoff,
+        }
+    }
+
+    /// The most times the task is restarted before the supervisor gives up.
+    #[must_use]
+    pub const fn max_restarts(&self) -> u32 {
+        self.max_restarts
+    }
+
+    /// The wait before restart number `restart` (starting at 1).
+    #[must_use]
+    pub fn backoff(&self, restart: u32) -> Duration {
+        let factor = 2u32.saturating_pow(restart.saturating_sub(1));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+/// Why a [`supervise`]d task stopped for good.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SupervisorExit {
+    /// The task finished without an error, e.g. after a graceful shutdown.
+    Finished {
+        /// How many times the task was restarted before it finished.
+        restarts: u32,
+    },
+    /// The task kept failing and the [`RestartPolicy`] ran out of restarts.
+    GaveUp {
+        /// How many times the task was restarted.
+        restarts: u32,
+        /// The panic message or error of the last failure.
+        last_failure: String,
+    },
+}
+
+/// Run the task produced by `serve` on its own tokio task, starting a fresh one whenever it panics or
+/// returns an error, subject to `policy`. Every restart is logged along with what went wrong.
+///
+/// `serve` is expected to bind and run the server, so it should build the service from state that
+/// outlives the task (e.g. a cloned router) if that state has to survive a restart.
+pub async fn supervise<F, Fut, E>(policy: RestartPolicy, mut serve: F) -> SupervisorExit
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<(), E>> + Send + 'static,
+    E: Display + Send + 'static,
+{
+    let mut restarts = 0;
+    loop {
+        let failure = match tokio::spawn(serve()).await {
+            Ok(Ok(())) => return SupervisorExit::Finished { restarts },
+            Ok(Err(err)) => format!("server error: {}", err),
+            Err(err) if err.is_panic() => format!("server panicked: {}", panic_message(err)),
+            Err(err) => format!("server task failed: {}", err),
+        };
+
+        if restarts >= policy.max_restarts() {
+            error!("{}, giving up after {} restart(s)", failure, restarts);
+            return SupervisorExit::GaveUp {
+                restarts,
+                last_failure: failure,
+            };
+        }
+
+        restarts += 1;
+        let backoff = policy.backoff(restarts);
+        warn!(
+            "{}, restarting in {}ms ({}/{})",
+            failure,
+            backoff.as_millis(),
+            restarts,
+            policy.max_restarts()
+        );
+        tokio::time::sleep(backoff).await;
+        info!("Restarting server (attempt {})", restarts);
+    }
+}
+
+/// The message of a panicked task, if it panicked with a string.
+fn panic_message(err: tokio::task::JoinError) -> String {
+    let payload = err.into_panic();
+    payload
+        .downcast_ref::<&str>()
+        .map(ToString::to_string)
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "<non-string panic payload>".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    };
+
+    use super::*;
+
+    fn quick(max_restarts: u32) -> RestartPolicy {
+        RestartPolicy::new(
+            max_restarts,
+            Duration::from_millis(1),
+            Duration::from_millis(4),
+        )
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_maximum() {
+        let policy = quick(10);
+        let waits: Vec<_> = (1..=4).map(|restart| policy.backoff(restart)).collect();
+        assert_eq!(waits, [1, 2, 4, 4].map(Duration::from_millis).to_vec());
+    }
+
+    #[tokio::test]
+    async fn panicking_serve_loops_are_restarted() {
+        let attempts = Arc::new(AtomicU32::new(0));
+        let exit = supervise(quick(5), || {
+            let attempts = Arc::clone(&attempts);
+            async move {
+                assert!(
+                    attempts.fetch_add(1, Ordering::SeqCst) >= 2,
+                    "injected serve loop panic"
+                );
+                Ok::<_, std::io::Error>(())
+            }
+        })
+        .await;
+
+        assert_eq!(exit, SupervisorExit::Finished { restarts: 2 });
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn supervisor_gives_up_after_max_restarts() {
+        let attempts = Arc::new(AtomicU32::new(0));
+        let exit = supervise(quick(2), || {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            async { Err::<(), _>("address in use") }
+        })
+        .await;
+
+        assert_eq!(
+            exit,
+            SupervisorExit::GaveUp {
+                restarts: 2,
+                last_failure: "server error: address in use".to_string(),
+            }
+        );
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    }
+}
@@ -3,21 +3,43 @@
 // This Source Code Form is subject to the terms of the Mozilla Public
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::net::SocketAddr;
+
+use super::{auth::AuthToken, instance::ServerSlot};
+use crate::core::server::{CorsConfig, ServerError, ServerInstance};
+
+/// A reply of any type, so that routes with and without CORS can be handled alike.
+type BoxedReply = Box<dyn warp::Reply>;
+
 mod filters {
-    use warp::Filter;
+    use warp::{filters::BoxedFilter, Filter};
 
-    use super::{handlers, models};
+    use super::{handlers, models, AuthToken, BoxedReply};
     use crate::{
-        core::types::{AddFunctionRequest, RemoveFunctionRequest},
+        core::server::{CorsConfig, DEFAULT_MAX_BODY_BYTES},
+        core::types::{AddFunctionRequest, AppInput, RemoveFunctionRequest},
         ComputeRequest,
     };
 
+    /// Extract JSON [`AppInput`] from request body, parsed with [`AppInput::from_json_slice`] like
+    /// on every other server.
+    fn json_body_app_input() -> impl Filter<Extract = (AppInput,), Error = warp::Rejection> + Clone
+    {
+        warp::body::content_length_limit(DEFAULT_MAX_BODY_BYTES as u64)
+            .and(warp::body::bytes())
+            .and_then(|bytes: warp::hyper::body::Bytes| async move {
+                crate::core::server::parse_app_input(&bytes)
+                    .map_err(|e| warp::reject::custom(handlers::InvalidInput(e)))
+            })
+    }
+
     /// Extract JSON [`ComputeRequest`] from request body.
     fn json_body_compute_request(
     ) -> impl Filter<Extract = (ComputeRequest,), Error = warp::Rejection> + Clone {
         // When accepting a body, we want a JSON body
         // (and to reject huge payloads)...
-        warp::body::content_length_limit(1024 * 16).and(warp::body::json())
+        warp::body::content_length_limit(DEFAULT_MAX_BODY_BYTES as u64).and(warp::body::json())
     }
 
     /// Extract JSON [`AddFunctionRequest`] from request body.
@@ -25,7 +47,7 @@ mod filters {
     ) -> impl Filter<Extract = (AddFunctionRequest,), Error = warp::Rejection> + Clone {
         // When accepting a body, we want a JSON body
         // (and to reject huge payloads)...
-        warp::body::content_length_limit(1024 * 16).and(warp::body::json())
+        warp::body::content_length_limit(DEFAULT_MAX_BODY_BYTES as u64).and(warp::body::json())
     }
 
     /// Extract JSON [`RemoveFunctionRequest`] from request body.
@@ -33,7 +55,7 @@ mod filters {
     ) -> impl Filter<Extract = (RemoveFunctionRequest,), Error = warp::Rejection> + Clone {
         // When accepting a body, we want a JSON body
         // (and to reject huge payloads)...
-        warp::body::content_length_limit(1024 * 16).and(warp::body::json())
+        warp::body::content_length_limit(DEFAULT_MAX_BODY_BYTES as u64).and(warp::body::json())
     }
 
     /// Clone (ref-counted) [`AppState`] for endpoint.
@@ -43,6 +65,24 @@ mod filters {
         warp::any().map(move || state.clone())
     }
 
+    /// POST /, any [`AppInput`]
+    pub fn post_app_input(
+        state: models::AppState,
+    ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+        warp::path::end()
+            .and(warp::post())
+            .and(json_body_app_input())
+            .and(warp::addr::remote())
+            .and(warp::header::optional::<String>(
+                crate::core::types::NONCE_HEADER,
+            ))
+            .and(warp::header::optional::<String>(
+                crate::core::types::REQUEST_ID_HEADER,
+            ))
+            .and(with_app_state(state))
+            .and_then(handlers::app_input_handler)
+    }
+
     /// POST /api
     pub fn post_compute_request(
         state: models::AppState,
@@ -50,6 +90,13 @@ mod filters {
         warp::path!("api")
             .and(warp::post())
             .and(json_body_compute_request())
+            .and(warp::addr::remote())
+            .and(warp::header::optional::<String>(
+                crate::core::types::NONCE_HEADER,
+            ))
+            .and(warp::header::optional::<String>(
+                crate::core::types::REQUEST_ID_HEADER,
+            ))
             .and(with_app_state(state))
             .and_then(handlers::process_input_handler)
     }
@@ -60,9 +107,71 @@ mod filters {
     ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
         warp::path!("add")
             .and(warp::post())
-            .and(json_body_remove_function())
+            .and(json_body_add_function())
             .and(with_app_state(state))
-            .and_then(handlers::remove_function_handler)
+            .and_then(handlers::add_function_handler)
+    }
+
+    /// POST /list
+    pub fn post_list_functions(
+        state: models::AppState,
+    ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+        warp::path!("list")
+            .and(warp::post())
+            .and(with_app_state(state))
+            .and_then(handlers::list_functions_handler)
+    }
+
+    /// POST /health
+    pub fn post_health_check(
+        state: models::AppState,
+    ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+        warp::path!("health")
+            .and(warp::post())
+            .and(with_app_state(state))
+            .and_then(handlers::health_check_handler)
+    }
+
+    /// All of the endpoints above, with warp's own rejections (bad JSON, oversized bodies, ...)
+    /// converted into the same [`AppError`](crate::AppError) JSON shape the handlers use.
+    pub fn routes(
+        state: models::AppState,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        post_app_input(state.clone())
+            .or(post_compute_request(state.clone()))
+            .or(post_add_function(state.clone()))
+            .or(post_remove_function(state.clone()))
+            .or(post_list_functions(state.clone()))
+            .or(post_health_check(state))
+            .recover(handlers::handle_rejection)
+    }
+
+    /// [`routes`], turning away requests without `auth_token` (if there is one) before anything
+    /// else is extracted from them, and answering cross-origin requests as `cors` allows.
+    /// Preflights are answered by the CORS filter before the token is checked.
+    pub fn server_routes(
+        state: models::AppState,
+        cors: &CorsConfig,
+        auth_token: Option<&AuthToken>,
+    ) -> BoxedFilter<(BoxedReply,)> {
+        let routes = match auth_token {
+            Some(token) => token
+                .warp()
+                .and(routes(state))
+                .recover(handlers::handle_rejection)
+                .map(|reply| Box::new(reply) as BoxedReply)
+                .boxed(),
+            None => routes(state)
+                .map(|reply| Box::new(reply) as BoxedReply)
+                .boxed(),
+        };
+        match cors.warp() {
+            Some(cors) => routes
+                .with(cors)
+                .map(|reply| Box::new(reply) as BoxedReply)
+                .boxed(),
+            None => routes,
+        }
     }
 
     /// POST /remove
@@ -71,9 +180,9 @@ mod filters {
     ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
         warp::path!("remove")
             .and(warp::post())
-            .and(json_body_add_function())
+            .and(json_body_remove_function())
             .and(with_app_state(state))
-            .and_then(handlers::add_function_handler)
+            .and_then(handlers::remove_function_handler)
     }
 }
 
@@ -84,18 +193,37 @@ mod handlers {
 
     use super::models::AppState;
     use crate::{
-        core::types::{AddFunctionRequest, AppError, RemoveFunctionRequest},
+        core::types::{
+            AddFunctionRequest, AppError, AppInput, AppOutput, AppResult, RemoveFunctionRequest,
+            RequestContext,
+        },
         ComputeRequest,
     };
 
+    /// Build the [`RequestContext`] for a request, making up a request id if the client didn't
+    /// send one.
+    fn request_context(
+        peer_addr: Option<std::net::SocketAddr>,
+        nonce: Option<String>,
+        request_id: Option<String>,
+    ) -> RequestContext {
+        let request_id = request_id
+            .filter(|id| !id.is_empty())
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        RequestContext::new()
+            .with_peer_addr(peer_addr)
+            .with_nonce(nonce)
+            .with_request_id(request_id)
+    }
+
     pub async fn add_function_handler(
         input: AddFunctionRequest,
         cfm: AppState,
     ) -> Result<impl warp::Reply, Infallible> {
         let cfm = cfm.lock().await;
-        let result = unsafe { cfm.load_plugin(input.lib_path().to_string()).await };
+        let result = unsafe { cfm.load_plugin_info(input.lib_path().to_string()).await };
         match result {
-            Ok(_) => Ok(hyper::StatusCode::OK.into_response()),
+            Ok(info) => Ok(AppOutput::add_function_success(info).into_response()),
             Err(e) => {
                 let error: AppError = e.into();
                 Ok(error.into_response())
@@ -118,10 +246,54 @@ mod handlers {
         }
     }
 
+    pub async fn list_functions_handler(cfm: AppState) -> Result<impl warp::Reply, Infallible> {
+        let functions = cfm.lock().await.list_functions().await;
+        Ok(AppOutput::FunctionList(functions).into_response())
+    }
+
+    pub async fn health_check_handler(cfm: AppState) -> Result<impl warp::Reply, Infallible> {
+        let report = cfm.lock().await.health_check().await;
+        Ok(AppOutput::HealthReport(report).into_response())
+    }
+
+    /// An [`AppInput`] body that didn't parse, see [`crate::core::server::parse_app_input`].
+    #[derive(Debug)]
+    pub struct InvalidInput(pub AppError);
+
+    impl warp::reject::Reject for InvalidInput {}
+
+    /// Convert the rejections produced by warp's body filters and the auth check into [`AppError`]
+    /// responses. Anything else is passed on so warp can keep trying other routes.
+    pub async fn handle_rejection(
+        rejection: warp::Rejection,
+    ) -> Result<warp::reply::Response, warp::Rejection> {
+        let error = if rejection
+            .find::<crate::core::server::auth::Unauthorized>()
+            .is_some()
+        {
+            AppError::Unauthorized
+        } else if let Some(too_large) = rejection.find::<warp::reject::PayloadTooLarge>() {
+            AppError::PayloadTooLarge(too_large.to_string())
+        } else if let Some(bad_json) = rejection.find::<warp::filters::body::BodyDeserializeError>()
+        {
+            AppError::MalformedBody(bad_json.to_string())
+        } else if let Some(InvalidInput(error)) = rejection.find() {
+            error.clone()
+        } else {
+            return Err(rejection);
+        };
+
+        Ok(error.into_response())
+    }
+
     pub async fn process_input_handler(
-        input: ComputeRequest,
+        mut input: ComputeRequest,
+        peer_addr: Option<std::net::SocketAddr>,
+        nonce: Option<String>,
+        request_id: Option<String>,
         cfm: AppState,
     ) -> Result<impl warp::Reply, Infallible> {
+        input.set_context(request_context(peer_addr, nonce, request_id));
         let cfm = cfm.lock().await;
         let result = cfm.push_request(&input).await;
         match result {
@@ -129,6 +301,41 @@ mod handlers {
             Err(e) => Ok(e.into_response()),
         }
     }
+
+    /// Handle any [`AppInput`], the same envelope the axum server accepts on `POST /`.
+    pub async fn app_input_handler(
+        mut input: AppInput,
+        peer_addr: Option<std::net::SocketAddr>,
+        nonce: Option<String>,
+        request_id: Option<String>,
+        cfm: AppState,
+    ) -> Result<impl warp::Reply, Infallible> {
+        input.set_context(request_context(peer_addr, nonce, request_id));
+        let cfm = cfm.lock().await;
+        let result: AppResult<AppOutput> = match &input {
+            AppInput::AddComputeFunction(add) => {
+                unsafe { cfm.load_plugin_info(add.lib_path().to_string()).await }
+                    .map(AppOutput::add_function_success)
+                    .map_err(Into::into)
+            }
+            AppInput::RemoveComputeFunction(remove) => cfm
+                .unload_plugin(remove.target())
+                .await
+                .map(|_| AppOutput::RemoveFunctionSuccess)
+                .map_err(Into::into),
+            AppInput::Execute(request) => cfm
+                .push_request(request)
+                .await
+                .map(AppOutput::compute_response),
+            AppInput::ListFunctions => Ok(AppOutput::FunctionList(cfm.list_functions().await)),
+            AppInput::HealthCheck => Ok(AppOutput::HealthReport(cfm.health_check().await)),
+        };
+        drop(cfm);
+        match result {
+            Ok(output) => Ok(output.into_response()),
+            Err(e) => Ok(e.into_response()),
+        }
+    }
 }
 
 mod models {
@@ -144,3 +351,366 @@ mod models {
         Arc::new(Mutex::new(ComputeFunctionManager::with_logger()))
     }
 }
+
+/// The warp backend as a [`ServerInstance`], serving a manager of its own with the builtin logger
+/// already loaded.
+#[derive(Debug)]
+pub struct WarpServer {
+    state: models::AppState,
+    cors: CorsConfig,
+    auth_token: Option<AuthToken>,
+    running: ServerSlot,
+}
+
+impl WarpServer {
+    /// Create a new [`WarpServer`], to be started with [`ServerInstance::start`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            state: models::create_app_state(),
+            cors: CorsConfig::default(),
+            auth_token: None,
+            running: ServerSlot::default(),
+        }
+    }
+
+    /// Answer cross-origin requests as `cors` allows, from the next time the server is started.
+    #[must_use]
+    pub fn with_cors(mut self, cors: CorsConfig) -> Self {
+        self.cors = cors;
+        self
+    }
+
+    /// Require every request to carry `Authorization: Bearer <token>` from the next time the server
+    /// is started. Requests that don't are answered with a `401`
+    /// ([`AppError::Unauthorized`](crate::AppError::Unauthorized)) before their body is read, so in
+    /// particular no library can be added without the token.
+    ///
+    /// ## Panics
+    /// If `token` is empty.
+    #[must_use]
+    pub fn with_auth_token(mut self, token: impl Into<String>) -> Self {
+        self.auth_token = Some(AuthToken::new(token));
+        self
+    }
+}
+
+impl WarpServer {
+    /// Serve `manager` on `addr`, on every route the [`WarpServer`] has, until `shutdown_signal`
+    /// fires. Requests without `auth_token` (if there is one) are turned away, see
+    /// [`WarpServer::with_auth_token`]. Once the server has stopped the manager is
+    /// [shut down](crate::ComputeFunctionManager::shutdown).
+    ///
+    /// ## Errors
+    /// [`ServerError::Bind`] if `addr` can't be bound, in which case nothing is spawned.
+    pub fn serve(
+        addr: &SocketAddr,
+        manager: crate::ComputeFunctionManager,
+        auth_token: Option<&AuthToken>,
+        shutdown_signal: tokio::sync::oneshot::Receiver<()>,
+    ) -> Result<tokio::task::JoinHandle<()>, ServerError> {
+        let state: models::AppState = std::sync::Arc::new(tokio::sync::Mutex::new(manager));
+        let routes = filters::server_routes(state.clone(), &CorsConfig::default(), auth_token);
+        let (_, server) = warp::serve(routes)
+            .try_bind_with_graceful_shutdown(*addr, async move {
+                let _ = shutdown_signal.await;
+            })
+            .map_err(|e| ServerError::Bind {
+                addr: *addr,
+                message: e.to_string(),
+            })?;
+
+        Ok(tokio::task::spawn(async move {
+            server.await;
+            match std::sync::Arc::try_unwrap(state) {
+                Ok(manager) => manager.into_inner().shutdown().await,
+                Err(_) => tracing::warn!(
+                    "The manager is still in use after the server stopped, not shutting it down"
+                ),
+            }
+        }))
+    }
+}
+
+impl Default for WarpServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ServerInstance for WarpServer {
+    type Error = ServerError;
+
+    fn start(&self, addr: &SocketAddr) -> Result<(), ServerError> {
+        let routes =
+            filters::server_routes(self.state.clone(), &self.cors, self.auth_token.as_ref());
+        self.running.start(|shutdown| {
+            warp::serve(routes)
+                .try_bind_with_graceful_shutdown(*addr, async move {
+                    let _ = shutdown.await;
+                })
+                .map_err(|e| ServerError::Bind {
+                    addr: *addr,
+                    message: e.to_string(),
+                })
+        })
+    }
+
+    fn stop(&self) -> Result<(), ServerError> {
+        self.running.stop()
+    }
+
+    fn local_addr(&self) -> Option<SocketAddr> {
+        self.running.local_addr()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        async_trait,
+        core::types::{AddFunctionRequest, BadRequestError, RemoveFunctionRequest},
+        json, AppInput, ComputeFunction, ComputeRequest, ComputeResponse, TargetComputeFunc,
+    };
+
+    #[derive(Debug)]
+    struct EchoPeer;
+
+    #[async_trait]
+    impl ComputeFunction for EchoPeer {
+        fn name(&self) -> &'static str {
+            "echo_peer"
+        }
+
+        async fn receive_request(
+            &self,
+            request: &ComputeRequest,
+        ) -> Result<ComputeResponse, BadRequestError> {
+            let peer = request.context().peer_addr().map(|addr| addr.to_string());
+            Ok(ComputeResponse::json_ok(json!({ "peer": peer })))
+        }
+    }
+
+    #[tokio::test]
+    async fn plugins_can_read_the_connecting_address() {
+        let state = models::create_app_state();
+        state.lock().await.load_builtin_instance(Box::new(EchoPeer));
+        let filter = filters::post_compute_request(state);
+
+        let peer: SocketAddr = ([10, 1, 2, 3], 4567).into();
+        let response = warp::test::request()
+            .method("POST")
+            .path("/api")
+            .remote_addr(peer)
+            .json(&json!({ "target": "echo_peer", "data": null }))
+            .reply(&filter)
+            .await;
+
+        assert_eq!(response.status(), 200);
+        let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(body, json!({ "peer": "10.1.2.3:4567" }));
+    }
+
+    #[tokio::test]
+    async fn lists_loaded_functions() {
+        let state = models::create_app_state();
+        state.lock().await.load_builtin_instance(Box::new(EchoPeer));
+        let filter = filters::routes(state);
+
+        let response = warp::test::request()
+            .method("POST")
+            .path("/list")
+            .reply(&filter)
+            .await;
+
+        assert_eq!(response.status(), 200);
+        let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert!(body
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|info| info["name"] == "echo_peer"));
+    }
+
+    #[tokio::test]
+    async fn add_and_remove_take_their_own_requests() {
+        let state = models::create_app_state();
+        state.lock().await.load_builtin_instance(Box::new(EchoPeer));
+        let filter = filters::routes(state.clone());
+        let post = |path: &str, body: Vec<u8>| {
+            warp::test::request()
+                .method("POST")
+                .path(path)
+                .header("content-type", "application/json")
+                .body(body)
+        };
+
+        let missing = std::env::temp_dir().join(format!("missing-{}.so", uuid::Uuid::new_v4()));
+        let add = AddFunctionRequest::new(missing.to_string_lossy().into_owned());
+        let response = post("/add", serde_json::to_vec(&add).unwrap())
+            .reply(&filter)
+            .await;
+        let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(body["code"], "loading", "{}", body);
+
+        let remove = RemoveFunctionRequest::new(TargetComputeFunc::new("echo_peer".to_string()));
+        let response = post("/remove", serde_json::to_vec(&remove).unwrap())
+            .reply(&filter)
+            .await;
+        assert_eq!(response.status(), 200);
+        assert!(!state
+            .lock()
+            .await
+            .list_functions()
+            .await
+            .iter()
+            .any(|info| info.name() == "echo_peer"));
+    }
+
+    #[tokio::test]
+    async fn oversized_bodies_are_rejected_as_app_errors() {
+        let filter = filters::routes(models::create_app_state());
+
+        let response = warp::test::request()
+            .method("POST")
+            .path("/api")
+            .body(vec![b' '; crate::core::server::DEFAULT_MAX_BODY_BYTES + 1])
+            .reply(&filter)
+            .await;
+
+        assert_eq!(response.status(), 413);
+        let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(body["code"], "payload_too_large");
+    }
+
+    #[tokio::test]
+    async fn inputs_are_parsed_like_on_every_server() {
+        let filter = filters::routes(models::create_app_state());
+        let post = |body: Vec<u8>| {
+            warp::test::request()
+                .method("POST")
+                .path("/")
+                .header("content-type", "application/json")
+                .body(body)
+        };
+
+        // Well-formed, but an empty library path fails validation while parsing.
+        let invalid = AppInput::AddComputeFunction(AddFunctionRequest::new("  ".to_string()));
+        let response = post(serde_json::to_vec(&invalid).unwrap())
+            .reply(&filter)
+            .await;
+        assert_eq!(response.status(), 400);
+        let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(body["code"], "bad_input");
+
+        let response = post(b"{ not json".to_vec()).reply(&filter).await;
+        assert_eq!(response.status(), 400);
+        let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(body["code"], "malformed_body");
+
+        let response = post(serde_json::to_vec(&AppInput::ListFunctions).unwrap())
+            .reply(&filter)
+            .await;
+        assert_eq!(response.status(), 200);
+    }
+
+    #[tokio::test]
+    async fn malformed_json_is_rejected_as_an_app_error() {
+        let filter = filters::routes(models::create_app_state());
+
+        let response = warp::test::request()
+            .method("POST")
+            .path("/api")
+            .header("content-type", "application/json")
+            .body("{ not json")
+            .reply(&filter)
+            .await;
+
+        assert_eq!(response.status(), 400);
+        let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(body["code"], "malformed_body");
+    }
+
+    #[tokio::test]
+    async fn cors_headers_are_only_sent_when_allowed() {
+        let list = || {
+            warp::test::request()
+                .method("POST")
+                .path("/list")
+                .header("origin", "http://localhost:5173")
+        };
+
+        let closed =
+            filters::server_routes(models::create_app_state(), &CorsConfig::default(), None);
+        let response = list().reply(&closed).await;
+        assert_eq!(response.status(), 200);
+        assert!(!response
+            .headers()
+            .contains_key("access-control-allow-origin"));
+
+        let open = filters::server_routes(
+            models::create_app_state(),
+            &CorsConfig::new().allow_origin("http://localhost:5173"),
+            None,
+        );
+        let response = list().reply(&open).await;
+        assert_eq!(response.status(), 200);
+        assert_eq!(
+            response.headers()["access-control-allow-origin"],
+            "http://localhost:5173"
+        );
+    }
+
+    #[tokio::test]
+    async fn requests_without_the_auth_token_are_turned_away() {
+        let token = AuthToken::new("s3cret");
+        let filter = filters::server_routes(
+            models::create_app_state(),
+            &CorsConfig::new().allow_origin("http://localhost:5173"),
+            Some(&token),
+        );
+        let add = || {
+            warp::test::request()
+                .method("POST")
+                .path("/add")
+                .json(&json!("/definitely/not/libthere.so"))
+        };
+
+        for authorization in [None, Some("Bearer wrong"), Some("s3cret")] {
+            let mut request = add();
+            if let Some(authorization) = authorization {
+                request = request.header("authorization", authorization);
+            }
+            let response = request.reply(&filter).await;
+            assert_eq!(response.status(), 401, "{:?}", authorization);
+            assert_eq!(response.headers()["www-authenticate"], "Bearer");
+            let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+            assert_eq!(body["code"], "unauthorized");
+        }
+
+        // The token is checked before the body is even looked at.
+        let response = warp::test::request()
+            .method("POST")
+            .path("/api")
+            .body(vec![b' '; crate::core::server::DEFAULT_MAX_BODY_BYTES + 1])
+            .reply(&filter)
+            .await;
+        assert_eq!(response.status(), 401);
+
+        let response = add()
+            .header("authorization", "Bearer s3cret")
+            .reply(&filter)
+            .await;
+        assert_ne!(response.status(), 401);
+
+        let response = warp::test::request()
+            .method("OPTIONS")
+            .path("/add")
+            .header("origin", "http://localhost:5173")
+            .header("access-control-request-method", "POST")
+            .reply(&filter)
+            .await;
+        assert!(response.status().is_success(), "{}", response.status());
+    }
+}
@@ -0,0 +1,18 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use crate::core::types::{RequestContext, TargetComputeFunc};
+
+/// Decides whether a request may reach a given [`ComputeFunction`](crate::ComputeFunction).
+///
+/// Attach one to the manager with
+/// [`ComputeFunctionManager::set_auth_policy`](crate::ComputeFunctionManager::set_auth_policy) to
+/// apply access rules per function, per tenant, per client address, or whatever else is in the
+/// [`RequestContext`].
+pub trait AuthPolicy: Send + Sync + std::fmt::Debug {
+    /// Return `true` if the request described by `ctx` is allowed to call `target`.
+    fn authorize(&self, ctx: &RequestContext, target: &TargetComputeFunc) -> bool;
+}
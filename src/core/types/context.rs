@@ -0,0 +1,118 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::{net::SocketAddr, time::Duration};
+
+use tokio::time::Instant;
+
+/// Information about where and how a [`ComputeRequest`](crate::ComputeRequest) arrived.
+///
+/// This is filled in by the server that received the request rather than by the client, so it is never
+/// part of the serialized request body.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RequestContext {
+    peer_addr: Option<SocketAddr>,
+    tenant: Option<String>,
+    deadline: Option<Instant>,
+    nonce: Option<String>,
+    request_id: Option<String>,
+}
+
+impl RequestContext {
+    /// Create a new, empty, [`RequestContext`].
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            peer_addr: None,
+            tenant: None,
+            deadline: None,
+            nonce: None,
+            request_id: None,
+        }
+    }
+
+    /// Consume this [`RequestContext`] and return it with the given peer address.
+    #[must_use]
+    pub const fn with_peer_addr(mut self, peer_addr: Option<SocketAddr>) -> Self {
+        self.peer_addr = peer_addr;
+        self
+    }
+
+    /// Consume this [`RequestContext`] and return it with the given tenant.
+    #[must_use]
+    pub fn with_tenant(mut self, tenant: impl Into<String>) -> Self {
+        self.tenant = Some(tenant.into());
+        self
+    }
+
+    /// The tenant the request was made on behalf of, if known.
+    #[must_use]
+    pub fn tenant(&self) -> Option<&str> {
+        self.tenant.as_deref()
+    }
+
+    /// Consume this [`RequestContext`] and return it with the given deadline, after which the
+    /// request is abandoned.
+    #[must_use]
+    pub const fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Consume this [`RequestContext`] and return it with a deadline `timeout` from now.
+    #[must_use]
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        self.with_deadline(Instant::now() + timeout)
+    }
+
+    /// The point in time after which the request is abandoned, if it has one.
+    #[must_use]
+    pub const fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// How much of the deadline is left, or [`None`] if the request has no deadline. Functions that
+    /// make downstream calls of their own should use this as their timeout rather than a fixed one.
+    #[must_use]
+    pub fn remaining(&self) -> Option<Duration> {
+        self.deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+
+    /// Consume this [`RequestContext`] and return it with the given (client chosen, single use)
+    /// nonce, used for replay protection.
+    #[must_use]
+    pub fn with_nonce(mut self, nonce: Option<String>) -> Self {
+        self.nonce = nonce;
+        self
+    }
+
+    /// The nonce the client sent with the request, if any.
+    #[must_use]
+    pub fn nonce(&self) -> Option<&str> {
+        self.nonce.as_deref()
+    }
+
+    /// Consume this [`RequestContext`] and return it with the given id, used to tell requests apart in
+    /// the logs.
+    #[must_use]
+    pub fn with_request_id(mut self, request_id: impl Into<String>) -> Self {
+        self.request_id = Some(request_id.into());
+        self
+    }
+
+    /// The id of the request, if one was assigned.
+    #[must_use]
+    pub fn request_id(&self) -> Option<&str> {
+        self.request_id.as_deref()
+    }
+
+    /// The address of the client that sent the request, if the server was able to determine it.
+    #[must_use]
+    pub const fn peer_addr(&self) -> Option<SocketAddr> {
+        self.peer_addr
+    }
+}
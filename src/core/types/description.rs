@@ -0,0 +1,243 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+use crate::core::types::ComputeFunction;
+
+/// How many times a function has been called since it was loaded, how many of those calls failed,
+/// and how much data went through it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct CallCounts {
+    pub calls: u64,
+    pub errors: u64,
+    /// The total serialized size of the request payloads sent to the function, in bytes.
+    pub bytes_in: u64,
+    /// The total serialized size of the response payloads the function returned, in bytes.
+    pub bytes_out: u64,
+}
+
+/// How many times a function has been called since it was loaded, how many of those calls failed,
+/// and how long they took.
+///
+/// Durations cover the function handling the request, not waiting for the manager's locks or the
+/// request queue.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct FunctionStats {
+    pub call_count: u64,
+    pub error_count: u64,
+    /// The time spent in all calls together.
+    pub total_duration: Duration,
+    /// The time spent in the slowest call.
+    pub max_duration: Duration,
+}
+
+impl FunctionStats {
+    /// The average time spent in a call, or zero if there have been none.
+    #[must_use]
+    pub fn mean_duration(&self) -> Duration {
+        self.total_duration
+            .as_nanos()
+            .checked_div(u128::from(self.call_count))
+            .map_or(Duration::ZERO, |nanos| {
+                Duration::from_nanos(u64::try_from(nanos).unwrap_or(u64::MAX))
+            })
+    }
+}
+
+/// Everything known about a single loaded [`ComputeFunction`], gathered in one place for admin
+/// tooling. Serializes to the body of the `GET /functions/:name` route.
+#[derive(Debug, Clone, Serialize)]
+pub struct FunctionDescription {
+    name: String,
+    version: Option<String>,
+    description: Option<String>,
+    input_schema: Option<JsonValue>,
+    output_schema: Option<JsonValue>,
+    examples: Vec<JsonValue>,
+    metrics: CallCounts,
+}
+
+impl FunctionDescription {
+    /// Describe `function`, which is registered under `name`, with the given call `metrics`.
+    #[must_use]
+    pub fn new(name: &str, function: &dyn ComputeFunction, metrics: CallCounts) -> Self {
+        Self {
+            name: name.to_string(),
+            version: function.version().map(ToString::to_string),
+            description: function.description().map(ToString::to_string),
+            input_schema: function.input_schema(),
+            output_schema: function.output_schema(),
+            examples: function.examples(),
+            metrics,
+        }
+    }
+
+    /// The name the function is registered under.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[must_use]
+    pub fn version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+
+    #[must_use]
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    #[must_use]
+    pub const fn input_schema(&self) -> Option<&JsonValue> {
+        self.input_schema.as_ref()
+    }
+
+    #[must_use]
+    pub const fn output_schema(&self) -> Option<&JsonValue> {
+        self.output_schema.as_ref()
+    }
+
+    #[must_use]
+    pub fn examples(&self) -> &[JsonValue] {
+        &self.examples
+    }
+
+    #[must_use]
+    pub const fn metrics(&self) -> CallCounts {
+        self.metrics
+    }
+}
+
+/// Where a loaded function came from.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(not(feature = "pascal-case-wire"), serde(rename_all = "snake_case"))]
+pub enum FunctionOrigin {
+    /// A builtin, created by the manager itself.
+    Builtin,
+    /// A plugin loaded from the dynamic library at `path`.
+    Dynamic { path: String },
+    /// A plugin loaded from the WebAssembly module at `path`.
+    Wasm { path: String },
+    /// A function that runs `command` with `args` as a separate process for every request.
+    Subprocess { command: String, args: Vec<String> },
+    /// A function that forwards every request to the HTTP endpoint at `url`.
+    Proxy { url: String },
+}
+
+/// A short summary of a loaded function, as returned by
+/// [`ComputeFunctionManager::list_functions`](crate::ComputeFunctionManager::list_functions).
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct FunctionInfo {
+    name: String,
+    origin: FunctionOrigin,
+    loaded_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    aliases: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    input_schema: Option<JsonValue>,
+}
+
+impl FunctionInfo {
+    /// Create a new [`FunctionInfo`] for the function registered as `name`.
+    #[must_use]
+    pub fn new(name: &str, origin: FunctionOrigin, loaded_at: DateTime<Utc>) -> Self {
+        Self {
+            name: name.to_string(),
+            origin,
+            loaded_at,
+            aliases: Vec::new(),
+            input_schema: None,
+        }
+    }
+
+    /// Add the JSON Schema the function's request data is checked against, see
+    /// [`ComputeFunction::input_schema`].
+    #[must_use]
+    pub fn with_input_schema(mut self, input_schema: Option<JsonValue>) -> Self {
+        self.input_schema = input_schema;
+        self
+    }
+
+    /// Add the other names the function can be reached under, see
+    /// [`ComputeFunctionManager::add_alias`](crate::ComputeFunctionManager::add_alias).
+    #[must_use]
+    pub fn with_aliases(mut self, aliases: Vec<String>) -> Self {
+        self.aliases = aliases;
+        self
+    }
+
+    /// The name the function is registered under.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[must_use]
+    pub const fn origin(&self) -> &FunctionOrigin {
+        &self.origin
+    }
+
+    /// When the function was loaded (or last reloaded).
+    #[must_use]
+    pub const fn loaded_at(&self) -> DateTime<Utc> {
+        self.loaded_at
+    }
+
+    /// The other names the function can be reached under, sorted.
+    #[must_use]
+    pub fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+
+    /// The JSON Schema the function's request data is checked against, if it declared one.
+    #[must_use]
+    pub const fn input_schema(&self) -> Option<&JsonValue> {
+        self.input_schema.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(not(feature = "pascal-case-wire"))]
+    #[test]
+    fn function_info_round_trips_through_json() {
+        let info = FunctionInfo::new(
+            "sample",
+            FunctionOrigin::Dynamic {
+                path: "/plugins/sample.so".to_string(),
+            },
+            Utc::now(),
+        );
+
+        let wire = serde_json::to_value(&info).unwrap();
+        assert_eq!(
+            wire["origin"],
+            serde_json::json!({ "dynamic": { "path": "/plugins/sample.so" } })
+        );
+        assert!(wire.get("aliases").is_none());
+        assert_eq!(serde_json::from_value::<FunctionInfo>(wire).unwrap(), info);
+
+        let aliased = info.with_aliases(vec!["alias".to_string()]);
+        let wire = serde_json::to_value(&aliased).unwrap();
+        assert_eq!(wire["aliases"], serde_json::json!(["alias"]));
+        assert_eq!(
+            serde_json::from_value::<FunctionInfo>(wire).unwrap(),
+            aliased
+        );
+        assert_eq!(
+            serde_json::to_value(FunctionOrigin::Builtin).unwrap(),
+            serde_json::json!("builtin")
+        );
+    }
+}
@@ -4,30 +4,72 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-use axum::Json;
+use std::time::Duration;
+
 use serde::{Deserialize, Serialize};
-use serde_json::json;
 use thiserror::Error;
 
-use crate::core::types::{
-    BadInputError, BadRequestError, GenericStatusCode, LoadingError, TargetComputeFunc,
-    UnloadingError,
+use crate::{
+    core::types::{
+        AppInput, BadInputError, BadRequestError, Busy, GenericStatusCode, LoadingError,
+        TargetComputeFunc, UnloadingError,
+    },
+    util::error_envelope,
 };
 
 pub type AppResult<T> = Result<T, AppError>;
 
+/// The `Retry-After` hint, in seconds, sent with [`AppError::Overloaded`] responses.
+pub const OVERLOADED_RETRY_AFTER_SECS: u64 = 1;
+
 #[derive(Debug, Error, Deserialize, Serialize, Clone)]
 pub enum AppError {
     #[error("{0}")]
-    BadInput(BadInputError),
+    BadInput(#[source] BadInputError),
     #[error("{0}")]
-    BadRequest(BadRequestError),
+    BadRequest(#[source] BadRequestError),
     #[error("Target compute function '{0}' not found")]
     TargetNotFound(TargetComputeFunc),
     #[error("Error loading compute function: {0}")]
-    Loading(LoadingError),
+    Loading(#[source] LoadingError),
     #[error("Error unloading compute function: {0}")]
-    Unloading(UnloadingError),
+    Unloading(#[source] UnloadingError),
+    /// The request body could not be parsed.
+    #[error("Malformed request body: {0}")]
+    MalformedBody(String),
+    /// The request body was larger than the server accepts.
+    #[error("Request body too large: {0}")]
+    PayloadTooLarge(String),
+    /// The request didn't carry the bearer token the server was configured with (see the servers'
+    /// `with_auth_token`), so it wasn't looked at any further.
+    #[error("Missing or invalid bearer token")]
+    Unauthorized,
+    /// The request was not allowed to reach its target by the manager's
+    /// [`AuthPolicy`](crate::AuthPolicy).
+    #[error("Access to compute function '{0}' denied")]
+    Forbidden(TargetComputeFunc),
+    /// The request carried a nonce that was already used within the replay protection window,
+    /// see [`ComputeFunctionManager::set_replay_protection`](crate::ComputeFunctionManager::set_replay_protection).
+    #[error("Request nonce '{0}' has already been used")]
+    Replayed(String),
+    /// The target function didn't finish before the request's deadline.
+    #[error("Compute function '{0}' did not finish before the request deadline")]
+    DeadlineExceeded(TargetComputeFunc),
+    /// The target function didn't finish within the timeout given to
+    /// [`ComputeFunctionManager::push_request_timeout`](crate::ComputeFunctionManager::push_request_timeout).
+    #[error("Compute function '{target}' timed out after {}ms", elapsed.as_millis())]
+    Timeout {
+        target: TargetComputeFunc,
+        elapsed: Duration,
+    },
+    /// The request was rejected to relieve backpressure (a concurrency limit, a full queue, a memory
+    /// budget, ...). Clients should back off and retry, see [`AppError::retry_after`].
+    #[error("Server overloaded: {0}")]
+    Overloaded(String),
+    /// The manager is shutting down and no longer accepts new requests, see
+    /// [`ComputeFunctionManager::drain`](crate::ComputeFunctionManager::drain).
+    #[error("Server is draining and not accepting new requests")]
+    Draining,
     #[error("Unknown error occurred: {0}")]
     Other(String),
     #[error("You should not be seeing this.")]
@@ -40,52 +82,192 @@ impl AppError {
         msg.to_string().into()
     }
 
+    /// Create an [`AppError::Overloaded`] with the given reason.
+    #[must_use]
+    pub fn overloaded(reason: &str) -> Self {
+        Self::Overloaded(reason.to_string())
+    }
+
+    /// A short, stable, machine readable identifier for the kind of error, included in the error
+    /// responses so clients don't have to match on messages.
+    #[must_use]
+    pub const fn code(&self) -> &'static str {
+        match self {
+            Self::BadInput(_) => "bad_input",
+            Self::BadRequest(_) => "bad_request",
+            Self::TargetNotFound(_) => "target_not_found",
+            Self::MalformedBody(_) => "malformed_body",
+            Self::PayloadTooLarge(_) => "payload_too_large",
+            Self::Unauthorized => "unauthorized",
+            Self::Forbidden(_) => "forbidden",
+            Self::Replayed(_) => "replayed_request",
+            Self::DeadlineExceeded(_) => "deadline_exceeded",
+            Self::Timeout { .. } => "timeout",
+            Self::Loading(_) => "loading",
+            Self::Unloading(_) => "unloading",
+            Self::Overloaded(_) => "overloaded",
+            Self::Draining => "draining",
+            Self::Other(_) => "other",
+            Self::None => "none",
+        }
+    }
+
+    /// The number of seconds a client should wait before retrying, sent as the `Retry-After`
+    /// header. Only [`AppError::Overloaded`] errors carry a hint.
+    #[must_use]
+    pub const fn retry_after(&self) -> Option<u64> {
+        match self {
+            Self::Overloaded(_) => Some(OVERLOADED_RETRY_AFTER_SECS),
+            _ => None,
+        }
+    }
+
     #[must_use]
     pub const fn as_generic_status_code(&self) -> GenericStatusCode {
         match self {
-            Self::BadInput(_) | Self::BadRequest(_) => GenericStatusCode::BadRequest,
+            Self::BadInput(_) | Self::BadRequest(_) | Self::MalformedBody(_) => {
+                GenericStatusCode::BadRequest
+            }
+            Self::PayloadTooLarge(_) => GenericStatusCode::PayloadTooLarge,
+            Self::Unauthorized => GenericStatusCode::Other(401),
+            Self::Forbidden(_) => GenericStatusCode::Other(403),
+            Self::Replayed(_) => GenericStatusCode::Conflict,
+            Self::DeadlineExceeded(_) | Self::Timeout { .. } => GenericStatusCode::Other(504),
             Self::Unloading(un) => match un {
                 UnloadingError::TargetNotFound(_) => GenericStatusCode::NotFound,
                 UnloadingError::UnableToUnload(_) => GenericStatusCode::InternalError,
             },
             Self::Loading(load) => match load {
-                LoadingError::FunctionNameCollision(_) => GenericStatusCode::Conflict,
-                LoadingError::BadPath(_) => GenericStatusCode::PreconditionFailed,
-                LoadingError::PathNotFound(_) => GenericStatusCode::NotFound,
+                LoadingError::FunctionNameCollision(_) | LoadingError::ReloadCancelled(_) => {
+                    GenericStatusCode::Conflict
+                }
+                LoadingError::BadPath(_) | LoadingError::AbiMismatch { .. } => {
+                    GenericStatusCode::PreconditionFailed
+                }
+                LoadingError::LoadTimeout(_) => GenericStatusCode::Other(504),
+                LoadingError::PathNotAbsolute(_) | LoadingError::PathOutsideBaseDir(_) => {
+                    GenericStatusCode::BadRequest
+                }
+                LoadingError::PathNotFound(_) | LoadingError::NotReloadable(_) => {
+                    GenericStatusCode::NotFound
+                }
                 _ => GenericStatusCode::InternalError,
             },
             Self::TargetNotFound(_) => GenericStatusCode::NotFound,
+            Self::Overloaded(_) | Self::Draining => GenericStatusCode::ServiceUnavailable,
             Self::Other(_) | Self::None => GenericStatusCode::InternalError,
         }
     }
 
-    /// FIXME: Change this to be feature gated (or delete it if a different backend is chosen).
+    /// The JSON body sent for this error, see [`error_envelope`], with the [`AppError::details`] as
+    /// its `details`.
+    #[must_use]
+    pub fn envelope(&self) -> serde_json::Value {
+        error_envelope(
+            self.as_generic_status_code(),
+            self.code(),
+            &self.to_string(),
+            self.details(),
+        )
+    }
+
+    /// What the error responses tell clients about the error beyond its message: the `target`
+    /// function and the `request_id` of the request, where they are known. Only ever these, never
+    /// the request's data (which the client already has, and can be large) or anything about the
+    /// server, like the paths libraries are loaded from. `None` if there is nothing to tell.
+    #[must_use]
+    pub fn details(&self) -> Option<serde_json::Value> {
+        let (target, request_id) = match self {
+            Self::BadInput(err) => match err.input() {
+                Some(AppInput::Execute(request)) => {
+                    (Some(request.target().name()), request.request_id())
+                }
+                _ => (None, None),
+            },
+            Self::BadRequest(err) => (
+                Some(
+                    err.request()
+                        .map_or(err.sender(), |request| request.target().name()),
+                ),
+                err.request_id(),
+            ),
+            Self::TargetNotFound(target)
+            | Self::Forbidden(target)
+            | Self::DeadlineExceeded(target)
+            | Self::Timeout { target, .. }
+            | Self::Unloading(UnloadingError::TargetNotFound(target)) => {
+                (Some(target.name()), None)
+            }
+            _ => (None, None),
+        };
+
+        let mut details = serde_json::Map::new();
+        if let Some(target) = target {
+            details.insert("target".to_string(), target.into());
+        }
+        if let Some(request_id) = request_id {
+            details.insert("request_id".to_string(), request_id.into());
+        }
+        (!details.is_empty()).then_some(serde_json::Value::Object(details))
+    }
+
     /// Consume this error and converts it to an [`axum`] [`axum::response::Response`], for use
     /// in [`axum::Router`] and [`axum::Server`].
+    #[cfg(feature = "axum")]
     #[must_use]
     pub fn into_axum(self) -> axum::response::Response {
-        use axum::response::IntoResponse;
+        use axum::{
+            http::{
+                header::{RETRY_AFTER, WWW_AUTHENTICATE},
+                HeaderValue,
+            },
+            response::IntoResponse,
+            Json,
+        };
 
         let status = self.as_generic_status_code().to_status_code();
-        let body = Json(json!({
-            "error": self,
-        }));
+        let mut resp = (status, Json(self.envelope())).into_response();
+        let retry_after = self.retry_after();
+        if let Some(secs) = retry_after {
+            resp.headers_mut()
+                .insert(RETRY_AFTER, HeaderValue::from(secs));
+        }
+        if matches!(self, Self::Unauthorized) {
+            resp.headers_mut()
+                .insert(WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+        }
 
-        (status, body).into_response()
+        resp
     }
 
-    /// FIXME: Change this to be feature gated (or delete it if a different backend is chosen).
     /// Consume this error and converts it to a [`warp`] [`warp::reply::Response`], fulfilling
     /// the [`warp`] trait [`warp::Reply`], for convenient use in [`warp::Filter`]s.
+    #[cfg(feature = "warp")]
+    #[must_use]
     pub fn into_warp(self) -> warp::reply::Response {
-        use warp::{reply::json, Reply};
+        use warp::{
+            http::{
+                header::{RETRY_AFTER, WWW_AUTHENTICATE},
+                HeaderValue,
+            },
+            reply::json,
+            Reply,
+        };
 
         let status = self.as_generic_status_code().to_status_code();
-        let mut resp = json(&self).into_response();
+        let mut resp = json(&self.envelope()).into_response();
         {
             let resp_status = resp.status_mut();
             *resp_status = status;
         }
+        if let Some(secs) = self.retry_after() {
+            resp.headers_mut()
+                .insert(RETRY_AFTER, HeaderValue::from(secs));
+        }
+        if matches!(self, Self::Unauthorized) {
+            resp.headers_mut()
+                .insert(WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+        }
 
         resp
     }
@@ -115,6 +297,12 @@ impl From<UnloadingError> for AppError {
     }
 }
 
+impl From<Busy> for AppError {
+    fn from(e: Busy) -> Self {
+        Self::Overloaded(e.to_string())
+    }
+}
+
 impl From<String> for AppError {
     fn from(s: String) -> Self {
         Self::Other(s)
@@ -131,14 +319,166 @@ impl Into<GenericStatusCode> for AppError {
     }
 }
 
+#[cfg(feature = "axum")]
 impl axum::response::IntoResponse for AppError {
     fn into_response(self) -> axum::response::Response {
         self.into_axum()
     }
 }
 
+#[cfg(feature = "warp")]
 impl warp::Reply for AppError {
     fn into_response(self) -> warp::reply::Response {
         self.into_warp()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as _;
+
+    use super::*;
+    use crate::core::types::ComputeRequest;
+
+    /// Walk the `source()` chain of `err`, returning the message of every error in it.
+    fn chain(err: &AppError) -> Vec<String> {
+        let mut messages = vec![err.to_string()];
+        let mut current = err.source();
+        while let Some(source) = current {
+            messages.push(source.to_string());
+            current = source.source();
+        }
+        messages
+    }
+
+    #[test]
+    fn wrapping_variants_expose_their_source() {
+        let target = TargetComputeFunc::new("missing".to_string());
+        let request = ComputeRequest::new(target.clone(), serde_json::Value::Null);
+        let leaves: Vec<(AppError, String)> = vec![
+            (
+                BadInputError::new("bad", AppInput::Execute(request)).into(),
+                "BadInputError: bad".to_string(),
+            ),
+            (
+                BadRequestError::without_request("logger", "bad").into(),
+                "BadRequestError from logger: bad".to_string(),
+            ),
+            (
+                LoadingError::name_collision(&"logger").into(),
+                LoadingError::name_collision(&"logger").to_string(),
+            ),
+            (
+                UnloadingError::TargetNotFound(target.clone()).into(),
+                UnloadingError::TargetNotFound(target).to_string(),
+            ),
+        ];
+
+        for (err, leaf) in leaves {
+            let messages = chain(&err);
+            assert_eq!(messages.len(), 2, "{:?}", messages);
+            assert_eq!(messages[1], leaf);
+        }
+    }
+
+    #[test]
+    fn envelopes_only_detail_the_target_and_request_id() {
+        let request = ComputeRequest::new(
+            TargetComputeFunc::new("echo".to_string()),
+            serde_json::json!({ "secret": "hunter2" }),
+        )
+        .with_context(crate::RequestContext::new().with_request_id("abc-123"));
+
+        let rejected: AppError = BadRequestError::new("echo", "bad", Some(request.clone())).into();
+        assert_eq!(
+            rejected.envelope()["details"],
+            serde_json::json!({ "target": "echo", "request_id": "abc-123" })
+        );
+        let invalid: AppError = BadInputError::new("bad", AppInput::Execute(request)).into();
+        assert_eq!(
+            invalid.envelope()["details"],
+            serde_json::json!({ "target": "echo", "request_id": "abc-123" })
+        );
+
+        let not_found: AppError =
+            LoadingError::PathNotFound("/srv/plugins/libsecret.so".to_string()).into();
+        assert_eq!(not_found.envelope()["details"], serde_json::Value::Null);
+        assert_eq!(
+            AppError::overloaded("queue full").envelope()["details"],
+            serde_json::Value::Null
+        );
+    }
+
+    #[test]
+    fn non_wrapping_variants_have_no_source() {
+        assert!(AppError::other("nope").source().is_none());
+        assert!(AppError::overloaded("full").source().is_none());
+    }
+
+    #[test]
+    fn overloaded_is_a_503_with_a_stable_code() {
+        let err = AppError::overloaded("queue full");
+
+        assert_eq!(err.as_generic_status_code().to_u16(), 503);
+        assert_eq!(err.code(), "overloaded");
+        assert_eq!(err.retry_after(), Some(OVERLOADED_RETRY_AFTER_SECS));
+        assert_eq!(AppError::from(Busy).code(), "overloaded");
+        assert_eq!(AppError::other("nope").retry_after(), None);
+    }
+
+    #[test]
+    fn draining_is_a_503_without_a_retry_hint() {
+        assert_eq!(AppError::Draining.as_generic_status_code().to_u16(), 503);
+        assert_eq!(AppError::Draining.code(), "draining");
+        assert_eq!(AppError::Draining.retry_after(), None);
+    }
+
+    #[cfg(feature = "axum")]
+    #[test]
+    fn overloaded_axum_response_has_retry_after() {
+        let resp = AppError::overloaded("queue full").into_axum();
+
+        assert_eq!(resp.status(), 503);
+        assert_eq!(resp.headers()["retry-after"], "1");
+        assert!(AppError::other("nope")
+            .into_axum()
+            .headers()
+            .get("retry-after")
+            .is_none());
+    }
+
+    #[cfg(all(feature = "axum", feature = "warp"))]
+    #[tokio::test]
+    async fn backends_send_identical_envelopes() {
+        let errors = vec![
+            AppError::MalformedBody("expected value at line 1 column 1".to_string()),
+            AppError::TargetNotFound(TargetComputeFunc::new("missing".to_string())),
+            AppError::overloaded("queue full"),
+            AppError::Unauthorized,
+            BadRequestError::without_request("logger", "bad").into(),
+        ];
+
+        for err in errors {
+            let axum = err.clone().into_axum();
+            let warp = err.clone().into_warp();
+            assert_eq!(axum.status(), warp.status());
+
+            let axum = hyper::body::to_bytes(axum.into_body()).await.unwrap();
+            let warp = hyper::body::to_bytes(warp.into_body()).await.unwrap();
+            assert_eq!(axum, warp);
+            let body: serde_json::Value = serde_json::from_slice(&axum).unwrap();
+            assert_eq!(body["code"], err.code());
+            assert_eq!(body["status"], err.as_generic_status_code().to_u16());
+            assert_eq!(body["message"], err.to_string());
+        }
+    }
+
+    #[cfg(feature = "warp")]
+    #[test]
+    fn overloaded_warp_response_has_retry_after() {
+        let resp = AppError::overloaded("queue full").into_warp();
+
+        assert_eq!(resp.status(), 503);
+        assert_eq!(resp.headers()["retry-after"], "1");
+    }
+}
@@ -14,7 +14,7 @@ use crate::core::types::AppInput;
 #[derive(Debug, Error, Deserialize, Serialize, Clone)]
 pub struct BadInputError {
     message: String,
-    input: AppInput,
+    input: Option<AppInput>,
 }
 
 impl BadInputError {
@@ -23,7 +23,17 @@ impl BadInputError {
     pub fn new(message: &str, input: AppInput) -> Self {
         Self {
             message: message.to_string(),
-            input,
+            input: Some(input),
+        }
+    }
+
+    /// Create a new [`BadInputError`] for input that could not be parsed into an [`AppInput`] at
+    /// all, so there is no input to attach.
+    #[must_use]
+    pub fn without_input(message: &str) -> Self {
+        Self {
+            message: message.to_string(),
+            input: None,
         }
     }
 
@@ -33,8 +43,8 @@ impl BadInputError {
     }
 
     #[must_use]
-    pub const fn input(&self) -> &AppInput {
-        &self.input
+    pub const fn input(&self) -> Option<&AppInput> {
+        self.input.as_ref()
     }
 }
 
@@ -16,6 +16,8 @@ pub struct BadRequestError {
     sender: String,
     message: String,
     request: Option<ComputeRequest>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    request_id: Option<String>,
 }
 
 impl BadRequestError {
@@ -24,6 +26,10 @@ impl BadRequestError {
         Self {
             sender: sender.to_string(),
             message: msg.to_string(),
+            request_id: request
+                .as_ref()
+                .and_then(ComputeRequest::request_id)
+                .map(ToString::to_string),
             request,
         }
     }
@@ -52,6 +58,29 @@ impl BadRequestError {
     pub const fn has_request(&self) -> bool {
         self.request.is_some()
     }
+
+    /// Consume this error and return it with the given request id, see
+    /// [`ComputeRequest::request_id`].
+    #[must_use]
+    pub fn with_request_id(mut self, request_id: impl Into<String>) -> Self {
+        self.request_id = Some(request_id.into());
+        self
+    }
+
+    /// The id of the request that was rejected, if it had one. Errors the manager receives from a
+    /// function are given the id of the request they answer, unless the function set one itself.
+    #[must_use]
+    pub fn request_id(&self) -> Option<&str> {
+        self.request_id.as_deref()
+    }
+
+    /// This error with the id of `request`, unless it already has one.
+    pub(crate) fn or_request_id_of(self, request: &ComputeRequest) -> Self {
+        match request.request_id() {
+            Some(request_id) if self.request_id.is_none() => self.with_request_id(request_id),
+            _ => self,
+        }
+    }
 }
 
 impl fmt::Display for BadRequestError {
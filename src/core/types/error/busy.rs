@@ -0,0 +1,16 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Returned by the non-blocking `try_*` methods of the
+/// [`ComputeFunctionManager`](crate::ComputeFunctionManager) when they would have had to wait,
+/// either for a lock or for room in the request queue. Callers that prefer shedding load to
+/// queueing it should treat this as "try again later".
+#[derive(Debug, Error, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[error("Compute function manager is busy, try again later")]
+pub struct Busy;
@@ -14,6 +14,12 @@ use thiserror::Error;
 pub enum LoadingError {
     /// The path provided was invalid.
     BadPath(String),
+    /// The path provided was relative, all library paths must be absolute. Contains the path as it
+    /// was given, so callers can resolve it against a base directory and try again.
+    PathNotAbsolute(String),
+    /// The path provided was relative and resolving it against the base directory led outside of
+    /// it, e.g. through `..` or a symlink.
+    PathOutsideBaseDir(String),
     /// No library could be found **or there were insufficient permissions to access it**.
     PathNotFound(String),
     /// The library file was found but could not be loaded.
@@ -22,8 +28,17 @@ pub enum LoadingError {
     ConstructorLoadFailure(String),
     /// The `_plugin_create` function returned a null pointer.
     ConstructorCallFailure,
+    /// The library was built against another [`PLUGIN_ABI_VERSION`](crate::PLUGIN_ABI_VERSION).
+    /// Libraries that don't export `_plugin_abi_version` are reported as version `0`.
+    AbiMismatch { expected: u32, found: u32 },
     /// The plugin manager already contains an instance of the given plugin.
     FunctionNameCollision(String),
+    /// The function can't be reloaded because it isn't loaded, or wasn't loaded from a library.
+    NotReloadable(String),
+    /// The function was unloaded while it was being reloaded, so the reload was abandoned.
+    ReloadCancelled(String),
+    /// The library was not loaded because the time budget for loading a batch of libraries ran out.
+    LoadTimeout(String),
 }
 
 impl LoadingError {
@@ -51,20 +66,60 @@ impl LoadingError {
         Self::BadPath(err.to_string())
     }
 
+    /// Create a [`LoadingError::PathNotAbsolute`] for the given path.
+    #[must_use]
+    pub fn path_not_absolute<S: ToString>(path: &S) -> Self {
+        Self::PathNotAbsolute(path.to_string())
+    }
+
+    /// Create a [`LoadingError::PathOutsideBaseDir`] for the given path.
+    #[must_use]
+    pub fn path_outside_base_dir<S: ToString>(path: &S) -> Self {
+        Self::PathOutsideBaseDir(path.to_string())
+    }
+
     /// Create a [`LoadingError::PathNotFound`] with the given message.
     #[must_use]
     pub fn path_not_found<S: ToString>(err: &S) -> Self {
         Self::PathNotFound(err.to_string())
     }
 
+    /// Create a [`LoadingError::NotReloadable`] for the given function name.
+    #[must_use]
+    pub fn not_reloadable<S: ToString>(name: &S) -> Self {
+        Self::NotReloadable(name.to_string())
+    }
+
+    /// Create a [`LoadingError::ReloadCancelled`] for the given function name.
+    #[must_use]
+    pub fn reload_cancelled<S: ToString>(name: &S) -> Self {
+        Self::ReloadCancelled(name.to_string())
+    }
+
+    /// Create a [`LoadingError::LoadTimeout`] for the given library path.
+    #[must_use]
+    pub fn load_timeout<S: ToString>(path: &S) -> Self {
+        Self::LoadTimeout(path.to_string())
+    }
+
     /// Create a [`LoadingError::ConstructorCallFailure`] with the given message.
     #[must_use]
     pub const fn ctor_call_failure() -> Self {
         Self::ConstructorCallFailure
     }
 
+    /// Create a [`LoadingError::AbiMismatch`] for a library built against ABI version `found`.
+    #[must_use]
+    pub const fn abi_mismatch(found: u32) -> Self {
+        Self::AbiMismatch {
+            expected: crate::core::PLUGIN_ABI_VERSION,
+            found,
+        }
+    }
+
     /// Gets the message contained in this [`LoadingError`], unless it is a
-    /// [`LoadingError::ConstructorCallFailure`], in which case it returns None.
+    /// [`LoadingError::ConstructorCallFailure`] or [`LoadingError::AbiMismatch`], in which case it
+    /// returns None.
     #[must_use]
     pub fn inner_msg(&self) -> Option<&str> {
         match self {
@@ -72,8 +127,13 @@ impl LoadingError {
             | Self::PathNotFound(s)
             | Self::ConstructorLoadFailure(s)
             | Self::FunctionNameCollision(s)
-            | Self::BadPath(s) => Some(s),
-            Self::ConstructorCallFailure => None,
+            | Self::BadPath(s)
+            | Self::PathNotAbsolute(s)
+            | Self::PathOutsideBaseDir(s)
+            | Self::NotReloadable(s)
+            | Self::ReloadCancelled(s)
+            | Self::LoadTimeout(s) => Some(s),
+            Self::ConstructorCallFailure | Self::AbiMismatch { .. } => None,
         }
     }
 
@@ -85,8 +145,13 @@ impl LoadingError {
             | Self::PathNotFound(s)
             | Self::ConstructorLoadFailure(s)
             | Self::FunctionNameCollision(s)
-            | Self::BadPath(s) => !s.is_empty(),
-            Self::ConstructorCallFailure => false,
+            | Self::BadPath(s)
+            | Self::PathNotAbsolute(s)
+            | Self::PathOutsideBaseDir(s)
+            | Self::NotReloadable(s)
+            | Self::ReloadCancelled(s)
+            | Self::LoadTimeout(s) => !s.is_empty(),
+            Self::ConstructorCallFailure | Self::AbiMismatch { .. } => false,
         }
     }
 }
@@ -108,11 +173,37 @@ impl fmt::Display for LoadingError {
             Self::ConstructorCallFailure => {
                 write!(f, "ComputeFunction construction failed (returned null ptr)")
             }
+            Self::AbiMismatch { expected, found } => write!(
+                f,
+                "ComputeFunction Library was built against plugin ABI version {} (expected {})",
+                found, expected
+            ),
             Self::PathNotFound(msg) => write!(f, "No library found at path: {}", msg),
-            Self::BadPath(msg) => write!(
+            Self::BadPath(msg) => write!(f, "Given path is badly formed: {}", msg),
+            Self::PathNotAbsolute(path) => write!(
                 f,
-                "Given path is badly formed (all paths must be absolute): {}",
-                msg
+                "Given path `{}` is not absolute (all paths must be absolute)",
+                path
+            ),
+            Self::PathOutsideBaseDir(path) => write!(
+                f,
+                "Given path `{}` resolves to outside of the base directory",
+                path
+            ),
+            Self::NotReloadable(name) => write!(
+                f,
+                "ComputeFunction `{}` was not loaded from a library and can't be reloaded",
+                name
+            ),
+            Self::LoadTimeout(path) => write!(
+                f,
+                "ComputeFunction Library `{}` was skipped because the load time budget ran out",
+                path
+            ),
+            Self::ReloadCancelled(name) => write!(
+                f,
+                "Reload of ComputeFunction `{}` was cancelled because it is being unloaded",
+                name
             ),
         }
     }
@@ -7,11 +7,13 @@
 mod app_error;
 mod bad_input;
 mod bad_req;
+mod busy;
 mod loading;
 mod unloading;
 
 pub use app_error::{AppError, AppResult};
 pub use bad_input::BadInputError;
 pub use bad_req::BadRequestError;
+pub use busy::Busy;
 pub use loading::LoadingError;
 pub use unloading::UnloadingError;
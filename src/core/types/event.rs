@@ -0,0 +1,57 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::core::types::LoadingError;
+
+/// Something that happened to a [`ComputeFunctionManager`](crate::ComputeFunctionManager).
+///
+/// Events are reported to the sink set with
+/// [`ComputeFunctionManager::set_event_sink`](crate::ComputeFunctionManager::set_event_sink).
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(not(feature = "pascal-case-wire"), serde(rename_all = "snake_case"))]
+pub enum ManagerEvent {
+    /// A plugin library was loaded and its function registered as `name`.
+    Loaded { name: String, path: String },
+    /// The function `name` was unloaded.
+    Unloaded { name: String },
+    /// Loading the plugin library at `path` failed.
+    LoadFailed { path: String, error: LoadingError },
+    /// A request for `target` was handled, successfully or not. Requests rejected before they were
+    /// dispatched (invalid, unauthorized, replayed or shed) are reported as well.
+    RequestDispatched {
+        target: String,
+        /// The status of the function's response, or of the error that took its place.
+        status: u16,
+        /// The [`AppError::code`](crate::AppError::code) if the request failed in the manager
+        /// rather than with an error response from the function.
+        error_code: Option<&'static str>,
+        elapsed: Duration,
+    },
+}
+
+/// The callback set with
+/// [`ComputeFunctionManager::set_event_sink`](crate::ComputeFunctionManager::set_event_sink).
+pub struct EventSink(Box<dyn Fn(ManagerEvent) + Send + Sync>);
+
+impl EventSink {
+    pub fn new(sink: impl Fn(ManagerEvent) + Send + Sync + 'static) -> Self {
+        Self(Box::new(sink))
+    }
+
+    pub fn emit(&self, event: ManagerEvent) {
+        (self.0)(event);
+    }
+}
+
+impl std::fmt::Debug for EventSink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("EventSink")
+    }
+}
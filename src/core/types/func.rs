@@ -8,7 +8,10 @@ use std::any::Any;
 
 use async_trait::async_trait;
 
-use crate::core::types::{BadRequestError, ComputeRequest, ComputeResponse};
+use crate::core::types::{
+    BadRequestError, BodyStream, ComputeRequest, ComputeResponse, HealthStatus, InputLimits,
+    ResponseSink, TargetComputeFunc,
+};
 
 #[async_trait]
 /// A plugin which allows you to add extra functionality to the REST client.
@@ -22,6 +25,46 @@ pub trait ComputeFunction: Any + Send + Sync + std::fmt::Debug {
     /// A callback fired immediately before the plugin is unloaded. Use this if
     /// you need to do any cleanup.
     fn on_plugin_unload(&self) {}
+    /// The version of the function, purely informational.
+    fn version(&self) -> Option<&'static str> {
+        None
+    }
+    /// A short, human readable, description of what the function does.
+    fn description(&self) -> Option<&'static str> {
+        None
+    }
+    /// An optional JSON Schema describing the data this function accepts. The manager checks the
+    /// data of every request against it and rejects those that don't match with a
+    /// [`BadRequestError`] before they reach [`ComputeFunction::receive_request`], so functions
+    /// declaring one don't need to validate the shape of their input themselves. It is also shown
+    /// in the function's description and listing.
+    fn input_schema(&self) -> Option<serde_json::Value> {
+        None
+    }
+    /// Example request data for the function, for documentation.
+    fn examples(&self) -> Vec<serde_json::Value> {
+        Vec::new()
+    }
+    /// An optional JSON Schema describing the data this function responds with. When it is
+    /// declared, the manager checks every response against it in debug builds (or always, when
+    /// strict output validation is enabled) so that plugins breaking their own contract are
+    /// caught early.
+    fn output_schema(&self) -> Option<serde_json::Value> {
+        None
+    }
+    /// Limits on the shape of the request data this function accepts. The manager rejects requests
+    /// exceeding them with a [`BadRequestError`] before they reach
+    /// [`ComputeFunction::receive_request`], in addition to any limits configured on the manager.
+    fn input_limits(&self) -> Option<InputLimits> {
+        None
+    }
+    /// Probe whether the function is able to handle requests, for orchestration readiness checks
+    /// (see [`ComputeFunctionManager::health_check`](crate::ComputeFunctionManager::health_check)).
+    /// Functions that depend on something else, like a network service, can override this to check
+    /// on it; everything else is always [`HealthStatus::Ok`].
+    async fn health(&self) -> HealthStatus {
+        HealthStatus::Ok
+    }
     /// Other than `name`, this is the only function that **must** be implemented.
     /// It takes a **non-mutable** self to encourage interior mutability and thread-safety.
     /// See the [`ComputeRequest`] documentation for more information on the input.
@@ -32,6 +75,32 @@ pub trait ComputeFunction: Any + Send + Sync + std::fmt::Debug {
         &self,
         request: &ComputeRequest,
     ) -> Result<ComputeResponse, BadRequestError>;
+    /// Receive a raw, unbuffered, request body for functions that ingest more data than should be
+    /// held in memory at once. Functions that don't override this reject streamed bodies.
+    async fn receive_body_stream(
+        &self,
+        target: &TargetComputeFunc,
+        _body: BodyStream,
+    ) -> Result<ComputeResponse, BadRequestError> {
+        Err(BadRequestError::without_request(
+            self.name(),
+            &format!("'{}' does not accept streamed request bodies", target),
+        ))
+    }
+    /// Handle a request whose output is sent as a stream of frames instead of a single response,
+    /// for functions that report progress while they work (see the `/ws` route of the axum
+    /// server). Send each frame to `sink` as it becomes available; once this returns the stream is
+    /// over. Functions that don't override this simply emit one frame, the result of
+    /// [`ComputeFunction::receive_request`].
+    async fn receive_streaming(
+        &self,
+        request: &ComputeRequest,
+        sink: ResponseSink,
+    ) -> Result<(), BadRequestError> {
+        let response = self.receive_request(request).await?;
+        sink.send(response).await;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -57,7 +126,7 @@ mod tests {
             &self,
             _request: &ComputeRequest,
         ) -> Result<ComputeResponse, BadRequestError> {
-            Ok(ComputeResponse::json_ok(
+            Ok(ComputeResponse::from_data(
                 json!({ "message": "Hello, World!" }),
             ))
         }
@@ -77,7 +146,56 @@ mod tests {
                 let mut lock = self.logs.lock().await;
                 lock.push(request.data().clone());
             }
-            Ok(ComputeResponse::ok())
+            Ok(ComputeResponse::empty())
         }
     }
+
+    fn request(target: &str, data: serde_json::Value) -> ComputeRequest {
+        ComputeRequest::new(TargetComputeFunc::new(target.to_string()), data)
+    }
+
+    #[tokio::test]
+    async fn plugins_answer_requests() {
+        let response = FakePlugin
+            .receive_request(&request("FakePlugin", json!(null)))
+            .await
+            .unwrap();
+        assert_eq!(response.data(), Some(json!({ "message": "Hello, World!" })));
+
+        let logger = ShittyCloudLogger::default();
+        for line in ["first", "second"] {
+            let response = logger
+                .receive_request(&request("ShittyCloudLogger", json!(line)))
+                .await
+                .unwrap();
+            assert!(response.data().is_none());
+        }
+        assert_eq!(
+            *logger.logs.lock().await,
+            vec![json!("first"), json!("second")]
+        );
+    }
+
+    #[tokio::test]
+    async fn default_streaming_emits_the_single_response() {
+        let (sink, mut frames) = ResponseSink::channel(1);
+        FakePlugin
+            .receive_streaming(&request("FakePlugin", json!(null)), sink)
+            .await
+            .unwrap();
+
+        let frame = frames.recv().await.unwrap();
+        assert_eq!(frame.data(), Some(json!({ "message": "Hello, World!" })));
+        assert!(frames.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn streamed_bodies_are_rejected_by_default() {
+        let body =
+            BodyStream::from_stream(futures_util::stream::empty::<Result<Vec<u8>, String>>());
+        let result = FakePlugin
+            .receive_body_stream(&TargetComputeFunc::new("FakePlugin".to_string()), body)
+            .await;
+        assert!(result.is_err());
+    }
 }
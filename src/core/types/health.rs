@@ -0,0 +1,34 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use serde::{Deserialize, Serialize};
+
+/// How a [`ComputeFunction`](crate::ComputeFunction) reports it is doing, see
+/// [`ComputeFunction::health`](crate::ComputeFunction::health).
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(not(feature = "pascal-case-wire"), serde(rename_all = "snake_case"))]
+pub enum HealthStatus {
+    /// The function is ready to handle requests.
+    Ok,
+    /// The function still handles requests, but not as well as it should, for the given reason.
+    Degraded(String),
+    /// The function can't handle requests right now, for the given reason.
+    Unhealthy(String),
+}
+
+impl HealthStatus {
+    /// Whether the function can handle requests, i.e. it is not [`HealthStatus::Unhealthy`].
+    #[must_use]
+    pub const fn is_ready(&self) -> bool {
+        !matches!(self, Self::Unhealthy(_))
+    }
+}
+
+impl Default for HealthStatus {
+    fn default() -> Self {
+        Self::Ok
+    }
+}
@@ -6,11 +6,138 @@
 
 use serde::{Deserialize, Serialize};
 
-use crate::core::types::{AddFunctionRequest, ComputeRequest, RemoveFunctionRequest};
+use crate::core::types::{
+    AddFunctionRequest, BadInputError, ComputeRequest, RemoveFunctionRequest, RequestContext,
+};
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
+#[cfg_attr(not(feature = "pascal-case-wire"), serde(rename_all = "snake_case"))]
 pub enum AppInput {
     AddComputeFunction(AddFunctionRequest),
     RemoveComputeFunction(RemoveFunctionRequest),
     Execute(ComputeRequest),
+    /// List every loaded function, see
+    /// [`ComputeFunctionManager::list_functions`](crate::ComputeFunctionManager::list_functions).
+    ListFunctions,
+    /// Check on the health of every loaded function, see
+    /// [`ComputeFunctionManager::health_check`](crate::ComputeFunctionManager::health_check).
+    HealthCheck,
+}
+
+impl AppInput {
+    /// Parse and validate untrusted JSON into an [`AppInput`]. This is how every server parses the
+    /// body of a `POST /`.
+    ///
+    /// This never panics, no matter what `bytes` contains, which is checked by the `app_input`
+    /// fuzz target in `fuzz/`.
+    ///
+    /// ## Errors
+    /// A [`BadInputError`] if `bytes` is not a valid [`AppInput`], or if the parsed input fails
+    /// validation (see [`ComputeRequest::validate`]).
+    pub fn from_json_slice(bytes: &[u8]) -> Result<Self, BadInputError> {
+        let input: Self = serde_json::from_slice(bytes)
+            .map_err(|e| BadInputError::without_input(&format!("Malformed input: {}", e)))?;
+        input.validate()?;
+        Ok(input)
+    }
+
+    /// Check that the input makes sense beyond being well-formed.
+    ///
+    /// ## Errors
+    /// A [`BadInputError`] describing the first problem found.
+    pub fn validate(&self) -> Result<(), BadInputError> {
+        match self {
+            Self::AddComputeFunction(add) if add.lib_path().trim().is_empty() => Err(
+                BadInputError::new("Library path must not be empty", self.clone()),
+            ),
+            Self::Execute(req) => req
+                .validate()
+                .map_err(|e| BadInputError::new(e.message(), self.clone())),
+            Self::AddComputeFunction(_)
+            | Self::RemoveComputeFunction(_)
+            | Self::ListFunctions
+            | Self::HealthCheck => Ok(()),
+        }
+    }
+
+    /// Attach the given [`RequestContext`] to the wrapped [`ComputeRequest`], if there is one.
+    pub fn set_context(&mut self, context: RequestContext) {
+        if let Self::Execute(req) = self {
+            req.set_context(context);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(not(feature = "pascal-case-wire"))]
+    #[test]
+    fn parses_valid_input() {
+        let input = AppInput::from_json_slice(
+            br#"{"execute":{"target":"logger","data":"hi","priority":0}}"#,
+        )
+        .unwrap();
+        assert!(matches!(input, AppInput::Execute(req) if req.target().name() == "logger"));
+    }
+
+    #[test]
+    fn malformed_input_has_no_attached_input() {
+        let err = AppInput::from_json_slice(b"{\"execute\":").unwrap_err();
+        assert!(err.message().starts_with("Malformed input"));
+        assert!(err.input().is_none());
+    }
+
+    #[cfg(not(feature = "pascal-case-wire"))]
+    #[test]
+    fn invalid_input_keeps_the_parsed_input() {
+        let err = AppInput::from_json_slice(br#"{"add_compute_function":"  "}"#).unwrap_err();
+        assert!(matches!(err.input(), Some(AppInput::AddComputeFunction(_))));
+    }
+
+    #[test]
+    fn fuzz_corpus_never_panics() {
+        let corpus = concat!(env!("CARGO_MANIFEST_DIR"), "/fuzz/corpus/app_input");
+        for entry in std::fs::read_dir(corpus).unwrap() {
+            let bytes = std::fs::read(entry.unwrap().path()).unwrap();
+            let _ = AppInput::from_json_slice(&bytes);
+        }
+    }
+
+    #[cfg(not(feature = "pascal-case-wire"))]
+    #[test]
+    fn uses_snake_case_variant_names() {
+        let input = AppInput::RemoveComputeFunction(RemoveFunctionRequest::new(
+            crate::TargetComputeFunc::new("logger".to_string()),
+        ));
+
+        let wire = serde_json::to_value(&input).unwrap();
+        assert_eq!(
+            wire,
+            serde_json::json!({ "remove_compute_function": "logger" })
+        );
+        let back: AppInput = serde_json::from_value(wire).unwrap();
+        assert!(
+            matches!(back, AppInput::RemoveComputeFunction(rm) if rm.target().name() == "logger")
+        );
+    }
+
+    #[cfg(not(feature = "pascal-case-wire"))]
+    #[test]
+    fn parses_list_functions_with_or_without_a_value() {
+        for wire in [&br#""list_functions""#[..], br#"{"list_functions":null}"#] {
+            let input = AppInput::from_json_slice(wire).unwrap();
+            assert!(matches!(input, AppInput::ListFunctions));
+        }
+    }
+
+    #[cfg(feature = "pascal-case-wire")]
+    #[test]
+    fn uses_pascal_case_variant_names() {
+        let input = AppInput::AddComputeFunction(AddFunctionRequest::new("/lib.so".to_string()));
+
+        let wire = serde_json::to_value(&input).unwrap();
+        assert_eq!(wire, serde_json::json!({ "AddComputeFunction": "/lib.so" }));
+    }
 }
@@ -0,0 +1,130 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use serde_json::Value as JsonValue;
+
+/// Limits on the shape of request data, checked before the data ever reaches a function.
+///
+/// This keeps a request with thousands of keys or a huge string from making a function do an
+/// unreasonable amount of work. Limits apply at every level of nesting, and the keys of objects count as strings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputLimits {
+    max_object_keys: usize,
+    max_string_len: usize,
+}
+
+impl Default for InputLimits {
+    fn default() -> Self {
+        Self::UNLIMITED
+    }
+}
+
+impl InputLimits {
+    /// Limits that accept everything.
+    pub const UNLIMITED: Self = Self::new(usize::MAX, usize::MAX);
+
+    /// Create new [`InputLimits`].
+    ///
+    /// ## Arguments
+    /// - `max_object_keys` - The maximum number of keys any single object may have
+    /// - `max_string_len` - The maximum length, in bytes, of any single string
+    #[must_use]
+    pub const fn new(max_object_keys: usize, max_string_len: usize) -> Self {
+        Self {
+            max_object_keys,
+            max_string_len,
+        }
+    }
+
+    /// The maximum number of keys any single object may have.
+    #[must_use]
+    pub const fn max_object_keys(&self) -> usize {
+        self.max_object_keys
+    }
+
+    /// The maximum length, in bytes, of any single string.
+    #[must_use]
+    pub const fn max_string_len(&self) -> usize {
+        self.max_string_len
+    }
+
+    /// Check `data` against these limits.
+    ///
+    /// ## Errors
+    /// A message describing the first limit that `data` exceeds.
+    pub fn check(&self, data: &JsonValue) -> Result<(), String> {
+        // Walk the value with an explicit stack so deeply nested input can't overflow ours.
+        let mut pending = vec![data];
+        while let Some(value) = pending.pop() {
+            match value {
+                JsonValue::String(s) => self.check_string(s)?,
+                JsonValue::Array(items) => pending.extend(items),
+                JsonValue::Object(map) => {
+                    if map.len() > self.max_object_keys {
+                        return Err(format!(
+                            "Object has {} keys, the limit is {}",
+                            map.len(),
+                            self.max_object_keys
+                        ));
+                    }
+                    for (key, value) in map {
+                        self.check_string(key)?;
+                        pending.push(value);
+                    }
+                }
+                JsonValue::Null | JsonValue::Bool(_) | JsonValue::Number(_) => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_string(&self, s: &str) -> Result<(), String> {
+        if s.len() > self.max_string_len {
+            return Err(format!(
+                "String is {} bytes long, the limit is {}",
+                s.len(),
+                self.max_string_len
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn rejects_objects_with_too_many_keys() {
+        let limits = InputLimits::new(2, 64);
+
+        assert!(limits.check(&json!({ "a": 1, "b": 2 })).is_ok());
+        let err = limits
+            .check(&json!({ "nested": { "a": 1, "b": 2, "c": 3 } }))
+            .unwrap_err();
+        assert_eq!(err, "Object has 3 keys, the limit is 2");
+    }
+
+    #[test]
+    fn rejects_strings_that_are_too_long() {
+        let limits = InputLimits::new(8, 4);
+
+        assert!(limits.check(&json!(["abcd"])).is_ok());
+        assert!(limits.check(&json!(["abcde"])).is_err());
+        assert!(limits.check(&json!({ "abcde": null })).is_err());
+    }
+
+    #[test]
+    fn unlimited_accepts_everything() {
+        let keys: serde_json::Map<_, _> = (0..1_000).map(|i| (i.to_string(), json!(i))).collect();
+        assert!(InputLimits::UNLIMITED
+            .check(&JsonValue::Object(keys))
+            .is_ok());
+    }
+}
@@ -0,0 +1,141 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::time::Duration;
+
+use serde_json::{json, Value as JsonValue};
+
+/// Name of the request header clients send to ask for [`ExecutionMeta`] in the response.
+pub const META_REQUEST_HEADER: &str = "x-compute-meta";
+/// Name of the request header carrying the single use nonce checked by replay protection.
+pub const NONCE_HEADER: &str = "x-compute-nonce";
+/// Name of the request header carrying the id used to correlate a request across log lines and
+/// errors. Requests without one are given a fresh UUID.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+/// Response header naming the function that handled the request.
+pub const FUNCTION_HEADER: &str = "x-compute-function";
+/// Response header holding how long the request took, in milliseconds.
+pub const DURATION_HEADER: &str = "x-compute-duration-ms";
+/// Response header holding the [`CacheStatus`] of the request.
+pub const CACHE_HEADER: &str = "x-compute-cache";
+
+/// Whether a response was served from a cache.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum CacheStatus {
+    Hit,
+    Miss,
+    /// The request never went through a cache.
+    Bypass,
+}
+
+impl CacheStatus {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Hit => "hit",
+            Self::Miss => "miss",
+            Self::Bypass => "bypass",
+        }
+    }
+}
+
+impl std::fmt::Display for CacheStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// How a client asked to receive [`ExecutionMeta`], taken from the [`META_REQUEST_HEADER`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum MetaMode {
+    /// No metadata is attached, the default when the header is missing.
+    Off,
+    /// Metadata is attached as `X-Compute-*` response headers.
+    Headers,
+    /// Metadata is attached as headers and also as a `_meta` block in JSON object responses.
+    Body,
+}
+
+impl Default for MetaMode {
+    fn default() -> Self {
+        Self::Off
+    }
+}
+
+impl MetaMode {
+    /// Parse the value of the [`META_REQUEST_HEADER`]. `body` asks for the `_meta` block, any other
+    /// value other than `0`, `off` or `false` asks for headers only.
+    #[must_use]
+    pub fn from_header(value: Option<&str>) -> Self {
+        match value.map(|v| v.trim().to_ascii_lowercase()) {
+            None => Self::Off,
+            Some(v) if v == "body" => Self::Body,
+            Some(v) if v == "0" || v == "off" || v == "false" => Self::Off,
+            Some(_) => Self::Headers,
+        }
+    }
+}
+
+/// Metadata describing how a request was executed, returned alongside (never inside) the
+/// response data so it can help with debugging without changing the response itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecutionMeta {
+    function: String,
+    duration: Duration,
+    cache: CacheStatus,
+}
+
+impl ExecutionMeta {
+    /// Create new [`ExecutionMeta`].
+    ///
+    /// ## Arguments
+    /// - `function` - The name of the function that handled the request
+    /// - `duration` - How long handling the request took
+    /// - `cache` - Whether the response came from a cache
+    #[must_use]
+    pub fn new(function: &str, duration: Duration, cache: CacheStatus) -> Self {
+        Self {
+            function: function.to_string(),
+            duration,
+            cache,
+        }
+    }
+
+    #[must_use]
+    pub fn function(&self) -> &str {
+        &self.function
+    }
+
+    #[must_use]
+    pub const fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    #[must_use]
+    pub const fn cache(&self) -> CacheStatus {
+        self.cache
+    }
+
+    /// The response headers, as `(name, value)` pairs, that carry this metadata.
+    #[must_use]
+    pub fn headers(&self) -> [(&'static str, String); 3] {
+        [
+            (FUNCTION_HEADER, self.function.clone()),
+            (DURATION_HEADER, self.duration.as_millis().to_string()),
+            (CACHE_HEADER, self.cache.to_string()),
+        ]
+    }
+
+    /// This metadata as the JSON object used for the `_meta` block.
+    #[must_use]
+    pub fn to_json(&self) -> JsonValue {
+        json!({
+            "function": self.function,
+            "duration_ms": self.duration.as_millis(),
+            "cache": self.cache.as_str(),
+        })
+    }
+}
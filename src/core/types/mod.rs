@@ -4,22 +4,44 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+mod auth;
+mod context;
+mod description;
 mod error;
+mod event;
 mod func;
+mod health;
 mod input;
+mod limits;
+mod meta;
+mod outcome;
 mod output;
 mod req;
 mod resp;
 mod status;
+mod stream;
 mod targets;
 
+pub use auth::AuthPolicy;
+pub use context::RequestContext;
+pub use description::{
+    CallCounts, FunctionDescription, FunctionInfo, FunctionOrigin, FunctionStats,
+};
 pub use error::{
-    AppError, AppResult, BadInputError, BadRequestError, LoadingError, UnloadingError,
+    AppError, AppResult, BadInputError, BadRequestError, Busy, LoadingError, UnloadingError,
 };
+pub use event::{EventSink, ManagerEvent};
 pub use func::ComputeFunction;
+pub use health::HealthStatus;
 pub use input::AppInput;
+pub use limits::InputLimits;
+pub use meta::{
+    CacheStatus, ExecutionMeta, MetaMode, META_REQUEST_HEADER, NONCE_HEADER, REQUEST_ID_HEADER,
+};
+pub use outcome::LoadOutcome;
 pub use output::AppOutput;
 pub use req::{AddFunctionRequest, ComputeRequest, RemoveFunctionRequest};
 pub use resp::{ComputeJsonResponse, ComputeResponse};
 pub use status::*;
+pub use stream::{BodyStream, ResponseSink};
 pub use targets::TargetComputeFunc;
@@ -0,0 +1,39 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+/// What came of successfully asking a [`ComputeFunctionManager`](crate::ComputeFunctionManager) to
+/// load a function. Both variants hold the name the function is registered under.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoadOutcome {
+    /// The function was not loaded before and now is.
+    Loaded(String),
+    /// The function was already loaded (from the same builtin or library), so nothing changed.
+    AlreadyPresent(String),
+}
+
+impl LoadOutcome {
+    /// The name the function is registered under.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Loaded(name) | Self::AlreadyPresent(name) => name,
+        }
+    }
+
+    /// Consume the outcome and return the name the function is registered under.
+    #[must_use]
+    pub fn into_name(self) -> String {
+        match self {
+            Self::Loaded(name) | Self::AlreadyPresent(name) => name,
+        }
+    }
+
+    /// Whether the function was newly loaded.
+    #[must_use]
+    pub const fn is_loaded(&self) -> bool {
+        matches!(self, Self::Loaded(_))
+    }
+}
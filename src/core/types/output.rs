@@ -4,16 +4,27 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+use std::collections::HashMap;
+
+#[cfg(feature = "hyper")]
 use hyper::StatusCode;
 use serde::{Deserialize, Serialize};
 
-use crate::core::types::{ComputeResponse, GenericStatusCode};
+use crate::core::types::{ComputeResponse, FunctionInfo, GenericStatusCode, HealthStatus};
 
 #[derive(Debug, Deserialize, Serialize)]
+#[cfg_attr(not(feature = "pascal-case-wire"), serde(rename_all = "snake_case"))]
 pub enum AppOutput {
     ComputeResponse(ComputeResponse),
-    AddFunctionSuccess,
+    /// A function was added (or was already loaded from the same library), described by its
+    /// [`FunctionInfo`] so clients learn the name to target.
+    AddFunctionSuccess(FunctionInfo),
     RemoveFunctionSuccess,
+    /// Every loaded function, in answer to an [`AppInput::ListFunctions`](crate::AppInput::ListFunctions).
+    FunctionList(Vec<FunctionInfo>),
+    /// The health of every loaded function by name, in answer to an
+    /// [`AppInput::HealthCheck`](crate::AppInput::HealthCheck).
+    HealthReport(HashMap<String, HealthStatus>),
     // Other(String),
     Other {
         status: GenericStatusCode,
@@ -22,9 +33,9 @@ pub enum AppOutput {
 }
 
 impl AppOutput {
-    /// Create a new [`AppOutput::AddFunctionSuccess`].
-    pub const fn add_function_success() -> Self {
-        Self::AddFunctionSuccess
+    /// Create a new [`AppOutput::AddFunctionSuccess`] for the function described by `info`.
+    pub const fn add_function_success(info: FunctionInfo) -> Self {
+        Self::AddFunctionSuccess(info)
     }
 
     /// Create a new [`AppOutput::RemoveFunctionSuccess`].
@@ -46,10 +57,15 @@ impl AppOutput {
     }
 
     /// Gets the http status code for this output.
+    #[cfg(feature = "hyper")]
     pub fn status(&self) -> hyper::StatusCode {
         match self {
-            Self::AddFunctionSuccess => StatusCode::CREATED,
-            Self::RemoveFunctionSuccess => StatusCode::OK,
+            Self::AddFunctionSuccess(_) => GenericStatusCode::Created.to_status_code(),
+            Self::RemoveFunctionSuccess | Self::FunctionList(_) => StatusCode::OK,
+            Self::HealthReport(report) if report.values().all(HealthStatus::is_ready) => {
+                StatusCode::OK
+            }
+            Self::HealthReport(_) => StatusCode::SERVICE_UNAVAILABLE,
             Self::ComputeResponse(cr) => cr.status().to_status_code(),
             Self::Other { status, .. } => (*status).to_status_code(),
         }
@@ -61,19 +77,25 @@ impl AppOutput {
 
         match self {
             Self::ComputeResponse(cr) => cr.data(),
+            Self::AddFunctionSuccess(info) => Some(json!(info)),
+            Self::FunctionList(functions) => Some(json!(functions)),
+            Self::HealthReport(report) => Some(json!(report)),
             Self::Other { message, .. } => message.as_ref().map(|s| json!(s)),
-            Self::AddFunctionSuccess | Self::RemoveFunctionSuccess => None,
+            Self::RemoveFunctionSuccess => None,
         }
     }
 
-    /// FIXME: Change this to be feature gated (or delete it if a different backend is chosen).
     /// Consume this [`AppOutput`] and converts it to a [`warp`] [`warp::reply::Response`], fulfilling
     /// the [`warp`] trait [`warp::Reply`], for convenient use in [`warp::Filter`]s.
+    #[cfg(feature = "warp")]
     pub fn into_warp(self) -> warp::reply::Response {
         use warp::{
             reply::{json, with_status},
             Reply,
         };
+        if let Self::ComputeResponse(response) = self {
+            return response.into_warp();
+        }
         let code = self.status();
         match self.data() {
             Some(d) => with_status(json(&d).into_response(), code).into_response(),
@@ -81,11 +103,14 @@ impl AppOutput {
         }
     }
 
-    /// FIXME: Change this to be feature gated (or delete it if a different backend is chosen).
     /// Consume this [`AppOutput`] and converts it to a [`axum`] [`axum::response::Response`].
+    #[cfg(feature = "axum")]
     pub fn into_axum(self) -> axum::response::Response {
         use axum::{response::IntoResponse, Json};
 
+        if let Self::ComputeResponse(response) = self {
+            return response.into_axum();
+        }
         let code = self.status();
         match self.data() {
             Some(s) => (code, Json(s)).into_response(),
@@ -94,14 +119,40 @@ impl AppOutput {
     }
 }
 
+#[cfg(feature = "axum")]
 impl axum::response::IntoResponse for AppOutput {
     fn into_response(self) -> axum::response::Response {
         self.into_axum()
     }
 }
 
+#[cfg(feature = "warp")]
 impl warp::Reply for AppOutput {
     fn into_response(self) -> warp::reply::Response {
         self.into_warp()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::types::FunctionOrigin;
+
+    #[cfg(not(feature = "pascal-case-wire"))]
+    #[test]
+    fn uses_snake_case_variant_names() {
+        let info = FunctionInfo::new("logger", FunctionOrigin::Builtin, chrono::Utc::now());
+        let output = AppOutput::add_function_success(info.clone());
+        assert_eq!(output.data().unwrap()["name"], "logger");
+        let wire = serde_json::to_value(output).unwrap();
+        assert_eq!(wire["add_function_success"]["name"], "logger");
+        assert_eq!(wire["add_function_success"]["origin"], "builtin");
+        assert!(matches!(
+            serde_json::from_value(wire).unwrap(),
+            AppOutput::AddFunctionSuccess(loaded) if loaded == info
+        ));
+
+        let wire = serde_json::to_value(AppOutput::remove_function_success()).unwrap();
+        assert_eq!(wire, serde_json::json!("remove_function_success"));
+    }
+}
@@ -4,25 +4,56 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 
-use crate::core::types::TargetComputeFunc;
+use crate::core::types::{BadRequestError, RequestContext, TargetComputeFunc};
 
-// TODO: While it is good that I have already extracted [`TargetComputeFunc`], I need to be
-//       a better job of handling input dispatch. The target needs to be parsed to get the
-//       basename, the extended path, and maybe even parameters and queries. The more options
-//       an implementer has, the better.
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct ComputeRequest {
     target: TargetComputeFunc,
     data: JsonValue,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    priority: Option<u8>,
+    #[serde(skip)]
+    context: RequestContext,
 }
 
 impl ComputeRequest {
+    /// The priority used for requests that do not specify one.
+    pub const DEFAULT_PRIORITY: u8 = 128;
+
     #[must_use]
     pub const fn new(target: TargetComputeFunc, data: JsonValue) -> Self {
-        Self { target, data }
+        Self {
+            target,
+            data,
+            priority: None,
+            context: RequestContext::new(),
+        }
+    }
+
+    /// Consume this [`ComputeRequest`] and return it with the given priority. Higher values are
+    /// served first when the manager is queueing requests.
+    #[must_use]
+    pub const fn with_priority(mut self, priority: u8) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+
+    /// Consume this [`ComputeRequest`] and return it with the given [`RequestContext`].
+    #[must_use]
+    pub fn with_context(mut self, context: RequestContext) -> Self {
+        self.context = context;
+        self
+    }
+
+    /// Replace the [`RequestContext`] of this request. Used by the servers to attach connection
+    /// information after the request body has been deserialized.
+    pub fn set_context(&mut self, context: RequestContext) {
+        self.context = context;
     }
 
     #[must_use]
@@ -34,6 +65,60 @@ impl ComputeRequest {
     pub const fn data(&self) -> &JsonValue {
         &self.data
     }
+
+    /// The sub-path of the target, see [`TargetComputeFunc::subpath`].
+    #[must_use]
+    pub fn subpath(&self) -> &str {
+        self.target.subpath()
+    }
+
+    /// The query of the target, see [`TargetComputeFunc::query`].
+    #[must_use]
+    pub fn query(&self) -> &HashMap<String, String> {
+        self.target.query()
+    }
+
+    /// Information about how this request arrived, such as the address of the client.
+    #[must_use]
+    pub const fn context(&self) -> &RequestContext {
+        &self.context
+    }
+
+    /// Check that this request is well formed enough to be dispatched, so that clients get a clear
+    /// error instead of a misleading [`AppError::TargetNotFound`](crate::AppError::TargetNotFound).
+    ///
+    /// ## Errors
+    /// - [`BadRequestError`] if the target's basename is empty or only whitespace
+    pub fn validate(&self) -> Result<(), BadRequestError> {
+        if self.target.basename().trim().is_empty() {
+            return Err(BadRequestError::new(
+                "ComputeRequest",
+                "Target must not be empty",
+                Some(self.clone()),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// The id used to correlate this request across log lines and errors, see
+    /// [`RequestContext::request_id`]. The servers take it from the [`REQUEST_ID_HEADER`] or make one
+    /// up, so every request that came in over HTTP has one.
+    ///
+    /// [`REQUEST_ID_HEADER`]: crate::core::types::REQUEST_ID_HEADER
+    #[must_use]
+    pub fn request_id(&self) -> Option<&str> {
+        self.context.request_id()
+    }
+
+    /// The priority of this request, or [`ComputeRequest::DEFAULT_PRIORITY`] if none was given.
+    #[must_use]
+    pub const fn priority(&self) -> u8 {
+        match self.priority {
+            Some(priority) => priority,
+            None => Self::DEFAULT_PRIORITY,
+        }
+    }
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -64,3 +149,31 @@ impl RemoveFunctionRequest {
         &self.0
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn request(target: &str) -> ComputeRequest {
+        ComputeRequest::new(TargetComputeFunc::new(target.to_string()), json!(null))
+    }
+
+    #[test]
+    fn validate_accepts_a_named_target() {
+        assert!(request("logger").validate().is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_an_empty_target() {
+        let err = request("").validate().unwrap_err();
+        assert_eq!(err.message(), "Target must not be empty");
+        assert!(err.has_request());
+    }
+
+    #[test]
+    fn validate_rejects_a_whitespace_target() {
+        assert!(request("  \t\n").validate().is_err());
+    }
+}
@@ -4,21 +4,69 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+use std::collections::HashMap;
+
+#[cfg(feature = "hyper")]
 use hyper::StatusCode;
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 
-use crate::core::types::GenericStatusCode;
+use crate::{
+    core::types::{BadRequestError, GenericStatusCode},
+    util::error_envelope,
+};
 
 #[derive(Deserialize, Serialize, Debug, Clone, Default)]
 pub struct ComputeJsonResponse {
     status: GenericStatusCode,
     data: JsonValue,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    headers: Option<HashMap<String, String>>,
 }
 
 impl ComputeJsonResponse {
     pub const fn new(status: GenericStatusCode, data: JsonValue) -> Self {
-        Self { status, data }
+        Self {
+            status,
+            data,
+            headers: None,
+        }
+    }
+
+    /// Send `name: value` along with this response, see [`ComputeResponse::with_header`].
+    #[must_use]
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        insert_header(&mut self.headers, name.into(), value.into());
+        self
+    }
+}
+
+/// Headers a function may not set on its response: those the HTTP layer manages itself
+/// (hop-by-hop headers and the framing of the body) and HTTP/2 pseudo-headers.
+const RESERVED_HEADERS: &[&str] = &[
+    "connection",
+    "content-length",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+/// Whether a function may set the header `name`, see [`RESERVED_HEADERS`].
+fn is_settable_header(name: &str) -> bool {
+    !name.starts_with(':')
+        && !RESERVED_HEADERS
+            .iter()
+            .any(|reserved| reserved.eq_ignore_ascii_case(name))
+}
+
+/// Add `name: value` to `headers`, unless `name` is a header functions can't set.
+fn insert_header(headers: &mut Option<HashMap<String, String>>, name: String, value: String) {
+    if is_settable_header(&name) {
+        headers.get_or_insert_with(HashMap::new).insert(name, value);
     }
 }
 
@@ -26,6 +74,41 @@ impl ComputeJsonResponse {
 pub enum ComputeResponse {
     NoContent(GenericStatusCode),
     Json(ComputeJsonResponse),
+    /// A response from a function that handled the request but failed to compute a result, with a
+    /// JSON body describing the failure.
+    Error(ComputeJsonResponse),
+    /// A response whose body is raw bytes rather than JSON, like an image, served as is with the
+    /// given `Content-Type`. The bytes are base64 encoded wherever the response itself is
+    /// serialized, e.g. in an [`AppOutput`](crate::core::types::AppOutput).
+    Binary {
+        status: GenericStatusCode,
+        content_type: String,
+        #[serde(with = "base64_bytes")]
+        bytes: Vec<u8>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        headers: Option<HashMap<String, String>>,
+    },
+    /// A response without a body that still sends headers, see
+    /// [`ComputeResponse::no_content_with_headers`].
+    HeadersOnly {
+        status: GenericStatusCode,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        headers: Option<HashMap<String, String>>,
+    },
+}
+
+/// (De)serializes bytes as a base64 string, for [`ComputeResponse::Binary`].
+mod base64_bytes {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&base64::encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        base64::decode(encoded).map_err(serde::de::Error::custom)
+    }
 }
 
 impl Default for ComputeResponse {
@@ -41,25 +124,139 @@ impl ComputeResponse {
         Self::NoContent(GenericStatusCode::Ok)
     }
 
+    /// Create a new [`ComputeResponse`] with status `Ok` and no data, the same as
+    /// [`ComputeResponse::ok`], for functions that have nothing to say.
+    #[must_use]
+    pub const fn empty() -> Self {
+        Self::ok()
+    }
+
     /// Create a new [`ComputeResponse`] with no content and the given status.
     #[must_use]
     pub const fn status_only(status: GenericStatusCode) -> Self {
         Self::NoContent(status)
     }
 
+    /// Create a new [`ComputeResponse`] with no content and the given status that sends `headers`
+    /// along, for functions that only have metadata to report, like a cache warmer answering a
+    /// `204` with the number of entries it warmed in a header. Headers that can't be set are
+    /// skipped, as with [`ComputeResponse::with_header`].
+    #[must_use]
+    pub fn no_content_with_headers<K, V>(
+        status: GenericStatusCode,
+        headers: impl IntoIterator<Item = (K, V)>,
+    ) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        headers.into_iter().fold(
+            Self::HeadersOnly {
+                status,
+                headers: None,
+            },
+            |response, (name, value)| response.with_header(name, value),
+        )
+    }
+
     /// Create a new [`ComputeResponse`] with status `Ok` and the given JSON data.
     #[must_use]
     pub const fn json_ok(data: JsonValue) -> Self {
-        Self::Json(ComputeJsonResponse {
-            status: GenericStatusCode::Ok,
-            data,
-        })
+        Self::Json(ComputeJsonResponse::new(GenericStatusCode::Ok, data))
+    }
+
+    /// Create a new [`ComputeResponse`] with status `Ok` and the given JSON data, the same as
+    /// [`ComputeResponse::json_ok`].
+    #[must_use]
+    pub const fn from_data(data: JsonValue) -> Self {
+        Self::json_ok(data)
     }
 
     /// Create a new [`ComputeResponse`] with the given status code and json data.
     #[must_use]
     pub const fn json(status: GenericStatusCode, data: JsonValue) -> Self {
-        Self::Json(ComputeJsonResponse { status, data })
+        Self::Json(ComputeJsonResponse::new(status, data))
+    }
+
+    /// Create a new [`ComputeResponse::Error`] with the given status code and the usual
+    /// [`error_envelope`] as its body, with `code` as a short, stable identifier for the kind of
+    /// error and `message` for humans. This is for functions that handled the request but want to
+    /// answer with a non-2xx status of their choosing, say a `422` when the data is well formed but
+    /// can't be processed, rather than failing with a [`BadRequestError`], which is always a `400`.
+    #[must_use]
+    pub fn error(status: GenericStatusCode, code: &str, message: &str) -> Self {
+        Self::Error(ComputeJsonResponse::new(
+            status,
+            error_envelope(status, code, message, None),
+        ))
+    }
+
+    /// Create a new [`ComputeResponse::Binary`] with the given status code, serving `bytes` as
+    /// `content_type`.
+    #[must_use]
+    pub fn binary(
+        status: GenericStatusCode,
+        content_type: impl Into<String>,
+        bytes: Vec<u8>,
+    ) -> Self {
+        Self::Binary {
+            status,
+            content_type: content_type.into(),
+            bytes,
+            headers: None,
+        }
+    }
+
+    /// Send `name: value` along with this response, replacing any value set for `name` before. This
+    /// is how a function sets headers like `Cache-Control` on its response. Pseudo-headers and
+    /// headers the HTTP layer manages itself, like `Connection` or `Content-Length`, are ignored,
+    /// as is everything on a [`ComputeResponse::NoContent`], which has nowhere to keep headers (see
+    /// [`ComputeResponse::no_content_with_headers`] for a body-less response that has).
+    #[must_use]
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        match &mut self {
+            Self::NoContent(_) => {}
+            Self::Json(json) | Self::Error(json) => {
+                insert_header(&mut json.headers, name.into(), value.into());
+            }
+            Self::Binary { headers, .. } | Self::HeadersOnly { headers, .. } => {
+                insert_header(headers, name.into(), value.into());
+            }
+        }
+        self
+    }
+
+    /// Combine the responses of several compute functions into a single `Json` response. The
+    /// data is an object keyed by function name, each entry holding that function's `status` and
+    /// `data` (`null` for `NoContent` responses). The aggregate status is the worst (numerically
+    /// highest) status among the responses, so a single failure is never masked by the others.
+    ///
+    /// ## Arguments
+    /// * `responses` - The function names paired with the response each one returned.
+    ///
+    /// ## Returns
+    /// The merged [`ComputeResponse`], which is `Ok` with an empty object if `responses` is empty.
+    #[must_use]
+    pub fn merge(responses: Vec<(String, Self)>) -> Self {
+        let mut status = GenericStatusCode::Ok;
+        let mut merged = serde_json::Map::with_capacity(responses.len());
+
+        for (name, response) in responses {
+            let response_status = response.status();
+            if response_status.to_u16() > status.to_u16() {
+                status = response_status;
+            }
+
+            merged.insert(
+                name,
+                serde_json::json!({
+                    "status": response_status.to_u16(),
+                    "data": response.data().unwrap_or(JsonValue::Null),
+                }),
+            );
+        }
+
+        Self::json(status, JsonValue::Object(merged))
     }
 }
 
@@ -68,29 +265,80 @@ impl ComputeResponse {
     #[must_use]
     pub const fn status(&self) -> GenericStatusCode {
         match self {
-            Self::NoContent(status) => *status,
-            Self::Json(json) => json.status,
+            Self::NoContent(status)
+            | Self::Binary { status, .. }
+            | Self::HeadersOnly { status, .. } => *status,
+            Self::Json(json) | Self::Error(json) => json.status,
         }
     }
 
     /// Get the [`hyper::StatusCode`] for this response.
+    #[cfg(feature = "hyper")]
     #[must_use]
     pub fn http_status(&self) -> StatusCode {
         self.status().to_status_code()
     }
 
-    /// Gets the inner json data of this response if it contains any, None otherwise.
+    /// Gets the inner json data of this response if it contains any, None otherwise. A
+    /// [`ComputeResponse::Binary`] has no JSON data, see [`ComputeResponse::bytes`].
     #[must_use]
     pub fn data(&self) -> Option<JsonValue> {
         match self {
-            ComputeResponse::NoContent(_) => None,
-            ComputeResponse::Json(ComputeJsonResponse { data, .. }) => Some(data.clone()),
+            Self::NoContent(_) | Self::Binary { .. } | Self::HeadersOnly { .. } => None,
+            Self::Json(ComputeJsonResponse { data, .. })
+            | Self::Error(ComputeJsonResponse { data, .. }) => Some(data.clone()),
+        }
+    }
+
+    /// Borrow the data in this response, if any, without cloning it like [`ComputeResponse::data`].
+    #[must_use]
+    pub const fn data_ref(&self) -> Option<&JsonValue> {
+        match self {
+            Self::NoContent(_) | Self::Binary { .. } | Self::HeadersOnly { .. } => None,
+            Self::Json(ComputeJsonResponse { data, .. })
+            | Self::Error(ComputeJsonResponse { data, .. }) => Some(data),
+        }
+    }
+
+    /// The raw body of a [`ComputeResponse::Binary`], None for every other response.
+    #[must_use]
+    pub fn bytes(&self) -> Option<&[u8]> {
+        match self {
+            Self::Binary { bytes, .. } => Some(bytes),
+            _ => None,
+        }
+    }
+
+    /// The headers set with [`ComputeResponse::with_header`], if any.
+    #[must_use]
+    pub const fn headers(&self) -> Option<&HashMap<String, String>> {
+        match self {
+            Self::NoContent(_) => None,
+            Self::Json(ComputeJsonResponse { headers, .. })
+            | Self::Error(ComputeJsonResponse { headers, .. })
+            | Self::Binary { headers, .. }
+            | Self::HeadersOnly { headers, .. } => headers.as_ref(),
+        }
+    }
+
+    /// The `Content-Type` of a [`ComputeResponse::Binary`], None for every other response.
+    #[must_use]
+    pub fn content_type(&self) -> Option<&str> {
+        match self {
+            Self::Binary { content_type, .. } => Some(content_type),
+            _ => None,
         }
     }
 
-    /// FIXME: Change this to be feature gated (or delete it if a different backend is chosen).
+    /// Whether this is a [`ComputeResponse::Error`].
+    #[must_use]
+    pub const fn is_error(&self) -> bool {
+        matches!(self, Self::Error(_))
+    }
+
     /// Consume this [`ComputeResponse`] and converts it to a [`warp`] [`warp::reply::Response`], fulfilling
     /// the [`warp`] trait [`warp::Reply`], for convenient use in [`warp::Filter`]s.
+    #[cfg(feature = "warp")]
     #[must_use]
     pub fn into_warp(self) -> warp::reply::Response {
         use warp::{
@@ -98,34 +346,103 @@ impl ComputeResponse {
             Reply,
         };
         let code = self.http_status();
-        match self.data() {
-            Some(val) => with_status(json(&val).into_response(), code).into_response(),
-            None => code.into_response(),
-        }
+        let headers = self.headers().cloned();
+        let mut response = match self {
+            Self::Binary {
+                content_type,
+                bytes,
+                ..
+            } => {
+                let mut response = with_status(bytes, code).into_response();
+                set_content_type(response.headers_mut(), &content_type);
+                response
+            }
+            other => match other.data() {
+                Some(val) => with_status(json(&val).into_response(), code).into_response(),
+                None => code.into_response(),
+            },
+        };
+        apply_headers(response.headers_mut(), headers);
+        response
     }
 
-    /// FIXME: Change this to be feature gated (or delete it if a different backend is chosen).
-    /// Consume this [`AppOutput`] and converts it to a [`axum`] [`axum::response::Response`].
+    /// Consume this [`ComputeResponse`] and converts it to a [`axum`] [`axum::response::Response`].
+    #[cfg(feature = "axum")]
     #[must_use]
     pub fn into_axum(self) -> axum::response::Response {
         use axum::{response::IntoResponse, Json};
-        match self {
-            Self::NoContent(status) => status.to_status_code().into_response(),
-            Self::Json(ComputeJsonResponse { status, data }) => {
-                (status.to_status_code(), Json(data)).into_response()
+        let (mut response, headers) = match self {
+            Self::NoContent(status) => (status.to_status_code().into_response(), None),
+            Self::HeadersOnly { status, headers } => {
+                (status.to_status_code().into_response(), headers)
+            }
+            Self::Json(ComputeJsonResponse {
+                status,
+                data,
+                headers,
+            })
+            | Self::Error(ComputeJsonResponse {
+                status,
+                data,
+                headers,
+            }) => (
+                (status.to_status_code(), Json(data)).into_response(),
+                headers,
+            ),
+            Self::Binary {
+                status,
+                content_type,
+                bytes,
+                headers,
+            } => {
+                let mut response = (status.to_status_code(), bytes).into_response();
+                set_content_type(response.headers_mut(), &content_type);
+                (response, headers)
             }
+        };
+        apply_headers(response.headers_mut(), headers);
+        response
+    }
+}
+
+/// Replace the `Content-Type` in `headers` with `content_type`, leaving the one already there if
+/// `content_type` isn't a valid header value.
+#[cfg(feature = "hyper")]
+fn set_content_type(headers: &mut hyper::HeaderMap, content_type: &str) {
+    if let Ok(value) = hyper::header::HeaderValue::from_str(content_type) {
+        headers.insert(hyper::header::CONTENT_TYPE, value);
+    }
+}
+
+/// Add the headers a function set with [`ComputeResponse::with_header`] to `target`, skipping any it
+/// may not set (in case they came in through deserialization) and any that aren't valid headers.
+#[cfg(feature = "hyper")]
+fn apply_headers(target: &mut hyper::HeaderMap, headers: Option<HashMap<String, String>>) {
+    use hyper::header::{HeaderName, HeaderValue};
+
+    for (name, value) in headers.into_iter().flatten() {
+        if !is_settable_header(&name) {
+            continue;
+        }
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(name.as_bytes()),
+            HeaderValue::from_str(&value),
+        ) {
+            target.insert(name, value);
         }
     }
 }
 
 // ====== Server Impls ======
 
+#[cfg(feature = "axum")]
 impl axum::response::IntoResponse for ComputeResponse {
     fn into_response(self) -> axum::response::Response {
         self.into_axum()
     }
 }
 
+#[cfg(feature = "warp")]
 impl warp::Reply for ComputeResponse {
     fn into_response(self) -> warp::reply::Response {
         self.into_warp()
@@ -142,6 +459,25 @@ impl From<Option<JsonValue>> for ComputeResponse {
         }
     }
 }
+/// Lets functions that compute a `Result` internally return `Ok(result.into())`. Errors become a
+/// [`ComputeResponse::Error`] with status `BadRequest` and the usual [`error_envelope`] as its body.
+impl From<Result<JsonValue, BadRequestError>> for ComputeResponse {
+    fn from(result: Result<JsonValue, BadRequestError>) -> Self {
+        match result {
+            Ok(data) => Self::json_ok(data),
+            Err(err) => Self::Error(ComputeJsonResponse::new(
+                GenericStatusCode::BadRequest,
+                error_envelope(
+                    GenericStatusCode::BadRequest,
+                    "bad_request",
+                    &err.to_string(),
+                    None,
+                ),
+            )),
+        }
+    }
+}
+
 impl From<JsonValue> for ComputeResponse {
     fn from(data: JsonValue) -> Self {
         Self::json_ok(data)
@@ -157,3 +493,220 @@ impl From<()> for ComputeResponse {
         Self::ok()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn merging_ok_responses_is_ok() {
+        let merged = ComputeResponse::merge(vec![
+            ("first".to_string(), ComputeResponse::json_ok(json!(1))),
+            ("second".to_string(), ComputeResponse::ok()),
+        ]);
+
+        assert_eq!(merged.status().to_u16(), 200);
+        assert_eq!(
+            merged.data(),
+            Some(json!({
+                "first": { "status": 200, "data": 1 },
+                "second": { "status": 200, "data": null },
+            }))
+        );
+    }
+
+    #[test]
+    fn merging_takes_the_worst_status() {
+        let merged = ComputeResponse::merge(vec![
+            ("good".to_string(), ComputeResponse::json_ok(json!("fine"))),
+            (
+                "bad".to_string(),
+                ComputeResponse::json(GenericStatusCode::InternalError, json!("broken")),
+            ),
+            (
+                "missing".to_string(),
+                ComputeResponse::status_only(GenericStatusCode::NotFound),
+            ),
+        ]);
+
+        assert_eq!(merged.status().to_u16(), 500);
+        let data = merged.data().unwrap();
+        assert_eq!(data["good"]["status"], 200);
+        assert_eq!(data["bad"]["data"], "broken");
+        assert_eq!(data["missing"]["status"], 404);
+    }
+
+    #[test]
+    fn ok_results_convert_to_json_responses() {
+        let response: ComputeResponse = Ok(json!({ "sum": 3 })).into();
+
+        assert!(!response.is_error());
+        assert_eq!(response.status().to_u16(), 200);
+        assert_eq!(response.data(), Some(json!({ "sum": 3 })));
+    }
+
+    #[test]
+    fn err_results_convert_to_error_responses() {
+        let err = BadRequestError::without_request("adder", "not a number");
+        let response: ComputeResponse = Err(err).into();
+
+        assert!(response.is_error());
+        assert_eq!(response.status().to_u16(), 400);
+        assert_eq!(
+            response.data(),
+            Some(json!({
+                "status": 400,
+                "code": "bad_request",
+                "message": "BadRequestError from adder: not a number",
+                "details": null,
+            }))
+        );
+    }
+
+    #[test]
+    fn binary_responses_serialize_their_bytes_as_base64() {
+        let response = ComputeResponse::binary(
+            GenericStatusCode::Ok,
+            "image/png",
+            vec![0x89, b'P', b'N', b'G'],
+        );
+
+        let wire = serde_json::to_value(&response).unwrap();
+        assert_eq!(
+            wire,
+            json!({ "Binary": { "status": 200, "content_type": "image/png", "bytes": "iVBORw==" } })
+        );
+        let back: ComputeResponse = serde_json::from_value(wire).unwrap();
+        assert_eq!(back.bytes(), Some(&[0x89, b'P', b'N', b'G'][..]));
+        assert_eq!(back.content_type(), Some("image/png"));
+        assert!(back.data().is_none());
+    }
+
+    #[cfg(feature = "axum")]
+    #[tokio::test]
+    async fn binary_responses_are_served_raw() {
+        let response =
+            ComputeResponse::binary(GenericStatusCode::Ok, "image/png", vec![1, 2, 3]).into_axum();
+
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.headers()["content-type"], "image/png");
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(&body[..], &[1, 2, 3]);
+    }
+
+    #[test]
+    fn reserved_headers_cannot_be_set() {
+        let response = ComputeResponse::json_ok(json!(1))
+            .with_header("Cache-Control", "no-store")
+            .with_header("Connection", "close")
+            .with_header("transfer-encoding", "chunked")
+            .with_header(":status", "204");
+
+        let headers = response.headers().unwrap();
+        assert_eq!(headers.len(), 1);
+        assert_eq!(headers["Cache-Control"], "no-store");
+        assert!(ComputeResponse::ok()
+            .with_header("X-Id", "1")
+            .headers()
+            .is_none());
+        assert!(ComputeResponse::json_ok(json!(1)).headers().is_none());
+    }
+
+    #[cfg(feature = "axum")]
+    #[tokio::test]
+    async fn headers_are_sent_with_the_response() {
+        let response = ComputeResponse::json_ok(json!({ "ok": true }))
+            .with_header("Cache-Control", "max-age=60")
+            .with_header("X-Request-Id", "abc")
+            .into_axum();
+
+        assert_eq!(response.headers()["cache-control"], "max-age=60");
+        assert_eq!(response.headers()["x-request-id"], "abc");
+        assert_eq!(response.headers()["content-type"], "application/json");
+
+        let plain = ComputeResponse::json_ok(json!({ "ok": true })).into_axum();
+        assert!(!plain.headers().contains_key("cache-control"));
+    }
+
+    #[test]
+    fn metadata_only_responses_keep_their_headers() {
+        let response = ComputeResponse::no_content_with_headers(
+            GenericStatusCode::from_u16(204),
+            [("X-Warmed", "12"), ("Content-Length", "3")],
+        );
+
+        assert_eq!(response.status().to_u16(), 204);
+        assert!(response.data().is_none());
+        let headers = response.headers().unwrap();
+        assert_eq!(headers.len(), 1);
+        assert_eq!(headers["X-Warmed"], "12");
+
+        let wire = serde_json::to_string(&response).unwrap();
+        let back: ComputeResponse = serde_json::from_str(&wire).unwrap();
+        assert_eq!(back.headers(), response.headers());
+    }
+
+    #[cfg(feature = "axum")]
+    #[tokio::test]
+    async fn metadata_only_responses_are_served_without_a_body() {
+        let response = ComputeResponse::no_content_with_headers(
+            GenericStatusCode::from_u16(204),
+            [("X-Warmed", "12")],
+        )
+        .with_header("X-Skipped", "3")
+        .into_axum();
+
+        assert_eq!(response.status(), 204);
+        assert_eq!(response.headers()["x-warmed"], "12");
+        assert_eq!(response.headers()["x-skipped"], "3");
+        assert!(!response.headers().contains_key("content-type"));
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert!(body.is_empty());
+    }
+
+    #[cfg(feature = "warp")]
+    #[test]
+    fn metadata_only_responses_are_served_without_a_body_by_warp() {
+        let response = ComputeResponse::no_content_with_headers(
+            GenericStatusCode::from_u16(204),
+            [("X-Warmed", "12")],
+        )
+        .into_warp();
+
+        assert_eq!(response.status(), 204);
+        assert_eq!(response.headers()["x-warmed"], "12");
+    }
+
+    #[test]
+    fn error_responses_carry_their_status_and_message() {
+        let response = ComputeResponse::error(
+            GenericStatusCode::from_u16(422),
+            "invalid_width",
+            "width must be positive",
+        );
+
+        assert!(response.is_error());
+        assert_eq!(response.status().to_u16(), 422);
+        #[cfg(feature = "hyper")]
+        assert_eq!(response.http_status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(
+            response.data(),
+            Some(json!({
+                "status": 422,
+                "code": "invalid_width",
+                "message": "width must be positive",
+                "details": null,
+            }))
+        );
+    }
+
+    #[test]
+    fn merging_nothing_is_an_empty_ok() {
+        let merged = ComputeResponse::merge(Vec::new());
+
+        assert_eq!(merged.status().to_u16(), 200);
+        assert_eq!(merged.data(), Some(json!({})));
+    }
+}
@@ -4,18 +4,25 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+#[cfg(feature = "hyper")]
 use hyper::StatusCode;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-#[derive(Debug, Copy, Clone, Deserialize, Serialize)]
+/// An HTTP-like status. On the wire it is the plain numeric code (e.g. `404`), see
+/// [`GenericStatusCode::to_u16`] and [`GenericStatusCode::from_u16`].
+#[derive(Debug, Copy, Clone)]
 pub enum GenericStatusCode {
     Ok,
     Created,
     NotFound,
     BadRequest,
     InternalError,
+    BadGateway,
     Conflict,
     PreconditionFailed,
+    PayloadTooLarge,
+    TooManyRequests,
+    ServiceUnavailable,
     Other(u16),
     Unknown,
 }
@@ -36,7 +43,11 @@ impl GenericStatusCode {
             404 => Self::NotFound,
             409 => Self::Conflict,
             412 => Self::PreconditionFailed,
+            413 => Self::PayloadTooLarge,
+            429 => Self::TooManyRequests,
             500 => Self::InternalError,
+            502 => Self::BadGateway,
+            503 => Self::ServiceUnavailable,
             0 => Self::Unknown,
             _ => Self::Other(i),
         }
@@ -50,13 +61,18 @@ impl GenericStatusCode {
             Self::NotFound => 404,
             Self::Conflict => 409,
             Self::PreconditionFailed => 412,
+            Self::PayloadTooLarge => 413,
+            Self::TooManyRequests => 429,
             Self::BadRequest => 400,
             Self::InternalError => 500,
+            Self::BadGateway => 502,
+            Self::ServiceUnavailable => 503,
             Self::Other(i) => i,
             Self::Unknown => 0,
         }
     }
 
+    #[cfg(feature = "hyper")]
     pub fn to_status_code(self) -> StatusCode {
         match self {
             Self::Ok => StatusCode::OK,
@@ -64,14 +80,19 @@ impl GenericStatusCode {
             Self::NotFound => StatusCode::NOT_FOUND,
             Self::BadRequest => StatusCode::BAD_REQUEST,
             Self::InternalError => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::BadGateway => StatusCode::BAD_GATEWAY,
             Self::Conflict => StatusCode::CONFLICT,
             Self::PreconditionFailed => StatusCode::PRECONDITION_FAILED,
+            Self::PayloadTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            Self::TooManyRequests => StatusCode::TOO_MANY_REQUESTS,
+            Self::ServiceUnavailable => StatusCode::SERVICE_UNAVAILABLE,
             Self::Other(i) => StatusCode::from_u16(i).unwrap_or(StatusCode::IM_A_TEAPOT),
             Self::Unknown => StatusCode::IM_A_TEAPOT,
         }
     }
 }
 
+#[cfg(feature = "hyper")]
 impl From<StatusCode> for GenericStatusCode {
     fn from(code: StatusCode) -> Self {
         match code {
@@ -80,8 +101,12 @@ impl From<StatusCode> for GenericStatusCode {
             StatusCode::NOT_FOUND => Self::NotFound,
             StatusCode::CONFLICT => Self::Conflict,
             StatusCode::PRECONDITION_FAILED => Self::PreconditionFailed,
+            StatusCode::PAYLOAD_TOO_LARGE => Self::PayloadTooLarge,
+            StatusCode::TOO_MANY_REQUESTS => Self::TooManyRequests,
             StatusCode::BAD_REQUEST => Self::BadRequest,
             StatusCode::INTERNAL_SERVER_ERROR => Self::InternalError,
+            StatusCode::BAD_GATEWAY => Self::BadGateway,
+            StatusCode::SERVICE_UNAVAILABLE => Self::ServiceUnavailable,
             StatusCode::IM_A_TEAPOT => Self::Unknown,
             _ => Self::Other(code.as_u16()),
         }
@@ -93,3 +118,77 @@ impl From<u16> for GenericStatusCode {
         Self::from_u16(code)
     }
 }
+
+impl Serialize for GenericStatusCode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u16(self.to_u16())
+    }
+}
+
+impl<'de> Deserialize<'de> for GenericStatusCode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        u16::deserialize(deserializer).map(Self::from_u16)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KNOWN: &[u16] = &[200, 201, 400, 404, 409, 412, 413, 429, 500, 502, 503];
+
+    #[test]
+    fn known_codes_round_trip_through_u16() {
+        for &code in KNOWN {
+            let status = GenericStatusCode::from_u16(code);
+            assert!(!matches!(status, GenericStatusCode::Other(_)), "{}", code);
+            assert_eq!(status.to_u16(), code);
+        }
+        assert!(matches!(
+            GenericStatusCode::from_u16(201),
+            GenericStatusCode::Created
+        ));
+        assert!(matches!(
+            GenericStatusCode::from_u16(503),
+            GenericStatusCode::ServiceUnavailable
+        ));
+        assert!(matches!(
+            GenericStatusCode::from_u16(418),
+            GenericStatusCode::Other(418)
+        ));
+    }
+
+    #[cfg(feature = "hyper")]
+    #[test]
+    fn known_codes_round_trip_through_status_code() {
+        for &code in KNOWN {
+            let status = GenericStatusCode::from_u16(code).to_status_code();
+            assert_eq!(status.as_u16(), code);
+            assert_eq!(GenericStatusCode::from(status).to_u16(), code);
+        }
+        assert_eq!(
+            GenericStatusCode::Created.to_status_code(),
+            StatusCode::CREATED
+        );
+    }
+
+    #[test]
+    fn serializes_as_the_numeric_code() {
+        let cases = [
+            (GenericStatusCode::Ok, serde_json::json!(200)),
+            (GenericStatusCode::TooManyRequests, serde_json::json!(429)),
+            (GenericStatusCode::Other(418), serde_json::json!(418)),
+            (GenericStatusCode::Unknown, serde_json::json!(0)),
+        ];
+        for (status, wire) in cases {
+            assert_eq!(serde_json::to_value(status).unwrap(), wire);
+            let back: GenericStatusCode = serde_json::from_value(wire).unwrap();
+            assert_eq!(back.to_u16(), status.to_u16());
+        }
+        assert!(matches!(
+            serde_json::from_value(serde_json::json!(0)).unwrap(),
+            GenericStatusCode::Unknown
+        ));
+        assert!(serde_json::from_value::<GenericStatusCode>(serde_json::json!("ok")).is_err());
+    }
+}
@@ -0,0 +1,93 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::{fmt, pin::Pin};
+
+use bytes::Bytes;
+use futures_util::{Stream, StreamExt};
+use tokio::sync::mpsc;
+
+use crate::core::types::ComputeResponse;
+
+/// A raw request body delivered to [`ComputeFunction::receive_body_stream`](crate::ComputeFunction::receive_body_stream)
+/// one chunk at a time, so that functions can process uploads without the whole thing ever being
+/// buffered in memory. This is independent of whichever server received the body.
+pub struct BodyStream {
+    inner: Pin<Box<dyn Stream<Item = Result<Bytes, String>> + Send>>,
+}
+
+impl BodyStream {
+    /// Wrap any stream of byte chunks as a [`BodyStream`]. Errors are converted to their message.
+    pub fn from_stream<S, B, E>(stream: S) -> Self
+    where
+        S: Stream<Item = Result<B, E>> + Send + 'static,
+        B: Into<Bytes>,
+        E: fmt::Display,
+    {
+        Self {
+            inner: Box::pin(stream.map(|chunk| chunk.map(Into::into).map_err(|e| e.to_string()))),
+        }
+    }
+
+    /// Wait for the next chunk of the body, or [`None`] once the body is exhausted.
+    ///
+    /// ## Errors
+    /// The message of the underlying transport error, if reading the body failed.
+    pub async fn next_chunk(&mut self) -> Option<Result<Bytes, String>> {
+        self.inner.next().await
+    }
+}
+
+impl fmt::Debug for BodyStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BodyStream").finish_non_exhaustive()
+    }
+}
+
+/// Where [`ComputeFunction::receive_streaming`](crate::ComputeFunction::receive_streaming) sends
+/// its output, one [`ComputeResponse`] frame at a time, for functions that report progress before
+/// they are done. Sending waits while the receiving end is behind, so a slow client slows the
+/// function down rather than frames piling up in memory.
+#[derive(Clone)]
+pub struct ResponseSink {
+    sender: mpsc::Sender<ComputeResponse>,
+}
+
+impl ResponseSink {
+    /// A new sink, together with the receiving end of its frames. At most `capacity` frames are
+    /// buffered before [`ResponseSink::send`] waits for the receiver.
+    ///
+    /// ## Panics
+    /// If `capacity` is `0`.
+    #[must_use]
+    pub fn channel(capacity: usize) -> (Self, mpsc::Receiver<ComputeResponse>) {
+        let (sender, receiver) = mpsc::channel(capacity);
+        (Self { sender }, receiver)
+    }
+
+    /// Send a frame, waiting for room if the receiver is behind.
+    ///
+    /// ## Returns
+    /// `false` if the receiver has gone away, in which case nobody will see this or any later frame
+    /// and the function may as well stop.
+    pub async fn send(&self, frame: ComputeResponse) -> bool {
+        self.sender.send(frame).await.is_ok()
+    }
+
+    /// Whether the receiver has gone away, see [`ResponseSink::send`].
+    #[must_use]
+    pub fn is_closed(&self) -> bool {
+        self.sender.is_closed()
+    }
+}
+
+impl fmt::Debug for ResponseSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResponseSink")
+            .field("closed", &self.is_closed())
+            .finish()
+    }
+}
@@ -4,32 +4,193 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+use std::collections::HashMap;
+
+use once_cell::sync::OnceCell;
 use serde::{Deserialize, Serialize};
 
-// TODO: See extended comment on ComputeResponse below.
 /// An input identifier that indicates which compute function this request
 /// is intended for.
 ///
-/// For now it is just a string, but i'd like to have it be
-/// more of a file URI. I'm sure whatever server framework I end up using will
-/// have utilities (or `hyper` itself might have something) to help with this
-/// functionality.
-#[derive(Deserialize, Serialize, Debug, Clone)]
-pub struct TargetComputeFunc(String);
+/// Targets look like a relative URI, `[namespace/]name[/sub/path][?key=value&...]`:
+/// - the [basename](TargetComputeFunc::basename) is the name the function is registered under,
+///   which is either a plain name (`logger`) or a namespaced one (`math/add`)
+/// - anything after the basename is the [sub-path](TargetComputeFunc::subpath), so sub-paths are
+///   only available to namespaced functions (`math/add/exact` but not `logger/verbose`, which is the
+///   function `verbose` in the `logger` namespace)
+/// - the [query](TargetComputeFunc::query) follows a `?`
+///
+/// A plain name with no sub-path or query is its own basename, so targets that are just a function
+/// name keep working.
+///
+/// The target is parsed the first time one of its parts is asked for and the result is kept, so
+/// routing a request doesn't parse it again on every lookup.
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(transparent)]
+pub struct TargetComputeFunc {
+    name: String,
+    #[serde(skip)]
+    parsed: OnceCell<ParsedTarget>,
+}
+
+/// The parts of a [`TargetComputeFunc`], as ranges into its name where possible.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ParsedTarget {
+    /// The length of the basename.
+    basename_end: usize,
+    /// Where the sub-path starts, or the length of the basename if there is none.
+    subpath_start: usize,
+    /// Where the path ends, i.e. the position of the `?` if there is a query.
+    path_end: usize,
+    query: HashMap<String, String>,
+}
+
+impl ParsedTarget {
+    fn parse(name: &str) -> Self {
+        let (path, query) = match name.split_once('?') {
+            Some((path, query)) => (path, Some(query)),
+            None => (name, None),
+        };
+        let (basename_end, subpath_start) =
+            Self::basename_len(path).map_or((path.len(), path.len()), |len| (len, len + 1));
+        let query = query
+            .unwrap_or_default()
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| match pair.split_once('=') {
+                Some((key, value)) => (key.to_string(), value.to_string()),
+                None => (pair.to_string(), String::new()),
+            })
+            .collect();
+
+        Self {
+            basename_end,
+            subpath_start,
+            path_end: path.len(),
+            query,
+        }
+    }
+
+    /// The length of the basename in `path`, if there is a sub-path after it.
+    fn basename_len(path: &str) -> Option<usize> {
+        let namespace = path.find('/')?;
+        path[namespace + 1..]
+            .find('/')
+            .map(|name| namespace + 1 + name)
+    }
+}
+
 impl TargetComputeFunc {
     #[must_use]
     pub const fn new(name: String) -> Self {
-        Self(name)
+        Self {
+            name,
+            parsed: OnceCell::new(),
+        }
     }
 
+    /// The target exactly as it was given, including any sub-path and query.
     #[must_use]
     pub fn name(&self) -> &str {
-        &self.0
+        &self.name
+    }
+
+    /// The name of the function this target resolves to, i.e. the target without its sub-path and
+    /// query.
+    #[must_use]
+    pub fn basename(&self) -> &str {
+        &self.name[..self.parsed().basename_end]
+    }
+
+    /// The part of the path after the [basename](TargetComputeFunc::basename), without the leading
+    /// `/`. Empty if there is none.
+    #[must_use]
+    pub fn subpath(&self) -> &str {
+        let parsed = self.parsed();
+        &self.name[parsed.subpath_start..parsed.path_end]
+    }
+
+    /// The `key=value` pairs following the `?`, if any. A key without a `=` maps to an empty string,
+    /// and if a key is repeated the last value wins. Keys and values are taken as-is, without any
+    /// percent-decoding.
+    #[must_use]
+    pub fn query(&self) -> &HashMap<String, String> {
+        &self.parsed().query
+    }
+
+    fn parsed(&self) -> &ParsedTarget {
+        self.parsed.get_or_init(|| ParsedTarget::parse(&self.name))
+    }
+}
+
+impl std::fmt::Debug for TargetComputeFunc {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("TargetComputeFunc")
+            .field(&self.name)
+            .finish()
     }
 }
 
 impl std::fmt::Display for TargetComputeFunc {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.0)
+        write!(f, "{}", self.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(name: &str) -> TargetComputeFunc {
+        TargetComputeFunc::new(name.to_string())
+    }
+
+    #[test]
+    fn plain_names_are_their_own_basename() {
+        let logger = target("logger");
+        assert_eq!(logger.basename(), "logger");
+        assert_eq!(logger.subpath(), "");
+        assert!(logger.query().is_empty());
+    }
+
+    #[test]
+    fn targets_are_split_into_basename_subpath_and_query() {
+        let add = target("math/add?round=true");
+        assert_eq!(add.basename(), "math/add");
+        assert_eq!(add.subpath(), "");
+        assert_eq!(add.query().get("round").map(String::as_str), Some("true"));
+
+        let exact = target("math/add/exact/i128?round&digits=2&digits=3");
+        assert_eq!(exact.basename(), "math/add");
+        assert_eq!(exact.subpath(), "exact/i128");
+        let query = exact.query();
+        assert_eq!(query.len(), 2);
+        assert_eq!(query["round"], "");
+        assert_eq!(query["digits"], "3");
+
+        assert_eq!(target("logger?level=debug").basename(), "logger");
+        assert_eq!(target("logger/verbose").basename(), "logger/verbose");
+    }
+
+    #[test]
+    fn parse_results_are_stable() {
+        let exact = target("math/add/exact?round=true");
+        let first = (exact.basename(), exact.subpath(), exact.query().clone());
+        for _ in 0..3 {
+            assert_eq!(
+                (exact.basename(), exact.subpath(), exact.query().clone()),
+                first
+            );
+        }
+        assert_eq!(exact.parsed.get(), Some(&ParsedTarget::parse(exact.name())));
+
+        let copy = exact.clone();
+        assert_eq!(copy.basename(), "math/add");
+        assert_eq!(copy.subpath(), "exact");
+
+        let json = serde_json::to_string(&exact).unwrap();
+        assert_eq!(json, "\"math/add/exact?round=true\"");
+        let back: TargetComputeFunc = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.subpath(), "exact");
     }
 }
@@ -0,0 +1,57 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use serde_json::json;
+
+use crate::{async_trait, BadRequestError, ComputeFunction, ComputeRequest, ComputeResponse};
+
+/// Answers every request with its own `data`, untouched, alongside the name it was sent to. Handy
+/// for smoke-testing a server without having to build a plugin first.
+#[derive(Debug, Default)]
+pub struct Echo;
+
+#[async_trait]
+impl ComputeFunction for Echo {
+    fn name(&self) -> &'static str {
+        "echo"
+    }
+
+    #[allow(clippy::unused_async)]
+    async fn receive_request(
+        &self,
+        request: &ComputeRequest,
+    ) -> Result<ComputeResponse, BadRequestError> {
+        Ok(ComputeResponse::json_ok(json!({
+            "target": request.target().name(),
+            "data": request.data(),
+        })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        functions::BuiltinFunctionList, BuiltinFunction, ComputeFunctionManager, TargetComputeFunc,
+    };
+
+    #[tokio::test]
+    async fn echoes_the_data_and_target_back() {
+        let manager = ComputeFunctionManager::with_builtins(&BuiltinFunctionList::from([
+            BuiltinFunction::Echo,
+        ]));
+        let request = ComputeRequest::new(
+            TargetComputeFunc::new("echo".to_string()),
+            json!({ "x": 1 }),
+        );
+
+        let response = manager.push_request(&request).await.unwrap();
+        assert_eq!(
+            response.data(),
+            Some(json!({ "target": "echo", "data": { "x": 1 } }))
+        );
+    }
+}
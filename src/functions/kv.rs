@@ -0,0 +1,226 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::{
+    collections::HashMap,
+    sync::{Mutex, MutexGuard, PoisonError},
+    time::{Duration, Instant},
+};
+
+use serde_json::{json, Value as JsonValue};
+
+use super::multi_string_keys;
+use crate::{async_trait, BadRequestError, ComputeFunction, ComputeRequest, ComputeResponse};
+
+/// A value held by the [`KvStore`], and the moment it stops being readable, if ever.
+type Entry = (JsonValue, Option<Instant>);
+
+/// A small in-memory key/value store, reachable like any other function:
+///
+/// - `{"action": "set", "key": "a", "value": ..., "ttl_secs": 60}` stores `value` under `key`, for
+///   `ttl_secs` seconds if given and forever otherwise, or if `ttl_secs` runs past what the clock
+///   can represent.
+/// - `{"action": "get", "key": "a"}` answers with `{"key": "a", "value": ...}`.
+/// - `{"action": "delete", "key": "a"}` removes `key`, if it is there.
+///
+/// Entries are expired lazily, a read of an expired key behaves as if it was never set and every
+/// `set` sweeps out whatever has expired since.
+///
+/// A plain [`std::sync::Mutex`] guards the map since it is only held for a single map operation and
+/// never across an `.await`.
+#[derive(Debug, Default)]
+pub struct KvStore {
+    entries: Mutex<HashMap<String, Entry>>,
+}
+
+impl KvStore {
+    fn lock_entries(&self) -> MutexGuard<'_, HashMap<String, Entry>> {
+        // Every section holding the lock is a single non-panicking map operation, so a poisoned
+        // lock still holds consistent data.
+        self.entries.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn set(&self, key: String, value: JsonValue, ttl: Option<Duration>) {
+        let now = Instant::now();
+        let mut entries = self.lock_entries();
+        entries.retain(|_, (_, expires_at)| !is_expired(*expires_at, now));
+        entries.insert(key, (value, ttl.and_then(|ttl| now.checked_add(ttl))));
+    }
+
+    fn get(&self, key: &str) -> Option<JsonValue> {
+        let mut entries = self.lock_entries();
+        match entries.get(key) {
+            Some((_, expires_at)) if is_expired(*expires_at, Instant::now()) => {
+                entries.remove(key);
+                None
+            }
+            Some((value, _)) => Some(value.clone()),
+            None => None,
+        }
+    }
+
+    fn delete(&self, key: &str) {
+        self.lock_entries().remove(key);
+    }
+}
+
+fn is_expired(expires_at: Option<Instant>, now: Instant) -> bool {
+    expires_at.map_or(false, |expires_at| expires_at <= now)
+}
+
+#[async_trait]
+impl ComputeFunction for KvStore {
+    fn name(&self) -> &'static str {
+        "kv"
+    }
+
+    #[allow(clippy::unused_async)]
+    async fn receive_request(
+        &self,
+        request: &ComputeRequest,
+    ) -> Result<ComputeResponse, BadRequestError> {
+        let bad_request = |msg: &str| BadRequestError::new(self.name(), msg, Some(request.clone()));
+
+        let obj = request.data().as_object().ok_or_else(|| {
+            bad_request(r#"Data must be an object, e.g. {"action": "get", "key": "a"}"#)
+        })?;
+
+        let action = multi_string_keys(obj, &["action", "act"], None, |s| Some(s.to_lowercase()));
+        let action = action
+            .ok_or_else(|| bad_request("Missing the 'action' to perform, set, get or delete"))?;
+        let key = multi_string_keys(obj, &["key", "k"], None, |s| Some(s.to_string()))
+            .ok_or_else(|| bad_request("Missing the 'key' to act on"))?;
+
+        match action.as_str() {
+            "set" => {
+                let value = obj
+                    .get("value")
+                    .cloned()
+                    .ok_or_else(|| bad_request("Missing the 'value' to set"))?;
+                let ttl = match obj.get("ttl_secs") {
+                    None | Some(JsonValue::Null) => None,
+                    Some(ttl) => Some(Duration::from_secs(ttl.as_u64().ok_or_else(|| {
+                        bad_request("'ttl_secs' must be a non-negative whole number of seconds")
+                    })?)),
+                };
+                self.set(key, value, ttl);
+                Ok(ComputeResponse::ok())
+            }
+            "get" => match self.get(&key) {
+                Some(value) => Ok(ComputeResponse::json_ok(
+                    json!({ "key": key, "value": value }),
+                )),
+                None => Err(bad_request(&format!(
+                    "No value is stored under key '{}'",
+                    key
+                ))),
+            },
+            "delete" => {
+                self.delete(&key);
+                Ok(ComputeResponse::ok())
+            }
+            other => Err(bad_request(&format!(
+                "Unknown action '{}', expected one of set, get or delete",
+                other
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TargetComputeFunc;
+
+    async fn send(store: &KvStore, data: JsonValue) -> Result<ComputeResponse, BadRequestError> {
+        let request = ComputeRequest::new(TargetComputeFunc::new("kv".to_string()), data);
+        store.receive_request(&request).await
+    }
+
+    #[tokio::test]
+    async fn stores_reads_and_deletes_values() {
+        let store = KvStore::default();
+
+        let set = send(
+            &store,
+            json!({ "action": "set", "key": "a", "value": [1, 2] }),
+        )
+        .await
+        .unwrap();
+        assert!(matches!(set, ComputeResponse::NoContent(_)));
+
+        let got = send(&store, json!({ "action": "get", "key": "a" }))
+            .await
+            .unwrap();
+        assert_eq!(got.data(), Some(json!({ "key": "a", "value": [1, 2] })));
+
+        let deleted = send(&store, json!({ "action": "delete", "key": "a" }))
+            .await
+            .unwrap();
+        assert!(matches!(deleted, ComputeResponse::NoContent(_)));
+
+        let missing = send(&store, json!({ "action": "get", "key": "a" }))
+            .await
+            .unwrap_err();
+        assert!(missing.message().contains("'a'"));
+    }
+
+    #[tokio::test]
+    async fn expired_values_are_gone() {
+        let store = KvStore::default();
+        send(
+            &store,
+            json!({ "action": "set", "key": "brief", "value": 1, "ttl_secs": 0 }),
+        )
+        .await
+        .unwrap();
+        send(
+            &store,
+            json!({ "action": "set", "key": "lasting", "value": 2, "ttl_secs": 60 }),
+        )
+        .await
+        .unwrap();
+
+        assert!(send(&store, json!({ "action": "get", "key": "brief" }))
+            .await
+            .is_err());
+        assert!(send(&store, json!({ "action": "get", "key": "lasting" }))
+            .await
+            .is_ok());
+        assert_eq!(store.lock_entries().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn huge_ttls_never_expire() {
+        let store = KvStore::default();
+        send(
+            &store,
+            json!({ "action": "set", "key": "a", "value": 1, "ttl_secs": u64::MAX }),
+        )
+        .await
+        .unwrap();
+
+        assert!(send(&store, json!({ "action": "get", "key": "a" }))
+            .await
+            .is_ok());
+        assert!(store.lock_entries()["a"].1.is_none());
+    }
+
+    #[tokio::test]
+    async fn malformed_requests_are_bad_requests() {
+        let store = KvStore::default();
+        for data in [
+            json!("get a"),
+            json!({ "key": "a" }),
+            json!({ "action": "get" }),
+            json!({ "action": "set", "key": "a" }),
+            json!({ "action": "set", "key": "a", "value": 1, "ttl_secs": -1 }),
+            json!({ "action": "swap", "key": "a" }),
+        ] {
+            assert!(send(&store, data.clone()).await.is_err(), "{}", data);
+        }
+    }
+}
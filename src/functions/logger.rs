@@ -4,13 +4,27 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-use std::{convert::Infallible, str::FromStr};
+use std::{
+    convert::Infallible,
+    fs::{File, OpenOptions},
+    io::Write,
+    path::PathBuf,
+    str::FromStr,
+    sync::{Mutex, PoisonError},
+};
 
+use serde_json::{json, Value as JsonValue};
 use tracing::{debug, error, info, trace, warn};
 
-use crate::{async_trait, BadRequestError, ComputeFunction, ComputeRequest, ComputeResponse};
+use super::multi_string_keys;
+use crate::{
+    async_trait, BadRequestError, ComputeFunction, ComputeRequest, ComputeResponse, InputLimits,
+};
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+/// The severity of a log entry. Levels are ordered from [`LogLevel::Trace`] (least severe) to
+/// [`LogLevel::Error`] (most severe), and [`LogLevel::Unknown`] compares (and hashes) as equal to
+/// [`LogLevel::Info`], see [`LogLevel::as_u8`].
+#[derive(Debug, Copy, Clone)]
 pub enum LogLevel {
     Trace,
     Debug,
@@ -54,8 +68,232 @@ impl std::fmt::Display for LogLevel {
     }
 }
 
-#[derive(Debug, Default)]
-pub struct Logger;
+impl LogLevel {
+    /// How severe this level is, from `0` for [`LogLevel::Trace`] up to `4` for [`LogLevel::Error`].
+    /// [`LogLevel::Unknown`] is as severe as [`LogLevel::Info`].
+    #[must_use]
+    pub const fn as_u8(self) -> u8 {
+        match self {
+            Self::Trace => 0,
+            Self::Debug => 1,
+            Self::Info | Self::Unknown => 2,
+            Self::Warn => 3,
+            Self::Error => 4,
+        }
+    }
+
+    /// The level with the given severity, the inverse of [`LogLevel::as_u8`]. Anything above `4` is
+    /// [`LogLevel::Error`].
+    #[must_use]
+    pub const fn from_u8(severity: u8) -> Self {
+        match severity {
+            0 => Self::Trace,
+            1 => Self::Debug,
+            2 => Self::Info,
+            3 => Self::Warn,
+            _ => Self::Error,
+        }
+    }
+
+    /// Get the [`tracing::level_filters::LevelFilter`] that lets through events at this level and
+    /// above. [`LogLevel::Unknown`] is treated as [`LogLevel::Info`], as it is everywhere else.
+    #[must_use]
+    pub fn to_level_filter(self) -> tracing::level_filters::LevelFilter {
+        tracing::level_filters::LevelFilter::from_level(self.into())
+    }
+}
+
+impl PartialEq for LogLevel {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_u8() == other.as_u8()
+    }
+}
+
+impl Eq for LogLevel {}
+
+impl std::hash::Hash for LogLevel {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.as_u8().hash(state);
+    }
+}
+
+impl PartialOrd for LogLevel {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for LogLevel {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.as_u8().cmp(&other.as_u8())
+    }
+}
+
+impl From<LogLevel> for tracing::Level {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Trace => Self::TRACE,
+            LogLevel::Debug => Self::DEBUG,
+            LogLevel::Info | LogLevel::Unknown => Self::INFO,
+            LogLevel::Warn => Self::WARN,
+            LogLevel::Error => Self::ERROR,
+        }
+    }
+}
+
+/// Where a [`Logger`] writes the entries it receives.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Sink {
+    /// Emit each entry as a `tracing` event at its level, the default.
+    Tracing,
+    /// Append each entry as a line to the file at the given path.
+    File(PathBuf),
+    /// Both emit a `tracing` event and append to the file at the given path.
+    Both(PathBuf),
+}
+
+impl Default for Sink {
+    fn default() -> Self {
+        Self::Tracing
+    }
+}
+
+impl Sink {
+    /// The file this sink appends to, if any.
+    #[must_use]
+    pub fn path(&self) -> Option<&PathBuf> {
+        match self {
+            Self::Tracing => None,
+            Self::File(path) | Self::Both(path) => Some(path),
+        }
+    }
+}
+
+/// Writes the entries it receives to its [`Sink`], through `tracing` unless configured otherwise.
+///
+/// When the file of a [`Sink::File`] can't be opened, or a line can't be written to it, the entry is
+/// emitted through `tracing` instead so it isn't lost. A [`Logger::strict`] logger still does that
+/// but also fails the request with a [`BadRequestError`].
+///
+/// Entries below the [`Logger::min_level`] are skipped, by default nothing is.
+#[derive(Debug)]
+pub struct Logger {
+    sink: Sink,
+    file: Option<Mutex<File>>,
+    open_error: Option<String>,
+    strict: bool,
+    min_level: LogLevel,
+}
+
+impl Default for Logger {
+    fn default() -> Self {
+        Self {
+            sink: Sink::default(),
+            file: None,
+            open_error: None,
+            strict: false,
+            min_level: LogLevel::Trace,
+        }
+    }
+}
+
+impl Logger {
+    /// A log entry only ever uses a handful of keys, anything more than this is not a log.
+    pub const MAX_KEYS: usize = 32;
+    /// The longest message (or any other string) a single log entry may contain.
+    pub const MAX_STRING_LEN: usize = 16 * 1024;
+
+    /// Create a new [`Logger`] writing to `sink`, opening (or creating) its file in append mode.
+    /// A file that fails to open is logged and the logger falls back to `tracing`.
+    #[must_use]
+    pub fn with_sink(sink: Sink) -> Self {
+        let (file, open_error) = match sink.path() {
+            None => (None, None),
+            Some(path) => match OpenOptions::new().create(true).append(true).open(path) {
+                Ok(file) => (Some(Mutex::new(file)), None),
+                Err(e) => {
+                    let message = format!("Unable to open log file {}: {}", path.display(), e);
+                    warn!("{}, falling back to tracing", message);
+                    (None, Some(message))
+                }
+            },
+        };
+
+        Self {
+            sink,
+            file,
+            open_error,
+            ..Self::default()
+        }
+    }
+
+    /// Create a new [`Logger`] writing through `tracing` that skips every entry less severe than
+    /// `min_level`. [`LogLevel::Unknown`] is as severe as [`LogLevel::Info`].
+    #[must_use]
+    pub fn with_min_level(min_level: LogLevel) -> Self {
+        Self {
+            min_level,
+            ..Self::default()
+        }
+    }
+
+    /// Create a new [`Logger`] appending to the file at `path`, the same as
+    /// `Logger::with_sink(Sink::File(path))`.
+    #[must_use]
+    pub fn with_file(path: impl Into<PathBuf>) -> Self {
+        Self::with_sink(Sink::File(path.into()))
+    }
+
+    /// Consume this logger and return one that fails requests with a [`BadRequestError`] when its
+    /// file could not be opened or written to, rather than only falling back to `tracing`.
+    #[must_use]
+    pub fn strict(mut self) -> Self {
+        self.strict = true;
+        self
+    }
+
+    /// The [`Sink`] this logger writes to.
+    #[must_use]
+    pub const fn sink(&self) -> &Sink {
+        &self.sink
+    }
+
+    /// The least severe [`LogLevel`] this logger writes, anything below it is skipped.
+    #[must_use]
+    pub const fn min_level(&self) -> LogLevel {
+        self.min_level
+    }
+
+    /// Whether an entry at `level` is severe enough to be written.
+    fn allows(&self, level: LogLevel) -> bool {
+        level >= self.min_level
+    }
+
+    /// Write `log` to the sink.
+    ///
+    /// ## Errors
+    /// Returns a message describing why the file could not be written to, only if this logger is
+    /// [`Logger::strict`]. The entry has been emitted through `tracing` regardless.
+    fn write(&self, lvl: LogLevel, log: &str) -> Result<(), String> {
+        let written = match (&self.file, &self.open_error) {
+            (Some(file), _) => {
+                let mut file = file.lock().unwrap_or_else(PoisonError::into_inner);
+                writeln!(file, "{}", log).map_err(|e| format!("Unable to write to log file: {}", e))
+            }
+            (None, Some(open_error)) => Err(open_error.clone()),
+            (None, None) => Ok(()),
+        };
+
+        if written.is_err() || !matches!(self.sink, Sink::File(_)) {
+            send_log(lvl, log);
+        }
+
+        match written {
+            Err(e) if self.strict => Err(e),
+            _ => Ok(()),
+        }
+    }
+}
 
 #[async_trait]
 impl ComputeFunction for Logger {
@@ -63,77 +301,91 @@ impl ComputeFunction for Logger {
         "logger"
     }
 
+    /// Every key and string of the request ends up in the formatted log, so keep them bounded.
+    fn input_limits(&self) -> Option<InputLimits> {
+        Some(InputLimits::new(Self::MAX_KEYS, Self::MAX_STRING_LEN))
+    }
+
     #[allow(clippy::unused_async)]
     async fn receive_request(
         &self,
         request: &ComputeRequest,
     ) -> Result<ComputeResponse, BadRequestError> {
-        let data = request.data();
-        let is_str = data.is_string();
-        let is_obj = data.is_object();
-
-        if !is_str && !is_obj {
-            return Err(BadRequestError::new(
-                self.name(),
-                "Data must be an object or string",
-                Some(request.clone()),
+        let bad_request = |msg: &str| BadRequestError::new(self.name(), msg, Some(request.clone()));
+
+        if let Some(batch) = request.data().as_array() {
+            let entries = batch
+                .iter()
+                .enumerate()
+                .map(|(i, item)| {
+                    entry_of(item).ok_or_else(|| {
+                        bad_request(&format!("Element {} must be an object or string", i))
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let mut logged = 0;
+            for (level, log) in entries.iter().filter(|(level, _)| self.allows(*level)) {
+                self.write(*level, log).map_err(|e| bad_request(&e))?;
+                logged += 1;
+            }
+
+            return Ok(ComputeResponse::json_ok(
+                json!({ "logged": logged, "skipped": entries.len() - logged }),
             ));
         }
 
-        if is_str {
-            match data.as_str() {
-                Some(s) => send_log(LogLevel::Info, s),
-                None => {
-                    return Err(BadRequestError::new(
-                        self.name(),
-                        "Unable to convert data (which returned true for is_string) to a string",
-                        Some(request.clone()),
-                    ))
-                }
-            }
-        } else {
-            let obj =
-                match data.as_object() {
-                    Some(o) => o,
-                    None => return Err(BadRequestError::new(
-                        self.name(),
-                        "Unable to convert data (which returned true for is_object) to a object",
-                        Some(request.clone()),
-                    )),
-                };
-
-            let level = multi_string_keys(obj, &["level", "lvl", "l"], LogLevel::default(), |s| {
-                s.parse::<LogLevel>().unwrap_or_default()
-            });
-
-            let msg = multi_string_keys(
-                obj,
-                &["message", "msg", "m", "text", "log"],
-                "".to_string(),
-                std::string::ToString::to_string,
-            );
-
-            let sender = multi_string_keys(
-                obj,
-                &["sender", "s", "app", "self", "this"],
-                "".to_string(),
-                std::string::ToString::to_string,
-            );
-
-            let ts = get_timestamp();
-
-            let log = obj.get("data").map_or_else(
-                || format!("{}:[{}]{}| {}", ts, level, sender, msg),
-                |d| format!("{}:[{}]{}| {} | {}", ts, level, sender, msg, d),
-            );
-
-            send_log(level, &log);
+        let (level, log) = entry_of(request.data())
+            .ok_or_else(|| bad_request("Data must be an object, string, or array of them"))?;
+        if !self.allows(level) {
+            return Ok(ComputeResponse::json_ok(json!({ "skipped": true })));
         }
+        self.write(level, &log).map_err(|e| bad_request(&e))?;
 
         Ok(ComputeResponse::ok())
     }
 }
 
+/// The level and formatted line for a single log entry, either a plain string (logged at
+/// [`LogLevel::Info`]) or an object with the message, level, sender and any extra data.
+///
+/// ## Returns
+/// `None` if `value` is neither a string nor an object.
+fn entry_of(value: &JsonValue) -> Option<(LogLevel, String)> {
+    if let Some(s) = value.as_str() {
+        return Some((LogLevel::Info, s.to_string()));
+    }
+
+    let obj = value.as_object()?;
+
+    let level = multi_string_keys(obj, &["level", "lvl", "l"], LogLevel::default(), |s| {
+        s.parse::<LogLevel>().unwrap_or_default()
+    });
+
+    let msg = multi_string_keys(
+        obj,
+        &["message", "msg", "m", "text", "log"],
+        "".to_string(),
+        std::string::ToString::to_string,
+    );
+
+    let sender = multi_string_keys(
+        obj,
+        &["sender", "s", "app", "self", "this"],
+        "".to_string(),
+        std::string::ToString::to_string,
+    );
+
+    let ts = get_timestamp();
+
+    let log = obj.get("data").map_or_else(
+        || format!("{}:[{}]{}| {}", ts, level, sender, msg),
+        |d| format!("{}:[{}]{}| {} | {}", ts, level, sender, msg, d),
+    );
+
+    Some((level, log))
+}
+
 #[allow(
     clippy::cognitive_complexity,
     reason = "Simple function, but macro expansion apparently makes it fucking huge."
@@ -142,10 +394,9 @@ fn send_log(lvl: LogLevel, log: &str) {
     match lvl {
         LogLevel::Trace => trace!("{}", log),
         LogLevel::Debug => debug!("{}", log),
-        LogLevel::Info => info!("{}", log),
+        LogLevel::Info | LogLevel::Unknown => info!("{}", log),
         LogLevel::Warn => warn!("{}", log),
         LogLevel::Error => error!("{}", log),
-        LogLevel::Unknown => debug!("{}", log),
     }
 }
 
@@ -156,24 +407,238 @@ fn get_timestamp() -> String {
     Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true)
 }
 
-/// Helper function try multiple variations of a key to find one that might exist.
-/// I only had need for retrieving strings so I didn't make it generic over the
-/// type of value. If I used it more often it could probably be made so.
-///
-/// TODO: Maybe I could provide it in the planned exported utility library for
-/// function implementations.
-fn multi_string_keys<Output, F: Fn(&str) -> Output>(
-    map: &serde_json::Map<String, serde_json::Value>,
-    keys: &[&str],
-    def: Output,
-    converter: F,
-) -> Output {
-    for &key in keys {
-        if let Some(v) = map.get(key) {
-            if let Some(s) = v.as_str() {
-                return converter(s);
+#[cfg(test)]
+mod tests {
+    use tracing::{level_filters::LevelFilter, Level};
+
+    use super::*;
+
+    #[test]
+    fn converts_to_tracing_levels() {
+        assert_eq!(Level::from(LogLevel::Trace), Level::TRACE);
+        assert_eq!(Level::from(LogLevel::Debug), Level::DEBUG);
+        assert_eq!(Level::from(LogLevel::Info), Level::INFO);
+        assert_eq!(Level::from(LogLevel::Warn), Level::WARN);
+        assert_eq!(Level::from(LogLevel::Error), Level::ERROR);
+        assert_eq!(Level::from(LogLevel::Unknown), Level::INFO);
+    }
+
+    #[test]
+    fn equal_levels_convert_to_the_same_tracing_level() {
+        let levels = [
+            LogLevel::Trace,
+            LogLevel::Debug,
+            LogLevel::Info,
+            LogLevel::Warn,
+            LogLevel::Error,
+            LogLevel::Unknown,
+        ];
+        for a in levels {
+            for b in levels {
+                assert_eq!(a == b, Level::from(a) == Level::from(b), "{:?} {:?}", a, b);
+                assert_eq!(a.to_level_filter() == b.to_level_filter(), a == b);
             }
         }
     }
-    def
+
+    #[test]
+    fn levels_are_ordered_by_severity() {
+        assert!(LogLevel::Error > LogLevel::Warn);
+        assert!(LogLevel::Warn > LogLevel::Info);
+        assert!(LogLevel::Info > LogLevel::Debug);
+        assert!(LogLevel::Debug > LogLevel::Trace);
+
+        assert_eq!(LogLevel::Unknown, LogLevel::Info);
+        assert!(LogLevel::Unknown < LogLevel::Warn && LogLevel::Unknown > LogLevel::Debug);
+    }
+
+    #[test]
+    fn converts_to_and_from_u8() {
+        for level in [
+            LogLevel::Trace,
+            LogLevel::Debug,
+            LogLevel::Info,
+            LogLevel::Warn,
+            LogLevel::Error,
+        ] {
+            assert_eq!(LogLevel::from_u8(level.as_u8()), level);
+        }
+        assert_eq!(LogLevel::Unknown.as_u8(), LogLevel::Info.as_u8());
+        assert!(matches!(LogLevel::from_u8(2), LogLevel::Info));
+        assert_eq!(LogLevel::from_u8(u8::MAX), LogLevel::Error);
+    }
+
+    #[test]
+    fn converts_to_level_filters() {
+        assert_eq!(LogLevel::Trace.to_level_filter(), LevelFilter::TRACE);
+        assert_eq!(LogLevel::Debug.to_level_filter(), LevelFilter::DEBUG);
+        assert_eq!(LogLevel::Info.to_level_filter(), LevelFilter::INFO);
+        assert_eq!(LogLevel::Warn.to_level_filter(), LevelFilter::WARN);
+        assert_eq!(LogLevel::Error.to_level_filter(), LevelFilter::ERROR);
+        assert_eq!(LogLevel::Unknown.to_level_filter(), LevelFilter::INFO);
+    }
+
+    #[tokio::test]
+    async fn manager_enforces_the_logger_limits() {
+        let manager = crate::ComputeFunctionManager::with_logger();
+        let target = crate::TargetComputeFunc::new("logger".to_string());
+
+        let keys: serde_json::Map<_, _> = (0..=Logger::MAX_KEYS)
+            .map(|i| (i.to_string(), serde_json::json!(i)))
+            .collect();
+        let too_many = ComputeRequest::new(target.clone(), serde_json::Value::Object(keys));
+        assert!(manager.push_request(&too_many).await.is_err());
+
+        let long = "x".repeat(Logger::MAX_STRING_LEN + 1);
+        let too_long = ComputeRequest::new(target.clone(), serde_json::json!({ "msg": long }));
+        assert!(manager.push_request(&too_long).await.is_err());
+
+        let fine = ComputeRequest::new(target, serde_json::json!({ "msg": "hello" }));
+        assert!(manager.push_request(&fine).await.is_ok());
+    }
+
+    fn log_request(data: serde_json::Value) -> ComputeRequest {
+        ComputeRequest::new(crate::TargetComputeFunc::new("logger".to_string()), data)
+    }
+
+    #[tokio::test]
+    async fn file_sink_appends_each_entry() {
+        let path = std::env::temp_dir().join(format!("logger-{}.log", uuid::Uuid::new_v4()));
+        let logger = Logger::with_file(&path);
+        assert_eq!(logger.sink(), &Sink::File(path.clone()));
+
+        logger
+            .receive_request(&log_request(serde_json::json!("first")))
+            .await
+            .unwrap();
+        logger
+            .receive_request(&log_request(
+                serde_json::json!({ "level": "warn", "msg": "second" }),
+            ))
+            .await
+            .unwrap();
+
+        let written = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let lines: Vec<&str> = written.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], "first");
+        assert!(lines[1].contains("[ Warn]") && lines[1].ends_with("second"));
+    }
+
+    #[tokio::test]
+    async fn unopenable_files_fall_back_to_tracing_unless_strict() {
+        let path = std::env::temp_dir()
+            .join(uuid::Uuid::new_v4().to_string())
+            .join("missing-dir.log");
+        let request = log_request(serde_json::json!("hello"));
+
+        let lenient = Logger::with_sink(Sink::Both(path.clone()));
+        assert!(lenient.receive_request(&request).await.is_ok());
+
+        let strict = Logger::with_file(&path).strict();
+        let err = strict.receive_request(&request).await.unwrap_err();
+        assert!(err.message().contains("Unable to open log file"));
+    }
+
+    #[tokio::test]
+    async fn batches_log_every_entry() {
+        let path = std::env::temp_dir().join(format!("logger-{}.log", uuid::Uuid::new_v4()));
+        let logger = Logger::with_file(&path);
+
+        let response = logger
+            .receive_request(&log_request(serde_json::json!([
+                "plain",
+                { "level": "error", "msg": "broken" },
+                { "level": "debug", "msg": "detail" },
+            ])))
+            .await
+            .unwrap();
+
+        let written = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            response.data(),
+            Some(serde_json::json!({ "logged": 3, "skipped": 0 }))
+        );
+        let lines: Vec<&str> = written.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].contains("[Error]") && lines[2].contains("[Debug]"));
+    }
+
+    #[tokio::test]
+    async fn batches_with_an_invalid_entry_are_rejected_whole() {
+        let path = std::env::temp_dir().join(format!("logger-{}.log", uuid::Uuid::new_v4()));
+        let logger = Logger::with_file(&path);
+
+        let err = logger
+            .receive_request(&log_request(serde_json::json!(["fine", "also fine", 42])))
+            .await
+            .unwrap_err();
+
+        let written = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(err.message().contains("Element 2"), "{}", err.message());
+        assert!(written.is_empty());
+    }
+
+    #[tokio::test]
+    async fn single_values_are_still_logged_without_a_count() {
+        let logger = Logger::default();
+        let response = logger
+            .receive_request(&log_request(serde_json::json!("hello")))
+            .await
+            .unwrap();
+        assert!(response.data().is_none());
+        assert!(logger
+            .receive_request(&log_request(serde_json::json!(42)))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn entries_below_the_min_level_are_skipped() {
+        let logger = Logger::with_min_level(LogLevel::Warn);
+        assert_eq!(logger.min_level(), LogLevel::Warn);
+
+        let info = logger
+            .receive_request(&log_request(
+                serde_json::json!({ "level": "info", "msg": "chatter" }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(info.data(), Some(serde_json::json!({ "skipped": true })));
+
+        let warning = logger
+            .receive_request(&log_request(
+                serde_json::json!({ "level": "warn", "msg": "careful" }),
+            ))
+            .await
+            .unwrap();
+        assert!(warning.data().is_none());
+
+        let batch = logger
+            .receive_request(&log_request(serde_json::json!([
+                "plain strings are info",
+                { "level": "unknown", "msg": "so is this" },
+                { "level": "error", "msg": "broken" },
+            ])))
+            .await
+            .unwrap();
+        assert_eq!(
+            batch.data(),
+            Some(serde_json::json!({ "logged": 1, "skipped": 2 }))
+        );
+    }
+
+    #[tokio::test]
+    async fn default_logger_only_uses_tracing() {
+        let logger = Logger::default();
+        assert_eq!(logger.sink(), &Sink::Tracing);
+        assert!(logger
+            .strict()
+            .receive_request(&log_request(serde_json::json!("hello")))
+            .await
+            .is_ok());
+    }
 }
@@ -0,0 +1,357 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::str::FromStr;
+
+use serde_json::{json, Value as JsonValue};
+
+use super::multi_string_keys;
+use crate::{async_trait, BadRequestError, ComputeFunction, ComputeRequest, ComputeResponse};
+
+/// The arithmetic operations understood by [`Math`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum MathOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+impl FromStr for MathOp {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "add" | "+" | "sum" => Ok(Self::Add),
+            "sub" | "-" | "subtract" => Ok(Self::Sub),
+            "mul" | "*" | "multiply" => Ok(Self::Mul),
+            "div" | "/" | "divide" => Ok(Self::Div),
+            _ => Err(format!(
+                "Unknown operation '{}', expected one of add, sub, mul or div",
+                s
+            )),
+        }
+    }
+}
+
+/// A single operand, kept as an integer for as long as it is one so integer math stays exact.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Operand {
+    Int(i128),
+    Float(f64),
+}
+
+impl Operand {
+    /// Read an operand from JSON, or [`None`] if `value` isn't a number.
+    fn from_json(value: &JsonValue) -> Option<Self> {
+        value
+            .as_i64()
+            .map(|i| Self::Int(i.into()))
+            .or_else(|| value.as_u64().map(|u| Self::Int(u.into())))
+            .or_else(|| value.as_f64().map(Self::Float))
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    const fn as_f64(self) -> f64 {
+        match self {
+            Self::Int(i) => i as f64,
+            Self::Float(f) => f,
+        }
+    }
+
+    const fn as_int(self) -> Option<i128> {
+        match self {
+            Self::Int(i) => Some(i),
+            Self::Float(_) => None,
+        }
+    }
+}
+
+const TOO_LARGE: &str = "Result is too large to be represented";
+
+impl MathOp {
+    /// Fold `operands` from left to right with this operation.
+    ///
+    /// As long as every operand is an integer the result is computed exactly, and is an integer
+    /// itself. Only a float operand (or a division that doesn't come out even) makes it a float.
+    ///
+    /// ## Errors
+    /// Returns a message describing the problem if there are no operands, any divisor is zero or
+    /// the result does not fit in an `i64` / `u64` (integers) or a finite number (floats).
+    fn apply(self, operands: &[Operand]) -> Result<JsonValue, String> {
+        if operands.is_empty() {
+            return Err("At least one operand is required".to_string());
+        }
+
+        if let Some(integers) = operands
+            .iter()
+            .map(|operand| operand.as_int())
+            .collect::<Option<Vec<_>>>()
+        {
+            if let Some(result) = self.apply_integers(&integers)? {
+                return i64::try_from(result)
+                    .map(JsonValue::from)
+                    .or_else(|_| u64::try_from(result).map(JsonValue::from))
+                    .map_err(|_| TOO_LARGE.to_string());
+            }
+        }
+
+        let floats = operands
+            .iter()
+            .map(|operand| operand.as_f64())
+            .collect::<Vec<_>>();
+        self.apply_floats(&floats).map(JsonValue::from)
+    }
+
+    /// Fold non-empty `operands` with checked `i128` arithmetic, or return [`None`] if a division
+    /// doesn't come out even and the fold has to be done with floats instead.
+    fn apply_integers(self, operands: &[i128]) -> Result<Option<i128>, String> {
+        let (&first, rest) = operands
+            .split_first()
+            .ok_or_else(|| "At least one operand is required".to_string())?;
+
+        let mut result = first;
+        for (i, &operand) in rest.iter().enumerate() {
+            result = match self {
+                Self::Add => result.checked_add(operand),
+                Self::Sub => result.checked_sub(operand),
+                Self::Mul => result.checked_mul(operand),
+                Self::Div if operand == 0 => {
+                    return Err(format!("Division by zero (operand {} is 0)", i + 1))
+                }
+                Self::Div if result.checked_rem(operand) != Some(0) => return Ok(None),
+                Self::Div => result.checked_div(operand),
+            }
+            .ok_or_else(|| TOO_LARGE.to_string())?;
+        }
+
+        Ok(Some(result))
+    }
+
+    /// Fold non-empty `operands` with `f64` arithmetic.
+    fn apply_floats(self, operands: &[f64]) -> Result<f64, String> {
+        let (&first, rest) = operands
+            .split_first()
+            .ok_or_else(|| "At least one operand is required".to_string())?;
+
+        let mut result = first;
+        for (i, &operand) in rest.iter().enumerate() {
+            result = match self {
+                Self::Add => result + operand,
+                Self::Sub => result - operand,
+                Self::Mul => result * operand,
+                Self::Div if operand == 0.0 => {
+                    return Err(format!("Division by zero (operand {} is 0)", i + 1))
+                }
+                Self::Div => result / operand,
+            };
+        }
+
+        if result.is_finite() {
+            Ok(result)
+        } else {
+            Err(TOO_LARGE.to_string())
+        }
+    }
+}
+
+/// Simple arithmetic over a list of numbers, e.g. `{"op": "add", "operands": [1, 2, 3]}` answers
+/// with `{"result": 6}`. The operands are folded from left to right, so `sub` and `div` take the
+/// first operand and subtract (or divide by) each of the rest in turn.
+///
+/// Integers are added, subtracted and multiplied exactly, and a result that doesn't fit in an `i64`
+/// or `u64` is a bad request rather than being wrapped or rounded. Floating point math is only used
+/// once a float is involved, so `[1, 0.5]` adds up to `1.5` and `[12, 2, 4]` divides to `1.5`.
+#[derive(Debug, Default)]
+pub struct Math;
+
+#[async_trait]
+impl ComputeFunction for Math {
+    fn name(&self) -> &'static str {
+        "math"
+    }
+
+    #[allow(clippy::unused_async)]
+    async fn receive_request(
+        &self,
+        request: &ComputeRequest,
+    ) -> Result<ComputeResponse, BadRequestError> {
+        let bad_request = |msg: &str| BadRequestError::new(self.name(), msg, Some(request.clone()));
+
+        let obj = request.data().as_object().ok_or_else(|| {
+            bad_request(r#"Data must be an object, e.g. {"op": "add", "operands": [1, 2]}"#)
+        })?;
+
+        let op = multi_string_keys(obj, &["op", "operation"], None, |s| {
+            Some(s.parse::<MathOp>())
+        })
+        .ok_or_else(|| bad_request("Missing the 'op' (or 'operation') to perform"))?
+        .map_err(|msg| bad_request(&msg))?;
+
+        let operands = obj
+            .get("operands")
+            .and_then(serde_json::Value::as_array)
+            .ok_or_else(|| bad_request("'operands' must be an array of numbers"))?
+            .iter()
+            .enumerate()
+            .map(|(i, operand)| {
+                Operand::from_json(operand).ok_or_else(|| {
+                    bad_request(&format!("Operand {} is not a number: {}", i, operand))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let result = op.apply(&operands).map_err(|msg| bad_request(&msg))?;
+
+        Ok(ComputeResponse::json_ok(json!({ "result": result })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TargetComputeFunc;
+
+    async fn compute(data: JsonValue) -> Result<ComputeResponse, BadRequestError> {
+        let request = ComputeRequest::new(TargetComputeFunc::new("math".to_string()), data);
+        Math.receive_request(&request).await
+    }
+
+    async fn result_of(data: JsonValue) -> JsonValue {
+        compute(data).await.unwrap().data().unwrap()["result"].clone()
+    }
+
+    async fn error_of(data: JsonValue) -> String {
+        compute(data).await.unwrap_err().message().to_string()
+    }
+
+    #[tokio::test]
+    async fn adds() {
+        assert_eq!(
+            result_of(json!({ "op": "add", "operands": [1, 2, 3] })).await,
+            json!(6)
+        );
+    }
+
+    #[tokio::test]
+    async fn subtracts_from_the_first_operand() {
+        assert_eq!(
+            result_of(json!({ "op": "sub", "operands": [10, 2, 3] })).await,
+            json!(5)
+        );
+    }
+
+    #[tokio::test]
+    async fn multiplies() {
+        assert_eq!(
+            result_of(json!({ "op": "mul", "operands": [2, 3, 4] })).await,
+            json!(24)
+        );
+    }
+
+    #[tokio::test]
+    async fn divides_the_first_operand() {
+        assert_eq!(
+            result_of(json!({ "op": "div", "operands": [12, 2, 4] })).await,
+            json!(1.5)
+        );
+    }
+
+    #[tokio::test]
+    async fn even_integer_division_stays_an_integer() {
+        assert_eq!(
+            result_of(json!({ "op": "div", "operands": [12, 2, 3] })).await,
+            json!(2)
+        );
+    }
+
+    #[tokio::test]
+    async fn large_integers_stay_exact() {
+        // 2^53 + 1 is the first integer an f64 can't represent.
+        assert_eq!(
+            result_of(json!({ "op": "add", "operands": [9_007_199_254_740_993_i64, 0] })).await,
+            json!(9_007_199_254_740_993_i64)
+        );
+        // Past i64::MAX, but still a u64.
+        assert_eq!(
+            result_of(json!({ "op": "add", "operands": [i64::MAX, 1] })).await,
+            json!(9_223_372_036_854_775_808_u64)
+        );
+        assert_eq!(
+            result_of(json!({ "op": "mul", "operands": [u64::MAX, 1] })).await,
+            json!(u64::MAX)
+        );
+        assert_eq!(
+            result_of(json!({ "op": "sub", "operands": [i64::MIN, -1] })).await,
+            json!(i64::MIN + 1)
+        );
+    }
+
+    #[tokio::test]
+    async fn integer_overflow_is_a_bad_request() {
+        for data in [
+            json!({ "op": "add", "operands": [u64::MAX, 1] }),
+            json!({ "op": "sub", "operands": [i64::MIN, 1] }),
+            json!({ "op": "mul", "operands": [u64::MAX, u64::MAX, u64::MAX] }),
+        ] {
+            let message = error_of(data.clone()).await;
+            assert!(message.contains("too large"), "{}: {}", data, message);
+        }
+    }
+
+    #[tokio::test]
+    async fn floats_use_floating_point_math() {
+        assert_eq!(
+            result_of(json!({ "op": "add", "operands": [1, 0.5] })).await,
+            json!(1.5)
+        );
+        assert_eq!(
+            result_of(json!({ "op": "add", "operands": [0.1, 0.2] })).await,
+            json!(0.1 + 0.2)
+        );
+        assert_eq!(
+            result_of(json!({ "op": "mul", "operands": [2.0, 3] })).await,
+            json!(6.0)
+        );
+        let message = error_of(json!({ "op": "mul", "operands": [f64::MAX, 2] })).await;
+        assert!(message.contains("too large"), "{}", message);
+    }
+
+    #[tokio::test]
+    async fn accepts_operation_as_the_key() {
+        assert_eq!(
+            result_of(json!({ "operation": "ADD", "operands": [0.5, 0.25] })).await,
+            json!(0.75)
+        );
+    }
+
+    #[tokio::test]
+    async fn division_by_zero_is_a_bad_request() {
+        let message = error_of(json!({ "op": "div", "operands": [1, 2, 0] })).await;
+        assert!(message.contains("Division by zero"), "{}", message);
+    }
+
+    #[tokio::test]
+    async fn non_numeric_operands_are_a_bad_request() {
+        let message = error_of(json!({ "op": "add", "operands": [1, "two"] })).await;
+        assert!(message.contains("Operand 1 is not a number"), "{}", message);
+    }
+
+    #[tokio::test]
+    async fn malformed_requests_are_bad_requests() {
+        assert!(error_of(json!({ "op": "pow", "operands": [1] }))
+            .await
+            .contains("Unknown operation 'pow'"));
+        assert!(error_of(json!({ "operands": [1] })).await.contains("'op'"));
+        assert!(error_of(json!({ "op": "add" }))
+            .await
+            .contains("'operands'"));
+        assert!(error_of(json!({ "op": "add", "operands": [] }))
+            .await
+            .contains("At least one operand"));
+        assert!(error_of(json!([1, 2])).await.contains("must be an object"));
+    }
+}
@@ -3,27 +3,91 @@
 // This Source Code Form is subject to the terms of the Mozilla Public
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
-use std::collections::HashSet;
+use std::{
+    collections::{BTreeMap, HashSet},
+    str::FromStr,
+};
 
+use thiserror::Error;
+
+mod echo;
+mod kv;
 mod logger;
+mod math;
 
-pub use logger::{LogLevel, Logger};
+pub use echo::Echo;
+pub use kv::KvStore;
+pub use logger::Logger;
+pub use math::Math;
 
 use crate::ComputeFunction;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum BuiltinFunction {
     Logger,
+    Echo,
+    Math,
+    KvStore,
 }
 
 impl BuiltinFunction {
+    /// Every [`BuiltinFunction`] variant, mostly useful for iterating over the available builtins.
+    pub const ALL: &'static [Self] = &[Self::Logger, Self::Echo, Self::Math, Self::KvStore];
+
+    #[must_use]
     pub fn create(self) -> Box<dyn ComputeFunction> {
         match self {
             Self::Logger => Box::new(Logger::default()),
+            Self::Echo => Box::new(Echo),
+            Self::Math => Box::new(Math),
+            Self::KvStore => Box::new(KvStore::default()),
+        }
+    }
+
+    /// The name this builtin is known by, which matches the [`ComputeFunction::name`] of the
+    /// instance it creates.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Logger => "logger",
+            Self::Echo => "echo",
+            Self::Math => "math",
+            Self::KvStore => "kv",
         }
     }
 }
 
+/// Error returned when parsing a [`BuiltinFunction`] from a string that does not name one.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("Unknown builtin function: '{0}'")]
+pub struct UnknownBuiltinError(String);
+
+impl UnknownBuiltinError {
+    /// The name that failed to parse.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for BuiltinFunction {
+    type Err = UnknownBuiltinError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .find(|builtin| builtin.name().eq_ignore_ascii_case(s.trim()))
+            .copied()
+            .ok_or_else(|| UnknownBuiltinError(s.to_string()))
+    }
+}
+
+impl std::fmt::Display for BuiltinFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub struct BuiltinFunctionList(HashSet<BuiltinFunction>);
 
@@ -36,6 +100,10 @@ impl BuiltinFunctionList {
         self.0.insert(function)
     }
 
+    pub fn remove(&mut self, function: BuiltinFunction) -> bool {
+        self.0.remove(&function)
+    }
+
     pub fn add_multiple(&mut self, functions: &[BuiltinFunction]) -> usize {
         let mut success = 0;
         for function in functions {
@@ -54,6 +122,10 @@ impl BuiltinFunctionList {
     pub fn create_all(&self) -> Vec<Box<dyn ComputeFunction>> {
         self.0.iter().map(|function| function.create()).collect()
     }
+
+    pub fn iter(&self) -> impl Iterator<Item = BuiltinFunction> + '_ {
+        self.0.iter().copied()
+    }
 }
 
 impl<T> From<T> for BuiltinFunctionList
@@ -69,3 +141,148 @@ where
         list
     }
 }
+
+/// A factory that creates a fresh instance of a [`ComputeFunction`].
+pub type BuiltinFactory = Box<dyn Fn() -> Box<dyn ComputeFunction> + Send + Sync>;
+
+/// An open counterpart to [`BuiltinFunction`] for registering always-available functions by name.
+///
+/// This lets crates embedding `local-compute` add their own "builtins". Pass it to
+/// [`ComputeFunctionManager::with_registry`](crate::ComputeFunctionManager::with_registry) to have
+/// every registered function created when the manager starts up.
+#[derive(Default)]
+pub struct BuiltinRegistry {
+    factories: BTreeMap<String, BuiltinFactory>,
+}
+
+impl BuiltinRegistry {
+    /// Create a new, empty, [`BuiltinRegistry`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new [`BuiltinRegistry`] with every [`BuiltinFunction`] already registered under its
+    /// [`BuiltinFunction::name`].
+    #[must_use]
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::new();
+        for &builtin in BuiltinFunction::ALL {
+            registry.register(builtin.name(), move || builtin.create());
+        }
+        registry
+    }
+
+    /// Register `factory` under `name`, which is also the name the created function is reachable by.
+    ///
+    /// ## Returns
+    /// `true` if the factory was registered, `false` if `name` was already taken (in which case the
+    /// existing factory is kept).
+    pub fn register<F>(&mut self, name: impl Into<String>, factory: F) -> bool
+    where
+        F: Fn() -> Box<dyn ComputeFunction> + Send + Sync + 'static,
+    {
+        let name = name.into();
+        if self.factories.contains_key(&name) {
+            return false;
+        }
+        self.factories.insert(name, Box::new(factory));
+        true
+    }
+
+    /// Whether a factory is registered under `name`.
+    #[must_use]
+    pub fn contains(&self, name: &str) -> bool {
+        self.factories.contains_key(name)
+    }
+
+    /// The names of every registered factory, in sorted order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.factories.keys().map(String::as_str)
+    }
+
+    /// Create the function registered under `name`, if there is one.
+    #[must_use]
+    pub fn create(&self, name: &str) -> Option<Box<dyn ComputeFunction>> {
+        self.factories.get(name).map(|factory| factory())
+    }
+
+    /// Create every registered function, paired with the name it was registered under.
+    #[must_use]
+    pub fn create_all(&self) -> Vec<(String, Box<dyn ComputeFunction>)> {
+        self.factories
+            .iter()
+            .map(|(name, factory)| (name.clone(), factory()))
+            .collect()
+    }
+}
+
+impl std::fmt::Debug for BuiltinRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BuiltinRegistry")
+            .field("names", &self.factories.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// Helper function try multiple variations of a key to find one that might exist.
+/// I only had need for retrieving strings so I didn't make it generic over the
+/// type of value. If I used it more often it could probably be made so.
+///
+/// TODO: Maybe I could provide it in the planned exported utility library for
+/// function implementations.
+fn multi_string_keys<Output, F: Fn(&str) -> Output>(
+    map: &serde_json::Map<String, serde_json::Value>,
+    keys: &[&str],
+    def: Output,
+    converter: F,
+) -> Output {
+    for &key in keys {
+        if let Some(v) = map.get(key) {
+            if let Some(s) = v.as_str() {
+                return converter(s);
+            }
+        }
+    }
+    def
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_known_names() {
+        for &builtin in BuiltinFunction::ALL {
+            assert_eq!(builtin.name().parse::<BuiltinFunction>(), Ok(builtin));
+            assert_eq!(builtin.to_string().parse::<BuiltinFunction>(), Ok(builtin));
+        }
+    }
+
+    #[test]
+    fn parsing_ignores_case() {
+        for name in ["logger", "Logger", "LOGGER", "lOgGeR", " logger "] {
+            assert_eq!(name.parse::<BuiltinFunction>(), Ok(BuiltinFunction::Logger));
+        }
+    }
+
+    #[test]
+    fn unknown_name_is_an_error() {
+        let err = "not-a-builtin".parse::<BuiltinFunction>().unwrap_err();
+        assert_eq!(err.name(), "not-a-builtin");
+    }
+
+    #[test]
+    fn registry_keeps_the_first_factory_for_a_name() {
+        let mut registry = BuiltinRegistry::with_builtins();
+
+        assert!(registry.contains("logger"));
+        assert!(!registry.register("logger", || Box::new(Logger::default())));
+        assert!(registry.register("audit", || Box::new(Logger::default())));
+        assert_eq!(
+            registry.names().collect::<Vec<_>>(),
+            vec!["audit", "echo", "kv", "logger", "math"]
+        );
+        assert!(registry.create("missing").is_none());
+    }
+}
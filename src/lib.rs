@@ -2,8 +2,7 @@
     crate_visibility_modifier,
     label_break_value,
     lint_reasons,
-    path_try_exists,
-    try_blocks
+    path_try_exists
 )]
 #![warn(
     clippy::pedantic,
@@ -27,24 +26,73 @@
 )]
 #![allow(dead_code, clippy::module_name_repetitions)]
 
+#[cfg(feature = "cli")]
 mod cli;
 crate mod core;
 mod dynamic_libs;
 mod functions;
+#[cfg(all(feature = "axum", any(test, feature = "test-util")))]
+pub mod test_support;
 crate mod util;
 
-pub use crate::core::types::{BadRequestError, ComputeFunction, ComputeRequest, ComputeResponse};
+pub use crate::core::{
+    types::{
+        AppError, AppInput, AppResult, AuthPolicy, BadInputError, BadRequestError, CallCounts,
+        ComputeFunction, ComputeJsonResponse, ComputeRequest, ComputeResponse, FunctionDescription,
+        FunctionInfo, FunctionOrigin, FunctionStats, HealthStatus, InputLimits, LoadOutcome,
+        ManagerEvent, RequestContext, ResponseSink, TargetComputeFunc,
+    },
+    ComputeFunctionManager, ComputeFunctionManagerBuilder, PLUGIN_ABI_VERSION,
+};
+#[cfg(any(feature = "axum", feature = "warp"))]
+pub use crate::core::server::{
+    spawn_server, AuthToken, Backend, CorsConfig, ServerError, ServerInstance,
+};
+#[cfg(feature = "tls")]
+pub use crate::core::server::run_axum_tls;
+#[cfg(feature = "axum")]
+pub use crate::core::server::{run_axum_rest, ServerSyncType};
+#[cfg(feature = "cli")]
+pub use crate::cli::{Cli, CliError, Command};
+pub use crate::functions::{BuiltinFunction, BuiltinRegistry};
 pub use async_trait::async_trait;
 pub use serde_json::{json, Value as JsonValue};
 
-pub async fn run() {
-    let addr = std::net::SocketAddr::from(([127, 0, 0, 1], 3000));
-    let handle: tokio::task::JoinHandle<Result<(), hyper::Error>> =
-        core::server::run_hello_server(addr).await;
+/// Serve a [`ComputeFunctionManager`] with the builtin logger loaded on `addr` (or
+/// `127.0.0.1:3000`) with `backend` (or [`Backend::default`]), taking any [`AppInput`] on `POST /`.
+///
+/// The server runs until the process is asked to stop, with Ctrl-C or (on Unix) `SIGTERM`, and then
+/// shuts down gracefully: requests that are already being handled are allowed to finish, and then
+/// every function is unloaded. This is the intended entrypoint for anything beyond tests and
+/// experiments, the lower level server runners only stop when their shutdown oneshot fires and leave
+/// installing signal handlers to the caller.
+///
+/// If there is an `auth_token`, every request has to carry it as `Authorization: Bearer <token>`
+/// and is answered with a `401` otherwise.
+///
+/// ## Errors
+/// - [`ServerError::Unsupported`] if `backend` isn't compiled in
+/// - [`ServerError::Bind`] if `addr` can't be bound
+/// - [`ServerError::Failed`] if the server fails while serving
+#[cfg(any(feature = "axum", feature = "warp"))]
+pub async fn run(
+    addr: Option<std::net::SocketAddr>,
+    backend: Option<Backend>,
+    auth_token: Option<&AuthToken>,
+) -> Result<(), ServerError> {
+    let addr = addr.unwrap_or_else(|| std::net::SocketAddr::from(([127, 0, 0, 1], 3000)));
+    let backend = backend.unwrap_or_default();
+    let manager = ComputeFunctionManager::with_logger();
 
-    if let Err(err) = handle.await {
-        eprintln!("{}", err);
-    }
+    tracing::info!("Serving with {} on {}", backend, addr);
+    core::server::serve(
+        backend,
+        &addr,
+        manager,
+        auth_token,
+        core::server::shutdown_on_signal(),
+    )
+    .await
 }
 
 // Dumb
@@ -0,0 +1,216 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! An in-memory harness for testing against the [axum] server without binding a socket, enabled by
+//! the `test-util` feature.
+//!
+//! [`TestServer`] drives the same [`Router`] the server would use, one request at a time through
+//! [`tower::ServiceExt::oneshot`]. Over HTTP an [`AppOutput`](crate::core::types::AppOutput) is
+//! reduced to a status and (maybe) a JSON body, so that is what comes back as a [`TestResponse`].
+
+use std::sync::{Arc, Mutex, PoisonError};
+
+use axum::{
+    body::Body,
+    http::{HeaderMap, Request, StatusCode},
+    Router,
+};
+use serde_json::Value as JsonValue;
+use tokio::sync::RwLock;
+use tower::ServiceExt;
+
+use crate::core::{
+    server::{AxumServer, DEFAULT_MAX_BODY_BYTES},
+    types::{
+        AddFunctionRequest, AppInput, ComputeRequest, FunctionInfo, RemoveFunctionRequest,
+        TargetComputeFunc,
+    },
+    ComputeFunctionManager,
+};
+
+/// An axum server that handles requests in memory, see the [module docs](self).
+#[derive(Debug)]
+pub struct TestServer {
+    // A `Router` isn't `Sync`, so it is behind a lock to keep the server usable from `Send` futures.
+    // The lock is only held to clone it.
+    router: Mutex<Router>,
+    manager: Arc<RwLock<ComputeFunctionManager>>,
+}
+
+impl TestServer {
+    /// Serve `manager` behind the `RwLock` router, the one the server picks by default.
+    #[must_use]
+    pub fn new(manager: ComputeFunctionManager) -> Self {
+        let manager = Arc::new(RwLock::new(manager));
+        Self {
+            router: Mutex::new(AxumServer::rw_router(
+                Arc::clone(&manager),
+                DEFAULT_MAX_BODY_BYTES,
+            )),
+            manager,
+        }
+    }
+
+    /// The manager behind the server, for setting up or checking state directly.
+    #[must_use]
+    pub const fn manager(&self) -> &Arc<RwLock<ComputeFunctionManager>> {
+        &self.manager
+    }
+
+    /// Send any HTTP request to the server.
+    ///
+    /// ## Panics
+    /// If the response body can't be read.
+    pub async fn request(&self, request: Request<Body>) -> TestResponse {
+        let router = self
+            .router
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        let response = match router.oneshot(request).await {
+            Ok(response) => response,
+            Err(never) => match never {},
+        };
+        let status = response.status();
+        let headers = response.headers().clone();
+        let bytes = hyper::body::to_bytes(response.into_body())
+            .await
+            .expect("Failed to read the response body");
+        let body = (!bytes.is_empty()).then(|| {
+            serde_json::from_slice(&bytes)
+                .unwrap_or_else(|_| JsonValue::String(String::from_utf8_lossy(&bytes).into_owned()))
+        });
+
+        TestResponse {
+            status,
+            headers,
+            body,
+        }
+    }
+
+    /// Send `input` the way a client would, as a JSON `POST /`.
+    ///
+    /// ## Panics
+    /// If `input` can't be serialized or the response body can't be read.
+    pub async fn send(&self, input: &AppInput) -> TestResponse {
+        let body = serde_json::to_vec(input).expect("Failed to serialize the AppInput");
+        let request = Request::post("/")
+            .header("content-type", "application/json")
+            .body(Body::from(body))
+            .expect("Failed to build the request");
+        self.request(request).await
+    }
+
+    /// Load the plugin library at `path`, see [`AppInput::AddComputeFunction`].
+    pub async fn load(&self, path: &str) -> TestResponse {
+        let input = AppInput::AddComputeFunction(AddFunctionRequest::new(path.to_string()));
+        self.send(&input).await
+    }
+
+    /// Execute `target` with `data`, see [`AppInput::Execute`].
+    pub async fn execute(&self, target: &str, data: JsonValue) -> TestResponse {
+        let request = ComputeRequest::new(TargetComputeFunc::new(target.to_string()), data);
+        self.send(&AppInput::Execute(request)).await
+    }
+
+    /// Unload the function `target`, see [`AppInput::RemoveComputeFunction`].
+    pub async fn unload(&self, target: &str) -> TestResponse {
+        let input = AppInput::RemoveComputeFunction(RemoveFunctionRequest::new(
+            TargetComputeFunc::new(target.to_string()),
+        ));
+        self.send(&input).await
+    }
+
+    /// The functions the server reports as loaded, see [`AppInput::ListFunctions`].
+    ///
+    /// ## Panics
+    /// If the server doesn't answer with a list of functions.
+    pub async fn list_functions(&self) -> Vec<FunctionInfo> {
+        let response = self.send(&AppInput::ListFunctions).await;
+        assert_eq!(response.status(), StatusCode::OK, "{:?}", response.body());
+        serde_json::from_value(response.body().cloned().unwrap_or_default())
+            .expect("The server did not answer with a list of functions")
+    }
+}
+
+/// What a [`TestServer`] answered with.
+#[derive(Debug, Clone)]
+pub struct TestResponse {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Option<JsonValue>,
+}
+
+impl TestResponse {
+    /// The HTTP status of the response.
+    #[must_use]
+    pub const fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// The headers of the response.
+    #[must_use]
+    pub const fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// The JSON body of the response, or `None` if it was empty. Bodies that aren't JSON are
+    /// returned as a JSON string.
+    #[must_use]
+    pub const fn body(&self) -> Option<&JsonValue> {
+        self.body.as_ref()
+    }
+
+    /// The `code` of the error envelope, if the server answered with one.
+    #[must_use]
+    pub fn error_code(&self) -> Option<&str> {
+        self.body.as_ref()?.get("code")?.as_str()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[cfg(not(feature = "pascal-case-wire"))]
+    #[tokio::test]
+    async fn load_execute_unload_flow() {
+        let server = TestServer::new(ComputeFunctionManager::with_logger());
+
+        let load = server.load("/definitely/not/libthere.so").await;
+        assert_eq!(load.status(), StatusCode::NOT_FOUND);
+        assert_eq!(load.error_code(), Some("loading"));
+
+        let execute = server.execute("logger", json!("hello")).await;
+        assert!(execute.status().is_success(), "{:?}", execute.body());
+
+        assert_eq!(server.unload("logger").await.status(), StatusCode::OK);
+        assert!(server.list_functions().await.is_empty());
+
+        let missing = server.execute("logger", json!("hello")).await;
+        assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+        assert_eq!(missing.error_code(), Some("target_not_found"));
+    }
+
+    #[cfg(not(feature = "pascal-case-wire"))]
+    #[tokio::test]
+    async fn load_answers_with_the_loaded_function() {
+        let server = TestServer::new(ComputeFunctionManager::new());
+        let path = crate::util::fixtures::sample_plugin_path();
+
+        let load = server.load(&path).await;
+        assert_eq!(load.status(), StatusCode::CREATED);
+        let body = load.body().unwrap();
+        assert_eq!(body["name"], crate::util::fixtures::SAMPLE_PLUGIN_NAME);
+        assert_eq!(body["origin"], json!({ "dynamic": { "path": path } }));
+
+        let name = body["name"].as_str().unwrap();
+        let execute = server.execute(name, json!(1)).await;
+        assert!(execute.status().is_success(), "{:?}", execute.body());
+    }
+}
@@ -0,0 +1,58 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use serde_json::{json, Value as JsonValue};
+
+use crate::core::types::GenericStatusCode;
+
+/// Build the JSON body shared by every error response, whichever backend or code path produced it:
+///
+/// ```json
+/// { "status": 400, "code": "bad_request", "message": "...", "details": null }
+/// ```
+///
+/// `code` is a short, stable identifier for the kind of error (see
+/// [`AppError::code`](crate::AppError::code)), `message` is meant for humans, and `details` carries
+/// anything structured about the error (`null` if there is nothing).
+#[must_use]
+pub fn error_envelope(
+    status: GenericStatusCode,
+    code: &str,
+    message: &str,
+    details: Option<JsonValue>,
+) -> JsonValue {
+    json!({
+        "status": status.to_u16(),
+        "code": code,
+        "message": message,
+        "details": details.unwrap_or(JsonValue::Null),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn always_has_every_field() {
+        let envelope = error_envelope(
+            GenericStatusCode::NotFound,
+            "target_not_found",
+            "nope",
+            None,
+        );
+
+        assert_eq!(
+            envelope,
+            json!({
+                "status": 404,
+                "code": "target_not_found",
+                "message": "nope",
+                "details": null,
+            })
+        );
+    }
+}
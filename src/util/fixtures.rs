@@ -0,0 +1,73 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Locations of the dynamically loaded plugin fixtures in `examples/`. Cargo builds the examples as
+//! part of `cargo test`, but not for `cargo test --lib`, so run `cargo build --examples` first in
+//! that case.
+
+use std::path::{Path, PathBuf};
+
+/// The [`ComputeFunction::name`](crate::ComputeFunction::name) of the `sample_plugin` fixture.
+pub const SAMPLE_PLUGIN_NAME: &str = "sample_plugin";
+
+/// Gets the absolute path to the compiled `cdylib` for the example named `name`.
+///
+/// ## Panics
+/// If the example has not been built yet.
+pub fn example_library(name: &str) -> PathBuf {
+    use std::env::consts::{DLL_PREFIX, DLL_SUFFIX};
+
+    // Test binaries live in `target/<profile>/deps`, examples in `target/<profile>/examples`.
+    let exe = std::env::current_exe().expect("Unable to locate the running test binary.");
+    let profile_dir = exe
+        .parent()
+        .and_then(Path::parent)
+        .expect("Test binary is not inside a cargo target directory.");
+    let path = profile_dir
+        .join("examples")
+        .join(format!("{}{}{}", DLL_PREFIX, name, DLL_SUFFIX));
+
+    assert!(
+        path.exists(),
+        "Plugin fixture `{}` has not been built, run `cargo build --examples` first.",
+        path.display()
+    );
+    path
+}
+
+/// Gets the absolute path to the compiled `sample_plugin` fixture as a [`String`], ready to be
+/// handed to [`ComputeFunctionManager::load_plugin`](crate::ComputeFunctionManager::load_plugin).
+pub fn sample_plugin_path() -> String {
+    example_library(SAMPLE_PLUGIN_NAME)
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// The [`ComputeFunction::name`](crate::ComputeFunction::name) of [`FIXED_WASM_MODULE`].
+#[cfg(feature = "wasm")]
+pub const FIXED_WASM_NAME: &str = "wasm_fixed";
+
+/// A WebAssembly module, in text format, that answers every request with `{"fixed": true}`.
+#[cfg(feature = "wasm")]
+pub const FIXED_WASM_MODULE: &str = r#"
+(module
+  (memory (export "memory") 1)
+  (data (i32.const 0) "wasm_fixed")
+  (data (i32.const 16) "{\"Json\":{\"status\":200,\"data\":{\"fixed\":true}}}")
+  (func (export "alloc") (param i32) (result i32) (i32.const 1024))
+  (func (export "name") (result i64) (i64.const 10))
+  (func (export "receive_request") (param i32 i32) (result i64)
+    (i64.or (i64.shl (i64.const 16) (i64.const 32)) (i64.const 45))))
+"#;
+
+/// Writes the WebAssembly text `wat` to a new `.wat` file in the temporary directory, returning its
+/// path.
+#[cfg(feature = "wasm")]
+pub fn wasm_module(wat: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("{}.wat", uuid::Uuid::new_v4()));
+    std::fs::write(&path, wat).expect("Unable to write the WebAssembly fixture.");
+    path
+}
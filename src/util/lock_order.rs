@@ -0,0 +1,360 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! A named [`tokio::sync::Mutex`] (and [`tokio::sync::RwLock`]) that, in debug builds with the
+//! `deadlock-detect` feature, checks that locks are always taken in a consistent order and panics
+//! on the first inversion it sees. Read and write locks of an [`OrderedRwLock`] are ordered the
+//! same way, since a reader waiting behind a writer can deadlock just like a mutex.
+//!
+//! Every time a lock is taken while others are held, the `held -> acquired` pairs are recorded. If a
+//! lock is later taken while holding one that it was previously taken *before*, two code paths could
+//! deadlock each other, so the detector panics naming both locks. Without the feature (or in release
+//! builds) this is a thin wrapper with no bookkeeping at all.
+//!
+//! Held locks are tracked per thread. That is exact for a current-thread runtime (which is what
+//! `#[tokio::test]` uses), but a task that moves threads while holding a lock can confuse it on a
+//! multi-threaded runtime, which is why this is a debugging aid and never on by default.
+
+use std::ops::{Deref, DerefMut};
+
+use tokio::sync::{Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard, TryLockError};
+
+/// A [`tokio::sync::Mutex`] with a name, used to report lock-order inversions when the
+/// `deadlock-detect` feature is enabled.
+#[derive(Debug)]
+pub struct OrderedMutex<T> {
+    name: &'static str,
+    inner: Mutex<T>,
+}
+
+impl<T> OrderedMutex<T> {
+    /// Create a new [`OrderedMutex`] named `name`. Names should be unique, since the lock order is
+    /// recorded by name.
+    pub const fn new(name: &'static str, value: T) -> Self {
+        Self {
+            name,
+            inner: Mutex::const_new(value),
+        }
+    }
+
+    /// The name this lock was created with.
+    #[must_use]
+    pub const fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Lock the mutex, see [`tokio::sync::Mutex::lock`].
+    ///
+    /// ## Panics
+    /// With `deadlock-detect` enabled in a debug build, if taking this lock now inverts an order
+    /// that was seen before.
+    pub async fn lock(&self) -> OrderedMutexGuard<'_, T> {
+        let held = detect::before_acquire(self.name);
+        let guard = self.inner.lock().await;
+        OrderedMutexGuard {
+            guard,
+            _held: held.acquired(),
+        }
+    }
+
+    /// Try to lock the mutex without waiting, see [`tokio::sync::Mutex::try_lock`].
+    ///
+    /// ## Errors
+    /// A [`TryLockError`] if the lock is currently held.
+    ///
+    /// ## Panics
+    /// With `deadlock-detect` enabled in a debug build, if taking this lock now inverts an order
+    /// that was seen before.
+    pub fn try_lock(&self) -> Result<OrderedMutexGuard<'_, T>, TryLockError> {
+        let held = detect::before_acquire(self.name);
+        let guard = self.inner.try_lock()?;
+        Ok(OrderedMutexGuard {
+            guard,
+            _held: held.acquired(),
+        })
+    }
+
+    /// Get the underlying data through exclusive access, which can never deadlock so it isn't
+    /// tracked.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.inner.get_mut()
+    }
+}
+
+/// The guard returned by [`OrderedMutex::lock`], which releases the lock (and its place in the
+/// lock order) when dropped.
+#[derive(Debug)]
+pub struct OrderedMutexGuard<'a, T> {
+    guard: MutexGuard<'a, T>,
+    _held: detect::Held,
+}
+
+impl<T> Deref for OrderedMutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.guard
+    }
+}
+
+impl<T> DerefMut for OrderedMutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.guard
+    }
+}
+
+/// A [`tokio::sync::RwLock`] with a name, used to report lock-order inversions when the
+/// `deadlock-detect` feature is enabled.
+#[derive(Debug)]
+pub struct OrderedRwLock<T> {
+    name: &'static str,
+    inner: RwLock<T>,
+}
+
+impl<T: Send + Sync> OrderedRwLock<T> {
+    /// Create a new [`OrderedRwLock`] named `name`. Names should be unique, since the lock order is
+    /// recorded by name.
+    pub const fn new(name: &'static str, value: T) -> Self {
+        Self {
+            name,
+            inner: RwLock::const_new(value),
+        }
+    }
+
+    /// The name this lock was created with.
+    #[must_use]
+    pub const fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Lock for shared access, see [`tokio::sync::RwLock::read`].
+    ///
+    /// ## Panics
+    /// With `deadlock-detect` enabled in a debug build, if taking this lock now inverts an order
+    /// that was seen before.
+    pub async fn read(&self) -> OrderedRwLockReadGuard<'_, T> {
+        let held = detect::before_acquire(self.name);
+        let guard = self.inner.read().await;
+        OrderedRwLockReadGuard {
+            guard,
+            _held: held.acquired(),
+        }
+    }
+
+    /// Try to lock for shared access without waiting, see [`tokio::sync::RwLock::try_read`].
+    ///
+    /// ## Errors
+    /// A [`TryLockError`] if the lock is currently held for writing.
+    ///
+    /// ## Panics
+    /// With `deadlock-detect` enabled in a debug build, if taking this lock now inverts an order
+    /// that was seen before.
+    pub fn try_read(&self) -> Result<OrderedRwLockReadGuard<'_, T>, TryLockError> {
+        let held = detect::before_acquire(self.name);
+        let guard = self.inner.try_read()?;
+        Ok(OrderedRwLockReadGuard {
+            guard,
+            _held: held.acquired(),
+        })
+    }
+
+    /// Lock for exclusive access, see [`tokio::sync::RwLock::write`].
+    ///
+    /// ## Panics
+    /// With `deadlock-detect` enabled in a debug build, if taking this lock now inverts an order
+    /// that was seen before.
+    pub async fn write(&self) -> OrderedRwLockWriteGuard<'_, T> {
+        let held = detect::before_acquire(self.name);
+        let guard = self.inner.write().await;
+        OrderedRwLockWriteGuard {
+            guard,
+            _held: held.acquired(),
+        }
+    }
+
+    /// Try to lock for exclusive access without waiting, see [`tokio::sync::RwLock::try_write`].
+    ///
+    /// ## Errors
+    /// A [`TryLockError`] if the lock is currently held.
+    ///
+    /// ## Panics
+    /// With `deadlock-detect` enabled in a debug build, if taking this lock now inverts an order
+    /// that was seen before.
+    pub fn try_write(&self) -> Result<OrderedRwLockWriteGuard<'_, T>, TryLockError> {
+        let held = detect::before_acquire(self.name);
+        let guard = self.inner.try_write()?;
+        Ok(OrderedRwLockWriteGuard {
+            guard,
+            _held: held.acquired(),
+        })
+    }
+
+    /// Get the underlying data through exclusive access, which can never deadlock so it isn't
+    /// tracked.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.inner.get_mut()
+    }
+}
+
+/// The guard returned by [`OrderedRwLock::read`].
+#[derive(Debug)]
+pub struct OrderedRwLockReadGuard<'a, T> {
+    guard: RwLockReadGuard<'a, T>,
+    _held: detect::Held,
+}
+
+impl<T> Deref for OrderedRwLockReadGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.guard
+    }
+}
+
+/// The guard returned by [`OrderedRwLock::write`].
+#[derive(Debug)]
+pub struct OrderedRwLockWriteGuard<'a, T> {
+    guard: RwLockWriteGuard<'a, T>,
+    _held: detect::Held,
+}
+
+impl<T> Deref for OrderedRwLockWriteGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.guard
+    }
+}
+
+impl<T> DerefMut for OrderedRwLockWriteGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.guard
+    }
+}
+
+#[cfg(all(feature = "deadlock-detect", debug_assertions))]
+mod detect {
+    use std::{cell::RefCell, collections::HashSet, sync::Mutex};
+
+    lazy_static::lazy_static! {
+        /// Every `(held, acquired)` pair of lock names seen so far.
+        static ref ORDER: Mutex<HashSet<(&'static str, &'static str)>> = Mutex::default();
+    }
+
+    thread_local! {
+        static HELD: RefCell<Vec<&'static str>> = const { RefCell::new(Vec::new()) };
+    }
+
+    /// A lock that is about to be taken, and has passed the order check.
+    pub struct Pending(&'static str);
+
+    impl Pending {
+        pub fn acquired(self) -> Held {
+            HELD.with(|held| held.borrow_mut().push(self.0));
+            Held(self.0)
+        }
+    }
+
+    /// A lock that is currently held.
+    #[derive(Debug)]
+    pub struct Held(&'static str);
+
+    impl Drop for Held {
+        fn drop(&mut self) {
+            HELD.with(|held| {
+                let mut held = held.borrow_mut();
+                if let Some(index) = held.iter().rposition(|name| *name == self.0) {
+                    held.remove(index);
+                }
+            });
+        }
+    }
+
+    /// Check taking `name` against the recorded order, then record the order it is taken in.
+    pub fn before_acquire(name: &'static str) -> Pending {
+        let held: Vec<&'static str> = HELD.with(|held| held.borrow().clone());
+        let mut order = ORDER
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        for &other in held.iter().filter(|other| **other != name) {
+            if order.contains(&(name, other)) {
+                drop(order);
+                panic!(
+                    "Potential deadlock: lock `{}` was taken while holding `{}`, but `{}` has \
+                     previously been taken while holding `{}`",
+                    name, other, other, name
+                );
+            }
+            order.insert((other, name));
+        }
+        drop(order);
+        Pending(name)
+    }
+}
+
+#[cfg(not(all(feature = "deadlock-detect", debug_assertions)))]
+mod detect {
+    pub struct Pending;
+
+    impl Pending {
+        #[allow(clippy::unused_self)]
+        pub const fn acquired(self) -> Held {
+            Held
+        }
+    }
+
+    #[derive(Debug)]
+    pub struct Held;
+
+    pub const fn before_acquire(_name: &'static str) -> Pending {
+        Pending
+    }
+}
+
+#[cfg(all(test, feature = "deadlock-detect", debug_assertions))]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn consistent_order_is_fine() {
+        let a = OrderedMutex::new("consistent_a", ());
+        let b = OrderedMutex::new("consistent_b", ());
+
+        for _ in 0..2 {
+            let _a = a.lock().await;
+            let _b = b.lock().await;
+        }
+        let _b = b.lock().await;
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Potential deadlock")]
+    async fn inverted_order_panics() {
+        let a = OrderedMutex::new("inverted_a", ());
+        let b = OrderedMutex::new("inverted_b", ());
+
+        {
+            let _a = a.lock().await;
+            let _b = b.lock().await;
+        }
+        let _b = b.lock().await;
+        let _a = a.lock().await;
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Potential deadlock")]
+    async fn rw_locks_share_the_lock_order() {
+        let a = OrderedRwLock::new("rw_a", ());
+        let b = OrderedMutex::new("rw_b", ());
+
+        {
+            let _a = a.read().await;
+            let _b = b.lock().await;
+        }
+        let _b = b.lock().await;
+        let _a = a.write().await;
+    }
+}
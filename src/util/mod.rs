@@ -4,4 +4,11 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+mod envelope;
+#[cfg(test)]
+pub mod fixtures;
 pub mod hashing;
+pub mod lock_order;
+pub mod schema;
+
+pub use envelope::error_envelope;
@@ -0,0 +1,50 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use jsonschema::JSONSchema;
+use serde_json::Value as JsonValue;
+
+/// Validate `instance` against the JSON Schema `schema`.
+///
+/// ## Errors
+/// A list of human readable violations if `instance` does not satisfy `schema`, or a single
+/// entry describing the problem if `schema` itself is not a valid JSON Schema.
+pub fn validate(schema: &JsonValue, instance: &JsonValue) -> Result<(), Vec<String>> {
+    CompiledSchema::compile(schema).validate(instance)
+}
+
+/// A JSON Schema compiled once up front, for schemas that many instances are checked against.
+pub struct CompiledSchema(Result<JSONSchema, String>);
+
+impl CompiledSchema {
+    /// Compile `schema`. An invalid schema isn't an error yet, but fails every
+    /// [`CompiledSchema::validate`] with the reason it is invalid.
+    #[must_use]
+    pub fn compile(schema: &JsonValue) -> Self {
+        Self(JSONSchema::compile(schema).map_err(|err| format!("Invalid schema: {}", err)))
+    }
+
+    /// Validate `instance` against the schema, see [`validate`].
+    ///
+    /// ## Errors
+    /// The same as [`validate`].
+    pub fn validate(&self, instance: &JsonValue) -> Result<(), Vec<String>> {
+        let compiled = self.0.as_ref().map_err(|err| vec![err.clone()])?;
+        compiled.validate(instance).map_err(|errors| {
+            errors
+                .map(|err| format!("{} (at '{}')", err, err.instance_path))
+                .collect()
+        })
+    }
+}
+
+impl std::fmt::Debug for CompiledSchema {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("CompiledSchema")
+            .field(&self.0.as_ref().map(|_| "..."))
+            .finish()
+    }
+}